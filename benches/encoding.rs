@@ -0,0 +1,72 @@
+use botlib::models::Attachment;
+use botlib::{BinaryEncode, BotResponse, UserMessage};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hint::black_box;
+
+fn user_message() -> UserMessage {
+    let mut msg = UserMessage::text(
+        "bot",
+        "5511988887777",
+        "5511988887777",
+        "whatsapp",
+        "Olá, gostaria de saber o status do meu pedido 4821",
+    )
+    .with_attachment(
+        Attachment::image("https://cdn.example.com/a.jpg").with_mime_type("image/jpeg"),
+    );
+    msg.set_meta("order_id", 4821);
+    msg.set_meta(
+        "channel_message_id",
+        "wamid.HBgNNTUxMTk4ODg4Nzc3NxUCABIYFjNFQjBD",
+    );
+    msg
+}
+
+fn bot_response() -> BotResponse {
+    BotResponse::reply_to(&user_message(), "Seu pedido saiu para entrega.").with_suggestions([
+        "Rastrear",
+        "Falar com atendente",
+        "Encerrar",
+    ])
+}
+
+fn bench_user_message(c: &mut Criterion) {
+    let msg = user_message();
+    let json = serde_json::to_vec(&msg).unwrap_or_default();
+    let binary = msg.encode().unwrap_or_default();
+    let mut group = c.benchmark_group("user_message");
+    group.bench_function("json_encode", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&msg)))
+    });
+    group.bench_function("msgpack_encode", |b| b.iter(|| black_box(&msg).encode()));
+    group.bench_function("json_decode", |b| {
+        b.iter(|| serde_json::from_slice::<UserMessage>(black_box(&json)))
+    });
+    group.bench_function("msgpack_decode", |b| {
+        b.iter(|| UserMessage::decode(black_box(&binary)))
+    });
+    group.finish();
+}
+
+fn bench_bot_response(c: &mut Criterion) {
+    let response = bot_response();
+    let json = serde_json::to_vec(&response).unwrap_or_default();
+    let binary = response.encode().unwrap_or_default();
+    let mut group = c.benchmark_group("bot_response");
+    group.bench_function("json_encode", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&response)))
+    });
+    group.bench_function("msgpack_encode", |b| {
+        b.iter(|| black_box(&response).encode())
+    });
+    group.bench_function("json_decode", |b| {
+        b.iter(|| serde_json::from_slice::<BotResponse>(black_box(&json)))
+    });
+    group.bench_function("msgpack_decode", |b| {
+        b.iter(|| BotResponse::decode(black_box(&binary)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_user_message, bench_bot_response);
+criterion_main!(benches);
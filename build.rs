@@ -0,0 +1,88 @@
+//! Embeds build provenance for `version::build_info`. It describes the
+//! botlib checkout being compiled, not the binary that depends on it.
+//! Anything that cannot be determined, such as the commit of a crates.io
+//! build, is "unknown".
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::path::Path;
+use std::process::Command;
+
+const UNKNOWN: &str = "unknown";
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    run("git", args)
+}
+
+/// Reruns this script when the checked-out commit changes. The index is not
+/// watched: staging a file would otherwise rebuild the crate and restamp
+/// `BUILD_TIMESTAMP`. `GIT_DIRTY` therefore reflects the tree as of the last
+/// run. Paths that do not exist are skipped, as cargo would otherwise rerun
+/// on every build.
+fn watch_git() {
+    let Some(git_dir) = git(&["rev-parse", "--git-dir"]) else {
+        return;
+    };
+    let git_dir = Path::new(&git_dir);
+    let head = git_dir.join("HEAD");
+    let mut watched = vec![head.clone(), git_dir.join("packed-refs")];
+    if let Some(reference) = std::fs::read_to_string(&head)
+        .ok()
+        .and_then(|head| head.strip_prefix("ref:").map(|r| r.trim().to_string()))
+    {
+        watched.push(git_dir.join(reference));
+    }
+    for path in watched.iter().filter(|path| path.exists()) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
+
+fn build_timestamp() -> String {
+    let time = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<i64>().ok())
+        .and_then(|epoch| DateTime::<Utc>::from_timestamp(epoch, 0))
+        .unwrap_or_else(Utc::now);
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    watch_git();
+
+    let commit = git(&["rev-parse", "HEAD"]);
+    let short = git(&["rev-parse", "--short", "HEAD"]);
+    let dirty = commit.as_ref().and_then(|_| {
+        let status = Command::new("git")
+            .args(["status", "--porcelain"])
+            .output()
+            .ok()?;
+        status
+            .status
+            .success()
+            .then(|| (!status.stdout.is_empty()).to_string())
+    });
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+
+    for (key, value) in [
+        ("GIT_COMMIT_HASH", commit),
+        ("GIT_COMMIT_HASH_SHORT", short),
+        ("GIT_DIRTY", dirty),
+        ("BUILD_TIMESTAMP", Some(build_timestamp())),
+        ("RUSTC_VERSION", run(&rustc, &["--version"])),
+    ] {
+        println!(
+            "cargo:rustc-env={key}={}",
+            value.as_deref().unwrap_or(UNKNOWN)
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use botlib::http_client::LoggingInterceptor;
+use botlib::BotServerClient;
+use std::sync::Arc;
+use tracing_subscriber::EnvFilter;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let client = BotServerClient::new(std::env::var("BOTSERVER_URL").ok())
+        .with_interceptor(Arc::new(LoggingInterceptor));
+
+    match client.get::<serde_json::Value>("/health").await {
+        Ok(body) => tracing::info!(%body, "botserver is healthy"),
+        Err(e) => tracing::warn!(status_code = e.status_code(), error = %e, "health check failed"),
+    }
+}
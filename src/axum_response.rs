@@ -0,0 +1,385 @@
+use crate::branding::{render_error_page, PageError};
+use crate::error::BotError;
+use crate::limits::{format_limit_error_response, LimitExceeded};
+use crate::models::ApiResponse;
+use crate::problem::{ProblemDetails, PROBLEM_JSON_CONTENT_TYPE};
+use axum::body::Body;
+use axum::http::header::{
+    HeaderMap, HeaderValue, ACCEPT, CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER, WWW_AUTHENTICATE,
+};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use log::error;
+
+fn status(code: u16) -> StatusCode {
+    StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn with_retry_after(mut response: Response, retry_after_secs: Option<u64>) -> Response {
+    if let Some(secs) = retry_after_secs {
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(secs));
+    }
+    response
+}
+
+/// The quality `accept` gives `mime`: that of its own entry, else of its
+/// `type/*` entry, else of `*/*`, else 0.
+fn quality(accept: &str, mime: &str) -> f32 {
+    let kind = mime.split('/').next().unwrap_or_default();
+    accept
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let range = parts.next().unwrap_or_default();
+            let specificity = if range.eq_ignore_ascii_case(mime) {
+                2
+            } else if range
+                .strip_suffix("/*")
+                .is_some_and(|k| k.eq_ignore_ascii_case(kind))
+            {
+                1
+            } else if range == "*/*" {
+                0
+            } else {
+                return None;
+            };
+            let q = parts
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            Some((specificity, q))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map_or(0.0, |(_, q)| q)
+}
+
+/// True when the request's `Accept` ranks `text/html` above JSON, as
+/// browsers do. A missing header, `*/*` and API clients get JSON.
+#[must_use]
+pub fn prefers_html(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let json = quality(accept, "application/json").max(quality(accept, PROBLEM_JSON_CONTENT_TYPE));
+    quality(accept, "text/html") > json
+}
+
+/// `error` rendered as usual, or, for requests that `prefers_html`, with
+/// its body replaced by the branded `render_error_page`. The status and
+/// headers such as `Retry-After` are kept either way.
+pub fn negotiate_error<E>(request_headers: &HeaderMap, error: E) -> Response
+where
+    E: IntoResponse,
+    for<'a> &'a E: Into<PageError<'a>>,
+{
+    if !prefers_html(request_headers) {
+        return error.into_response();
+    }
+    let page = (&error).into();
+    let html = render_error_page(page.status_code(), page);
+    let mut response = error.into_response();
+    let headers = response.headers_mut();
+    headers.remove(CONTENT_LENGTH);
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+    *response.body_mut() = Body::from(html);
+    response
+}
+
+/// Renders the `ApiResponse` envelope with the error's status, plus
+/// `Retry-After` for rate limiting and `WWW-Authenticate` for auth failures.
+impl IntoResponse for BotError {
+    fn into_response(self) -> Response {
+        let status = status(self.status_code());
+        if status.is_server_error() {
+            error!("{} {}", status.as_u16(), self.display_chain());
+        }
+        let retry_after_secs = self.retry_after().map(|after| after.as_secs());
+        let auth = matches!(self.root(), Self::Auth(_));
+
+        let body: ApiResponse<()> = ApiResponse::from(self);
+        let mut response = with_retry_after((status, Json(body)).into_response(), retry_after_secs);
+        if auth {
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
+
+/// Renders `format_limit_error_response` with the limit's status code.
+impl IntoResponse for LimitExceeded {
+    fn into_response(self) -> Response {
+        let (code, body) = format_limit_error_response(&self);
+        let response = (
+            status(code),
+            [(CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+            body,
+        )
+            .into_response();
+        with_retry_after(response, self.retry_after_secs)
+    }
+}
+
+/// Renders the problem as `application/problem+json` with its status.
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status = status(self.status);
+        let retry_after_secs = self
+            .extensions
+            .get("retry_after_secs")
+            .and_then(serde_json::Value::as_u64);
+        let response = (
+            status,
+            [(
+                CONTENT_TYPE,
+                HeaderValue::from_static(PROBLEM_JSON_CONTENT_TYPE),
+            )],
+            Json(self),
+        )
+            .into_response();
+        with_retry_after(response, retry_after_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::BotResult;
+    use crate::limits::LimitType;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::routing::get;
+    use axum::Router;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    async fn missing_user() -> BotResult<Json<Value>> {
+        Err(BotError::not_found("User"))
+    }
+
+    async fn throttled() -> BotResult<Json<Value>> {
+        Err(BotError::rate_limited(30))
+    }
+
+    async fn unauthorized() -> BotResult<Json<Value>> {
+        Err(BotError::auth("token expired"))
+    }
+
+    async fn wrapped_throttled() -> BotResult<Json<Value>> {
+        Err(BotError::rate_limited(30).with_context("calling llm"))
+    }
+
+    async fn wrapped_unauthorized() -> BotResult<Json<Value>> {
+        Err(BotError::auth("token expired").with_context("loading inbox"))
+    }
+
+    async fn broken() -> BotResult<Json<Value>> {
+        Err(BotError::database("connection reset").with_context("loading bot"))
+    }
+
+    async fn ok() -> BotResult<Json<Value>> {
+        Ok(Json(json!({"id": 1})))
+    }
+
+    async fn upload_too_large() -> Result<&'static str, LimitExceeded> {
+        Err(LimitExceeded {
+            limit_type: LimitType::UploadSize,
+            current: 60,
+            maximum: 50,
+            retry_after_secs: None,
+        })
+    }
+
+    async fn too_many_calls() -> Result<&'static str, LimitExceeded> {
+        Err(LimitExceeded {
+            limit_type: LimitType::ApiCallsMinute,
+            current: 1001,
+            maximum: 1000,
+            retry_after_secs: Some(12),
+        })
+    }
+
+    async fn problem() -> ProblemDetails {
+        BotError::rate_limited(7).to_problem_with_base("https://x.test/p/", Some("/problem"))
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route("/problem", get(problem))
+            .route("/users/1", get(missing_user))
+            .route("/throttled", get(throttled))
+            .route("/private", get(unauthorized))
+            .route("/wrapped/throttled", get(wrapped_throttled))
+            .route("/wrapped/private", get(wrapped_unauthorized))
+            .route("/broken", get(broken))
+            .route("/ok", get(ok))
+            .route("/upload", get(upload_too_large))
+            .route("/calls", get(too_many_calls))
+    }
+
+    async fn call(uri: &str) -> (StatusCode, axum::http::HeaderMap, Value) {
+        let request = Request::get(uri).body(Body::empty()).unwrap_or_default();
+        let Ok(response) = router().oneshot(request).await;
+        let (parts, body) = response.into_parts();
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .unwrap_or_default();
+        let value = serde_json::from_slice(&bytes).unwrap_or_default();
+        (parts.status, parts.headers, value)
+    }
+
+    fn header<'a>(headers: &'a axum::http::HeaderMap, name: &str) -> Option<&'a str> {
+        headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn test_not_found_envelope() {
+        let (status, headers, body) = call("/users/1").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(header(&headers, "content-type"), Some("application/json"));
+        assert_eq!(
+            body,
+            json!({
+                "success": false,
+                "error": "User not found",
+                "code": "not_found",
+                "details": {"entity": "User"}
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_sets_retry_after() {
+        let (status, headers, body) = call("/throttled").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&headers, "retry-after"), Some("30"));
+        assert_eq!(body["code"], "rate_limited");
+    }
+
+    #[tokio::test]
+    async fn test_auth_sets_www_authenticate() {
+        let (status, headers, body) = call("/private").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(header(&headers, "www-authenticate"), Some("Bearer"));
+        assert_eq!(body["error"], "token expired");
+    }
+
+    #[tokio::test]
+    async fn test_context_keeps_retry_after_and_www_authenticate() {
+        let (status, headers, _) = call("/wrapped/throttled").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&headers, "retry-after"), Some("30"));
+
+        let (status, headers, _) = call("/wrapped/private").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(header(&headers, "www-authenticate"), Some("Bearer"));
+    }
+
+    #[tokio::test]
+    async fn test_server_error_and_success() {
+        let (status, headers, body) = call("/broken").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(headers.get("retry-after").is_none());
+        assert_eq!(body["code"], "database_error");
+
+        let (status, _, body) = call("/ok").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({"id": 1}));
+    }
+
+    #[tokio::test]
+    async fn test_limit_exceeded_status_mapping() {
+        let (status, headers, body) = call("/upload").await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(headers.get("retry-after").is_none());
+        assert_eq!(body["error"], "limit_exceeded");
+        assert_eq!(body["limit_type"], "upload_size");
+
+        let (status, headers, body) = call("/calls").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&headers, "retry-after"), Some("12"));
+        assert_eq!(body["error"], "rate_limit_exceeded");
+        assert_eq!(body["retry_after_secs"], 12);
+    }
+
+    fn accepting(accept: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Ok(value) = HeaderValue::from_str(accept) {
+            headers.insert(ACCEPT, value);
+        }
+        headers
+    }
+
+    #[test]
+    fn test_prefers_html() {
+        let browser = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+        assert!(prefers_html(&accepting(browser)));
+        assert!(prefers_html(&accepting("application/json;q=0.5, text/*")));
+        for accept in [
+            "",
+            "*/*",
+            "application/json",
+            "application/problem+json, text/html;q=0.9",
+            "text/html;q=0.5, application/*",
+        ] {
+            assert!(!prefers_html(&accepting(accept)), "{accept}");
+        }
+        assert!(!prefers_html(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_error() {
+        let exceeded = || LimitExceeded {
+            limit_type: LimitType::ApiCallsMinute,
+            current: 1001,
+            maximum: 1000,
+            retry_after_secs: Some(12),
+        };
+        let browser = accepting("text/html,*/*;q=0.8");
+        let response = negotiate_error(&browser, exceeded());
+        let (parts, body) = response.into_parts();
+        let html = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .map(|b| String::from_utf8_lossy(&b).into_owned())
+            .unwrap_or_default();
+        assert_eq!(parts.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            header(&parts.headers, "content-type"),
+            Some("text/html; charset=utf-8")
+        );
+        assert_eq!(header(&parts.headers, "retry-after"), Some("12"));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<p>Please try again in 12 seconds.</p>"));
+
+        let response = negotiate_error(&accepting("application/json"), BotError::auth("expired"));
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            header(response.headers(), "content-type"),
+            Some("application/json")
+        );
+        assert_eq!(
+            header(response.headers(), "www-authenticate"),
+            Some("Bearer")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_problem_details_content_type() {
+        let (status, headers, body) = call("/problem").await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            header(&headers, "content-type"),
+            Some(PROBLEM_JSON_CONTENT_TYPE)
+        );
+        assert_eq!(header(&headers, "retry-after"), Some("7"));
+        assert_eq!(body["type"], "https://x.test/p/rate-limited");
+        assert_eq!(body["instance"], "/problem");
+    }
+}
@@ -1,16 +1,77 @@
-
-use log::info;
+#[cfg(feature = "http-client")]
+mod assets;
+mod color;
+mod error_page;
+mod layers;
+#[cfg(feature = "http-client")]
+mod remote;
+mod render;
+mod report;
+mod theme;
+#[cfg(feature = "watch")]
+mod watch;
+
+#[cfg(feature = "http-client")]
+pub use assets::{fetch_asset, validate_assets, AssetReport, BrandAsset, MAX_ASSET_BYTES};
+pub use color::{Color, ParseColorError};
+pub use error_page::{render_error_page, PageError};
+pub use layers::{
+    env_var, BrandingLayers, BrandingProvenance, BrandingSource, PRODUCT_SEARCH_PATHS,
+};
+#[cfg(feature = "http-client")]
+pub use remote::{init_branding_remote, RemoteBranding, RemotePrecedence};
+pub use render::{ManifestIcon, WebManifest};
+pub use report::BrandingChange;
+pub use theme::{Theme, ThemeMode, MIN_TEXT_CONTRAST};
+#[cfg(feature = "watch")]
+pub use watch::{watch, BrandingWatcher};
+
+use crate::error::{BotError, BotResult, FieldError, ValidationErrors};
+use arc_swap::ArcSwap;
+use log::debug;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::path::Path;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
+
+/// The active config. An `ArcSwap` rather than a plain value so `watch` can
+/// replace it while readers hold on to the one they loaded.
+static BRANDING: OnceLock<ArcSwap<BrandingConfig>> = OnceLock::new();
 
-static BRANDING: OnceLock<BrandingConfig> = OnceLock::new();
+thread_local! {
+    /// The config `with_branding` put in place on this thread.
+    static OVERRIDE: RefCell<Option<Arc<BrandingConfig>>> = const { RefCell::new(None) };
+}
 
 const DEFAULT_PLATFORM_NAME: &str = "General Bots";
 const DEFAULT_PLATFORM_SHORT: &str = "GB";
 const DEFAULT_PLATFORM_DOMAIN: &str = "generalbots.com";
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+const DEFAULT_PRIMARY_COLOR: Color = Color::rgb(0x25, 0xd3, 0x66);
+const DEFAULT_SECONDARY_COLOR: Color = Color::rgb(0x07, 0x5e, 0x54);
+
+/// Every `BrandingConfig` field a `.product` file or `PLATFORM_*` variable
+/// can set, in declaration order.
+pub const BRANDING_FIELDS: [&str; 15] = [
+    "name",
+    "short_name",
+    "company",
+    "domain",
+    "support_email",
+    "logo_url",
+    "favicon_url",
+    "primary_color",
+    "secondary_color",
+    "footer_text",
+    "copyright",
+    "custom_css",
+    "terms_url",
+    "privacy_url",
+    "docs_url",
+];
+
+/// `Debug` and `Display` mask `support_email` and credentials in URLs, so
+/// the config can be logged as is.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BrandingConfig {
     pub name: String,
     pub short_name: String,
@@ -40,8 +101,8 @@ impl Default for BrandingConfig {
             support_email: Some("support@generalbots.com".to_string()),
             logo_url: None,
             favicon_url: None,
-            primary_color: Some("#25d366".to_string()),
-            secondary_color: Some("#075e54".to_string()),
+            primary_color: Some(DEFAULT_PRIMARY_COLOR.to_string()),
+            secondary_color: Some(DEFAULT_SECONDARY_COLOR.to_string()),
             footer_text: None,
             copyright: Some(format!(
                 "© {} pragmatismo.com.br. All rights reserved.",
@@ -57,192 +118,514 @@ impl Default for BrandingConfig {
 }
 
 impl BrandingConfig {
+    /// The branding from the standard `.product` locations, `PRODUCT_FILE`
+    /// and `PLATFORM_*` variables, layered as described on
+    /// `load_with_layers`.
     #[must_use]
     pub fn load() -> Self {
-        let search_paths = [
-            ".product",
-            "config/.product",
-            "/etc/botserver/.product",
-            "/opt/gbo/.product",
-        ];
+        let (config, provenance) = Self::load_with_layers(&BrandingLayers::from_env());
+        debug!("Branding: {config}\nSources:\n{provenance}");
+        config
+    }
 
-        for path in &search_paths {
-            if let Ok(config) = Self::load_from_file(path) {
-                info!("Loaded white-label branding from {path}: {}", config.name);
-                return config;
-            }
+    /// The branding in `path` on its own, with the fields it leaves out
+    /// unset and `is_white_label` derived as `load_with_layers` would.
+    ///
+    /// # Errors
+    /// Returns `BotError::Io` if `path` cannot be read.
+    pub fn load_from_file(path: impl AsRef<Path>) -> BotResult<Self> {
+        let mut config = Self::white_label_base();
+        let fields = read_product_file(path.as_ref())?;
+        let sets_short_name = fields.iter().any(|(key, _)| *key == "short_name");
+        for (key, value) in fields {
+            config.set_field(key, value);
         }
-
-        if let Ok(product_file) = std::env::var("PRODUCT_FILE") {
-            if let Ok(config) = Self::load_from_file(&product_file) {
-                info!(
-                    "Loaded white-label branding from PRODUCT_FILE={product_file}: {}",
-                    config.name
-                );
-                return config;
-            }
+        if !sets_short_name {
+            config.short_name = initials(&config.name);
         }
+        config.is_white_label = !config.diff_from_default().is_empty();
+        Ok(config)
+    }
 
-        let mut config = Self::default();
+    /// What a white-label deployment starts from: the default names, with
+    /// everything that identifies General Bots left unset.
+    fn white_label_base() -> Self {
+        Self {
+            name: DEFAULT_PLATFORM_NAME.to_string(),
+            short_name: DEFAULT_PLATFORM_SHORT.to_string(),
+            company: None,
+            domain: None,
+            support_email: None,
+            logo_url: None,
+            favicon_url: None,
+            primary_color: None,
+            secondary_color: None,
+            footer_text: None,
+            copyright: None,
+            custom_css: None,
+            terms_url: None,
+            privacy_url: None,
+            docs_url: None,
+            is_white_label: true,
+        }
+    }
 
-        if let Ok(name) = std::env::var("PLATFORM_NAME") {
-            config.name = name;
-            config.is_white_label = true;
+    /// The value of the field named `key`, one of `BRANDING_FIELDS`, or
+    /// `None` if it is unset or not a branding field.
+    #[must_use]
+    pub fn field(&self, key: &str) -> Option<&str> {
+        match key {
+            "name" => Some(&self.name),
+            "short_name" => Some(&self.short_name),
+            "company" => self.company.as_deref(),
+            "domain" => self.domain.as_deref(),
+            "support_email" => self.support_email.as_deref(),
+            "logo_url" => self.logo_url.as_deref(),
+            "favicon_url" => self.favicon_url.as_deref(),
+            "primary_color" => self.primary_color.as_deref(),
+            "secondary_color" => self.secondary_color.as_deref(),
+            "footer_text" => self.footer_text.as_deref(),
+            "copyright" => self.copyright.as_deref(),
+            "custom_css" => self.custom_css.as_deref(),
+            "terms_url" => self.terms_url.as_deref(),
+            "privacy_url" => self.privacy_url.as_deref(),
+            "docs_url" => self.docs_url.as_deref(),
+            _ => None,
         }
-        if let Ok(short) = std::env::var("PLATFORM_SHORT_NAME") {
-            config.short_name = short;
+    }
+
+    /// Sets the field named `key`, one of `BRANDING_FIELDS`; other keys are
+    /// ignored.
+    fn set_field(&mut self, key: &str, value: String) {
+        match key {
+            "name" => self.name = value,
+            "short_name" => self.short_name = value,
+            "company" => self.company = Some(value),
+            "domain" => self.domain = Some(value),
+            "support_email" => self.support_email = Some(value),
+            "logo_url" => self.logo_url = Some(value),
+            "favicon_url" => self.favicon_url = Some(value),
+            "primary_color" => self.primary_color = Some(value),
+            "secondary_color" => self.secondary_color = Some(value),
+            "footer_text" => self.footer_text = Some(value),
+            "copyright" => self.copyright = Some(value),
+            "custom_css" => self.custom_css = Some(value),
+            "terms_url" => self.terms_url = Some(value),
+            "privacy_url" => self.privacy_url = Some(value),
+            "docs_url" => self.docs_url = Some(value),
+            _ => {}
         }
-        if let Ok(company) = std::env::var("PLATFORM_COMPANY") {
-            config.company = Some(company);
+    }
+
+    /// Names are required, colors must be `#RGB`, `#RRGGBB` or `#RRGGBBAA`,
+    /// the support email needs a local part and a domain, and links must be
+    /// `http(s)://` URLs or absolute paths.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` listing every invalid field.
+    pub fn validate(&self) -> BotResult<()> {
+        let mut errors = ValidationErrors::new();
+        errors
+            .require("name", &self.name)
+            .require("short_name", &self.short_name);
+        if let Some(domain) = &self.domain {
+            errors.check(
+                is_domain(domain),
+                "domain",
+                "invalid_domain",
+                "domain must be a host name such as example.com",
+            );
         }
-        if let Ok(domain) = std::env::var("PLATFORM_DOMAIN") {
-            config.domain = Some(domain);
+        if let Some(email) = &self.support_email {
+            errors.check(
+                is_email(email),
+                "support_email",
+                "invalid_email",
+                "support_email must look like name@example.com",
+            );
         }
-        if let Ok(logo) = std::env::var("PLATFORM_LOGO_URL") {
-            config.logo_url = Some(logo);
+        for (field, color) in [
+            ("primary_color", &self.primary_color),
+            ("secondary_color", &self.secondary_color),
+        ] {
+            if let Some(color) = color.as_deref().filter(|c| Color::from_hex(c).is_none()) {
+                errors.push(invalid_color(field, color));
+            }
         }
-        if let Ok(color) = std::env::var("PLATFORM_PRIMARY_COLOR") {
-            config.primary_color = Some(color);
+        for (field, url) in [
+            ("logo_url", &self.logo_url),
+            ("favicon_url", &self.favicon_url),
+            ("terms_url", &self.terms_url),
+            ("privacy_url", &self.privacy_url),
+            ("docs_url", &self.docs_url),
+        ] {
+            if let Some(url) = url.as_deref().filter(|u| !is_link(u)) {
+                errors.push(
+                    FieldError::new(
+                        field,
+                        "invalid_url",
+                        format!("{field} must be an http(s) URL or an absolute path"),
+                    )
+                    .with_rejected_value(url),
+                );
+            }
         }
+        errors.into_result()
+    }
 
-        config
+    /// `primary_color` as a `Color`, or the General Bots green when it is
+    /// unset. Unlike `validate`, color names are accepted.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` with an `invalid_color` error if
+    /// `primary_color` is set but is not a color.
+    pub fn primary_color_parsed(&self) -> BotResult<Color> {
+        parse_color(
+            "primary_color",
+            self.primary_color.as_deref(),
+            DEFAULT_PRIMARY_COLOR,
+        )
     }
 
-    fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let path = Path::new(path);
-        if !path.exists() {
-            return Err("File not found".into());
-        }
+    /// `secondary_color` as a `Color`, or the General Bots teal when it is
+    /// unset. Unlike `validate`, color names are accepted.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` with an `invalid_color` error if
+    /// `secondary_color` is set but is not a color.
+    pub fn secondary_color_parsed(&self) -> BotResult<Color> {
+        parse_color(
+            "secondary_color",
+            self.secondary_color.as_deref(),
+            DEFAULT_SECONDARY_COLOR,
+        )
+    }
 
-        let content = std::fs::read_to_string(path)?;
+    /// `copyright`, or one for the current year naming the company, or
+    /// the platform when there is no company.
+    fn copyright_line(&self) -> String {
+        self.copyright.clone().unwrap_or_else(|| {
+            format!(
+                "© {} {}",
+                chrono::Utc::now().format("%Y"),
+                self.company.as_deref().unwrap_or(&self.name)
+            )
+        })
+    }
+
+    /// `footer_text`, or `Powered by <name>`.
+    fn footer_line(&self) -> String {
+        self.footer_text
+            .clone()
+            .unwrap_or_else(|| format!("Powered by {}", self.name))
+    }
 
-        if let Ok(config) = toml::from_str::<ProductFile>(&content) {
-            return Ok(config.into());
+    /// The config as a TOML `.product` file: fields in declaration order,
+    /// `None` fields left out.
+    ///
+    /// # Errors
+    /// Returns `BotError::Internal` if the config cannot be serialized.
+    pub fn to_toml_string(&self) -> BotResult<String> {
+        toml::to_string(&ProductFile::from(self))
+            .map_err(|e| BotError::internal(format!("Failed to serialize branding: {e}")))
+    }
+
+    /// Validate, then write the config to `path` as TOML. Keys that a TOML
+    /// file already at `path` holds but this crate does not know are kept
+    /// after the known ones; a file in the old `key=value` format is
+    /// replaced by TOML. The file is written beside `path` and renamed over
+    /// it, so a crash never leaves it half written.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` without touching the file if the
+    /// config is invalid, or `BotError::Io` if it cannot be read or written.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> BotResult<()> {
+        self.validate()?;
+        let path = path.as_ref();
+        let mut content = self.to_toml_string()?;
+        let unknown = unknown_keys(path)?;
+        if !unknown.is_empty() {
+            content.push_str(
+                &toml::to_string(&unknown).map_err(|e| {
+                    BotError::internal(format!("Failed to serialize branding: {e}"))
+                })?,
+            );
         }
+        write_atomically(path, &content)
+    }
+}
 
-        let mut config = Self {
-            is_white_label: true,
-            ..Self::default()
-        };
+/// Write `content` beside `path` and rename it over `path`, so a crash
+/// never leaves the file half written.
+fn write_atomically(path: &Path, content: &str) -> BotResult<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    std::fs::write(&temp, content)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
 
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
-                continue;
-            }
+/// Keys of a TOML product file that `ProductFile` does not read. Empty when
+/// there is no file or it is not a TOML product file, since the `key=value`
+/// format allows aliases that would otherwise be kept as unknown keys.
+fn unknown_keys(path: &Path) -> BotResult<toml::Table> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(toml::Table::new()),
+        Err(e) => return Err(e.into()),
+    };
+    if toml::from_str::<ProductFile>(&content).is_err() {
+        return Ok(toml::Table::new());
+    }
+    let mut table = toml::from_str::<toml::Table>(&content).unwrap_or_default();
+    table.retain(|key, _| !BRANDING_FIELDS.contains(&key));
+    Ok(table)
+}
 
-            if let Some((key, value)) = line.split_once('=') {
-                let key = key.trim().to_lowercase();
-                let value = value.trim().trim_matches('"').trim_matches('\'');
-
-                match key.as_str() {
-                    "name" | "platform_name" => config.name = value.to_string(),
-                    "short_name" | "short" => config.short_name = value.to_string(),
-                    "company" | "organization" => config.company = Some(value.to_string()),
-                    "domain" => config.domain = Some(value.to_string()),
-                    "support_email" | "email" => config.support_email = Some(value.to_string()),
-                    "logo_url" | "logo" => config.logo_url = Some(value.to_string()),
-                    "favicon_url" | "favicon" => config.favicon_url = Some(value.to_string()),
-                    "primary_color" | "color" => config.primary_color = Some(value.to_string()),
-                    "secondary_color" => config.secondary_color = Some(value.to_string()),
-                    "footer_text" | "footer" => config.footer_text = Some(value.to_string()),
-                    "copyright" => config.copyright = Some(value.to_string()),
-                    "custom_css" | "css" => config.custom_css = Some(value.to_string()),
-                    "terms_url" | "terms" => config.terms_url = Some(value.to_string()),
-                    "privacy_url" | "privacy" => config.privacy_url = Some(value.to_string()),
-                    "docs_url" | "docs" => config.docs_url = Some(value.to_string()),
-                    _ => {}
-                }
-            }
+/// The fields `path` sets, in file order. TOML is tried first; files that
+/// are not TOML product files are read as `key=value` lines, where the keys
+/// may use the older aliases.
+fn read_product_file(path: &Path) -> BotResult<Vec<(&'static str, String)>> {
+    let content = std::fs::read_to_string(path)?;
+
+    if let Ok(file) = toml::from_str::<ProductFile>(&content) {
+        return Ok(file.into_fields());
+    }
+
+    let mut fields = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
         }
 
-        Ok(config)
+        if let Some((key, value)) = line.split_once('=') {
+            let key = match key.trim().to_lowercase().as_str() {
+                "name" | "platform_name" => "name",
+                "short_name" | "short" => "short_name",
+                "company" | "organization" => "company",
+                "domain" => "domain",
+                "support_email" | "email" => "support_email",
+                "logo_url" | "logo" => "logo_url",
+                "favicon_url" | "favicon" => "favicon_url",
+                "primary_color" | "color" => "primary_color",
+                "secondary_color" => "secondary_color",
+                "footer_text" | "footer" => "footer_text",
+                "copyright" => "copyright",
+                "custom_css" | "css" => "custom_css",
+                "terms_url" | "terms" => "terms_url",
+                "privacy_url" | "privacy" => "privacy_url",
+                "docs_url" | "docs" => "docs_url",
+                _ => continue,
+            };
+            let value = value.trim().trim_matches('"').trim_matches('\'');
+            fields.push((key, value.to_string()));
+        }
     }
+
+    Ok(fields)
+}
+
+/// `Acme Bots` -> `AB`, the short name for a file that names the platform
+/// without giving one.
+fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .map(|w| w.chars().next().unwrap_or('X'))
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn parse_color(field: &str, value: Option<&str>, default: Color) -> BotResult<Color> {
+    value.map_or(Ok(default), |value| {
+        value.parse().map_err(|_| {
+            let mut errors = ValidationErrors::new();
+            errors.push(invalid_color(field, value));
+            BotError::ValidationFields(errors)
+        })
+    })
+}
+
+fn invalid_color(field: &str, value: &str) -> FieldError {
+    FieldError::new(
+        field,
+        "invalid_color",
+        format!("{field} must be a hex color such as #25d366"),
+    )
+    .with_rejected_value(value)
+}
+
+fn is_email(email: &str) -> bool {
+    email
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && is_domain(domain))
 }
 
-#[derive(Debug, Deserialize)]
+fn is_domain(domain: &str) -> bool {
+    domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'))
+}
+
+fn is_link(url: &str) -> bool {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"));
+    let valid = rest.map_or_else(
+        || url.starts_with('/') && !url.starts_with("//"),
+        |rest| !rest.is_empty(),
+    );
+    valid && !url.chars().any(char::is_whitespace)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct ProductFile {
     name: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     short_name: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     company: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     domain: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     support_email: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     logo_url: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     favicon_url: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     primary_color: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     secondary_color: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     footer_text: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     copyright: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     custom_css: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     terms_url: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     privacy_url: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     docs_url: Option<String>,
 }
 
-impl From<ProductFile> for BrandingConfig {
-    fn from(pf: ProductFile) -> Self {
-        let short_name = pf.short_name.unwrap_or_else(|| {
-            pf.name
-                .split_whitespace()
-                .map(|w| w.chars().next().unwrap_or('X'))
-                .collect::<String>()
-                .to_uppercase()
-        });
+impl ProductFile {
+    /// The fields this file sets, as `(key, value)` in `BRANDING_FIELDS`
+    /// order.
+    fn into_fields(self) -> Vec<(&'static str, String)> {
+        let values = [
+            Some(self.name),
+            self.short_name,
+            self.company,
+            self.domain,
+            self.support_email,
+            self.logo_url,
+            self.favicon_url,
+            self.primary_color,
+            self.secondary_color,
+            self.footer_text,
+            self.copyright,
+            self.custom_css,
+            self.terms_url,
+            self.privacy_url,
+            self.docs_url,
+        ];
+        BRANDING_FIELDS
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect()
+    }
+}
 
+impl From<&BrandingConfig> for ProductFile {
+    fn from(config: &BrandingConfig) -> Self {
         Self {
-            name: pf.name,
-            short_name,
-            company: pf.company,
-            domain: pf.domain,
-            support_email: pf.support_email,
-            logo_url: pf.logo_url,
-            favicon_url: pf.favicon_url,
-            primary_color: pf.primary_color,
-            secondary_color: pf.secondary_color,
-            footer_text: pf.footer_text,
-            copyright: pf.copyright,
-            custom_css: pf.custom_css,
-            terms_url: pf.terms_url,
-            privacy_url: pf.privacy_url,
-            docs_url: pf.docs_url,
-            is_white_label: true,
+            name: config.name.clone(),
+            short_name: Some(config.short_name.clone()),
+            company: config.company.clone(),
+            domain: config.domain.clone(),
+            support_email: config.support_email.clone(),
+            logo_url: config.logo_url.clone(),
+            favicon_url: config.favicon_url.clone(),
+            primary_color: config.primary_color.clone(),
+            secondary_color: config.secondary_color.clone(),
+            footer_text: config.footer_text.clone(),
+            copyright: config.copyright.clone(),
+            custom_css: config.custom_css.clone(),
+            terms_url: config.terms_url.clone(),
+            privacy_url: config.privacy_url.clone(),
+            docs_url: config.docs_url.clone(),
         }
     }
 }
 
+fn active() -> &'static ArcSwap<BrandingConfig> {
+    BRANDING.get_or_init(|| ArcSwap::from_pointee(BrandingConfig::load()))
+}
+
+fn set_active(config: BrandingConfig) {
+    let config = Arc::new(config);
+    BRANDING
+        .get_or_init(|| ArcSwap::new(Arc::clone(&config)))
+        .store(config);
+}
 
+/// Load the branding and make it the active config, replacing any loaded
+/// earlier.
 pub fn init_branding() {
-    let config = BrandingConfig::load();
-    let _ = BRANDING.set(config);
+    set_active(BrandingConfig::load());
+}
+
+/// Make `config` the active config, replacing any loaded earlier, without
+/// reading the filesystem or environment.
+pub fn init_branding_with(config: BrandingConfig) {
+    set_active(config);
+}
+
+/// Run `f` with `config` as what `branding` and the helpers built on it
+/// return on this thread, then restore what was there before, even if `f`
+/// panics. Other threads, including the workers of a multi-threaded async
+/// runtime, keep seeing the active config.
+pub fn with_branding<R>(config: BrandingConfig, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Arc<BrandingConfig>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            OVERRIDE.set(self.0.take());
+        }
+    }
+
+    let restore = Restore(OVERRIDE.replace(Some(Arc::new(config))));
+    let result = f();
+    drop(restore);
+    result
 }
 
+/// The config `with_branding` set on this thread, or else the active one:
+/// the last one set by an `init_branding` function or `watch`, loaded from
+/// the filesystem and environment on first use only when nothing was set.
+/// Hold on to it for as long as one consistent view is needed; a reload
+/// only affects later calls.
 #[must_use]
-pub fn branding() -> &'static BrandingConfig {
-    BRANDING.get_or_init(BrandingConfig::load)
+pub fn branding() -> Arc<BrandingConfig> {
+    OVERRIDE
+        .with_borrow(Option::clone)
+        .unwrap_or_else(|| active().load_full())
 }
 
 #[must_use]
-pub fn platform_name() -> &'static str {
-    &branding().name
+pub fn platform_name() -> String {
+    branding().name.clone()
 }
 
 #[must_use]
-pub fn platform_short() -> &'static str {
-    &branding().short_name
+pub fn platform_short() -> String {
+    branding().short_name.clone()
 }
 
 #[must_use]
@@ -252,21 +635,12 @@ pub fn is_white_label() -> bool {
 
 #[must_use]
 pub fn copyright_text() -> String {
-    branding().copyright.clone().unwrap_or_else(|| {
-        format!(
-            "© {} {}",
-            chrono::Utc::now().format("%Y"),
-            branding().company.as_deref().unwrap_or(&branding().name)
-        )
-    })
+    branding().copyright_line()
 }
 
 #[must_use]
 pub fn footer_text() -> String {
-    branding()
-        .footer_text
-        .clone()
-        .unwrap_or_else(|| format!("Powered by {}", platform_name()))
+    branding().footer_line()
 }
 
 #[must_use]
@@ -291,4 +665,278 @@ mod tests {
         let name = platform_name();
         assert!(!name.is_empty());
     }
+
+    #[test]
+    fn test_with_branding_in_sequence() {
+        let first = with_branding(acme(), || (platform_name(), platform_short()));
+        let second = with_branding(BrandingConfig::default(), || {
+            (platform_name(), log_prefix(), is_white_label())
+        });
+        assert_eq!(first, ("Acme Bots".to_string(), "AB".to_string()));
+        assert_eq!(
+            second,
+            ("General Bots".to_string(), "[GB]".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn test_with_branding_nests_and_restores() {
+        let globex = BrandingConfig {
+            name: "Globex".to_string(),
+            ..acme()
+        };
+        with_branding(acme(), || {
+            assert_eq!(
+                with_branding(globex, || (platform_name(), footer_text())),
+                ("Globex".to_string(), "Powered by Globex".to_string())
+            );
+            assert_eq!(platform_name(), "Acme Bots");
+
+            let panicked = std::panic::catch_unwind(|| {
+                with_branding(BrandingConfig::default(), || panic!("inside override"))
+            });
+            assert!(panicked.is_err());
+            assert_eq!(platform_name(), "Acme Bots");
+            assert_eq!(
+                std::thread::spawn(|| branding().name.clone()).join().ok(),
+                Some(active().load().name.clone())
+            );
+        });
+        assert!(OVERRIDE.with_borrow(Option::is_none));
+    }
+
+    fn acme() -> BrandingConfig {
+        BrandingConfig {
+            name: "Acme Bots".to_string(),
+            short_name: "AB".to_string(),
+            company: Some("Acme Inc".to_string()),
+            domain: Some("acme.example".to_string()),
+            support_email: Some("help@acme.example".to_string()),
+            logo_url: Some("/static/logo.svg".to_string()),
+            favicon_url: None,
+            primary_color: Some("#ff6600".to_string()),
+            secondary_color: None,
+            footer_text: None,
+            copyright: None,
+            custom_css: None,
+            terms_url: Some("https://acme.example/terms".to_string()),
+            privacy_url: None,
+            docs_url: None,
+            is_white_label: true,
+        }
+    }
+
+    #[test]
+    fn test_to_toml_string_skips_none() {
+        assert_eq!(
+            acme().to_toml_string().unwrap_or_default(),
+            "name = \"Acme Bots\"\n\
+             short_name = \"AB\"\n\
+             company = \"Acme Inc\"\n\
+             domain = \"acme.example\"\n\
+             support_email = \"help@acme.example\"\n\
+             logo_url = \"/static/logo.svg\"\n\
+             primary_color = \"#ff6600\"\n\
+             terms_url = \"https://acme.example/terms\"\n"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(BrandingConfig::default().validate().is_ok());
+        assert!(acme().validate().is_ok());
+
+        let broken = BrandingConfig {
+            short_name: " ".to_string(),
+            domain: Some("https://acme.example".to_string()),
+            support_email: Some("help".to_string()),
+            primary_color: Some("orange".to_string()),
+            secondary_color: Some("#12345".to_string()),
+            logo_url: Some("javascript:alert(1)".to_string()),
+            docs_url: Some("//cdn.example/docs".to_string()),
+            ..acme()
+        };
+        let fields: Vec<(String, String)> = match broken.validate() {
+            Err(BotError::ValidationFields(errors)) => errors
+                .errors()
+                .iter()
+                .map(|e| (e.field.clone(), e.code.clone()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let expected = [
+            ("short_name", "required"),
+            ("domain", "invalid_domain"),
+            ("support_email", "invalid_email"),
+            ("primary_color", "invalid_color"),
+            ("secondary_color", "invalid_color"),
+            ("logo_url", "invalid_url"),
+            ("docs_url", "invalid_url"),
+        ]
+        .map(|(field, code)| (field.to_string(), code.to_string()));
+        assert_eq!(fields, expected);
+    }
+
+    #[test]
+    fn test_parsed_colors() {
+        let config = BrandingConfig {
+            secondary_color: Some("Navy".to_string()),
+            ..acme()
+        };
+        assert_eq!(
+            config.primary_color_parsed().ok(),
+            Some(Color::rgb(0xff, 0x66, 0x00))
+        );
+        assert_eq!(
+            config.secondary_color_parsed().ok(),
+            Some(Color::rgb(0x00, 0x00, 0x80))
+        );
+        assert_eq!(
+            acme().secondary_color_parsed().ok(),
+            Some(DEFAULT_SECONDARY_COLOR)
+        );
+
+        let broken = BrandingConfig {
+            primary_color: Some("#12345".to_string()),
+            ..acme()
+        };
+        let rejected = match broken.primary_color_parsed() {
+            Err(BotError::ValidationFields(errors)) => errors
+                .errors()
+                .iter()
+                .map(|e| (e.field.clone(), e.code.clone(), e.rejected_value.clone()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        assert_eq!(
+            rejected,
+            [(
+                "primary_color".to_string(),
+                "invalid_color".to_string(),
+                Some(serde_json::json!("#12345"))
+            )]
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempfile::tempdir().ok();
+        let path = dir.as_ref().map(|d| d.path().join(".product"));
+        let path = path.unwrap_or_default();
+        assert!(acme().save_to_file(&path).is_ok());
+        let mut loaded = BrandingConfig::load_from_file(&path).ok();
+        assert_eq!(
+            loaded
+                .as_ref()
+                .map(|c| (c.name.as_str(), c.favicon_url.clone())),
+            Some(("Acme Bots", None))
+        );
+
+        if let Some(config) = loaded.as_mut() {
+            config.secondary_color = Some("#003366".to_string());
+            config.logo_url = None;
+            assert!(config.save_to_file(&path).is_ok());
+        }
+        let reloaded = BrandingConfig::load_from_file(&path).ok();
+        assert_eq!(
+            reloaded
+                .as_ref()
+                .map(BrandingConfig::to_toml_string)
+                .and_then(Result::ok),
+            loaded
+                .as_ref()
+                .map(BrandingConfig::to_toml_string)
+                .and_then(Result::ok)
+        );
+        assert!(reloaded.is_some_and(|c| c.logo_url.is_none() && c.is_white_label));
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        assert!(!Path::new(&temp).exists());
+    }
+
+    #[test]
+    fn test_save_keeps_unknown_keys() {
+        let dir = tempfile::tempdir().ok();
+        let path = dir.as_ref().map(|d| d.path().join(".product"));
+        let path = path.unwrap_or_default();
+        let original = "name = \"Acme Bots\"\n\
+                        logo_url = \"/old.png\"\n\
+                        theme = \"dark\"\n\
+                        \n\
+                        [features]\n\
+                        chat = true\n";
+        assert!(std::fs::write(&path, original).is_ok());
+        assert!(acme().save_to_file(&path).is_ok());
+
+        let saved = std::fs::read_to_string(&path).unwrap_or_default();
+        let table = toml::from_str::<toml::Table>(&saved).unwrap_or_default();
+        assert_eq!(table.get("theme").and_then(|v| v.as_str()), Some("dark"));
+        assert_eq!(
+            table
+                .get("features")
+                .and_then(|v| v.get("chat"))
+                .and_then(toml::Value::as_bool),
+            Some(true)
+        );
+        assert_eq!(
+            table.get("logo_url").and_then(|v| v.as_str()),
+            Some("/static/logo.svg")
+        );
+        assert!(saved.starts_with(&acme().to_toml_string().unwrap_or_default()));
+    }
+
+    #[test]
+    fn test_save_upgrades_key_value_file() {
+        let dir = tempfile::tempdir().ok();
+        let path = dir.as_ref().map(|d| d.path().join(".product"));
+        let path = path.unwrap_or_default();
+        let legacy = "# legacy branding\n\
+                      platform_name = Acme Bots\n\
+                      short = AB\n\
+                      color = '#ff6600'\n\
+                      email = help@acme.example\n";
+        assert!(std::fs::write(&path, legacy).is_ok());
+        let mut config = BrandingConfig::load_from_file(&path).unwrap_or_default();
+        assert_eq!(config.name, "Acme Bots");
+        assert_eq!(config.primary_color.as_deref(), Some("#ff6600"));
+        config.company = Some("Acme Inc".to_string());
+        assert!(config.save_to_file(&path).is_ok());
+
+        let saved = std::fs::read_to_string(&path).unwrap_or_default();
+        let table = toml::from_str::<toml::Table>(&saved).unwrap_or_default();
+        assert!(!table.contains_key("platform_name"));
+        assert!(!table.contains_key("color"));
+        assert_eq!(
+            table.get("name").and_then(|v| v.as_str()),
+            Some("Acme Bots")
+        );
+        let reloaded = BrandingConfig::load_from_file(&path).ok();
+        assert_eq!(
+            reloaded
+                .as_ref()
+                .map(BrandingConfig::to_toml_string)
+                .and_then(Result::ok),
+            config.to_toml_string().ok()
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_is_not_written() {
+        let dir = tempfile::tempdir().ok();
+        let path = dir.as_ref().map(|d| d.path().join(".product"));
+        let path = path.unwrap_or_default();
+        assert!(std::fs::write(&path, "name = \"Acme Bots\"\n").is_ok());
+        let broken = BrandingConfig {
+            primary_color: Some("not a color".to_string()),
+            ..acme()
+        };
+        assert!(matches!(
+            broken.save_to_file(&path),
+            Err(BotError::ValidationFields(_))
+        ));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap_or_default(),
+            "name = \"Acme Bots\"\n"
+        );
+    }
 }
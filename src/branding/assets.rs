@@ -0,0 +1,441 @@
+use super::BrandingConfig;
+use crate::error::{BotError, BotResult};
+use crate::http_client::BotServerClient;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+
+/// The largest logo or favicon `validate_assets` accepts, in bytes.
+pub const MAX_ASSET_BYTES: u64 = 512 * 1024;
+
+const SVG_MIME: &str = "image/svg+xml";
+
+/// An image fetched with `fetch_asset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrandAsset {
+    pub bytes: Vec<u8>,
+    /// The response's `image/*` content type, without parameters.
+    pub mime: String,
+    /// Width and height in pixels, read from PNG and GIF headers.
+    pub dimensions: Option<(u32, u32)>,
+}
+
+impl BrandAsset {
+    /// The asset as a `data:` URI, for email clients that block remote
+    /// images. SVGs are embedded without their `<script>` elements, `on*`
+    /// event handler attributes and `javascript:` links.
+    #[must_use]
+    pub fn to_data_uri(&self) -> String {
+        let encoded = if self.mime == SVG_MIME {
+            BASE64_STANDARD.encode(sanitize_svg(&String::from_utf8_lossy(&self.bytes)))
+        } else {
+            BASE64_STANDARD.encode(&self.bytes)
+        };
+        format!("data:{};base64,{encoded}", self.mime)
+    }
+}
+
+/// The outcome of `validate_assets` for each asset; `None` when the
+/// config does not set it.
+#[derive(Debug)]
+pub struct AssetReport {
+    pub logo: Option<BotResult<BrandAsset>>,
+    pub favicon: Option<BotResult<BrandAsset>>,
+}
+
+impl AssetReport {
+    /// True when every asset the config sets was fetched.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        [&self.logo, &self.favicon]
+            .into_iter()
+            .flatten()
+            .all(Result::is_ok)
+    }
+}
+
+/// GETs `url` as a brand asset with `client` stripped of its credentials,
+/// so nothing meant for the bot server reaches the asset's host.
+///
+/// # Errors
+/// Returns `BotError::Validation` if the body exceeds `max_bytes`, which
+/// aborts the download, if it is empty or if the content type is not
+/// `image/*`, and the client's error if the request fails.
+pub async fn fetch_asset(
+    client: &BotServerClient,
+    url: &str,
+    max_bytes: u64,
+) -> BotResult<BrandAsset> {
+    let response = client
+        .clone()
+        .without_auth()
+        .with_base_url(url)
+        .with_max_response_bytes(max_bytes)
+        .get_response("", HeaderMap::new())
+        .await?;
+    let mime = response
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    if !mime.starts_with("image/") {
+        return Err(BotError::validation(format!(
+            "{url} is not an image: content type {mime:?}"
+        )));
+    }
+    if response.body.is_empty() {
+        return Err(BotError::validation(format!("{url} is empty")));
+    }
+    Ok(BrandAsset {
+        dimensions: dimensions(&response.body),
+        bytes: response.body,
+        mime,
+    })
+}
+
+/// Fetches the logo and favicon of `config` concurrently, each capped at
+/// `MAX_ASSET_BYTES`. Paths are resolved against `domain`; a path without
+/// a domain, or a value that is not a link, is a validation error.
+pub async fn validate_assets(config: &BrandingConfig, client: &BotServerClient) -> AssetReport {
+    let check = |field: &'static str, url: Option<&str>| {
+        let resolved = url.map(|url| {
+            config
+                .absolute_link(Some(url))
+                .ok_or_else(|| BotError::validation(format!("{field} {url:?} cannot be fetched")))
+        });
+        async move {
+            match resolved? {
+                Ok(url) => Some(
+                    fetch_asset(client, &url, MAX_ASSET_BYTES)
+                        .await
+                        .map_err(|e| e.with_context(format!("Invalid {field}"))),
+                ),
+                Err(e) => Some(Err(e)),
+            }
+        }
+    };
+    let (logo, favicon) = futures_util::future::join(
+        check("logo_url", config.logo_url.as_deref()),
+        check("favicon_url", config.favicon_url.as_deref()),
+    )
+    .await;
+    AssetReport { logo, favicon }
+}
+
+fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let le = |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?));
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") && bytes.get(12..16) == Some(b"IHDR") {
+        return Some((be(16)?, be(20)?));
+    }
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some((u32::from(le(6)?), u32::from(le(8)?)));
+    }
+    None
+}
+
+fn sanitize_svg(svg: &str) -> String {
+    strip_unsafe_attributes(&strip_scripts(svg))
+}
+
+/// `svg` without its `<script>` elements, self-closing or not. An
+/// unclosed script drops the rest of the document.
+fn strip_scripts(svg: &str) -> String {
+    let lower = svg.to_ascii_lowercase();
+    let mut out = String::with_capacity(svg.len());
+    let mut rest = 0;
+    while let Some(start) = find_script(&lower, rest) {
+        out.push_str(&svg[rest..start]);
+        let Some(open_end) = lower[start..].find('>').map(|i| start + i + 1) else {
+            return out;
+        };
+        rest = if lower[..open_end].ends_with("/>") {
+            open_end
+        } else {
+            let Some(close) = lower[open_end..].find("</script") else {
+                return out;
+            };
+            let close = open_end + close;
+            match lower[close..].find('>') {
+                Some(i) => close + i + 1,
+                None => return out,
+            }
+        };
+    }
+    out.push_str(&svg[rest..]);
+    out
+}
+
+/// The start of the next `<script` tag at or after `from`, not matching
+/// longer names such as `<scripts`.
+fn find_script(lower: &str, from: usize) -> Option<usize> {
+    let mut at = from;
+    loop {
+        let start = at + lower[at..].find("<script")?;
+        let next = lower[start + "<script".len()..].chars().next();
+        if next.is_none_or(|c| c.is_ascii_whitespace() || matches!(c, '>' | '/')) {
+            return Some(start);
+        }
+        at = start + 1;
+    }
+}
+
+/// `svg` with every start tag rewritten without the attributes
+/// `is_safe_attribute` rejects. An unclosed tag drops the rest of the
+/// document.
+fn strip_unsafe_attributes(svg: &str) -> String {
+    let bytes = svg.as_bytes();
+    let mut out = String::with_capacity(svg.len());
+    let mut copied = 0;
+    let mut at = 0;
+    while let Some(offset) = svg[at..].find('<') {
+        let start = at + offset;
+        at = start + 1;
+        if !bytes.get(at).is_some_and(u8::is_ascii_alphabetic) {
+            continue;
+        }
+        out.push_str(&svg[copied..start]);
+        let Some((tag, end)) = clean_tag(svg, start) else {
+            return out;
+        };
+        out.push_str(&tag);
+        copied = end;
+        at = end;
+    }
+    out.push_str(&svg[copied..]);
+    out
+}
+
+/// The start tag at `start` with only its safe attributes, and the index
+/// just past it.
+fn clean_tag(svg: &str, start: usize) -> Option<(String, usize)> {
+    let bytes = svg.as_bytes();
+    let is_space = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_whitespace);
+    let ends_name = |i: usize, stops: &[u8]| {
+        bytes
+            .get(i)
+            .is_none_or(|b| b.is_ascii_whitespace() || stops.contains(b))
+    };
+    let mut i = start + 1;
+    while !ends_name(i, b"/>") {
+        i += 1;
+    }
+    let mut tag = svg[start..i].to_string();
+    loop {
+        while is_space(i) {
+            i += 1;
+        }
+        match bytes.get(i)? {
+            b'>' => {
+                tag.push('>');
+                return Some((tag, i + 1));
+            }
+            b'/' => {
+                tag.push_str(" /");
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let name_start = i;
+        while !ends_name(i, b"=/>") {
+            i += 1;
+        }
+        let name = &svg[name_start..i];
+        let mut value = "";
+        let mut j = i;
+        while is_space(j) {
+            j += 1;
+        }
+        if bytes.get(j) == Some(&b'=') {
+            j += 1;
+            while is_space(j) {
+                j += 1;
+            }
+            let value_start = j;
+            match bytes.get(j)? {
+                quote @ (b'"' | b'\'') => {
+                    let close = svg[j + 1..].find(char::from(*quote))?;
+                    j += close + 2;
+                    value = &svg[value_start + 1..j - 1];
+                }
+                _ => {
+                    while !ends_name(j, b">") {
+                        j += 1;
+                    }
+                    value = &svg[value_start..j];
+                }
+            }
+            i = j;
+        }
+        if is_safe_attribute(name, value) {
+            tag.push(' ');
+            tag.push_str(&svg[name_start..i]);
+        }
+    }
+}
+
+/// False for `on*` event handlers and for links to `javascript:` URLs.
+fn is_safe_attribute(name: &str, value: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    if name.starts_with("on") {
+        return false;
+    }
+    if name != "href" && !name.ends_with(":href") {
+        return true;
+    }
+    let link: String = value
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect();
+    !link.to_ascii_lowercase().starts_with("javascript:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::AuthScheme;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const PNG_2X3: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x02\0\0\0\x03\x08\x06\0\0\0";
+
+    async fn serve(server: &MockServer, route: &str, body: &[u8], mime: &str) {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body.to_vec(), mime))
+            .mount(server)
+            .await;
+    }
+
+    fn client() -> BotServerClient {
+        BotServerClient::new(None)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_png() {
+        let server = MockServer::start().await;
+        serve(&server, "/logo.png", PNG_2X3, "image/png").await;
+        let url = format!("{}/logo.png", server.uri());
+
+        let asset = fetch_asset(&client(), &url, 1024).await.ok();
+        assert_eq!(
+            asset.as_ref().map(|a| (a.mime.as_str(), a.dimensions)),
+            Some(("image/png", Some((2, 3))))
+        );
+        assert_eq!(
+            asset.map(|a| a.to_data_uri()),
+            Some(format!(
+                "data:image/png;base64,{}",
+                BASE64_STANDARD.encode(PNG_2X3)
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_and_wrong_type() {
+        let server = MockServer::start().await;
+        serve(&server, "/big.png", &[0; 4096], "image/png").await;
+        serve(&server, "/page", b"<html></html>", "text/html").await;
+        serve(&server, "/empty.png", b"", "image/png").await;
+
+        for (route, expected) in [
+            ("/big.png", "exceeded"),
+            ("/page", "not an image"),
+            ("/empty.png", "is empty"),
+        ] {
+            let url = format!("{}{route}", server.uri());
+            let result = fetch_asset(&client(), &url, 1024).await;
+            assert!(
+                matches!(&result, Err(BotError::Validation(msg)) if msg.contains(expected)),
+                "{route}: {result:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_svg_scripts_are_stripped() {
+        let server = MockServer::start().await;
+        let svg = "<svg xmlns=\"http://www.w3.org/2000/svg\"><SCRIPT type=\"text/javascript\">alert(1)</script >\
+                   <circle r=\"4\"/><script href=\"x.js\"/><scripts/></svg>";
+        serve(
+            &server,
+            "/logo.svg",
+            svg.as_bytes(),
+            "image/svg+xml; charset=utf-8",
+        )
+        .await;
+        let url = format!("{}/logo.svg", server.uri());
+
+        let asset = fetch_asset(&client(), &url, 1024).await.ok();
+        assert_eq!(
+            asset.as_ref().map(|a| (a.mime.as_str(), a.dimensions)),
+            Some((SVG_MIME, None))
+        );
+        let uri = asset.map(|a| a.to_data_uri()).unwrap_or_default();
+        let embedded = uri
+            .strip_prefix("data:image/svg+xml;base64,")
+            .and_then(|b| BASE64_STANDARD.decode(b).ok())
+            .and_then(|b| String::from_utf8(b).ok());
+        assert_eq!(
+            embedded.as_deref(),
+            Some("<svg xmlns=\"http://www.w3.org/2000/svg\"><circle r=\"4\" /><scripts /></svg>")
+        );
+        assert_eq!(strip_scripts("<svg><script>alert(1)"), "<svg>");
+    }
+
+    #[test]
+    fn test_svg_event_handlers_and_javascript_links_are_stripped() {
+        let svg = "<svg onload=\"alert(1)\" width='10'>\
+                   <a xlink:href=\" JavaScript:alert(2)\" href=/home><rect ONCLICK=alert(3) x=1 /></a>\
+                   <!-- <b> --></svg>";
+        assert_eq!(
+            sanitize_svg(svg),
+            "<svg width='10'><a href=/home><rect x=1 /></a><!-- <b> --></svg>"
+        );
+        assert_eq!(sanitize_svg("<svg><rect x=\"1"), "<svg>");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_sends_no_credentials() {
+        let server = MockServer::start().await;
+        serve(&server, "/logo.png", PNG_2X3, "image/png").await;
+        let url = format!("{}/logo.png", server.uri());
+        let client = client().with_auth(AuthScheme::bearer("bot-server-token"));
+
+        assert!(fetch_asset(&client, &url, 1024).await.is_ok());
+        let requests = server.received_requests().await.unwrap_or_default();
+        assert_eq!(requests.len(), 1);
+        assert!(requests
+            .iter()
+            .all(|r| !r.headers.contains_key("authorization")));
+    }
+
+    #[tokio::test]
+    async fn test_validate_assets() {
+        let server = MockServer::start().await;
+        serve(&server, "/logo.png", PNG_2X3, "image/png").await;
+        serve(&server, "/favicon.ico", b"<html></html>", "text/html").await;
+        let config = BrandingConfig {
+            logo_url: Some(format!("{}/logo.png", server.uri())),
+            favicon_url: Some(format!("{}/favicon.ico", server.uri())),
+            ..BrandingConfig::white_label_base()
+        };
+
+        let report = validate_assets(&config, &client()).await;
+        assert!(matches!(&report.logo, Some(Ok(a)) if a.dimensions == Some((2, 3))));
+        assert!(matches!(&report.favicon, Some(Err(e)) if e.to_string() == "Invalid favicon_url"));
+        assert!(!report.is_ok());
+
+        let config = BrandingConfig {
+            logo_url: Some("/static/logo.png".to_string()),
+            domain: None,
+            favicon_url: None,
+            ..config
+        };
+        let report = validate_assets(&config, &client()).await;
+        assert!(matches!(&report.logo, Some(Err(BotError::Validation(_)))));
+        assert!(report.favicon.is_none());
+    }
+}
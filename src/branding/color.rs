@@ -0,0 +1,357 @@
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The 16 CSS basic color names, their `grey`, `magenta` and `cyan`
+/// aliases, and `transparent`.
+const NAMED_COLORS: [(&str, Color); 20] = [
+    ("black", Color::rgb(0x00, 0x00, 0x00)),
+    ("silver", Color::rgb(0xc0, 0xc0, 0xc0)),
+    ("gray", Color::rgb(0x80, 0x80, 0x80)),
+    ("grey", Color::rgb(0x80, 0x80, 0x80)),
+    ("white", Color::rgb(0xff, 0xff, 0xff)),
+    ("maroon", Color::rgb(0x80, 0x00, 0x00)),
+    ("red", Color::rgb(0xff, 0x00, 0x00)),
+    ("purple", Color::rgb(0x80, 0x00, 0x80)),
+    ("fuchsia", Color::rgb(0xff, 0x00, 0xff)),
+    ("magenta", Color::rgb(0xff, 0x00, 0xff)),
+    ("green", Color::rgb(0x00, 0x80, 0x00)),
+    ("lime", Color::rgb(0x00, 0xff, 0x00)),
+    ("olive", Color::rgb(0x80, 0x80, 0x00)),
+    ("yellow", Color::rgb(0xff, 0xff, 0x00)),
+    ("navy", Color::rgb(0x00, 0x00, 0x80)),
+    ("blue", Color::rgb(0x00, 0x00, 0xff)),
+    ("teal", Color::rgb(0x00, 0x80, 0x80)),
+    ("aqua", Color::rgb(0x00, 0xff, 0xff)),
+    ("cyan", Color::rgb(0x00, 0xff, 0xff)),
+    ("transparent", Color::rgba(0x00, 0x00, 0x00, 0x00)),
+];
+
+/// An sRGB color with an alpha channel, for deriving shades and checking
+/// contrast. Parses from `#RGB`, `#RRGGBB`, `#RRGGBBAA` or a CSS basic
+/// color name, and displays as `#rrggbb`, with `aa` appended only when it
+/// is not opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid color {0:?}, expected #RGB, #RRGGBB, #RRGGBBAA or a color name")]
+pub struct ParseColorError(String);
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+
+    #[must_use]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, u8::MAX)
+    }
+
+    #[must_use]
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// `#RGB`, `#RRGGBB` or `#RRGGBBAA`, the only forms a `.product` file
+    /// may use.
+    #[must_use]
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize, len: usize| {
+            let value = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).ok()?;
+            Some(if len == 1 { value * 17 } else { value })
+        };
+        let len = match digits.len() {
+            3 => 1,
+            6 | 8 => 2,
+            _ => return None,
+        };
+        let alpha = if digits.len() == 8 {
+            channel(3, len)?
+        } else {
+            u8::MAX
+        };
+        Some(Self::rgba(
+            channel(0, len)?,
+            channel(1, len)?,
+            channel(2, len)?,
+            alpha,
+        ))
+    }
+
+    #[must_use]
+    pub const fn red(self) -> u8 {
+        self.r
+    }
+
+    #[must_use]
+    pub const fn green(self) -> u8 {
+        self.g
+    }
+
+    #[must_use]
+    pub const fn blue(self) -> u8 {
+        self.b
+    }
+
+    #[must_use]
+    pub const fn alpha(self) -> u8 {
+        self.a
+    }
+
+    #[must_use]
+    pub const fn with_alpha(self, alpha: u8) -> Self {
+        Self { a: alpha, ..self }
+    }
+
+    /// Hue in degrees, saturation and lightness in `0.0..=1.0`.
+    #[must_use]
+    pub fn to_hsl(self) -> (f64, f64, f64) {
+        let [r, g, b] = [self.r, self.g, self.b].map(|c| f64::from(c) / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        if delta < f64::EPSILON {
+            return (0.0, 0.0, lightness);
+        }
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if (max - r).abs() < f64::EPSILON {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if (max - g).abs() < f64::EPSILON {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// An opaque color from the values `to_hsl` returns; saturation and
+    /// lightness are clamped.
+    #[must_use]
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match sector {
+            s if s < 1.0 => (chroma, x, 0.0),
+            s if s < 2.0 => (x, chroma, 0.0),
+            s if s < 3.0 => (0.0, chroma, x),
+            s if s < 4.0 => (0.0, x, chroma),
+            s if s < 5.0 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = lightness - chroma / 2.0;
+        Self::rgb(to_channel(r + m), to_channel(g + m), to_channel(b + m))
+    }
+
+    /// The same hue, saturation and alpha with the HSL lightness raised by
+    /// `percent` points, stopping at white.
+    #[must_use]
+    pub fn lighten(self, percent: f64) -> Self {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Self::from_hsl(hue, saturation, lightness + percent / 100.0).with_alpha(self.a)
+    }
+
+    /// The same hue, saturation and alpha with the HSL lightness lowered by
+    /// `percent` points, stopping at black.
+    #[must_use]
+    pub fn darken(self, percent: f64) -> Self {
+        self.lighten(-percent)
+    }
+
+    /// WCAG relative luminance, from 0.0 for black to 1.0 for white. Alpha
+    /// is ignored.
+    #[must_use]
+    pub fn luminance(self) -> f64 {
+        let linear = |c: u8| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.039_28 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.0722_f64.mul_add(
+            linear(self.b),
+            0.2126_f64.mul_add(linear(self.r), 0.7152 * linear(self.g)),
+        )
+    }
+
+    /// WCAG contrast ratio, from 1.0 for equal luminance to 21.0.
+    #[must_use]
+    pub fn contrast_ratio(&self, other: &Self) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Black or white, whichever contrasts more with this color.
+    #[must_use]
+    pub fn best_text_on(self) -> Self {
+        if self.contrast_ratio(&Self::BLACK) >= self.contrast_ratio(&Self::WHITE) {
+            Self::BLACK
+        } else {
+            Self::WHITE
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != u8::MAX {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hex forms as `from_hex`, and names in any letter case. Surrounding
+/// whitespace is ignored.
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        Self::from_hex(trimmed)
+            .or_else(|| {
+                NAMED_COLORS
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(trimmed))
+                    .map(|(_, color)| *color)
+            })
+            .ok_or_else(|| ParseColorError(s.to_string()))
+    }
+}
+
+fn to_channel(value: f64) -> u8 {
+    let scaled = (value.clamp(0.0, 1.0) * 255.0).round();
+    (0..=u8::MAX)
+        .find(|c| f64::from(*c) >= scaled)
+        .unwrap_or(u8::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Option<Color> {
+        s.parse().ok()
+    }
+
+    #[test]
+    fn test_parse_hex_forms() {
+        assert_eq!(parse("#25d366"), Some(Color::rgb(0x25, 0xd3, 0x66)));
+        assert_eq!(parse("#25D366"), Some(Color::rgb(0x25, 0xd3, 0x66)));
+        assert_eq!(parse("#FfF"), Some(Color::WHITE));
+        assert_eq!(parse("#f60"), Some(Color::rgb(0xff, 0x66, 0x00)));
+        assert_eq!(
+            parse("#075e5480"),
+            Some(Color::rgba(0x07, 0x5e, 0x54, 0x80))
+        );
+        assert_eq!(parse("  #000\n"), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn test_parse_names() {
+        assert_eq!(parse("white"), Some(Color::WHITE));
+        assert_eq!(parse("Navy"), Some(Color::rgb(0, 0, 0x80)));
+        assert_eq!(parse("GREY"), parse("gray"));
+        assert_eq!(parse("transparent").map(Color::alpha), Some(0));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for invalid in [
+            "",
+            "#",
+            "25d366",
+            "#25d36",
+            "#25d3666",
+            "#25d366808",
+            "#ggg",
+            "#+12",
+            "#ééé",
+            "# 25d366",
+            "orange",
+            "rgb(0, 0, 0)",
+            "#1234",
+        ] {
+            assert_eq!(
+                invalid.parse::<Color>(),
+                Err(ParseColorError(invalid.to_string())),
+                "{invalid}"
+            );
+        }
+        assert_eq!(Color::from_hex("white"), None);
+    }
+
+    #[test]
+    fn test_display_canonicalizes() {
+        for (input, canonical) in [
+            ("#25D366", "#25d366"),
+            ("#FFF", "#ffffff"),
+            ("#075e54ff", "#075e54"),
+            ("#075E5480", "#075e5480"),
+            ("Teal", "#008080"),
+            ("transparent", "#00000000"),
+        ] {
+            let shown = parse(input).map(|c| c.to_string());
+            assert_eq!(shown.as_deref(), Some(canonical), "{input}");
+            assert_eq!(shown.and_then(|s| parse(&s)), parse(input), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_with_alpha() {
+        let color = Color::rgb(0x25, 0xd3, 0x66).with_alpha(0x40);
+        assert_eq!(color.to_string(), "#25d36640");
+        assert_eq!(color.with_alpha(u8::MAX), Color::rgb(0x25, 0xd3, 0x66));
+        assert_eq!(color.darken(100.0), Color::BLACK.with_alpha(0x40));
+    }
+
+    #[test]
+    fn test_hsl_round_trip() {
+        for hex in [
+            "#25d366", "#075e54", "#ff6600", "#808080", "#000000", "#ffffff",
+        ] {
+            let color = parse(hex).unwrap_or(Color::BLACK);
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Color::from_hsl(h, s, l), color, "{hex}");
+        }
+        let (h, s, l) = Color::rgb(255, 0, 0).to_hsl();
+        assert!(h.abs() < 1e-9 && (s - 1.0).abs() < 1e-9 && (l - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lighten_and_darken() {
+        let red = Color::rgb(255, 0, 0);
+        assert_eq!(red.lighten(100.0), Color::WHITE);
+        assert_eq!(red.lighten(500.0), Color::WHITE);
+        assert_eq!(red.darken(25.0).to_string(), "#800000");
+        assert_eq!(red.lighten(25.0).to_string(), "#ff8080");
+        assert_eq!(red.darken(-25.0), red.lighten(25.0));
+        assert_eq!(red.lighten(0.0), red);
+    }
+
+    #[test]
+    fn test_contrast() {
+        assert!((Color::BLACK.luminance()).abs() < 1e-9);
+        assert!((Color::WHITE.luminance() - 1.0).abs() < 1e-9);
+        assert!((Color::BLACK.contrast_ratio(&Color::WHITE) - 21.0).abs() < 1e-9);
+        assert!((Color::WHITE.contrast_ratio(&Color::BLACK) - 21.0).abs() < 1e-9);
+        assert!((Color::WHITE.contrast_ratio(&Color::WHITE) - 1.0).abs() < 1e-9);
+        assert_eq!(Color::rgb(0x07, 0x5e, 0x54).best_text_on(), Color::WHITE);
+        assert_eq!(Color::rgb(0x25, 0xd3, 0x66).best_text_on(), Color::BLACK);
+    }
+}
@@ -0,0 +1,255 @@
+use super::theme::ThemeMode;
+use super::{branding, is_link, BrandingConfig};
+use crate::error::{BotError, ErrorCategory};
+use crate::limits::LimitExceeded;
+use crate::markup::escape_html;
+
+/// An error `render_error_page` can describe.
+#[derive(Debug, Clone, Copy)]
+pub enum PageError<'a> {
+    Bot(&'a BotError),
+    Limit(&'a LimitExceeded),
+}
+
+impl<'a> From<&'a BotError> for PageError<'a> {
+    fn from(error: &'a BotError) -> Self {
+        Self::Bot(error)
+    }
+}
+
+impl<'a> From<&'a LimitExceeded> for PageError<'a> {
+    fn from(error: &'a LimitExceeded) -> Self {
+        Self::Limit(error)
+    }
+}
+
+impl PageError<'_> {
+    /// The status the error is served with.
+    #[must_use]
+    pub fn status_code(self) -> u16 {
+        match self {
+            Self::Bot(error) => error.status_code(),
+            Self::Limit(error) => error.limit_type.status_code(),
+        }
+    }
+
+    #[must_use]
+    pub fn retry_after_secs(self) -> Option<u64> {
+        match self {
+            Self::Bot(error) => error.retry_after().map(|d| d.as_secs()),
+            Self::Limit(error) => error.retry_after_secs,
+        }
+    }
+
+    /// The error's own message, shown for client errors only: server
+    /// errors and limits may name internals the visitor has no use for.
+    fn detail(self) -> Option<String> {
+        match self {
+            Self::Bot(error) if error.category() == ErrorCategory::UserError => {
+                Some(error.to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl BrandingConfig {
+    /// A self-contained HTML page for an error served with `status`: the
+    /// brand's colors, logo and support email around a message for the
+    /// visitor, with the retry delay when the error carries one. Client
+    /// errors also show their own message. Everything but the markup is
+    /// escaped.
+    #[must_use]
+    pub fn render_error_page<'a>(&self, status: u16, error: impl Into<PageError<'a>>) -> String {
+        let error = error.into();
+        let theme = self.to_theme(ThemeMode::Light);
+        let name = escape_html(&self.name);
+        let reason = reason_phrase(status);
+        let header = self.logo_url.as_deref().filter(|u| is_link(u)).map_or_else(
+            || name.clone(),
+            |logo| {
+                format!(
+                    "<img src=\"{}\" alt=\"{name}\" height=\"40\">",
+                    escape_html(logo)
+                )
+            },
+        );
+
+        let mut main = vec![
+            format!("<h1>{status} {reason}</h1>"),
+            format!("<p>{}</p>", visitor_message(status)),
+        ];
+        if let Some(detail) = error.detail() {
+            main.push(format!("<p class=\"detail\">{}</p>", escape_html(&detail)));
+        }
+        match error.retry_after_secs() {
+            Some(1) => main.push("<p>Please try again in 1 second.</p>".to_string()),
+            Some(secs) => main.push(format!("<p>Please try again in {secs} seconds.</p>")),
+            None if status == 429 || status >= 500 => {
+                main.push("<p>Please try again later.</p>".to_string());
+            }
+            None => {}
+        }
+        if let Some(email) = &self.support_email {
+            let email = escape_html(email);
+            main.push(format!(
+                "<p>Need help? Contact <a href=\"mailto:{email}\">{email}</a>.</p>"
+            ));
+        }
+
+        let mut page = vec![
+            "<!DOCTYPE html>".to_string(),
+            "<html>".to_string(),
+            "<head>".to_string(),
+            "<meta charset=\"utf-8\">".to_string(),
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">".to_string(),
+            format!("<title>{reason} - {name}</title>"),
+            "<style>".to_string(),
+            format!(
+                "body{{margin:0;font-family:Arial,Helvetica,sans-serif;background:{};color:{};}}",
+                theme.background, theme.text
+            ),
+            format!(
+                "header{{padding:16px 24px;background:{};color:{};font-size:20px;font-weight:bold;}}",
+                theme.primary, theme.on_primary
+            ),
+            "header img{display:block;border:0;}".to_string(),
+            "main{max-width:560px;margin:48px auto;padding:0 24px;line-height:1.5;}".to_string(),
+            format!("h1{{font-size:28px;border-bottom:3px solid {};}}", theme.secondary),
+            format!("a{{color:{};}}", theme.accent),
+            ".detail{font-family:monospace;}".to_string(),
+            "footer{padding:24px;font-size:12px;text-align:center;}".to_string(),
+            "</style>".to_string(),
+            "</head>".to_string(),
+            "<body>".to_string(),
+            format!("<header>{header}</header>"),
+            "<main>".to_string(),
+        ];
+        page.extend(main);
+        page.extend([
+            "</main>".to_string(),
+            format!("<footer>{}</footer>", escape_html(&self.copyright_line())),
+            "</body>".to_string(),
+            "</html>".to_string(),
+        ]);
+        page.join("\n")
+    }
+}
+
+/// `BrandingConfig::render_error_page` with the active branding.
+#[must_use]
+pub fn render_error_page<'a>(status: u16, error: impl Into<PageError<'a>>) -> String {
+    branding().render_error_page(status, error)
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Content",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Error",
+    }
+}
+
+fn visitor_message(status: u16) -> &'static str {
+    match status {
+        401 => "You need to sign in to continue.",
+        403 => "You do not have access to this.",
+        404 => "The page you are looking for could not be found.",
+        409 => "This changed while you were working on it. Reload and try again.",
+        413 => "What you sent is too large.",
+        429 => "You are sending requests too quickly.",
+        502 | 503 => "The service is temporarily unavailable.",
+        504 => "The service took too long to respond.",
+        500.. => "Something went wrong on our side.",
+        _ => "The request could not be completed.",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::LimitType;
+
+    fn acme() -> BrandingConfig {
+        BrandingConfig {
+            name: "Acme Bots".to_string(),
+            short_name: "AB".to_string(),
+            company: Some("Acme Inc".to_string()),
+            logo_url: Some("/static/logo.svg".to_string()),
+            support_email: Some("help@acme.example".to_string()),
+            primary_color: Some("#ff6600".to_string()),
+            secondary_color: Some("#003366".to_string()),
+            copyright: Some("© 2024 Acme Inc".to_string()),
+            ..BrandingConfig::white_label_base()
+        }
+    }
+
+    #[test]
+    fn test_rate_limited_page_golden() {
+        let exceeded = LimitExceeded {
+            limit_type: LimitType::ApiCallsMinute,
+            current: 1001,
+            maximum: 1000,
+            retry_after_secs: Some(30),
+        };
+        assert_eq!(
+            acme().render_error_page(429, &exceeded),
+            include_str!("../../tests/golden/branding_error_429.html").trim_end()
+        );
+        let page = acme().render_error_page(429, &BotError::rate_limited(1));
+        assert!(page.contains("<p>Please try again in 1 second.</p>"));
+    }
+
+    #[test]
+    fn test_server_error_page_golden() {
+        let error = BotError::database("password authentication failed for user \"bots\"");
+        let page = acme().render_error_page(500, &error);
+        assert!(!page.contains("password"));
+        assert_eq!(
+            page,
+            include_str!("../../tests/golden/branding_error_500.html").trim_end()
+        );
+    }
+
+    #[test]
+    fn test_error_page_escapes_dynamic_content() {
+        let config = BrandingConfig {
+            name: "Acme <Bots>".to_string(),
+            logo_url: Some("https://acme.example/logo.png?a=1&b=\"2\"".to_string()),
+            support_email: Some("\"help\"@acme.example".to_string()),
+            copyright: Some("© Acme & Sons".to_string()),
+            ..acme()
+        };
+        let error = BotError::validation("<script>alert('x')</script>");
+        let page = config.render_error_page(400, &error);
+        assert!(!page.contains("<script>"));
+        assert!(page.contains(
+            "<p class=\"detail\">Validation error: &lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;</p>"
+        ));
+        assert!(page.contains("<title>Bad Request - Acme &lt;Bots&gt;</title>"));
+        assert!(page.contains("src=\"https://acme.example/logo.png?a=1&amp;b=&quot;2&quot;\""));
+        assert!(page.contains("<a href=\"mailto:&quot;help&quot;@acme.example\">"));
+        assert!(page.contains("<footer>© Acme &amp; Sons</footer>"));
+
+        let page = BrandingConfig {
+            logo_url: None,
+            support_email: None,
+            ..config
+        }
+        .render_error_page(404, &BotError::not_found("Bot"));
+        assert!(page.contains("<header>Acme &lt;Bots&gt;</header>"));
+        assert!(page.contains("<p class=\"detail\">Bot not found</p>"));
+        assert!(!page.contains("mailto:"));
+        assert!(!page.contains("try again"));
+    }
+}
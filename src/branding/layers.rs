@@ -0,0 +1,440 @@
+#[cfg(feature = "http-client")]
+use super::remote::{RemoteBranding, RemotePrecedence};
+use super::{initials, read_product_file, BrandingConfig, BRANDING_FIELDS};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Where `BrandingConfig::load` looks for a `.product` file, in order.
+pub const PRODUCT_SEARCH_PATHS: [&str; 4] = [
+    ".product",
+    "config/.product",
+    "/etc/botserver/.product",
+    "/opt/gbo/.product",
+];
+
+/// The `PLATFORM_*` variable that overrides `field`, e.g.
+/// `PLATFORM_PRIMARY_COLOR` for `primary_color`.
+#[must_use]
+pub fn env_var(field: &str) -> String {
+    format!("PLATFORM_{}", field.to_uppercase())
+}
+
+/// Where a branding field's value came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum BrandingSource {
+    Default,
+    /// The first `.product` file found on the search path.
+    File {
+        path: PathBuf,
+    },
+    /// The file named by `PRODUCT_FILE`.
+    ProductFile {
+        path: PathBuf,
+    },
+    Env {
+        var: String,
+    },
+    /// A config fetched from `url` at startup.
+    Remote {
+        url: String,
+    },
+    /// The copy of a remote config cached at `path`, used when the fetch
+    /// failed.
+    RemoteCache {
+        path: PathBuf,
+    },
+}
+
+impl fmt::Display for BrandingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File { path } => write!(f, "file {}", path.display()),
+            Self::ProductFile { path } => write!(f, "PRODUCT_FILE {}", path.display()),
+            Self::Env { var } => write!(f, "env {var}"),
+            Self::Remote { url } => write!(f, "remote {url}"),
+            Self::RemoteCache { path } => write!(f, "remote cache {}", path.display()),
+        }
+    }
+}
+
+/// The source of every field of a loaded `BrandingConfig`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BrandingProvenance {
+    fields: BTreeMap<String, BrandingSource>,
+}
+
+impl Default for BrandingProvenance {
+    fn default() -> Self {
+        Self {
+            fields: BRANDING_FIELDS
+                .iter()
+                .map(|field| ((*field).to_string(), BrandingSource::Default))
+                .collect(),
+        }
+    }
+}
+
+impl BrandingProvenance {
+    /// The source of `field`, or `None` if it is not a branding field.
+    #[must_use]
+    pub fn source(&self, field: &str) -> Option<&BrandingSource> {
+        self.fields.get(field)
+    }
+
+    /// Fields and their sources in `BRANDING_FIELDS` order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &BrandingSource)> {
+        BRANDING_FIELDS
+            .into_iter()
+            .filter_map(|field| Some((field, self.fields.get(field)?)))
+    }
+
+    pub(super) fn set(&mut self, field: &str, source: BrandingSource) {
+        self.fields.insert(field.to_string(), source);
+    }
+}
+
+impl fmt::Display for BrandingProvenance {
+    /// One `field: source` line per field.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self
+            .iter()
+            .map(|(field, source)| format!("  {field}: {source}"))
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// The inputs `BrandingConfig::load_with_layers` reads. `new()` reads
+/// nothing; `from_env()` is what `BrandingConfig::load` uses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BrandingLayers {
+    /// Candidate `.product` files; the first one that can be read is used.
+    pub search_paths: Vec<PathBuf>,
+    pub product_file: Option<PathBuf>,
+    /// `PLATFORM_*` variables by name.
+    pub env: BTreeMap<String, String>,
+    #[cfg(feature = "http-client")]
+    pub remote: Option<RemoteBranding>,
+    #[cfg(feature = "http-client")]
+    pub remote_precedence: RemotePrecedence,
+}
+
+impl BrandingLayers {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `PRODUCT_SEARCH_PATHS`, `PRODUCT_FILE` and the `PLATFORM_*`
+    /// variables of this process.
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self {
+            search_paths: PRODUCT_SEARCH_PATHS.iter().map(PathBuf::from).collect(),
+            product_file: std::env::var_os("PRODUCT_FILE").map(PathBuf::from),
+            env: std::env::vars()
+                .filter(|(var, _)| var.starts_with("PLATFORM_"))
+                .collect(),
+            #[cfg(feature = "http-client")]
+            remote: None,
+            #[cfg(feature = "http-client")]
+            remote_precedence: RemotePrecedence::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_search_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_product_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.product_file = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_env(mut self, var: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(var.into(), value.into());
+        self
+    }
+
+    #[cfg(feature = "http-client")]
+    #[must_use]
+    pub fn with_remote(mut self, remote: RemoteBranding) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+
+    #[cfg(feature = "http-client")]
+    #[must_use]
+    pub const fn with_remote_precedence(mut self, precedence: RemotePrecedence) -> Self {
+        self.remote_precedence = precedence;
+        self
+    }
+}
+
+impl BrandingConfig {
+    /// Builds the config from `layers`, each overriding the ones before it:
+    /// defaults, the first readable search path file, `PRODUCT_FILE`, then
+    /// the `PLATFORM_*` variables, so a variable always wins. A remote
+    /// config goes where `remote_precedence` puts it, below the variables
+    /// unless set otherwise. A file only overrides the fields it sets. When
+    /// any file or remote config is found the config starts from the
+    /// default names only, without the General Bots company, links and
+    /// colors. A short name nobody sets is derived from the name once the
+    /// name is overridden. The config is white-label when it differs from
+    /// the default in any field.
+    #[must_use]
+    pub fn load_with_layers(layers: &BrandingLayers) -> (Self, BrandingProvenance) {
+        let file = layers
+            .search_paths
+            .iter()
+            .find_map(|path| Some((path, read_product_file(path).ok()?)));
+        let product_file =
+            layers
+                .product_file
+                .as_ref()
+                .and_then(|path| match read_product_file(path) {
+                    Ok(fields) => Some((path, fields)),
+                    Err(e) => {
+                        warn!("Ignoring PRODUCT_FILE={}: {e}", path.display());
+                        None
+                    }
+                });
+
+        #[cfg(feature = "http-client")]
+        let remote_at = |precedence| {
+            layers
+                .remote
+                .as_ref()
+                .filter(|_| layers.remote_precedence == precedence)
+        };
+        let has_files = file.is_some() || product_file.is_some();
+        #[cfg(feature = "http-client")]
+        let has_files = has_files || layers.remote.is_some();
+
+        let mut config = if has_files {
+            Self::white_label_base()
+        } else {
+            Self::default()
+        };
+        let mut provenance = BrandingProvenance::default();
+        #[cfg(feature = "http-client")]
+        if let Some(remote) = remote_at(RemotePrecedence::BelowFiles) {
+            remote.apply(&mut config, &mut provenance);
+        }
+        if let Some((path, fields)) = file {
+            info!("Loaded white-label branding from {}", path.display());
+            for (field, value) in fields {
+                config.set_field(field, value);
+                provenance.set(field, BrandingSource::File { path: path.clone() });
+            }
+        }
+        if let Some((path, fields)) = product_file {
+            info!(
+                "Loaded white-label branding from PRODUCT_FILE={}",
+                path.display()
+            );
+            for (field, value) in fields {
+                config.set_field(field, value);
+                provenance.set(field, BrandingSource::ProductFile { path: path.clone() });
+            }
+        }
+        #[cfg(feature = "http-client")]
+        if let Some(remote) = remote_at(RemotePrecedence::BelowEnv) {
+            remote.apply(&mut config, &mut provenance);
+        }
+        for field in BRANDING_FIELDS {
+            let var = env_var(field);
+            if let Some(value) = layers.env.get(&var) {
+                config.set_field(field, value.clone());
+                provenance.set(field, BrandingSource::Env { var });
+            }
+        }
+        #[cfg(feature = "http-client")]
+        if let Some(remote) = remote_at(RemotePrecedence::AboveEnv) {
+            remote.apply(&mut config, &mut provenance);
+        }
+        let name_source = provenance.source("name").cloned();
+        if provenance.source("short_name") == Some(&BrandingSource::Default) {
+            if let Some(source) = name_source.filter(|s| *s != BrandingSource::Default) {
+                config.short_name = initials(&config.name);
+                provenance.set("short_name", source);
+            }
+        }
+        config.is_white_label = !config.diff_from_default().is_empty();
+        (config, provenance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        assert!(std::fs::write(&path, content).is_ok());
+        path
+    }
+
+    #[test]
+    fn test_env_overrides_file_for_every_field() {
+        let dir = tempfile::tempdir().ok();
+        let dir = dir
+            .as_ref()
+            .map(|d| d.path().to_path_buf())
+            .unwrap_or_default();
+        let content: String = BRANDING_FIELDS
+            .iter()
+            .map(|field| format!("{field} = \"file {field}\"\n"))
+            .collect();
+        let file = write(&dir, ".product", &content);
+        let product_file = write(&dir, "product.toml", &content.replace("file ", "pf "));
+
+        let mut layers = BrandingLayers::new()
+            .with_search_path(&file)
+            .with_product_file(&product_file);
+        let (config, _) = BrandingConfig::load_with_layers(&layers);
+        for field in BRANDING_FIELDS {
+            assert_eq!(config.field(field), Some(format!("pf {field}").as_str()));
+        }
+
+        for field in BRANDING_FIELDS {
+            layers = layers.with_env(env_var(field), format!("env {field}"));
+        }
+        let (config, provenance) = BrandingConfig::load_with_layers(&layers);
+        for field in BRANDING_FIELDS {
+            assert_eq!(config.field(field), Some(format!("env {field}").as_str()));
+            assert_eq!(
+                provenance.source(field),
+                Some(&BrandingSource::Env {
+                    var: env_var(field)
+                })
+            );
+        }
+        assert!(config.is_white_label);
+    }
+
+    #[test]
+    fn test_layers_apply_in_order() {
+        let dir = tempfile::tempdir().ok();
+        let dir = dir
+            .as_ref()
+            .map(|d| d.path().to_path_buf())
+            .unwrap_or_default();
+        let file = write(
+            &dir,
+            ".product",
+            "name = \"Acme Bots\"\ncompany = \"Acme\"\nprimary_color = \"#ff6600\"\n",
+        );
+        let product_file = write(
+            &dir,
+            "tuned.product",
+            "company=Acme Inc\nsecondary_color=#003366\n",
+        );
+        let layers = BrandingLayers::new()
+            .with_search_path(dir.join("missing.product"))
+            .with_search_path(&file)
+            .with_product_file(&product_file)
+            .with_env("PLATFORM_PRIMARY_COLOR", "#cc0000")
+            .with_env("PLATFORM_UNRELATED", "ignored");
+        let (config, provenance) = BrandingConfig::load_with_layers(&layers);
+
+        assert_eq!(config.name, "Acme Bots");
+        assert_eq!(config.short_name, "AB");
+        assert_eq!(config.company.as_deref(), Some("Acme Inc"));
+        assert_eq!(config.primary_color.as_deref(), Some("#cc0000"));
+        assert_eq!(config.secondary_color.as_deref(), Some("#003366"));
+        assert_eq!(config.docs_url, None);
+        assert!(config.is_white_label);
+
+        let from_file = BrandingSource::File { path: file.clone() };
+        let from_product_file = BrandingSource::ProductFile {
+            path: product_file.clone(),
+        };
+        assert_eq!(provenance.source("name"), Some(&from_file));
+        assert_eq!(provenance.source("short_name"), Some(&from_file));
+        assert_eq!(provenance.source("company"), Some(&from_product_file));
+        assert_eq!(
+            provenance.source("secondary_color"),
+            Some(&from_product_file)
+        );
+        assert_eq!(
+            provenance.source("docs_url"),
+            Some(&BrandingSource::Default)
+        );
+        assert_eq!(provenance.source("unknown"), None);
+
+        let report = provenance.to_string();
+        assert_eq!(report.lines().count(), BRANDING_FIELDS.len());
+        assert!(report.starts_with(&format!(
+            "  name: file {}\n  short_name: file {}\n  company: PRODUCT_FILE {}\n",
+            file.display(),
+            file.display(),
+            product_file.display()
+        )));
+        assert!(report.contains("\n  primary_color: env PLATFORM_PRIMARY_COLOR\n"));
+        assert!(report.ends_with("\n  docs_url: default"));
+    }
+
+    #[test]
+    fn test_env_without_files() {
+        let (config, provenance) = BrandingConfig::load_with_layers(&BrandingLayers::new());
+        assert_eq!(config, BrandingConfig::default());
+        assert!(provenance
+            .iter()
+            .all(|(_, source)| *source == BrandingSource::Default));
+
+        let layers = BrandingLayers::new().with_env("PLATFORM_PRIMARY_COLOR", "#cc0000");
+        let (config, _) = BrandingConfig::load_with_layers(&layers);
+        assert_eq!(config.primary_color.as_deref(), Some("#cc0000"));
+        assert!(config.is_white_label);
+
+        let same = BrandingLayers::new().with_env("PLATFORM_NAME", "General Bots");
+        let (config, _) = BrandingConfig::load_with_layers(&same);
+        assert!(!config.is_white_label);
+
+        let layers = layers.with_env("PLATFORM_NAME", "Globex Assistant");
+        let (config, provenance) = BrandingConfig::load_with_layers(&layers);
+        assert_eq!(config.short_name, "GA");
+        assert_eq!(config.secondary_color.as_deref(), Some("#075e54"));
+        assert!(config.is_white_label);
+        assert_eq!(
+            provenance.source("short_name"),
+            Some(&BrandingSource::Env {
+                var: "PLATFORM_NAME".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_unreadable_product_file_is_skipped() {
+        let layers = BrandingLayers::new().with_product_file("/nonexistent/.product");
+        let (config, provenance) = BrandingConfig::load_with_layers(&layers);
+        assert_eq!(config, BrandingConfig::default());
+        assert_eq!(provenance, BrandingProvenance::default());
+    }
+
+    #[test]
+    fn test_provenance_serializes_tagged() {
+        let layers = BrandingLayers::new().with_env("PLATFORM_DOMAIN", "acme.example");
+        let (_, provenance) = BrandingConfig::load_with_layers(&layers);
+        let json = serde_json::to_value(&provenance).unwrap_or_default();
+        assert_eq!(
+            json["fields"]["domain"],
+            serde_json::json!({"source": "env", "var": "PLATFORM_DOMAIN"})
+        );
+        assert_eq!(
+            json["fields"]["name"],
+            serde_json::json!({"source": "default"})
+        );
+    }
+}
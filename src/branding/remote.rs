@@ -0,0 +1,399 @@
+use super::layers::{BrandingLayers, BrandingProvenance, BrandingSource};
+use super::{read_product_file, set_active, write_atomically, BrandingConfig, ProductFile};
+use crate::error::{BotError, BotResult};
+use crate::http_client::{BotServerClient, HttpResponse};
+use log::{debug, info, warn};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use std::path::{Path, PathBuf};
+
+/// Where `load_with_layers` puts a remote config among the other layers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RemotePrecedence {
+    /// Above the defaults only: `.product` files and `PLATFORM_*`
+    /// variables override it.
+    BelowFiles,
+    /// Above `.product` files, below `PLATFORM_*` variables.
+    #[default]
+    BelowEnv,
+    /// Above every other layer, so the central config always wins.
+    AboveEnv,
+}
+
+/// A branding config fetched from a URL, or the copy cached by an earlier
+/// fetch, to layer in with `BrandingLayers::with_remote`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteBranding {
+    source: BrandingSource,
+    fields: Vec<(&'static str, String)>,
+}
+
+impl RemoteBranding {
+    /// GETs `url` with `client` and reads the body as a `.product` config:
+    /// JSON when the content type says so, TOML otherwise. A config that
+    /// fails `validate` is rejected. With a `cache_path`, a fetched config
+    /// is written there as TOML and its `ETag` to `<cache_path>.etag`, and
+    /// the next fetch revalidates with `If-None-Match`, using the cache on
+    /// 304. When the fetch fails the cache is used instead; `None` means
+    /// neither worked. Failures are logged as warnings.
+    pub async fn fetch(
+        url: &str,
+        client: &BotServerClient,
+        cache_path: Option<&Path>,
+    ) -> Option<Self> {
+        match fetch_fields(url, client, cache_path).await {
+            Ok(fields) => {
+                return Some(Self {
+                    source: BrandingSource::Remote {
+                        url: url.to_string(),
+                    },
+                    fields,
+                })
+            }
+            Err(e) => warn!("Failed to fetch branding from {url}: {e}"),
+        }
+        let Some(path) = cache_path else {
+            warn!("No branding cache configured, ignoring {url}");
+            return None;
+        };
+        match read_product_file(path) {
+            Ok(fields) => {
+                warn!("Using the branding cached at {}", path.display());
+                Some(Self {
+                    source: BrandingSource::RemoteCache {
+                        path: path.to_path_buf(),
+                    },
+                    fields,
+                })
+            }
+            Err(e) => {
+                warn!("No branding cached at {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// `Remote` when the config was fetched or revalidated, `RemoteCache`
+    /// when the fetch failed and the cache was read.
+    #[must_use]
+    pub const fn source(&self) -> &BrandingSource {
+        &self.source
+    }
+
+    pub(super) fn apply(&self, config: &mut BrandingConfig, provenance: &mut BrandingProvenance) {
+        info!("Loaded white-label branding from {}", self.source);
+        for (field, value) in &self.fields {
+            config.set_field(field, value.clone());
+            provenance.set(field, self.source.clone());
+        }
+    }
+}
+
+impl BrandingConfig {
+    /// `load`, with the config at `url` layered in below the `PLATFORM_*`
+    /// variables, as `RemoteBranding::fetch` gets it. Never fails: without
+    /// the remote config or its cache this is `load`.
+    pub async fn load_remote(
+        url: &str,
+        client: &BotServerClient,
+        cache_path: Option<&Path>,
+    ) -> Self {
+        let mut layers = BrandingLayers::from_env();
+        layers.remote = RemoteBranding::fetch(url, client, cache_path).await;
+        let (config, provenance) = Self::load_with_layers(&layers);
+        debug!("Branding: {config}\nSources:\n{provenance}");
+        config
+    }
+}
+
+/// `init_branding` with the config at `url` layered in, as
+/// `BrandingConfig::load_remote` does.
+pub async fn init_branding_remote(url: &str, client: &BotServerClient, cache_path: Option<&Path>) {
+    set_active(BrandingConfig::load_remote(url, client, cache_path).await);
+}
+
+async fn fetch_fields(
+    url: &str,
+    client: &BotServerClient,
+    cache_path: Option<&Path>,
+) -> BotResult<Vec<(&'static str, String)>> {
+    let mut headers = HeaderMap::new();
+    let cached_etag = cache_path
+        .filter(|path| path.is_file())
+        .and_then(|path| std::fs::read_to_string(etag_path(path)).ok())
+        .and_then(|etag| HeaderValue::from_str(etag.trim()).ok());
+    if let Some(etag) = cached_etag {
+        headers.insert(IF_NONE_MATCH, etag);
+    }
+    let response = client
+        .clone()
+        .with_base_url(url)
+        .get_response("", headers)
+        .await?;
+
+    if let Some(path) = cache_path.filter(|_| response.status == StatusCode::NOT_MODIFIED) {
+        debug!("Branding at {url} not modified, using {}", path.display());
+        return read_product_file(path);
+    }
+
+    let file = parse_product_file(&response)?;
+    let content = toml::to_string(&file)
+        .map_err(|e| BotError::internal(format!("Failed to serialize branding: {e}")))?;
+    let fields = file.into_fields();
+    let mut config = BrandingConfig::white_label_base();
+    for (field, value) in &fields {
+        config.set_field(field, value.clone());
+    }
+    config.validate()?;
+
+    if let Some(path) = cache_path {
+        let etag = response.headers.get(ETAG).and_then(|v| v.to_str().ok());
+        if let Err(e) = write_cache(path, &content, etag) {
+            warn!("Failed to cache branding at {}: {e}", path.display());
+        }
+    }
+    Ok(fields)
+}
+
+/// JSON for `application/json` and `+json` content types, TOML otherwise.
+fn parse_product_file(response: &HttpResponse) -> BotResult<ProductFile> {
+    let is_json = response
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(str::trim)
+        .is_some_and(|mime| mime == "application/json" || mime.ends_with("+json"));
+    let body = std::str::from_utf8(&response.body)
+        .map_err(|e| BotError::config(format!("Remote branding is not UTF-8: {e}")))?;
+    if is_json {
+        serde_json::from_str(body)
+            .map_err(|e| BotError::config(format!("Invalid remote branding JSON: {e}")))
+    } else {
+        toml::from_str(body)
+            .map_err(|e| BotError::config(format!("Invalid remote branding TOML: {e}")))
+    }
+}
+
+/// The cache, then its `ETag` or the removal of a stale one, so a later
+/// revalidation never pairs a new file with an old tag.
+fn write_cache(path: &Path, content: &str, etag: Option<&str>) -> BotResult<()> {
+    write_atomically(path, content)?;
+    let etag_path = etag_path(path);
+    match etag {
+        Some(etag) => write_atomically(&etag_path, etag),
+        None => match std::fs::remove_file(&etag_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        },
+    }
+}
+
+fn etag_path(path: &Path) -> PathBuf {
+    let mut etag = path.as_os_str().to_owned();
+    etag.push(".etag");
+    PathBuf::from(etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::branding::env_var;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ACME_TOML: &str = "name = \"Acme Bots\"\n\
+                             company = \"Acme Inc\"\n\
+                             primary_color = \"#ff6600\"\n";
+
+    fn toml_response(body: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_raw(body.to_string(), "application/toml")
+    }
+
+    async fn server_with(response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/branding"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn client() -> BotServerClient {
+        BotServerClient::new(None)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_toml_and_cache() {
+        let server = server_with(toml_response(ACME_TOML)).await;
+        let dir = tempfile::tempdir().ok();
+        let cache = dir.as_ref().map(|d| d.path().join("branding.toml"));
+        let cache = cache.unwrap_or_default();
+        let url = format!("{}/branding", server.uri());
+
+        let remote = RemoteBranding::fetch(&url, &client(), Some(&cache)).await;
+        assert_eq!(
+            remote.as_ref().map(RemoteBranding::source),
+            Some(&BrandingSource::Remote { url: url.clone() })
+        );
+        let layers = remote
+            .map(|r| BrandingLayers::new().with_remote(r))
+            .unwrap_or_default();
+        let (config, provenance) = BrandingConfig::load_with_layers(&layers);
+        assert_eq!(config.name, "Acme Bots");
+        assert_eq!(config.short_name, "AB");
+        assert_eq!(config.company.as_deref(), Some("Acme Inc"));
+        assert_eq!(config.docs_url, None);
+        assert!(config.is_white_label);
+        assert_eq!(
+            provenance.source("company"),
+            Some(&BrandingSource::Remote { url })
+        );
+
+        let cached = BrandingConfig::load_from_file(&cache).ok();
+        assert_eq!(
+            cached.map(|c| (c.name, c.primary_color)),
+            Some(("Acme Bots".to_string(), Some("#ff6600".to_string())))
+        );
+        assert!(!etag_path(&cache).exists());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_json() {
+        let body = json!({"name": "Globex", "secondary_color": "#003366"});
+        let server = server_with(ResponseTemplate::new(200).set_body_json(body)).await;
+        let url = format!("{}/branding", server.uri());
+
+        let remote = RemoteBranding::fetch(&url, &client(), None).await;
+        let layers = remote
+            .map(|r| BrandingLayers::new().with_remote(r))
+            .unwrap_or_default();
+        let (config, _) = BrandingConfig::load_with_layers(&layers);
+        assert_eq!(config.name, "Globex");
+        assert_eq!(config.secondary_color.as_deref(), Some("#003366"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_fetch_falls_back_to_cache() {
+        let dir = tempfile::tempdir().ok();
+        let cache = dir.as_ref().map(|d| d.path().join("branding.toml"));
+        let cache = cache.unwrap_or_default();
+        let good = server_with(toml_response(ACME_TOML)).await;
+        let url = format!("{}/branding", good.uri());
+        assert!(RemoteBranding::fetch(&url, &client(), Some(&cache))
+            .await
+            .is_some());
+
+        let down = server_with(ResponseTemplate::new(503)).await;
+        let url = format!("{}/branding", down.uri());
+        let remote = RemoteBranding::fetch(&url, &client(), Some(&cache)).await;
+        assert_eq!(
+            remote.as_ref().map(RemoteBranding::source),
+            Some(&BrandingSource::RemoteCache {
+                path: cache.clone()
+            })
+        );
+        let layers = remote
+            .map(|r| BrandingLayers::new().with_remote(r))
+            .unwrap_or_default();
+        let (config, _) = BrandingConfig::load_with_layers(&layers);
+        assert_eq!(config.company.as_deref(), Some("Acme Inc"));
+
+        let invalid = server_with(toml_response(
+            "name = \"Acme\"\nprimary_color = \"orange\"\n",
+        ))
+        .await;
+        let url = format!("{}/branding", invalid.uri());
+        let remote = RemoteBranding::fetch(&url, &client(), Some(&cache)).await;
+        assert!(matches!(
+            remote.as_ref().map(RemoteBranding::source),
+            Some(BrandingSource::RemoteCache { .. })
+        ));
+        let cached = BrandingConfig::load_from_file(&cache).ok();
+        assert_eq!(cached.map(|c| c.name), Some("Acme Bots".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_failed_fetch_without_cache_uses_other_layers() {
+        let down = server_with(ResponseTemplate::new(500)).await;
+        let url = format!("{}/branding", down.uri());
+        assert_eq!(RemoteBranding::fetch(&url, &client(), None).await, None);
+
+        let dir = tempfile::tempdir().ok();
+        let cache = dir.as_ref().map(|d| d.path().join("missing.toml"));
+        let cache = cache.unwrap_or_default();
+        let unreachable = "http://127.0.0.1:9/branding";
+        assert_eq!(
+            RemoteBranding::fetch(unreachable, &client(), Some(&cache)).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_etag_revalidation() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/branding"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/branding"))
+            .respond_with(toml_response(ACME_TOML).insert_header("etag", "\"v1\""))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dir = tempfile::tempdir().ok();
+        let cache = dir.as_ref().map(|d| d.path().join("branding.toml"));
+        let cache = cache.unwrap_or_default();
+        let url = format!("{}/branding", server.uri());
+
+        let first = RemoteBranding::fetch(&url, &client(), Some(&cache)).await;
+        assert_eq!(
+            std::fs::read_to_string(etag_path(&cache)).ok().as_deref(),
+            Some("\"v1\"")
+        );
+        let second = RemoteBranding::fetch(&url, &client(), Some(&cache)).await;
+        assert!(first.is_some());
+        assert_eq!(second, first);
+    }
+
+    #[tokio::test]
+    async fn test_remote_precedence() {
+        let server = server_with(toml_response(ACME_TOML)).await;
+        let url = format!("{}/branding", server.uri());
+        let remote = RemoteBranding::fetch(&url, &client(), None).await;
+        let dir = tempfile::tempdir().ok();
+        let file = dir.as_ref().map(|d| d.path().join(".product"));
+        let file = file.unwrap_or_default();
+        assert!(std::fs::write(&file, "name = \"Local Bots\"\ncompany = \"Local\"\n").is_ok());
+
+        let base = BrandingLayers::new()
+            .with_search_path(&file)
+            .with_env(env_var("primary_color"), "#cc0000");
+        let base = remote.map(|r| base.clone().with_remote(r)).unwrap_or(base);
+        let company = |precedence| {
+            let layers = base.clone().with_remote_precedence(precedence);
+            let (config, _) = BrandingConfig::load_with_layers(&layers);
+            (config.company, config.primary_color)
+        };
+        let expect =
+            |company: &str, color: &str| (Some(company.to_string()), Some(color.to_string()));
+        assert_eq!(
+            company(RemotePrecedence::BelowFiles),
+            expect("Local", "#cc0000")
+        );
+        assert_eq!(
+            company(RemotePrecedence::BelowEnv),
+            expect("Acme Inc", "#cc0000")
+        );
+        assert_eq!(
+            company(RemotePrecedence::AboveEnv),
+            expect("Acme Inc", "#ff6600")
+        );
+    }
+}
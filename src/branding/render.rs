@@ -0,0 +1,228 @@
+use super::theme::ThemeMode;
+use super::{is_link, BrandingConfig};
+use crate::markup::escape_html;
+use serde::{Deserialize, Serialize};
+
+/// A web app manifest, as served at `/manifest.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebManifest {
+    pub name: String,
+    pub short_name: String,
+    pub start_url: String,
+    pub display: String,
+    pub theme_color: String,
+    pub background_color: String,
+    pub icons: Vec<ManifestIcon>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestIcon {
+    pub src: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+impl ManifestIcon {
+    /// An icon at `src`, typed by its extension. SVG icons scale, so they
+    /// are declared for `any` size.
+    fn new(src: &str) -> Self {
+        let path = src.split(['?', '#']).next().unwrap_or_default();
+        let extension = path
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        let mime_type = match extension.as_str() {
+            "png" => Some("image/png"),
+            "svg" => Some("image/svg+xml"),
+            "ico" => Some("image/x-icon"),
+            "webp" => Some("image/webp"),
+            "jpg" | "jpeg" => Some("image/jpeg"),
+            _ => None,
+        };
+        Self {
+            src: src.to_string(),
+            sizes: (extension == "svg").then(|| "any".to_string()),
+            mime_type: mime_type.map(str::to_string),
+        }
+    }
+}
+
+impl BrandingConfig {
+    /// The web app manifest for this brand: the primary color as the theme
+    /// color, the light theme's background, and the logo and favicon as
+    /// icons when they are valid links.
+    #[must_use]
+    pub fn to_web_manifest(&self) -> WebManifest {
+        let theme = self.to_theme(ThemeMode::Light);
+        WebManifest {
+            name: self.name.clone(),
+            short_name: self.short_name.clone(),
+            start_url: "/".to_string(),
+            display: "standalone".to_string(),
+            theme_color: theme.primary,
+            background_color: theme.background,
+            icons: [&self.logo_url, &self.favicon_url]
+                .into_iter()
+                .filter_map(|url| url.as_deref().filter(|u| is_link(u)))
+                .map(ManifestIcon::new)
+                .collect(),
+        }
+    }
+
+    /// `body_html` in a transactional email layout: a header in the
+    /// primary color with the logo, or the name when there is none, and a
+    /// footer with the footer text and copyright. `body_html` is inserted
+    /// as is; every branding field is escaped. A logo given as a path is
+    /// made absolute with `domain`, and left out when there is no domain.
+    #[must_use]
+    pub fn render_email_frame(&self, body_html: &str) -> String {
+        let theme = self.to_theme(ThemeMode::Light);
+        let name = escape_html(&self.name);
+        let header = self.email_logo_url().map_or_else(
+            || name.clone(),
+            |logo| {
+                format!(
+                    "<img src=\"{}\" alt=\"{name}\" height=\"40\" style=\"display:block;border:0;\">",
+                    escape_html(&logo)
+                )
+            },
+        );
+        let font = "font-family:Arial,Helvetica,sans-serif;";
+        [
+            "<!DOCTYPE html>".to_string(),
+            "<html>".to_string(),
+            "<head>".to_string(),
+            "<meta charset=\"utf-8\">".to_string(),
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">".to_string(),
+            format!("<title>{name}</title>"),
+            "</head>".to_string(),
+            format!("<body style=\"margin:0;padding:0;background-color:{};\">", theme.background),
+            "<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" cellspacing=\"0\">".to_string(),
+            "<tr><td align=\"center\" style=\"padding:24px;\">".to_string(),
+            "<table role=\"presentation\" width=\"600\" cellpadding=\"0\" cellspacing=\"0\" style=\"max-width:600px;background-color:#ffffff;\">".to_string(),
+            format!(
+                "<tr><td style=\"padding:16px 24px;background-color:{};color:{};{font}font-size:20px;font-weight:bold;\">{header}</td></tr>",
+                theme.primary, theme.on_primary
+            ),
+            format!(
+                "<tr><td style=\"padding:24px;color:{};{font}font-size:14px;line-height:1.5;\">{body_html}</td></tr>",
+                theme.text
+            ),
+            format!(
+                "<tr><td style=\"padding:16px 24px;border-top:3px solid {};color:{};{font}font-size:12px;\">{}<br>{}</td></tr>",
+                theme.secondary,
+                theme.text,
+                escape_html(&self.footer_line()),
+                escape_html(&self.copyright_line())
+            ),
+            "</table>".to_string(),
+            "</td></tr>".to_string(),
+            "</table>".to_string(),
+            "</body>".to_string(),
+            "</html>".to_string(),
+        ]
+        .join("\n")
+    }
+
+    fn email_logo_url(&self) -> Option<String> {
+        self.absolute_link(self.logo_url.as_deref())
+    }
+
+    /// `url` when it is a valid link, made absolute with `domain` when it
+    /// is a path. `None` for a path when there is no domain.
+    pub(super) fn absolute_link(&self, url: Option<&str>) -> Option<String> {
+        let url = url.filter(|u| is_link(u))?;
+        if !url.starts_with('/') {
+            return Some(url.to_string());
+        }
+        let domain = self.domain.as_deref()?;
+        Some(format!("https://{domain}{url}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acme() -> BrandingConfig {
+        BrandingConfig {
+            name: "Acme Bots".to_string(),
+            short_name: "AB".to_string(),
+            company: Some("Acme Inc".to_string()),
+            domain: Some("acme.example".to_string()),
+            logo_url: Some("/static/logo.svg".to_string()),
+            favicon_url: Some("https://cdn.acme.example/favicon.ico?v=2".to_string()),
+            primary_color: Some("#ff6600".to_string()),
+            secondary_color: Some("#003366".to_string()),
+            footer_text: Some("Acme Bots, 1 Road Runner Way".to_string()),
+            copyright: Some("© 2024 Acme Inc".to_string()),
+            ..BrandingConfig::white_label_base()
+        }
+    }
+
+    #[test]
+    fn test_web_manifest_golden() {
+        assert_eq!(
+            serde_json::to_string_pretty(&acme().to_web_manifest()).unwrap_or_default(),
+            include_str!("../../tests/golden/branding_manifest.json").trim_end()
+        );
+    }
+
+    #[test]
+    fn test_web_manifest_skips_invalid_icons() {
+        let config = BrandingConfig {
+            logo_url: Some("javascript:alert(1)".to_string()),
+            favicon_url: None,
+            ..acme()
+        };
+        assert!(config.to_web_manifest().icons.is_empty());
+        assert_eq!(
+            ManifestIcon::new("/icons/app.PNG").mime_type.as_deref(),
+            Some("image/png")
+        );
+        assert_eq!(ManifestIcon::new("/icons/app").mime_type, None);
+    }
+
+    #[test]
+    fn test_email_frame_golden() {
+        assert_eq!(
+            acme().render_email_frame("<p>Your order has shipped.</p>"),
+            include_str!("../../tests/golden/branding_email.html").trim_end()
+        );
+    }
+
+    #[test]
+    fn test_email_frame_escapes_branding() {
+        let config = BrandingConfig {
+            name: "Acme \"Bots\"".to_string(),
+            footer_text: Some("<script>alert('x')</script>".to_string()),
+            copyright: Some("© Acme & Sons".to_string()),
+            logo_url: Some("https://acme.example/logo.png?a=1&b=\"2\"".to_string()),
+            ..acme()
+        };
+        let html = config.render_email_frame("<p>Hi</p>");
+        assert!(!html.contains("<script>"));
+        assert!(
+            html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;<br>© Acme &amp; Sons")
+        );
+        assert!(html.contains("alt=\"Acme &quot;Bots&quot;\""));
+        assert!(html.contains("src=\"https://acme.example/logo.png?a=1&amp;b=&quot;2&quot;\""));
+        assert!(html.contains("<title>Acme &quot;Bots&quot;</title>"));
+        assert!(html.contains("<p>Hi</p>"));
+    }
+
+    #[test]
+    fn test_email_frame_without_logo_domain() {
+        let config = BrandingConfig {
+            domain: None,
+            footer_text: None,
+            ..acme()
+        };
+        let html = config.render_email_frame("");
+        assert!(!html.contains("<img"));
+        assert!(html.contains("font-weight:bold;\">Acme Bots</td>"));
+        assert!(html.contains(">Powered by Acme Bots<br>"));
+    }
+}
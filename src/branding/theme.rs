@@ -0,0 +1,201 @@
+use super::{BrandingConfig, Color, DEFAULT_PRIMARY_COLOR, DEFAULT_SECONDARY_COLOR};
+use serde::{Deserialize, Serialize};
+
+/// Lowest WCAG contrast ratio `to_theme` allows for text: level AA for
+/// normal text.
+pub const MIN_TEXT_CONTRAST: f64 = 4.5;
+
+/// How many points `-dark` and `-light` shades move the lightness of a
+/// color.
+const SHADE_STEP: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// Colors for a UI derived from the brand colors, as `#rrggbb`. `text` and
+/// `accent` are readable on `background`, and `on_primary` and
+/// `on_secondary` on the color they are named for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theme {
+    pub mode: ThemeMode,
+    pub background: String,
+    pub text: String,
+    pub primary: String,
+    pub on_primary: String,
+    pub secondary: String,
+    pub on_secondary: String,
+    /// The primary color, shaded until it can be used for links and icons
+    /// on `background`.
+    pub accent: String,
+}
+
+impl BrandingConfig {
+    fn primary(&self) -> Color {
+        self.primary_color_parsed()
+            .unwrap_or(DEFAULT_PRIMARY_COLOR)
+            .with_alpha(u8::MAX)
+    }
+
+    fn secondary(&self) -> Color {
+        self.secondary_color_parsed()
+            .unwrap_or(DEFAULT_SECONDARY_COLOR)
+            .with_alpha(u8::MAX)
+    }
+
+    /// A `:root` block of CSS custom properties for the brand colors: for
+    /// `primary` and `secondary`, `--gb-<color>` itself, `-dark` and
+    /// `-light` shades for hover and disabled states, and `-contrast` for
+    /// text on it. Missing or unparsable colors use the General Bots ones,
+    /// and alpha is dropped.
+    #[must_use]
+    pub fn to_css_variables(&self) -> String {
+        let mut css = String::from(":root {\n");
+        for (name, color) in [("primary", self.primary()), ("secondary", self.secondary())] {
+            for (suffix, shade) in [
+                ("", color),
+                ("-dark", color.darken(SHADE_STEP)),
+                ("-light", color.lighten(SHADE_STEP)),
+                ("-contrast", color.best_text_on()),
+            ] {
+                css.push_str(&format!("  --gb-{name}{suffix}: {shade};\n"));
+            }
+        }
+        css.push('}');
+        css
+    }
+
+    /// A theme tinted with the primary hue. Text that would fall below
+    /// `MIN_TEXT_CONTRAST` on the background is replaced by black or white,
+    /// and the accent is shaded towards the text color until it reaches
+    /// that ratio, ending at black or white if it never does.
+    #[must_use]
+    pub fn to_theme(&self, mode: ThemeMode) -> Theme {
+        let primary = self.primary();
+        let secondary = self.secondary();
+        let (hue, saturation, _) = primary.to_hsl();
+        let (background, text, fallback, step) = match mode {
+            ThemeMode::Light => (
+                Color::from_hsl(hue, saturation.min(0.3), 0.98),
+                Color::from_hsl(hue, saturation.min(0.2), 0.12),
+                Color::BLACK,
+                -5.0,
+            ),
+            ThemeMode::Dark => (
+                Color::from_hsl(hue, saturation.min(0.2), 0.08),
+                Color::from_hsl(hue, saturation.min(0.2), 0.92),
+                Color::WHITE,
+                5.0,
+            ),
+        };
+        let readable = |color: Color| color.contrast_ratio(&background) >= MIN_TEXT_CONTRAST;
+        let text = if readable(text) { text } else { fallback };
+        let accent = (0..20)
+            .map(|i| primary.lighten(step * f64::from(i)))
+            .find(|c| readable(*c))
+            .unwrap_or(fallback);
+
+        Theme {
+            mode,
+            background: background.to_string(),
+            text: text.to_string(),
+            primary: primary.to_string(),
+            on_primary: primary.best_text_on().to_string(),
+            secondary: secondary.to_string(),
+            on_secondary: secondary.best_text_on().to_string(),
+            accent: accent.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brand(primary: &str, secondary: Option<&str>) -> BrandingConfig {
+        BrandingConfig {
+            primary_color: Some(primary.to_string()),
+            secondary_color: secondary.map(str::to_string),
+            ..BrandingConfig::default()
+        }
+    }
+
+    fn parse(hex: &str) -> Color {
+        hex.parse().unwrap_or(Color::BLACK)
+    }
+
+    #[test]
+    fn test_css_variables_golden() {
+        assert_eq!(
+            BrandingConfig::default().to_css_variables(),
+            include_str!("../../tests/golden/branding_variables.css").trim_end()
+        );
+    }
+
+    #[test]
+    fn test_css_variables_fall_back_to_default_colors() {
+        let config = BrandingConfig {
+            primary_color: Some("brand-green".to_string()),
+            secondary_color: None,
+            ..BrandingConfig::default()
+        };
+        assert_eq!(
+            config.to_css_variables(),
+            BrandingConfig::default().to_css_variables()
+        );
+    }
+
+    #[test]
+    fn test_theme_golden() {
+        let config = BrandingConfig::default();
+        let themes = [ThemeMode::Light, ThemeMode::Dark].map(|mode| config.to_theme(mode));
+        assert_eq!(
+            serde_json::to_string_pretty(&themes).unwrap_or_default(),
+            include_str!("../../tests/golden/branding_themes.json").trim_end()
+        );
+    }
+
+    #[test]
+    fn test_theme_meets_contrast_for_any_brand_color() {
+        let levels = [0x00, 0x33, 0x66, 0x80, 0x99, 0xcc, 0xff];
+        for r in levels {
+            for g in levels {
+                for b in levels {
+                    let hex = Color::rgb(r, g, b).to_string();
+                    let config = brand(&hex, Some(&hex));
+                    for mode in [ThemeMode::Light, ThemeMode::Dark] {
+                        let theme = config.to_theme(mode);
+                        let background = parse(&theme.background);
+                        for (name, color, on) in [
+                            ("text", &theme.text, background),
+                            ("accent", &theme.accent, background),
+                            ("on_primary", &theme.on_primary, parse(&theme.primary)),
+                            ("on_secondary", &theme.on_secondary, parse(&theme.secondary)),
+                        ] {
+                            let ratio = parse(color).contrast_ratio(&on);
+                            assert!(
+                                ratio >= MIN_TEXT_CONTRAST,
+                                "{hex} {mode:?} {name}: {ratio:.2}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreadable_accent_is_shaded() {
+        // Pale yellow cannot be read on a light background as is.
+        let theme = brand("#ffff99", None).to_theme(ThemeMode::Light);
+        assert_eq!(theme.primary, "#ffff99");
+        assert_eq!(theme.on_primary, "#000000");
+        assert_ne!(theme.accent, theme.primary);
+        let dark = brand("#ffff99", None).to_theme(ThemeMode::Dark);
+        assert_eq!(dark.accent, "#ffff99");
+    }
+}
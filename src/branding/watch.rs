@@ -0,0 +1,266 @@
+use super::BrandingConfig;
+use crate::error::{BotError, BotResult};
+use arc_swap::ArcSwap;
+use log::{info, warn};
+use notify::{Config, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the polling fallback looks at the file.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A running branding watch started by `watch`. Dropping it stops the
+/// watch too.
+pub struct BrandingWatcher {
+    path: PathBuf,
+    stopped: Arc<AtomicBool>,
+    watcher: Box<dyn Watcher + Send>,
+}
+
+impl BrandingWatcher {
+    /// Stops watching. The active config stays whatever was loaded last.
+    pub fn stop(self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        drop(self.watcher);
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for BrandingWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BrandingWatcher")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reloads the branding from `path` whenever the file changes. A file that
+/// parses and passes `BrandingConfig::validate` becomes the active config
+/// and is passed to `on_change`; anything else is logged and the previous
+/// config stays active. The directory holding `path` is watched, so files
+/// replaced by rename, as `save_to_file` does, are picked up. Falls back to
+/// polling where the platform watcher cannot be started.
+///
+/// # Errors
+/// Returns `BotError::Config` if `path` has no file name or neither the
+/// platform watcher nor polling can watch its directory.
+pub fn watch<F>(path: impl AsRef<Path>, on_change: F) -> BotResult<BrandingWatcher>
+where
+    F: Fn(&BrandingConfig) + Send + Sync + 'static,
+{
+    watch_slot(path.as_ref(), super::active(), on_change)
+}
+
+fn watch_slot<F>(
+    path: &Path,
+    slot: &'static ArcSwap<BrandingConfig>,
+    on_change: F,
+) -> BotResult<BrandingWatcher>
+where
+    F: Fn(&BrandingConfig) + Send + Sync + 'static,
+{
+    let Some(name) = path.file_name().map(ToOwned::to_owned) else {
+        return Err(BotError::config(format!(
+            "Cannot watch {}: not a file path",
+            path.display()
+        )));
+    };
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let stopped = Arc::new(AtomicBool::new(false));
+    let on_change = Arc::new(on_change);
+    let handler = || {
+        let path = path.to_path_buf();
+        let name = name.clone();
+        let stopped = Arc::clone(&stopped);
+        let on_change = Arc::clone(&on_change);
+        move |event: notify::Result<notify::Event>| match event {
+            Ok(event)
+                if !stopped.load(Ordering::SeqCst)
+                    && !matches!(event.kind, EventKind::Access(_))
+                    && event.paths.iter().any(|p| p.file_name() == Some(&name)) =>
+            {
+                reload(&path, slot, on_change.as_ref());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Branding watch on {} failed: {e}", path.display()),
+        }
+    };
+
+    let watcher = RecommendedWatcher::new(handler(), Config::default())
+        .and_then(|watcher| start(watcher, dir))
+        .or_else(|e| {
+            warn!(
+                "Cannot watch {} for changes ({e}), polling every {}s instead",
+                dir.display(),
+                POLL_INTERVAL.as_secs()
+            );
+            PollWatcher::new(
+                handler(),
+                Config::default().with_poll_interval(POLL_INTERVAL),
+            )
+            .and_then(|watcher| start(watcher, dir))
+        })
+        .map_err(|e| BotError::config(format!("Cannot watch {}: {e}", dir.display())))?;
+
+    info!("Watching {} for branding changes", path.display());
+    Ok(BrandingWatcher {
+        path: path.to_path_buf(),
+        stopped,
+        watcher,
+    })
+}
+
+fn start<W: Watcher + Send + 'static>(
+    mut watcher: W,
+    dir: &Path,
+) -> notify::Result<Box<dyn Watcher + Send>> {
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok(Box::new(watcher))
+}
+
+/// Loads `path` into `slot` if it holds a valid config different from the
+/// active one. Empty files are skipped, since a writer that truncates
+/// before writing produces one for a moment.
+fn reload(path: &Path, slot: &ArcSwap<BrandingConfig>, on_change: &dyn Fn(&BrandingConfig)) {
+    if std::fs::metadata(path).is_ok_and(|m| m.len() == 0) {
+        return;
+    }
+    let config = match BrandingConfig::load_from_file(path) {
+        Ok(config) => config,
+        Err(e) => {
+            warn!(
+                "Keeping the current branding, cannot read {}: {e}",
+                path.display()
+            );
+            return;
+        }
+    };
+    if let Err(e) = config.validate() {
+        let details = match &e {
+            BotError::ValidationFields(errors) => errors
+                .errors()
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; "),
+            other => other.to_string(),
+        };
+        warn!(
+            "Keeping the current branding, {} is invalid: {details}",
+            path.display()
+        );
+        return;
+    }
+    if **slot.load() == config {
+        return;
+    }
+    info!("Reloaded branding from {}: {}", path.display(), config.name);
+    let config = Arc::new(config);
+    slot.store(Arc::clone(&config));
+    on_change(&config);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    fn slot() -> &'static ArcSwap<BrandingConfig> {
+        Box::leak(Box::new(ArcSwap::from_pointee(BrandingConfig::default())))
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        condition()
+    }
+
+    fn product(name: &str, color: &str) -> String {
+        format!("name = \"{name}\"\nprimary_color = \"{color}\"\n")
+    }
+
+    #[test]
+    fn test_reload_swaps_and_rolls_back() {
+        let dir = tempfile::tempdir().ok();
+        let path = dir.as_ref().map(|d| d.path().join(".product"));
+        let path = path.unwrap_or_default();
+        assert!(std::fs::write(&path, product("Acme Bots", "#ff6600")).is_ok());
+
+        let slot = slot();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&seen);
+        let watcher = watch_slot(&path, slot, move |config| {
+            if let Ok(mut seen) = recorder.lock() {
+                seen.push(config.name.clone());
+            }
+        });
+        assert!(watcher.is_ok());
+
+        let first = BrandingConfig {
+            name: "Globex".to_string(),
+            short_name: "GX".to_string(),
+            ..BrandingConfig::default()
+        };
+        assert!(first.save_to_file(&path).is_ok());
+        assert!(wait_for(|| slot.load().name == "Globex"));
+
+        assert!(std::fs::write(&path, product("Broken", "not a color")).is_ok());
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(slot.load().name, "Globex");
+
+        assert!(std::fs::write(&path, product("Initech", "#003366")).is_ok());
+        assert!(wait_for(|| slot.load().name == "Initech"));
+        assert_eq!(
+            seen.lock().map(|seen| seen.clone()).unwrap_or_default(),
+            ["Globex", "Initech"]
+        );
+        assert_eq!(slot.load().primary_color.as_deref(), Some("#003366"));
+
+        if let Ok(watcher) = watcher {
+            watcher.stop();
+        }
+        assert!(std::fs::write(&path, product("Umbrella", "#cc0000")).is_ok());
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(slot.load().name, "Initech");
+    }
+
+    #[test]
+    fn test_reload_ignores_unreadable_and_unchanged_files() {
+        let dir = tempfile::tempdir().ok();
+        let path = dir.as_ref().map(|d| d.path().join(".product"));
+        let path = path.unwrap_or_default();
+        let slot = slot();
+        let calls = AtomicBool::new(false);
+        let on_change = |_: &BrandingConfig| calls.store(true, Ordering::SeqCst);
+
+        reload(&path, slot, &on_change);
+        assert!(std::fs::write(&path, "").is_ok());
+        reload(&path, slot, &on_change);
+        assert!(!calls.load(Ordering::SeqCst));
+        assert_eq!(*slot.load_full(), BrandingConfig::default());
+
+        let same = BrandingConfig::default()
+            .to_toml_string()
+            .unwrap_or_default();
+        assert!(std::fs::write(&path, same).is_ok());
+        reload(&path, slot, &on_change);
+        assert!(!calls.load(Ordering::SeqCst));
+        assert_eq!(*slot.load_full(), BrandingConfig::default());
+    }
+}
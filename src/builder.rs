@@ -0,0 +1,672 @@
+use crate::error::ValidationErrors;
+use crate::message_types::MessageType;
+use crate::models::{
+    Attachment, BotResponse, Card, ContentFormat, MessagePayload, Suggestion, UserMessage,
+};
+use crate::version::{metadata_keys, ComponentSource, ComponentStatus, ComponentVersion};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn require_route(
+    errors: &mut ValidationErrors,
+    bot_id: &str,
+    user_id: &str,
+    session_id: &str,
+    channel: &str,
+) {
+    errors
+        .require("bot_id", bot_id)
+        .require("user_id", user_id)
+        .require("session_id", session_id)
+        .require("channel", channel);
+}
+
+/// Builds a `BotResponse` from named setters, so the routing ids cannot be
+/// swapped the way positional arguments can.
+///
+/// ```
+/// use botlib::BotResponse;
+///
+/// let response = BotResponse::builder()
+///     .bot_id("support-bot")
+///     .session_id("s-42")
+///     .user_id("5511988887777")
+///     .channel("whatsapp")
+///     .content("Seu pedido saiu para entrega.")
+///     .suggestion("Rastrear")
+///     .build();
+/// assert_eq!(response.map(|r| r.session_id).ok().as_deref(), Some("s-42"));
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct BotResponseBuilder {
+    response: BotResponse,
+}
+
+impl BotResponse {
+    pub fn builder() -> BotResponseBuilder {
+        BotResponseBuilder::default()
+    }
+}
+
+impl BotResponseBuilder {
+    pub fn bot_id(mut self, bot_id: impl Into<String>) -> Self {
+        self.response.bot_id = bot_id.into();
+        self
+    }
+
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.response.session_id = session_id.into();
+        self
+    }
+
+    pub fn user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.response.user_id = user_id.into();
+        self
+    }
+
+    pub fn channel(mut self, channel: impl Into<String>) -> Self {
+        self.response.channel = channel.into();
+        self
+    }
+
+    /// Routing, context and correlation copied from `source`, as in
+    /// `BotResponse::reply_to`.
+    pub fn in_reply_to(mut self, source: &UserMessage) -> Self {
+        self.response.bot_id.clone_from(&source.bot_id);
+        self.response.session_id.clone_from(&source.session_id);
+        self.response.user_id.clone_from(&source.user_id);
+        self.response.channel.clone_from(&source.channel);
+        self.response.context_name.clone_from(&source.context_name);
+        self.response.correlation_id = source.correlation_id;
+        self.response.reply_to_id = source.id;
+        self
+    }
+
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.response.content = content.into();
+        self
+    }
+
+    pub const fn content_format(mut self, format: ContentFormat) -> Self {
+        self.response.content_format = format;
+        self
+    }
+
+    pub fn suggestion(mut self, suggestion: impl Into<Suggestion>) -> Self {
+        self.response.suggestions.push(suggestion.into());
+        self
+    }
+
+    pub fn suggestions<I, S>(mut self, suggestions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Suggestion>,
+    {
+        self.response
+            .suggestions
+            .extend(suggestions.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn card(mut self, card: Card) -> Self {
+        self.response.cards.push(card);
+        self
+    }
+
+    pub fn mention(self, user_id: impl Into<String>, offset: usize, length: usize) -> Self {
+        Self {
+            response: self.response.with_mention(user_id, offset, length),
+        }
+    }
+
+    pub fn context(mut self, name: impl Into<String>) -> Self {
+        self.response.context_name = Some(name.into());
+        self
+    }
+
+    pub const fn correlation_id(mut self, correlation_id: Uuid) -> Self {
+        self.response.correlation_id = Some(correlation_id);
+        self
+    }
+
+    pub const fn reply_to_id(mut self, reply_to_id: Uuid) -> Self {
+        self.response.reply_to_id = Some(reply_to_id);
+        self
+    }
+
+    pub const fn send_at(mut self, send_at: DateTime<Utc>) -> Self {
+        self.response.send_at = Some(send_at);
+        self
+    }
+
+    pub const fn expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.response.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Defaults to `true`; pass `false` for a streamed chunk.
+    pub const fn is_complete(mut self, is_complete: bool) -> Self {
+        self.response.is_complete = is_complete;
+        self
+    }
+
+    pub fn meta(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.response.set_meta(key, value);
+        self
+    }
+
+    /// Ids and channel must be non-blank, and a complete response needs
+    /// content unless it carries suggestions or cards. Limits are left to
+    /// `BotResponse::validate`.
+    ///
+    /// # Errors
+    /// Returns one `required` `FieldError` per missing field.
+    pub fn build(self) -> Result<BotResponse, ValidationErrors> {
+        let response = self.response;
+        let mut errors = ValidationErrors::new();
+        require_route(
+            &mut errors,
+            &response.bot_id,
+            &response.user_id,
+            &response.session_id,
+            &response.channel,
+        );
+        if response.is_complete && response.suggestions.is_empty() && response.cards.is_empty() {
+            errors.require("content", &response.content);
+        }
+        if errors.is_empty() {
+            Ok(response)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Builds a `UserMessage` from named setters. The message gets a fresh id
+/// and the current time unless they are set.
+///
+/// ```
+/// use botlib::UserMessage;
+///
+/// let message = UserMessage::builder()
+///     .bot_id("support-bot")
+///     .user_id("5511988887777")
+///     .session_id("s-42")
+///     .channel("whatsapp")
+///     .content("Qual o status do meu pedido?")
+///     .build();
+/// assert_eq!(message.map(|m| m.user_id).ok().as_deref(), Some("5511988887777"));
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct UserMessageBuilder {
+    message: UserMessage,
+}
+
+impl Default for UserMessageBuilder {
+    fn default() -> Self {
+        Self {
+            message: UserMessage::text("", "", "", "", ""),
+        }
+    }
+}
+
+impl UserMessage {
+    pub fn builder() -> UserMessageBuilder {
+        UserMessageBuilder::default()
+    }
+}
+
+impl UserMessageBuilder {
+    pub fn bot_id(mut self, bot_id: impl Into<String>) -> Self {
+        self.message.bot_id = bot_id.into();
+        self
+    }
+
+    pub fn user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.message.user_id = user_id.into();
+        self
+    }
+
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.message.session_id = session_id.into();
+        self
+    }
+
+    pub fn channel(mut self, channel: impl Into<String>) -> Self {
+        self.message.channel = channel.into();
+        self
+    }
+
+    pub const fn id(mut self, id: Uuid) -> Self {
+        self.message.id = Some(id);
+        self
+    }
+
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.message.content = content.into();
+        self
+    }
+
+    pub const fn message_type(mut self, message_type: MessageType) -> Self {
+        self.message.message_type = message_type;
+        self
+    }
+
+    pub fn media(mut self, url: impl Into<String>) -> Self {
+        self.message.media_url = Some(url.into());
+        self
+    }
+
+    pub fn attachment(mut self, attachment: Attachment) -> Self {
+        self.message.attachments.push(attachment);
+        self
+    }
+
+    pub fn payload(mut self, payload: MessagePayload) -> Self {
+        self.message.payload = Some(payload);
+        self
+    }
+
+    pub fn mention(self, user_id: impl Into<String>, offset: usize, length: usize) -> Self {
+        Self {
+            message: self.message.with_mention(user_id, offset, length),
+        }
+    }
+
+    pub fn context(mut self, name: impl Into<String>) -> Self {
+        self.message.context_name = Some(name.into());
+        self
+    }
+
+    pub const fn correlation_id(mut self, correlation_id: Uuid) -> Self {
+        self.message.correlation_id = Some(correlation_id);
+        self
+    }
+
+    pub const fn reply_to_id(mut self, reply_to_id: Uuid) -> Self {
+        self.message.reply_to_id = Some(reply_to_id);
+        self
+    }
+
+    pub const fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.message.timestamp = timestamp;
+        self
+    }
+
+    pub fn meta(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.message.set_meta(key, value);
+        self
+    }
+
+    /// Ids and channel must be non-blank, and content is required unless
+    /// the message carries media, attachments or a payload. Limits and
+    /// payload checks are left to `UserMessage::validate`.
+    ///
+    /// # Errors
+    /// Returns one `required` `FieldError` per missing field.
+    pub fn build(self) -> Result<UserMessage, ValidationErrors> {
+        let message = self.message;
+        let mut errors = ValidationErrors::new();
+        require_route(
+            &mut errors,
+            &message.bot_id,
+            &message.user_id,
+            &message.session_id,
+            &message.channel,
+        );
+        if message.media_url.is_none()
+            && message.attachments.is_empty()
+            && message.payload.is_none()
+        {
+            errors.require("content", &message.content);
+        }
+        if errors.is_empty() {
+            Ok(message)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Builds a `ComponentVersion` from its name and version. Everything else
+/// starts empty, with status `Unknown` and source `External`.
+///
+/// ```
+/// use botlib::{ComponentSource, ComponentStatus, ComponentVersion};
+///
+/// let vault = ComponentVersion::builder("vault", "1.15.0")
+///     .with_status(ComponentStatus::Running)
+///     .with_source(ComponentSource::Docker)
+///     .with_description("Secrets store")
+///     .with_criticality(true)
+///     .build();
+/// assert!(vault.is_critical());
+/// assert_eq!(vault.description(), Some("Secrets store"));
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ComponentVersionBuilder {
+    component: ComponentVersion,
+}
+
+impl ComponentVersion {
+    pub fn builder(name: impl Into<String>, version: impl Into<String>) -> ComponentVersionBuilder {
+        ComponentVersionBuilder {
+            component: Self {
+                name: name.into(),
+                version: version.into(),
+                latest_version: None,
+                update_available: false,
+                status: ComponentStatus::Unknown,
+                last_checked: None,
+                source: ComponentSource::External,
+                metadata: HashMap::new(),
+                last_heartbeat: None,
+                heartbeat_timeout_secs: None,
+                status_before_stale: None,
+                depends_on: Vec::new(),
+                compat: HashMap::new(),
+                started_at: None,
+                restart_count: 0,
+                recent_restarts: Vec::new(),
+                last_error: None,
+            },
+        }
+    }
+}
+
+impl ComponentVersionBuilder {
+    pub const fn with_status(mut self, status: ComponentStatus) -> Self {
+        self.component.status = status;
+        self
+    }
+
+    pub fn with_source(mut self, source: ComponentSource) -> Self {
+        self.component.source = source;
+        self
+    }
+
+    pub fn with_description(self, description: impl Into<String>) -> Self {
+        self.with_metadata(metadata_keys::DESCRIPTION, description)
+    }
+
+    pub fn with_repo(self, url: impl Into<String>) -> Self {
+        self.with_metadata(metadata_keys::REPO, url)
+    }
+
+    /// Sets or clears the `metadata_keys::CRITICAL` flag.
+    pub fn with_criticality(mut self, critical: bool) -> Self {
+        if critical {
+            self.with_metadata(metadata_keys::CRITICAL, "true")
+        } else {
+            self.component.metadata.remove(metadata_keys::CRITICAL);
+            self
+        }
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.component.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    pub const fn with_last_checked(mut self, at: DateTime<Utc>) -> Self {
+        self.component.last_checked = Some(at);
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> ComponentVersion {
+        self.component
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Location;
+
+    fn missing<T>(result: Result<T, ValidationErrors>) -> Vec<String> {
+        result
+            .err()
+            .map(|errors| {
+                errors
+                    .errors()
+                    .iter()
+                    .filter(|e| e.code == "required")
+                    .map(|e| e.field.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn routed_response() -> BotResponseBuilder {
+        BotResponse::builder()
+            .bot_id("bot")
+            .session_id("s1")
+            .user_id("u1")
+            .channel("whatsapp")
+    }
+
+    fn routed_message() -> UserMessageBuilder {
+        UserMessage::builder()
+            .bot_id("bot")
+            .user_id("u1")
+            .session_id("s1")
+            .channel("whatsapp")
+    }
+
+    #[test]
+    fn test_bot_response_builder_sets_named_fields() {
+        let response = routed_response()
+            .content("Olá")
+            .content_format(ContentFormat::PlainText)
+            .suggestions(["Sim", "Não"])
+            .meta("trace", "t-1")
+            .build()
+            .ok();
+        assert_eq!(
+            response.as_ref().map(|r| (
+                r.bot_id.as_str(),
+                r.session_id.as_str(),
+                r.user_id.as_str(),
+                r.channel.as_str(),
+                r.content_format,
+                r.suggestions.len(),
+                r.is_complete
+            )),
+            Some((
+                "bot",
+                "s1",
+                "u1",
+                "whatsapp",
+                ContentFormat::PlainText,
+                2,
+                true
+            ))
+        );
+        assert_eq!(
+            response.as_ref().and_then(|r| r.get_meta_str("trace")),
+            Some("t-1")
+        );
+    }
+
+    #[test]
+    fn test_bot_response_builder_missing_fields() {
+        assert_eq!(
+            missing(BotResponse::builder().content("hi").build()),
+            ["bot_id", "user_id", "session_id", "channel"]
+        );
+        for (builder, field) in [
+            (routed_response().bot_id(" "), "bot_id"),
+            (routed_response().user_id(""), "user_id"),
+            (routed_response().session_id(""), "session_id"),
+            (routed_response().channel(""), "channel"),
+        ] {
+            assert_eq!(missing(builder.content("hi").build()), [field]);
+        }
+    }
+
+    #[test]
+    fn test_bot_response_builder_content_rule() {
+        assert_eq!(missing(routed_response().build()), ["content"]);
+        assert!(routed_response().suggestion("Menu").build().is_ok());
+        assert!(routed_response()
+            .card(Card::new("Plano Pro"))
+            .build()
+            .is_ok());
+        assert!(routed_response().is_complete(false).build().is_ok());
+    }
+
+    #[test]
+    fn test_bot_response_builder_in_reply_to() {
+        let source = UserMessage::text("bot", "u1", "s1", "web", "oi")
+            .with_context("vendas")
+            .with_correlation_id(Uuid::new_v4());
+        let built = BotResponse::builder()
+            .in_reply_to(&source)
+            .content("Olá!")
+            .build()
+            .ok();
+        let reply = BotResponse::reply_to(&source, "Olá!");
+        assert_eq!(
+            built.map(|r| (
+                r.bot_id,
+                r.session_id,
+                r.user_id,
+                r.channel,
+                r.context_name,
+                r.correlation_id,
+                r.reply_to_id
+            )),
+            Some((
+                reply.bot_id,
+                reply.session_id,
+                reply.user_id,
+                reply.channel,
+                reply.context_name,
+                reply.correlation_id,
+                reply.reply_to_id
+            ))
+        );
+    }
+
+    #[test]
+    fn test_user_message_builder_missing_fields() {
+        assert_eq!(
+            missing(UserMessage::builder().build()),
+            ["bot_id", "user_id", "session_id", "channel", "content"]
+        );
+        for (builder, field) in [
+            (routed_message().bot_id(""), "bot_id"),
+            (routed_message().user_id("\t"), "user_id"),
+            (routed_message().session_id(""), "session_id"),
+            (routed_message().channel(""), "channel"),
+        ] {
+            assert_eq!(missing(builder.content("hi").build()), [field]);
+        }
+        assert_eq!(missing(routed_message().build()), ["content"]);
+    }
+
+    #[test]
+    fn test_user_message_builder_content_optional_with_media() {
+        assert!(routed_message().media("https://x/a.jpg").build().is_ok());
+        assert!(routed_message()
+            .attachment(Attachment::file("https://x/a.pdf"))
+            .build()
+            .is_ok());
+        assert!(routed_message()
+            .payload(MessagePayload::Location(Location::new(-23.5, -46.6)))
+            .build()
+            .is_ok());
+
+        let id = Uuid::new_v4();
+        let message = routed_message().id(id).content("oi").build().ok();
+        assert_eq!(
+            message.map(|m| (m.id, m.user_id, m.session_id, m.message_type)),
+            Some((
+                Some(id),
+                "u1".to_string(),
+                "s1".to_string(),
+                MessageType::USER
+            ))
+        );
+    }
+
+    #[test]
+    fn test_component_version_builder_defaults() {
+        let component = ComponentVersion::builder("search", "8.11.0").build();
+        assert_eq!(component.name, "search");
+        assert_eq!(component.version, "8.11.0");
+        assert_eq!(component.status, ComponentStatus::Unknown);
+        assert_eq!(component.source, ComponentSource::External);
+        assert!(component.metadata.is_empty());
+        assert!(component.last_checked.is_none());
+        assert!(component.latest_version.is_none() && !component.update_available);
+        assert!(component.depends_on.is_empty() && component.compat.is_empty());
+        assert_eq!(component.description(), None);
+        assert_eq!(component.repo_url(), None);
+        assert!(!component.is_critical());
+    }
+
+    #[test]
+    fn test_component_version_builder_metadata() {
+        let component = ComponentVersion::builder("vault", "1.15.0")
+            .with_description("Secrets store")
+            .with_repo("https://github.com/hashicorp/vault")
+            .with_criticality(true)
+            .with_metadata("channel", "stable")
+            .build();
+        assert_eq!(component.description(), Some("Secrets store"));
+        assert_eq!(
+            component.repo_url(),
+            Some("https://github.com/hashicorp/vault")
+        );
+        assert!(component.is_critical());
+        assert_eq!(
+            component.metadata.get("channel").map(String::as_str),
+            Some("stable")
+        );
+
+        let demoted = ComponentVersion::builder("vault", "1.15.0")
+            .with_criticality(true)
+            .with_criticality(false)
+            .with_description("  ")
+            .build();
+        assert!(!demoted.is_critical());
+        assert!(!demoted.metadata.contains_key(metadata_keys::CRITICAL));
+        assert_eq!(demoted.description(), None);
+    }
+
+    #[test]
+    fn test_builtin_components_use_well_known_keys() {
+        let registry = crate::version::VersionRegistry::new();
+        let botserver = registry.get_component("botserver");
+        assert_eq!(
+            botserver.and_then(ComponentVersion::description),
+            Some("Core bot server")
+        );
+        assert_eq!(
+            botserver.and_then(ComponentVersion::repo_url),
+            Some("https://github.com/GeneralBots/botserver")
+        );
+        assert!(
+            botserver.is_some_and(|c| c.metadata.contains_key(metadata_keys::GIT_COMMIT)
+                && c.status == ComponentStatus::Running
+                && c.source == ComponentSource::Builtin
+                && c.last_checked.is_some())
+        );
+        assert_eq!(
+            registry
+                .get_component("llm")
+                .and_then(ComponentVersion::description),
+            Some("LLM integration (Claude, GPT, etc.)")
+        );
+        assert!(registry
+            .get_component("basic")
+            .is_some_and(|c| c.repo_url().is_none()));
+    }
+}
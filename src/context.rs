@@ -0,0 +1,209 @@
+use crate::models::Message;
+use std::fmt;
+use std::sync::Arc;
+
+/// Measures text against a `ContextWindow` budget. Closures
+/// `Fn(&str) -> usize` implement it, so a real tokenizer can be plugged in.
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+}
+
+impl<F: Fn(&str) -> usize + Send + Sync> TokenCounter for F {
+    fn count(&self, text: &str) -> usize {
+        self(text)
+    }
+}
+
+/// Counts characters, not bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CharCounter;
+
+impl TokenCounter for CharCounter {
+    fn count(&self, text: &str) -> usize {
+        text.chars().count()
+    }
+}
+
+/// Roughly four characters per token, rounded up. Good enough for budgeting
+/// when the model's tokenizer is not available.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApproxTokenCounter;
+
+impl TokenCounter for ApproxTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+/// Tracks how much of a model's context a prompt uses. Each pushed line
+/// costs `role: text` plus its newline.
+#[derive(Clone)]
+pub struct ContextWindow {
+    max: usize,
+    used: usize,
+    counter: Arc<dyn TokenCounter>,
+}
+
+impl fmt::Debug for ContextWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextWindow")
+            .field("max", &self.max)
+            .field("used", &self.used)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ContextWindow {
+    /// A window of `max_tokens`, counted with `ApproxTokenCounter`.
+    #[must_use]
+    pub fn new(max_tokens: usize) -> Self {
+        Self::with_counter(max_tokens, ApproxTokenCounter)
+    }
+
+    #[must_use]
+    pub fn chars(max_chars: usize) -> Self {
+        Self::with_counter(max_chars, CharCounter)
+    }
+
+    #[must_use]
+    pub fn with_counter(max: usize, counter: impl TokenCounter + 'static) -> Self {
+        Self {
+            max,
+            used: 0,
+            counter: Arc::new(counter),
+        }
+    }
+
+    #[must_use]
+    pub fn cost(&self, text: &str) -> usize {
+        self.counter.count(text)
+    }
+
+    fn line_cost(&self, role: &str, text: &str) -> usize {
+        self.cost(&format!("{role}: {text}\n"))
+    }
+
+    pub fn push(&mut self, role: &str, text: &str) -> &mut Self {
+        self.used = self.used.saturating_add(self.line_cost(role, text));
+        self
+    }
+
+    /// Whether `text` still fits; a text that exactly fills the window
+    /// fits.
+    #[must_use]
+    pub fn fits(&self, text: &str) -> bool {
+        self.used.saturating_add(self.cost(text)) <= self.max
+    }
+
+    #[must_use]
+    pub const fn remaining(&self) -> usize {
+        self.max.saturating_sub(self.used)
+    }
+
+    /// `(used, max)`, in the shape `BotResponse::with_context` takes.
+    #[must_use]
+    pub const fn usage(&self) -> (usize, usize) {
+        (self.used, self.max)
+    }
+
+    /// The newest `messages` whose lines fit in the remaining space, evicting
+    /// from the oldest end. Stops at the first message that does not fit so
+    /// the result stays contiguous.
+    #[must_use]
+    pub fn trim_to_fit<'a>(&self, messages: &'a [Message]) -> &'a [Message] {
+        let mut budget = self.remaining();
+        let mut start = messages.len();
+        for message in messages.iter().rev() {
+            let cost = self.line_cost(&message.direction.to_string(), &message.content);
+            if cost > budget {
+                break;
+            }
+            budget -= cost;
+            start -= 1;
+        }
+        messages.get(start..).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BotResponse, MessageDirection};
+
+    fn messages(contents: &[&str]) -> Vec<Message> {
+        contents
+            .iter()
+            .map(|content| Message::new("s1", MessageDirection::User, *content))
+            .collect()
+    }
+
+    fn contents(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_trim_evicts_oldest_first() {
+        let history = messages(&["first", "second", "third"]);
+        // "User: third\n" is 12 characters, "User: second\n" 13.
+        assert_eq!(
+            contents(ContextWindow::chars(25).trim_to_fit(&history)),
+            ["second", "third"]
+        );
+        assert_eq!(
+            contents(ContextWindow::chars(24).trim_to_fit(&history)),
+            ["third"]
+        );
+        assert!(ContextWindow::chars(11).trim_to_fit(&history).is_empty());
+
+        let mut window = ContextWindow::chars(37);
+        window.push("System", "hi");
+        assert_eq!(window.usage(), (11, 37));
+        assert_eq!(contents(window.trim_to_fit(&history)), ["second", "third"]);
+
+        let response = BotResponse::default().with_context_window("support", &window);
+        assert_eq!(
+            (response.context_length, response.context_max_length),
+            (11, 37)
+        );
+    }
+
+    #[test]
+    fn test_exact_boundary() {
+        let mut window = ContextWindow::chars(10);
+        assert!(window.fits("0123456789"));
+        assert!(!window.fits("0123456789a"));
+
+        window.push("Bot", "abc");
+        assert_eq!(window.usage(), (9, 10));
+        assert!(window.fits("x"));
+        assert!(!window.fits("xy"));
+        assert!(window.fits(""));
+        window.push("Bot", "");
+        assert_eq!(window.usage(), (15, 10));
+        assert_eq!(window.remaining(), 0);
+        assert!(!window.fits(""));
+    }
+
+    #[test]
+    fn test_multi_byte_characters_count_once() {
+        let window = ContextWindow::chars(4);
+        assert!(window.fits("☕ção"));
+        assert!(!window.fits("☕ção!"));
+
+        let tokens = ContextWindow::new(2);
+        assert_eq!(tokens.cost("☕☕☕☕"), 1);
+        assert_eq!(tokens.cost("Olá, café"), 3);
+        assert!(tokens.fits("12345678"));
+        assert!(!tokens.fits("123456789"));
+    }
+
+    #[test]
+    fn test_custom_counter() {
+        let words = ContextWindow::with_counter(3, |text: &str| text.split_whitespace().count());
+        assert!(words.fits("one two three"));
+        assert!(!words.fits("one two three four"));
+        let mut window = words.clone();
+        window.push("User", "hello there");
+        assert_eq!(window.usage(), (3, 3));
+    }
+}
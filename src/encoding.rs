@@ -0,0 +1,185 @@
+use crate::error::{BotError, BotResult};
+use crate::models::{
+    BotResponse, InboundFrame, MessageEvent, MessageEventType, Session, UserMessage,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Written as the first byte of every encoded value. Bump it when a change
+/// to the models cannot be read by older builds.
+pub const ENCODING_VERSION: u8 = 1;
+
+/// Compact binary encoding for the message bus: one version byte followed
+/// by MessagePack with named fields, so added fields with serde defaults
+/// stay readable in both directions.
+pub trait BinaryEncode: Serialize + DeserializeOwned {
+    /// # Errors
+    /// Returns `BotError::Internal` if the value cannot be serialized.
+    fn encode(&self) -> BotResult<Vec<u8>> {
+        let mut bytes = vec![ENCODING_VERSION];
+        rmp_serde::encode::write_named(&mut bytes, self)
+            .map_err(|e| BotError::internal(format!("MessagePack encoding failed: {e}")))?;
+        Ok(bytes)
+    }
+
+    /// # Errors
+    /// Returns `BotError::Validation` if `bytes` is empty, was written by a
+    /// newer schema version, or is not a valid encoding of `Self`.
+    fn decode(bytes: &[u8]) -> BotResult<Self> {
+        let Some((&version, body)) = bytes.split_first() else {
+            return Err(BotError::validation("encoded payload is empty"));
+        };
+        if version == 0 || version > ENCODING_VERSION {
+            return Err(BotError::validation(format!(
+                "encoded payload has schema version {version}, but this build reads versions 1 to {ENCODING_VERSION}"
+            )));
+        }
+        rmp_serde::from_slice(body)
+            .map_err(|e| BotError::validation(format!("invalid MessagePack payload: {e}")))
+    }
+}
+
+impl BinaryEncode for UserMessage {}
+impl BinaryEncode for BotResponse {}
+impl BinaryEncode for Session {}
+impl BinaryEncode for MessageEvent {}
+impl BinaryEncode for MessageEventType {}
+impl BinaryEncode for InboundFrame {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Attachment, Card, ContactCard, Location, Participant, SuggestionAction};
+    use chrono::Utc;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn round_trip<T: BinaryEncode>(value: &T) -> Option<serde_json::Value> {
+        let bytes = value.encode().ok()?;
+        assert_eq!(bytes.first(), Some(&ENCODING_VERSION));
+        T::decode(&bytes)
+            .ok()
+            .and_then(|back| serde_json::to_value(back).ok())
+    }
+
+    fn assert_round_trip<T: BinaryEncode>(value: &T) {
+        assert_eq!(round_trip(value), serde_json::to_value(value).ok());
+    }
+
+    fn user_message() -> UserMessage {
+        let mut msg = UserMessage::text("bot", "u1", "s1", "whatsapp", "Segue o comprovante")
+            .with_attachment(
+                Attachment::image("https://cdn.example.com/a.jpg")
+                    .with_mime_type("image/jpeg")
+                    .with_size(2048),
+            )
+            .with_mention("u2", 0, 5)
+            .with_correlation_id(Uuid::new_v4());
+        msg.set_meta(
+            "order",
+            json!({"id": 42, "items": ["a", "b"], "paid": true}),
+        );
+        msg.set_meta("score", 0.75);
+        msg
+    }
+
+    #[test]
+    fn test_user_message_round_trip() {
+        assert_round_trip(&user_message());
+        assert_round_trip(&UserMessage::location(
+            "bot",
+            "u1",
+            "s1",
+            "whatsapp",
+            Location::new(-23.5613, -46.6565).with_name("MASP"),
+        ));
+        assert_round_trip(&UserMessage::contact(
+            "bot",
+            "u1",
+            "s1",
+            "whatsapp",
+            ContactCard::new("Maria").with_phone("+5511988887777"),
+        ));
+        assert_round_trip(&UserMessage::deletion_of(Uuid::new_v4()));
+    }
+
+    #[test]
+    fn test_bot_response_round_trip() {
+        let mut response = BotResponse::reply_to(&user_message(), "Recebido!")
+            .with_suggestions(["Sim", "Não"])
+            .with_card(Card::new("Plano Pro").with_button(
+                "Assinar",
+                SuggestionAction::OpenUrl {
+                    url: "https://example.com/pro".to_string(),
+                },
+            ))
+            .with_send_at(Utc::now());
+        response.set_meta("trace", json!({"spans": [1, 2, 3]}));
+        assert_round_trip(&response);
+        assert_round_trip(
+            &BotResponse::streaming("bot", "s1", "u1", "web", "tok").next_chunk("Olá"),
+        );
+    }
+
+    #[test]
+    fn test_session_and_events_round_trip() {
+        assert_round_trip(
+            &Session::new(Uuid::new_v4(), Uuid::new_v4(), "Suporte")
+                .with_expiry(Utc::now() + chrono::Duration::hours(1))
+                .with_participant(Participant::new("u1", "Maria")),
+        );
+        let events = [
+            MessageEvent::typing_start("s1", "u1"),
+            MessageEvent::failed("s1", "u1", Uuid::new_v4(), "blocked"),
+            MessageEvent::reaction("s1", "u1", Uuid::new_v4(), "👍"),
+        ];
+        for event in &events {
+            assert_round_trip(event);
+            assert_round_trip(&event.event_type);
+            let bytes = event.encode().unwrap_or_default();
+            assert_eq!(MessageEvent::decode(&bytes).ok().as_ref(), Some(event));
+        }
+        assert_round_trip(&InboundFrame::from(user_message()));
+        assert_round_trip(&InboundFrame::from(MessageEvent::typing_stop("s1", "u1")));
+    }
+
+    #[test]
+    fn test_smaller_than_json() {
+        let msg = user_message();
+        let binary = msg.encode().map(|b| b.len()).unwrap_or_default();
+        let text = serde_json::to_vec(&msg)
+            .map(|b| b.len())
+            .unwrap_or_default();
+        assert!(binary > 0 && binary < text, "{binary} >= {text}");
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_input() {
+        let message = |bytes: &[u8]| match Session::decode(bytes) {
+            Err(BotError::Validation(message)) => message.into(),
+            _ => String::new(),
+        };
+        assert_eq!(message(&[]), "encoded payload is empty");
+
+        let mut bytes = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x")
+            .encode()
+            .unwrap_or_default();
+        if let Some(version) = bytes.first_mut() {
+            *version = ENCODING_VERSION + 1;
+        }
+        assert_eq!(
+            message(&bytes),
+            format!(
+                "encoded payload has schema version {}, but this build reads versions 1 to {ENCODING_VERSION}",
+                ENCODING_VERSION + 1
+            )
+        );
+        assert!(message(&[0]).contains("schema version 0"));
+        assert!(message(&[ENCODING_VERSION, 0xc1]).starts_with("invalid MessagePack payload"));
+
+        let event = MessageEvent::typing_start("s1", "u1")
+            .encode()
+            .unwrap_or_default();
+        assert!(message(&event).starts_with("invalid MessagePack payload"));
+    }
+}
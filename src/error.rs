@@ -1,35 +1,473 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::time::Duration;
 use thiserror::Error;
+use uuid::Uuid;
 
 pub type BotResult<T> = Result<T, BotError>;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request itself is wrong; retrying it unchanged will fail again.
+    UserError,
+    /// A temporary condition; the same request may succeed later.
+    Transient,
+    /// A server-side fault that retrying will not fix.
+    Permanent,
+    /// Not enough information to decide.
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejected_value: Option<serde_json::Value>,
+}
+
+impl FieldError {
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+            rejected_value: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_rejected_value(mut self, value: impl Into<serde_json::Value>) -> Self {
+        self.rejected_value = Some(value.into());
+        self
+    }
+}
+
+/// Field-level validation failures, serialized as `{"errors": [...]}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(
+        &mut self,
+        field: impl Into<String>,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> &mut Self {
+        self.push(FieldError::new(field, code, message))
+    }
+
+    pub fn push(&mut self, error: FieldError) -> &mut Self {
+        self.errors.push(error);
+        self
+    }
+
+    /// Append every error from `other`, keeping their order.
+    pub fn merge(&mut self, other: Self) -> &mut Self {
+        self.errors.extend(other.errors);
+        self
+    }
+
+    /// Record a `required` error when `value` is blank.
+    pub fn require(&mut self, field: &str, value: &str) -> &mut Self {
+        if value.trim().is_empty() {
+            self.add(field, "required", format!("{field} is required"));
+        }
+        self
+    }
+
+    /// Record a `too_long` error when `value` exceeds `max` characters.
+    pub fn max_length(&mut self, field: &str, value: &str, max: usize) -> &mut Self {
+        let length = value.chars().count();
+        if length > max {
+            self.push(
+                FieldError::new(
+                    field,
+                    "too_long",
+                    format!("{field} must be at most {max} characters"),
+                )
+                .with_rejected_value(length),
+            );
+        }
+        self
+    }
+
+    /// Record `code`/`message` for `field` unless `condition` holds.
+    pub fn check(
+        &mut self,
+        condition: bool,
+        field: &str,
+        code: &str,
+        message: impl Into<String>,
+    ) -> &mut Self {
+        if !condition {
+            self.add(field, code, message);
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    #[must_use]
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    /// `Ok(())` when nothing was recorded, otherwise
+    /// `BotError::ValidationFields`.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` holding every recorded error.
+    pub fn into_result(self) -> BotResult<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        Err(BotError::ValidationFields(self))
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut fields: Vec<&str> = Vec::new();
+        for error in &self.errors {
+            if !fields.contains(&error.field.as_str()) {
+                fields.push(&error.field);
+            }
+        }
+        let noun = if self.errors.len() == 1 {
+            "error"
+        } else {
+            "errors"
+        };
+        write!(
+            f,
+            "{} validation {noun}: {}",
+            self.errors.len(),
+            fields.join(", ")
+        )
+    }
+}
+
+/// Payload of `BotError::Database`: a message plus the driver error, if any,
+/// which `BotError::source()` exposes.
+#[derive(Debug)]
+pub struct DatabaseError {
+    message: String,
+    source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
+}
+
+impl DatabaseError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    pub fn with_source(
+        message: impl Into<String>,
+        source: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            source: Some(Box::new(source)),
+        }
+    }
+
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn mentions(&self, needles: &[&str]) -> bool {
+        let mut text = self.message.to_ascii_lowercase();
+        if let Some(source) = &self.source {
+            text.push(' ');
+            text.push_str(&source.to_string().to_ascii_lowercase());
+        }
+        needles.iter().any(|needle| text.contains(needle))
+    }
+}
+
+impl std::fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Database error: {}", self.message)
+    }
+}
+
+impl std::error::Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<String> for DatabaseError {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for DatabaseError {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+impl std::ops::Deref for DatabaseError {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.message
+    }
+}
+
+impl AsRef<str> for DatabaseError {
+    fn as_ref(&self) -> &str {
+        &self.message
+    }
+}
+
+impl PartialEq<str> for DatabaseError {
+    fn eq(&self, other: &str) -> bool {
+        self.message == other
+    }
+}
+
+impl PartialEq<&str> for DatabaseError {
+    fn eq(&self, other: &&str) -> bool {
+        self.message == *other
+    }
+}
+
+impl PartialEq<String> for DatabaseError {
+    fn eq(&self, other: &String) -> bool {
+        self.message == *other
+    }
+}
+
+/// Payload of `BotError::Auth`, `Validation` and `Conflict`: the message,
+/// plus the raw response body when the error was mapped from an HTTP status.
+/// It reads as the message, like a `String` would.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorMessage {
+    message: String,
+    body: Option<String>,
+}
+
+impl ErrorMessage {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            body: None,
+        }
+    }
+
+    pub fn with_body(message: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            body: Some(body.into()),
+        }
+    }
+
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The response body this error was mapped from, verbatim.
+    #[must_use]
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+}
+
+impl std::fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<String> for ErrorMessage {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for ErrorMessage {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<ErrorMessage> for String {
+    fn from(message: ErrorMessage) -> Self {
+        message.message
+    }
+}
+
+impl std::ops::Deref for ErrorMessage {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.message
+    }
+}
+
+impl AsRef<str> for ErrorMessage {
+    fn as_ref(&self) -> &str {
+        &self.message
+    }
+}
+
+impl PartialEq<str> for ErrorMessage {
+    fn eq(&self, other: &str) -> bool {
+        self.message == other
+    }
+}
+
+impl PartialEq<&str> for ErrorMessage {
+    fn eq(&self, other: &&str) -> bool {
+        self.message == *other
+    }
+}
+
+impl PartialEq<String> for ErrorMessage {
+    fn eq(&self, other: &String) -> bool {
+        self.message == *other
+    }
+}
+
+const CONFLICT_MARKERS: [&str; 5] = [
+    "23505",
+    "23p01",
+    "duplicate key",
+    "unique constraint",
+    "exclusion constraint",
+];
+
+const DEADLOCK_MARKERS: [&str; 5] = [
+    "40p01",
+    "40001",
+    "deadlock detected",
+    "could not serialize access",
+    "lock timeout",
+];
+
+impl From<ValidationErrors> for BotError {
+    fn from(errors: ValidationErrors) -> Self {
+        Self::ValidationFields(errors)
+    }
+}
+
+pub trait BotResultExt<T> {
+    /// Wrap the error, if any, under `context`. See `BotError::with_context`.
+    ///
+    /// # Errors
+    /// Returns the original error wrapped in `BotError::Context`.
+    fn context(self, context: impl Into<String>) -> BotResult<T>;
+
+    /// Like `context`, but only builds the message when there is an error.
+    ///
+    /// # Errors
+    /// Returns the original error wrapped in `BotError::Context`.
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> BotResult<T>;
+}
+
+impl<T, E: Into<BotError>> BotResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> BotResult<T> {
+        self.map_err(|err| err.into().with_context(context))
+    }
+
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> BotResult<T> {
+        self.map_err(|err| err.into().with_context(context()))
+    }
+}
+
+fn rate_limited_message(retry_after_secs: Option<u64>) -> String {
+    match retry_after_secs {
+        Some(secs) => format!("Rate limited: retry after {secs}s"),
+        None => "Rate limited".to_string(),
+    }
+}
+
+fn not_found_message(entity: &str, id: Option<&str>) -> String {
+    match id {
+        Some(id) => format!("{entity} {id} not found"),
+        None => format!("{entity} not found"),
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum BotError {
     #[error("Configuration error: {0}")]
     Config(String),
 
-    #[error("Database error: {0}")]
-    Database(String),
+    #[error(transparent)]
+    Database(DatabaseError),
 
     #[error("HTTP error: {status} - {message}")]
     Http { status: u16, message: String },
 
     #[error("Auth error: {0}")]
-    Auth(String),
+    Auth(ErrorMessage),
 
     #[error("Validation error: {0}")]
-    Validation(String),
+    Validation(ErrorMessage),
 
-    #[error("{entity} not found")]
-    NotFound { entity: String },
+    #[error("{0}")]
+    ValidationFields(ValidationErrors),
+
+    #[error("{}", not_found_message(entity, id.as_deref()))]
+    NotFound {
+        entity: String,
+        id: Option<String>,
+        tenant: Option<String>,
+        body: Option<String>,
+    },
 
     #[error("Conflict: {0}")]
-    Conflict(String),
+    Conflict(ErrorMessage),
+
+    /// A channel delivered a message kind the platform cannot handle, such
+    /// as a sticker or poll.
+    #[error("Unsupported {channel} message: {kind}")]
+    UnsupportedInbound { channel: String, kind: String },
 
-    #[error("Rate limited: retry after {retry_after_secs}s")]
-    RateLimited { retry_after_secs: u64 },
+    #[error("{}", rate_limited_message(*retry_after_secs))]
+    RateLimited {
+        retry_after_secs: Option<u64>,
+        body: Option<String>,
+    },
 
-    #[error("Service unavailable: {0}")]
-    ServiceUnavailable(String),
+    #[error("Service unavailable: {message}")]
+    ServiceUnavailable {
+        message: String,
+        retry_after_secs: Option<u64>,
+        body: Option<String>,
+    },
 
     #[error("Timeout after {duration_ms}ms")]
     Timeout { duration_ms: u64 },
@@ -45,6 +483,16 @@ pub enum BotError {
 
     #[error("{0}")]
     Other(String),
+
+    #[error(transparent)]
+    Anyhow(#[from] anyhow::Error),
+
+    #[error("{context}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<BotError>,
+    },
 }
 
 impl BotError {
@@ -53,7 +501,15 @@ impl BotError {
     }
 
     pub fn database(msg: impl Into<String>) -> Self {
-        Self::Database(msg.into())
+        Self::Database(DatabaseError::new(msg))
+    }
+
+    /// `Database` error keeping the driver error as its `source()`.
+    pub fn database_with_source(
+        msg: impl Into<String>,
+        source: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        Self::Database(DatabaseError::with_source(msg, source))
     }
 
     pub fn http(status: u16, msg: impl Into<String>) -> Self {
@@ -71,30 +527,79 @@ impl BotError {
     }
 
     pub fn auth(msg: impl Into<String>) -> Self {
-        Self::Auth(msg.into())
+        Self::Auth(ErrorMessage::new(msg))
     }
 
     pub fn validation(msg: impl Into<String>) -> Self {
-        Self::Validation(msg.into())
+        Self::Validation(ErrorMessage::new(msg))
     }
 
     pub fn not_found(entity: impl Into<String>) -> Self {
         Self::NotFound {
             entity: entity.into(),
+            id: None,
+            tenant: None,
+            body: None,
+        }
+    }
+
+    /// `NotFound` naming the missing record, e.g. `Session 7f3a not found`.
+    pub fn not_found_id(entity: impl Into<String>, id: impl Into<String>) -> Self {
+        Self::NotFound {
+            entity: entity.into(),
+            id: Some(id.into()),
+            tenant: None,
+            body: None,
+        }
+    }
+
+    /// `not_found_id` scoped to the tenant that was searched.
+    pub fn not_found_in_tenant(
+        entity: impl Into<String>,
+        id: impl Into<String>,
+        tenant: impl Into<String>,
+    ) -> Self {
+        Self::NotFound {
+            entity: entity.into(),
+            id: Some(id.into()),
+            tenant: Some(tenant.into()),
+            body: None,
         }
     }
 
     pub fn conflict(msg: impl Into<String>) -> Self {
-        Self::Conflict(msg.into())
+        Self::Conflict(ErrorMessage::new(msg))
     }
 
     #[must_use]
+    pub fn unsupported_inbound(channel: impl Into<String>, kind: impl Into<String>) -> Self {
+        Self::UnsupportedInbound {
+            channel: channel.into(),
+            kind: kind.into(),
+        }
+    }
+
     pub const fn rate_limited(retry_after_secs: u64) -> Self {
-        Self::RateLimited { retry_after_secs }
+        Self::RateLimited {
+            retry_after_secs: Some(retry_after_secs),
+            body: None,
+        }
     }
 
     pub fn service_unavailable(msg: impl Into<String>) -> Self {
-        Self::ServiceUnavailable(msg.into())
+        Self::ServiceUnavailable {
+            message: msg.into(),
+            retry_after_secs: None,
+            body: None,
+        }
+    }
+
+    pub fn service_unavailable_retry_after(msg: impl Into<String>, retry_after_secs: u64) -> Self {
+        Self::ServiceUnavailable {
+            message: msg.into(),
+            retry_after_secs: Some(retry_after_secs),
+            body: None,
+        }
     }
 
     #[must_use]
@@ -106,49 +611,431 @@ impl BotError {
         Self::Internal(msg.into())
     }
 
+    /// Wrap this error under `context`. `Display` shows only the context;
+    /// the original error stays reachable through `source()` and `chain()`,
+    /// and status, code and retryability are those of the innermost error.
     #[must_use]
-    pub const fn status_code(&self) -> u16 {
+    pub fn with_context(self, context: impl Into<String>) -> Self {
+        Self::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// This error followed by every `source()` beneath it.
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn std::error::Error + 'static)> {
+        std::iter::successors(Some(self as &(dyn std::error::Error + 'static)), |err| {
+            err.source()
+        })
+    }
+
+    /// The whole chain joined with `": "`, for logs.
+    #[must_use]
+    pub fn display_chain(&self) -> String {
+        self.chain()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(": ")
+    }
+
+    pub(crate) fn root(&self) -> &Self {
         match self {
+            Self::Context { source, .. } => source.root(),
+            _ => self,
+        }
+    }
+
+    #[must_use]
+    pub fn status_code(&self) -> u16 {
+        match self.root() {
             Self::Http { status, .. } => *status,
             Self::Auth(_) => 401,
-            Self::Validation(_) | Self::Json(_) => 400,
+            Self::Validation(_) | Self::ValidationFields(_) | Self::Json(_) => 400,
             Self::NotFound { .. } => 404,
             Self::Conflict(_) => 409,
+            Self::UnsupportedInbound { .. } => 422,
             Self::RateLimited { .. } => 429,
-            Self::ServiceUnavailable(_) => 503,
+            Self::ServiceUnavailable { .. } => 503,
             Self::Timeout { .. } => 504,
             Self::Config(_)
             | Self::Database(_)
             | Self::Internal(_)
             | Self::Io(_)
-            | Self::Other(_) => 500,
+            | Self::Other(_)
+            | Self::Anyhow(_)
+            | Self::Context { .. } => 500,
         }
     }
 
+    /// Stable machine-readable identifier for the variant, e.g. `"not_found"`.
     #[must_use]
-    pub const fn is_retryable(&self) -> bool {
+    pub fn error_code(&self) -> &'static str {
+        match self.root() {
+            Self::Config(_) => "config_error",
+            Self::Database(_) => "database_error",
+            Self::Http { .. } => "http_error",
+            Self::Auth(_) => "auth_error",
+            Self::Validation(_) | Self::ValidationFields(_) => "validation_error",
+            Self::NotFound { .. } => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::UnsupportedInbound { .. } => "unsupported_inbound",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::ServiceUnavailable { .. } => "service_unavailable",
+            Self::Timeout { .. } => "timeout",
+            Self::Internal(_) => "internal_error",
+            Self::Io(_) => "io_error",
+            Self::Json(_) => "json_error",
+            Self::Other(_) | Self::Anyhow(_) | Self::Context { .. } => "other",
+        }
+    }
+
+    fn detail(&self) -> String {
         match self {
-            Self::RateLimited { .. } | Self::ServiceUnavailable(_) | Self::Timeout { .. } => true,
-            Self::Http { status, .. } => *status >= 500,
+            Self::Database(err) => err.message().to_string(),
+            Self::Auth(msg) | Self::Validation(msg) | Self::Conflict(msg) => msg.to_string(),
+            Self::Config(msg)
+            | Self::Internal(msg)
+            | Self::Other(msg)
+            | Self::Http { message: msg, .. }
+            | Self::ServiceUnavailable { message: msg, .. } => msg.clone(),
+            Self::Io(err) => err.to_string(),
+            Self::Json(err) => err.to_string(),
+            Self::Anyhow(err) => format!("{err:#}"),
+            Self::Context { context, source } => format!("{context}: {}", source.detail()),
+            Self::NotFound { .. }
+            | Self::UnsupportedInbound { .. }
+            | Self::RateLimited { .. }
+            | Self::Timeout { .. }
+            | Self::ValidationFields(_) => self.to_string(),
+        }
+    }
+
+    /// How long to wait before retrying, when the error carries a hint.
+    #[must_use]
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.root() {
+            Self::RateLimited {
+                retry_after_secs: Some(retry_after_secs),
+                ..
+            }
+            | Self::ServiceUnavailable {
+                retry_after_secs: Some(retry_after_secs),
+                ..
+            } => Some(Duration::from_secs(*retry_after_secs)),
+            _ => None,
+        }
+    }
+
+    /// The raw body of the HTTP response this error was mapped from, when
+    /// it was. `Http` errors carry the body as their message.
+    #[must_use]
+    pub fn response_body(&self) -> Option<&str> {
+        match self.root() {
+            Self::Auth(msg) | Self::Validation(msg) | Self::Conflict(msg) => msg.body(),
+            Self::NotFound { body, .. }
+            | Self::RateLimited { body, .. }
+            | Self::ServiceUnavailable { body, .. } => body.as_deref(),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match self.root() {
+            Self::Validation(_)
+            | Self::ValidationFields(_)
+            | Self::NotFound { .. }
+            | Self::Conflict(_)
+            | Self::UnsupportedInbound { .. }
+            | Self::Auth(_)
+            | Self::Json(_) => ErrorCategory::UserError,
+            Self::RateLimited { .. } | Self::ServiceUnavailable { .. } | Self::Timeout { .. } => {
+                ErrorCategory::Transient
+            }
+            Self::Config(_) | Self::Database(_) | Self::Internal(_) => ErrorCategory::Permanent,
+            Self::Http { status, .. } => match status {
+                408 | 429 | 500.. => ErrorCategory::Transient,
+                400..=499 => ErrorCategory::UserError,
+                _ => ErrorCategory::Unknown,
+            },
+            Self::Io(_) | Self::Other(_) | Self::Anyhow(_) | Self::Context { .. } => {
+                ErrorCategory::Unknown
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        self.category() == ErrorCategory::Transient
+    }
+
+    /// `Conflict`, or a `Database` error whose message or source mentions a
+    /// Postgres unique/exclusion violation (`23505`, `23P01`, "duplicate
+    /// key", ...).
+    #[must_use]
+    pub fn is_conflict_like(&self) -> bool {
+        match self.root() {
+            Self::Conflict(_) => true,
+            Self::Database(err) => err.mentions(&CONFLICT_MARKERS),
             _ => false,
         }
     }
 
+    /// A `Database` error whose message or source mentions a deadlock,
+    /// serialization failure or lock timeout (`40P01`, `40001`, ...), which
+    /// is usually worth retrying as a whole transaction.
+    #[must_use]
+    pub fn is_deadlock_like(&self) -> bool {
+        matches!(self.root(), Self::Database(err) if err.mentions(&DEADLOCK_MARKERS))
+    }
+
     #[must_use]
-    pub const fn is_client_error(&self) -> bool {
+    pub fn is_client_error(&self) -> bool {
         let code = self.status_code();
-        code >= 400 && code < 500
+        (400..500).contains(&code)
     }
 
     #[must_use]
-    pub const fn is_server_error(&self) -> bool {
+    pub fn is_server_error(&self) -> bool {
         self.status_code() >= 500
     }
+
+    /// Structured log fields, in this order:
+    ///
+    /// - `error.kind`: `error_code()`, for every variant
+    /// - `error.status`: `status_code()`, for every variant
+    /// - `error.retryable`: `is_retryable()`, for every variant
+    /// - `error.entity`: only for `NotFound`
+    ///
+    /// `Context` wrappers report the fields of the error they wrap.
+    #[must_use]
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("error.kind", self.error_code().to_string()),
+            ("error.status", self.status_code().to_string()),
+            ("error.retryable", self.is_retryable().to_string()),
+        ];
+        if let Some(entity) = self.entity() {
+            fields.push(("error.entity", entity.to_string()));
+        }
+        fields
+    }
+
+    fn entity(&self) -> Option<&str> {
+        match self.root() {
+            Self::NotFound { entity, .. } => Some(entity),
+            _ => None,
+        }
+    }
 }
 
-impl From<anyhow::Error> for BotError {
-    fn from(err: anyhow::Error) -> Self {
-        Self::Other(err.to_string())
+/// Record `BotError::fields()` on `span` and emit an event carrying them:
+/// `warn` for client errors, `error` for everything else. Only fields the
+/// span declared (e.g. `error.kind = tracing::field::Empty`) are recorded.
+#[cfg(feature = "tracing")]
+pub fn record_error(span: &tracing::Span, err: &BotError) {
+    let kind = err.error_code();
+    let status = err.status_code();
+    let retryable = err.is_retryable();
+    let entity = err.entity();
+    span.record("error.kind", kind);
+    span.record("error.status", status);
+    span.record("error.retryable", retryable);
+    if let Some(entity) = entity {
+        span.record("error.entity", entity);
+    }
+
+    if err.is_client_error() {
+        tracing::warn!(
+            parent: span,
+            error.kind = kind,
+            error.status = status,
+            error.retryable = retryable,
+            error.entity = entity,
+            error = %err.display_chain(),
+            "request failed"
+        );
+    } else {
+        tracing::error!(
+            parent: span,
+            error.kind = kind,
+            error.status = status,
+            error.retryable = retryable,
+            error.entity = entity,
+            error = %err.display_chain(),
+            "request failed"
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ErrorBody {
+    code: String,
+    message: String,
+    status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_after_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tenant: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    channel: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    errors: Option<Vec<FieldError>>,
+}
+
+impl From<&BotError> for ErrorBody {
+    fn from(err: &BotError) -> Self {
+        let (retry_after_secs, entity, duration_ms) = match err.root() {
+            BotError::RateLimited {
+                retry_after_secs, ..
+            }
+            | BotError::ServiceUnavailable {
+                retry_after_secs, ..
+            } => (*retry_after_secs, None, None),
+            BotError::NotFound { entity, .. } => (None, Some(entity.clone()), None),
+            BotError::Timeout { duration_ms } => (None, None, Some(*duration_ms)),
+            _ => (None, None, None),
+        };
+        let errors = match err.root() {
+            BotError::ValidationFields(fields) => Some(fields.errors.clone()),
+            _ => None,
+        };
+        let (id, tenant) = match err.root() {
+            BotError::NotFound { id, tenant, .. } => (id.clone(), tenant.clone()),
+            _ => (None, None),
+        };
+        let (channel, kind) = match err.root() {
+            BotError::UnsupportedInbound { channel, kind } => {
+                (Some(channel.clone()), Some(kind.clone()))
+            }
+            _ => (None, None),
+        };
+        Self {
+            code: err.error_code().to_string(),
+            message: err.detail(),
+            status: err.status_code(),
+            retry_after_secs,
+            entity,
+            id,
+            tenant,
+            duration_ms,
+            channel,
+            kind,
+            errors,
+        }
+    }
+}
+
+impl From<ErrorBody> for BotError {
+    fn from(body: ErrorBody) -> Self {
+        let ErrorBody {
+            code,
+            message,
+            status,
+            retry_after_secs,
+            entity,
+            id,
+            tenant,
+            duration_ms,
+            channel,
+            kind,
+            errors,
+        } = body;
+        match code.as_str() {
+            "config_error" => Self::Config(message),
+            "database_error" => Self::database(message),
+            "http_error" => Self::Http { status, message },
+            "auth_error" => Self::auth(message),
+            "validation_error" => match errors {
+                Some(errors) => Self::ValidationFields(ValidationErrors { errors }),
+                None => Self::validation(message),
+            },
+            "not_found" => Self::NotFound {
+                entity: entity.unwrap_or(message),
+                id,
+                tenant,
+                body: None,
+            },
+            "conflict" => Self::conflict(message),
+            "unsupported_inbound" => Self::UnsupportedInbound {
+                channel: channel.unwrap_or_default(),
+                kind: kind.unwrap_or(message),
+            },
+            "rate_limited" => Self::RateLimited {
+                retry_after_secs,
+                body: None,
+            },
+            "service_unavailable" => Self::ServiceUnavailable {
+                message,
+                retry_after_secs,
+                body: None,
+            },
+            "timeout" => Self::Timeout {
+                duration_ms: duration_ms.unwrap_or_default(),
+            },
+            "internal_error" => Self::Internal(message),
+            "io_error" => Self::Io(std::io::Error::other(message)),
+            "json_error" => Self::Json(<serde_json::Error as serde::de::Error>::custom(message)),
+            _ => Self::Other(message),
+        }
+    }
+}
+
+impl BotError {
+    /// The raw message and the fields beyond `code` and `message` that
+    /// `from_envelope` needs to rebuild this error, for `ApiResponse`.
+    pub(crate) fn envelope_parts(&self) -> (String, Option<Map<String, Value>>) {
+        let body = ErrorBody::from(self);
+        let message = body.message.clone();
+        let mut details = match serde_json::to_value(body) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        };
+        details.remove("code");
+        details.remove("message");
+        if self.error_code() != "http_error" {
+            details.remove("status");
+        }
+        (message, (!details.is_empty()).then_some(details))
+    }
+
+    /// The error `envelope_parts` described, through the same mapping as
+    /// `Deserialize`. Unknown codes become `BotError::Other`.
+    pub(crate) fn from_envelope(
+        code: &str,
+        message: String,
+        details: Option<&Map<String, Value>>,
+    ) -> Self {
+        let mut fields = details.cloned().unwrap_or_default();
+        fields.insert("code".to_string(), Value::from(code));
+        fields.insert("message".to_string(), Value::from(message.clone()));
+        fields.entry("status").or_insert_with(|| Value::from(500));
+        serde_json::from_value::<ErrorBody>(Value::Object(fields))
+            .map_or_else(|_| Self::Other(message), Self::from)
+    }
+}
+
+/// Serializes as `{ code, message, status }` plus `retry_after_secs`,
+/// `entity`, `id`, `tenant`, `duration_ms`, `channel`, `kind` or `errors`
+/// when the variant carries them.
+impl Serialize for BotError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorBody::from(self).serialize(serializer)
+    }
+}
+
+/// Best-effort inverse of `Serialize`; unknown codes become `BotError::Other`.
+impl<'de> Deserialize<'de> for BotError {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ErrorBody::deserialize(deserializer).map(Self::from)
     }
 }
 
@@ -164,14 +1051,69 @@ impl From<&str> for BotError {
     }
 }
 
+impl From<uuid::Error> for BotError {
+    fn from(err: uuid::Error) -> Self {
+        Self::validation(format!("invalid UUID: {err}"))
+    }
+}
+
+impl From<chrono::ParseError> for BotError {
+    fn from(err: chrono::ParseError) -> Self {
+        Self::validation(format!("invalid date/time: {err}"))
+    }
+}
+
+impl From<std::num::ParseIntError> for BotError {
+    fn from(err: std::num::ParseIntError) -> Self {
+        Self::validation(format!("invalid integer: {err}"))
+    }
+}
+
+impl From<std::num::ParseFloatError> for BotError {
+    fn from(err: std::num::ParseFloatError) -> Self {
+        Self::validation(format!("invalid number: {err}"))
+    }
+}
+
+/// Parse `value` as a UUID, reporting a failure against `field`.
+///
+/// # Errors
+/// Returns `BotError::ValidationFields` with an `invalid_uuid` error for
+/// `field` carrying the rejected value.
+pub fn parse_uuid(field: &str, value: &str) -> BotResult<Uuid> {
+    Uuid::parse_str(value).map_err(|_| {
+        let mut errors = ValidationErrors::new();
+        errors.push(
+            FieldError::new(
+                field,
+                "invalid_uuid",
+                format!("{field} must be a valid UUID"),
+            )
+            .with_rejected_value(value),
+        );
+        BotError::ValidationFields(errors)
+    })
+}
+
 #[cfg(feature = "http-client")]
 impl From<reqwest::Error> for BotError {
     fn from(err: reqwest::Error) -> Self {
-        let status = err.status().map_or(500, |s| s.as_u16());
-        Self::Http {
-            status,
-            message: err.to_string(),
+        if err.is_timeout() {
+            return Self::Timeout { duration_ms: 0 };
+        }
+        if err.is_connect() {
+            return Self::service_unavailable(format!("connection failed: {err}"));
+        }
+        if let Some(status) = err.status() {
+            return Self::Http {
+                status: status.as_u16(),
+                message: err.to_string(),
+            };
+        }
+        if err.is_body() || err.is_decode() {
+            return Self::Internal(format!("failed to read response: {err}"));
         }
+        Self::Internal(err.to_string())
     }
 }
 
@@ -185,6 +1127,228 @@ mod tests {
         assert_eq!(err.to_string(), "Configuration error: missing API key");
     }
 
+    #[test]
+    fn test_parse_error_conversions() {
+        fn convert<T, E>(result: Result<T, E>) -> BotResult<T>
+        where
+            BotError: From<E>,
+        {
+            let value = result?;
+            Ok(value)
+        }
+
+        let errors = [
+            convert(Uuid::parse_str("not-a-uuid")).err(),
+            convert(chrono::NaiveDate::parse_from_str("2024-13-01", "%Y-%m-%d")).err(),
+            convert("12a".parse::<i64>()).err(),
+            convert("1.2.3".parse::<f64>()).err(),
+        ];
+        let prefixes = [
+            "Validation error: invalid UUID: ",
+            "Validation error: invalid date/time: ",
+            "Validation error: invalid integer: ",
+            "Validation error: invalid number: ",
+        ];
+        for (err, prefix) in errors.iter().zip(prefixes) {
+            let err = err.as_ref();
+            assert!(
+                err.is_some_and(|e| e.to_string().starts_with(prefix)),
+                "{err:?}"
+            );
+            assert_eq!(err.map(BotError::status_code), Some(400));
+            assert!(err.is_some_and(BotError::is_client_error));
+        }
+    }
+
+    #[test]
+    fn test_parse_uuid_reports_field() {
+        let id = Uuid::new_v4();
+        assert_eq!(parse_uuid("bot_id", &id.to_string()).ok(), Some(id));
+
+        let err = parse_uuid("bot_id", "42").err();
+        assert_eq!(err.as_ref().map(BotError::status_code), Some(400));
+        let expected = FieldError::new("bot_id", "invalid_uuid", "bot_id must be a valid UUID")
+            .with_rejected_value("42");
+        assert!(matches!(
+            err,
+            Some(BotError::ValidationFields(ref errors)) if errors.errors() == [expected]
+        ));
+    }
+
+    #[test]
+    fn test_structured_fields() {
+        let fields = |err: BotError| err.fields();
+        assert_eq!(
+            fields(BotError::not_found_id("Session", "7f3a")),
+            [
+                ("error.kind", "not_found".to_string()),
+                ("error.status", "404".to_string()),
+                ("error.retryable", "false".to_string()),
+                ("error.entity", "Session".to_string()),
+            ]
+        );
+        assert_eq!(
+            fields(BotError::rate_limited(30).with_context("sending message")),
+            [
+                ("error.kind", "rate_limited".to_string()),
+                ("error.status", "429".to_string()),
+                ("error.retryable", "true".to_string()),
+            ]
+        );
+        assert_eq!(
+            fields(BotError::database("down")),
+            [
+                ("error.kind", "database_error".to_string()),
+                ("error.status", "500".to_string()),
+                ("error.retryable", "false".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_record_error_level_and_span_fields() {
+        use std::sync::{Arc, Mutex, PoisonError};
+        use tracing::field::{Empty, Field, Visit};
+        use tracing::span::{Id, Record};
+        use tracing::{Event, Level, Subscriber};
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+        type Fields = Vec<(String, String)>;
+
+        #[derive(Clone, Default)]
+        struct Capture {
+            span: Arc<Mutex<Fields>>,
+            events: Arc<Mutex<Vec<(Level, Fields)>>>,
+        }
+
+        struct Visitor<'a>(&'a mut Fields);
+
+        impl Visit for Visitor<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0
+                    .push((field.name().to_string(), format!("{value:?}")));
+            }
+
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.push((field.name().to_string(), value.to_string()));
+            }
+        }
+
+        impl<S: Subscriber> Layer<S> for Capture {
+            fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+                let mut span = self.span.lock().unwrap_or_else(PoisonError::into_inner);
+                values.record(&mut Visitor(&mut span));
+            }
+
+            fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+                let mut fields = Fields::new();
+                event.record(&mut Visitor(&mut fields));
+                self.events
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push((*event.metadata().level(), fields));
+            }
+        }
+
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "handler",
+                error.kind = Empty,
+                error.status = Empty,
+                error.entity = Empty
+            );
+            record_error(&span, &BotError::not_found("User"));
+            record_error(&span, &BotError::internal("boom"));
+        });
+
+        let span = capture.span.lock().map(|s| s.clone()).unwrap_or_default();
+        assert!(span.contains(&("error.kind".to_string(), "internal_error".to_string())));
+        assert!(span.contains(&("error.entity".to_string(), "User".to_string())));
+        assert!(span.iter().all(|(name, _)| name != "error.retryable"));
+
+        let events = capture.events.lock().map(|e| e.clone()).unwrap_or_default();
+        let levels: Vec<Level> = events.iter().map(|(level, _)| *level).collect();
+        assert_eq!(levels, [Level::WARN, Level::ERROR]);
+        let has_entity = |(_, fields): &(Level, Fields)| {
+            fields.contains(&("error.entity".to_string(), "User".to_string()))
+        };
+        assert!(events.first().is_some_and(has_entity));
+        assert!(!events.last().is_some_and(has_entity));
+    }
+
+    #[derive(Debug)]
+    struct FakePgError(&'static str);
+
+    impl std::fmt::Display for FakePgError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl std::error::Error for FakePgError {}
+
+    #[test]
+    fn test_database_source_is_preserved() {
+        use std::error::Error as _;
+
+        let err = BotError::database_with_source(
+            "insert bot",
+            FakePgError("error returned from database: duplicate key value violates unique constraint \"bots_name_key\" (SQLSTATE 23505)"),
+        );
+        assert_eq!(err.to_string(), "Database error: insert bot");
+        assert!(matches!(err, BotError::Database(_)));
+        assert!(err
+            .source()
+            .is_some_and(|source| source.to_string().contains("23505")));
+        assert!(err.is_conflict_like());
+        assert!(!err.is_deadlock_like());
+        assert_eq!(err.error_code(), "database_error");
+
+        let err = BotError::database("plain").with_context("loading bot");
+        assert!(err.source().is_some_and(|s| s.source().is_none()));
+        assert!(!err.is_conflict_like());
+    }
+
+    #[test]
+    fn test_database_payload_reads_as_message() {
+        let err = BotError::database("connection refused");
+        assert!(matches!(err, BotError::Database(_)));
+        let BotError::Database(msg) = &err else {
+            return;
+        };
+        assert_eq!(msg.len(), "connection refused".len());
+        assert!(msg.starts_with("connection"));
+        assert!(msg == "connection refused");
+        assert!(*msg == "connection refused");
+        let owned = String::from("connection refused");
+        assert!(*msg == owned);
+        assert_eq!(msg.as_ref(), "connection refused");
+        assert_eq!(format!("{}", &**msg), "connection refused");
+    }
+
+    #[test]
+    fn test_database_deadlock_heuristics() {
+        let deadlock = BotError::database_with_source(
+            "update session",
+            FakePgError("deadlock detected (SQLSTATE 40P01)"),
+        );
+        assert!(deadlock.is_deadlock_like());
+        assert!(!deadlock.is_conflict_like());
+        assert!(deadlock
+            .display_chain()
+            .ends_with("Database error: update session: deadlock detected (SQLSTATE 40P01)"));
+
+        let serialization =
+            BotError::database("could not serialize access due to concurrent update")
+                .with_context("saving message");
+        assert!(serialization.is_deadlock_like());
+        assert!(BotError::conflict("duplicate name").is_conflict_like());
+        assert!(!BotError::conflict("duplicate name").is_deadlock_like());
+    }
+
     #[test]
     fn test_not_found_error() {
         let err = BotError::not_found("User");
@@ -192,6 +1356,21 @@ mod tests {
         assert_eq!(err.status_code(), 404);
     }
 
+    #[test]
+    fn test_not_found_with_id_and_tenant() {
+        let err = BotError::not_found_id("Session", "7f3a");
+        assert_eq!(err.to_string(), "Session 7f3a not found");
+        assert_eq!(err.error_code(), "not_found");
+
+        let err = BotError::not_found_in_tenant("Bot", "42", "acme");
+        assert_eq!(err.to_string(), "Bot 42 not found");
+        assert!(matches!(
+            err,
+            BotError::NotFound { ref id, ref tenant, .. }
+                if id.as_deref() == Some("42") && tenant.as_deref() == Some("acme")
+        ));
+    }
+
     #[test]
     fn test_http_error_with_status() {
         let err = BotError::http(503, "Service down");
@@ -223,10 +1402,299 @@ mod tests {
         assert_eq!(err.status_code(), 429);
     }
 
+    #[test]
+    fn test_service_unavailable_retry_after() {
+        let err = BotError::service_unavailable_retry_after("maintenance", 120);
+        assert_eq!(err.to_string(), "Service unavailable: maintenance");
+        assert_eq!(err.status_code(), 503);
+        assert!(matches!(
+            err,
+            BotError::ServiceUnavailable {
+                retry_after_secs: Some(120),
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn test_timeout_display() {
         let err = BotError::timeout(5000);
         assert_eq!(err.to_string(), "Timeout after 5000ms");
         assert_eq!(err.status_code(), 504);
     }
+
+    fn every_variant() -> Vec<BotError> {
+        vec![
+            BotError::config("missing API key"),
+            BotError::database("connection refused"),
+            BotError::http(418, "teapot"),
+            BotError::auth("expired token"),
+            BotError::validation("bad email"),
+            BotError::ValidationFields({
+                let mut errors = ValidationErrors::new();
+                errors.add("email", "invalid", "email is not valid");
+                errors
+            }),
+            BotError::not_found("User"),
+            BotError::not_found_in_tenant("Session", "7f3a", "acme"),
+            BotError::conflict("duplicate name"),
+            BotError::unsupported_inbound("telegram", "sticker"),
+            BotError::rate_limited(30),
+            BotError::service_unavailable_retry_after("maintenance", 120),
+            BotError::service_unavailable("down"),
+            BotError::timeout(5000),
+            BotError::internal("boom"),
+            BotError::Io(std::io::Error::other("disk full")),
+            BotError::Json(<serde_json::Error as serde::de::Error>::custom("bad json")),
+            BotError::Other("something else".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_error_serde_round_trip() {
+        for err in every_variant() {
+            let value = serde_json::to_value(&err).unwrap_or_default();
+            assert_eq!(value["code"], err.error_code());
+            assert_eq!(value["status"], err.status_code());
+            let back: Option<BotError> = serde_json::from_value(value).ok();
+            assert_eq!(
+                back.as_ref().map(BotError::error_code),
+                Some(err.error_code())
+            );
+            assert_eq!(
+                back.as_ref().map(BotError::status_code),
+                Some(err.status_code())
+            );
+            assert_eq!(back.map(|e| e.to_string()), Some(err.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_error_serialized_shape() {
+        let value = serde_json::to_value(BotError::not_found("User")).unwrap_or_default();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "code": "not_found",
+                "message": "User not found",
+                "status": 404,
+                "entity": "User"
+            })
+        );
+        let value = serde_json::to_value(BotError::not_found_in_tenant("Session", "7f3a", "acme"))
+            .unwrap_or_default();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "code": "not_found",
+                "message": "Session 7f3a not found",
+                "status": 404,
+                "entity": "Session",
+                "id": "7f3a",
+                "tenant": "acme"
+            })
+        );
+        let value = serde_json::to_value(BotError::rate_limited(30)).unwrap_or_default();
+        assert_eq!(value["retry_after_secs"], 30);
+        assert!(value.get("entity").is_none());
+    }
+
+    #[test]
+    fn test_unknown_code_deserializes_to_other() {
+        let err: Option<BotError> = serde_json::from_value(serde_json::json!({
+            "code": "quota_exceeded",
+            "message": "monthly quota used up",
+            "status": 402
+        }))
+        .ok();
+        assert!(matches!(err, Some(BotError::Other(ref msg)) if msg == "monthly quota used up"));
+    }
+
+    #[test]
+    fn test_context_preserves_source_chain() {
+        let result: BotResult<()> = Err(BotError::database("relation \"bots\" does not exist"));
+        let err = result
+            .context("querying bot table")
+            .with_context(|| format!("loading bot config for {}", "sales-bot"))
+            .err();
+
+        assert_eq!(
+            err.as_ref().map(ToString::to_string).as_deref(),
+            Some("loading bot config for sales-bot")
+        );
+        assert_eq!(err.as_ref().map(|e| e.chain().count()), Some(3));
+        let source = err
+            .as_ref()
+            .and_then(std::error::Error::source)
+            .map(ToString::to_string);
+        assert_eq!(source.as_deref(), Some("querying bot table"));
+        assert_eq!(
+            err.as_ref().map(BotError::display_chain).as_deref(),
+            Some(
+                "loading bot config for sales-bot: querying bot table: \
+                 Database error: relation \"bots\" does not exist"
+            )
+        );
+        assert_eq!(
+            err.as_ref().map(BotError::error_code),
+            Some("database_error")
+        );
+        assert_eq!(err.map(|e| e.status_code()), Some(500));
+    }
+
+    #[test]
+    fn test_context_keeps_inner_classification() {
+        let err = BotError::rate_limited(5).with_context("calling LLM gateway");
+        assert_eq!(err.status_code(), 429);
+        assert!(err.is_retryable());
+        assert_eq!(err.error_code(), "rate_limited");
+
+        let value = serde_json::to_value(&err).unwrap_or_default();
+        assert_eq!(value["retry_after_secs"], 5);
+        assert_eq!(
+            value["message"],
+            "calling LLM gateway: Rate limited: retry after 5s"
+        );
+    }
+
+    #[test]
+    fn test_anyhow_chain_is_kept_as_source() {
+        let io = std::io::Error::other("permission denied");
+        let err: BotError = anyhow::Error::new(io)
+            .context("reading /etc/botserver.toml")
+            .into();
+
+        assert_eq!(err.to_string(), "reading /etc/botserver.toml");
+        assert_eq!(err.chain().count(), 2);
+        assert_eq!(
+            err.display_chain(),
+            "reading /etc/botserver.toml: permission denied"
+        );
+        assert_eq!(err.error_code(), "other");
+    }
+
+    fn sample_errors() -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        errors
+            .require("content", " ")
+            .require("user_id", "")
+            .max_length("session_id", "abcdef", 4);
+        errors
+    }
+
+    #[test]
+    fn test_validation_errors_merge_and_display() {
+        let mut errors = sample_errors();
+        let mut more = ValidationErrors::new();
+        more.add("content", "profanity", "content is not allowed")
+            .check(false, "channel", "unsupported", "channel is not supported");
+        errors.merge(more);
+
+        assert_eq!(errors.len(), 5);
+        assert_eq!(
+            errors.to_string(),
+            "5 validation errors: content, user_id, session_id, channel"
+        );
+        assert_eq!(
+            errors
+                .errors()
+                .get(2)
+                .and_then(|e| e.rejected_value.clone()),
+            Some(serde_json::json!(6))
+        );
+
+        let mut single = ValidationErrors::new();
+        single.require("title", "");
+        assert_eq!(single.to_string(), "1 validation error: title");
+        assert!(ValidationErrors::new().into_result().is_ok());
+    }
+
+    #[test]
+    fn test_validation_errors_serialization() {
+        let errors = sample_errors();
+        let value = serde_json::to_value(&errors).unwrap_or_default();
+        assert_eq!(
+            value,
+            serde_json::json!({"errors": [
+                {"field": "content", "code": "required", "message": "content is required"},
+                {"field": "user_id", "code": "required", "message": "user_id is required"},
+                {
+                    "field": "session_id",
+                    "code": "too_long",
+                    "message": "session_id must be at most 4 characters",
+                    "rejected_value": 6
+                }
+            ]})
+        );
+
+        let err = BotError::from(errors.clone());
+        assert_eq!(err.status_code(), 400);
+        let body = serde_json::to_value(&err).unwrap_or_default();
+        assert_eq!(body["errors"], value["errors"]);
+        let back: Option<BotError> = serde_json::from_value(body).ok();
+        assert!(matches!(back, Some(BotError::ValidationFields(ref e)) if *e == errors));
+    }
+
+    fn expected_category(err: &BotError) -> ErrorCategory {
+        match err {
+            BotError::Validation(_)
+            | BotError::ValidationFields(_)
+            | BotError::NotFound { .. }
+            | BotError::Conflict(_)
+            | BotError::UnsupportedInbound { .. }
+            | BotError::Auth(_)
+            | BotError::Json(_) => ErrorCategory::UserError,
+            BotError::RateLimited { .. }
+            | BotError::ServiceUnavailable { .. }
+            | BotError::Timeout { .. } => ErrorCategory::Transient,
+            BotError::Config(_) | BotError::Database(_) | BotError::Internal(_) => {
+                ErrorCategory::Permanent
+            }
+            BotError::Http { status, .. } if *status == 418 => ErrorCategory::UserError,
+            BotError::Http { .. } => ErrorCategory::Transient,
+            BotError::Io(_) | BotError::Other(_) | BotError::Anyhow(_) => ErrorCategory::Unknown,
+            BotError::Context { source, .. } => expected_category(source),
+        }
+    }
+
+    #[test]
+    fn test_every_variant_is_categorized() {
+        let mut variants = every_variant();
+        variants.push(BotError::from(anyhow::anyhow!("opaque")));
+        variants.push(BotError::timeout(10).with_context("polling"));
+        variants.push(BotError::http(503, "upstream down"));
+        for err in variants {
+            assert_eq!(err.category(), expected_category(&err), "{err:?}");
+            assert_eq!(
+                err.is_retryable(),
+                err.category() == ErrorCategory::Transient
+            );
+        }
+    }
+
+    #[test]
+    fn test_http_status_categories() {
+        assert_eq!(BotError::http(400, "").category(), ErrorCategory::UserError);
+        assert_eq!(BotError::http(408, "").category(), ErrorCategory::Transient);
+        assert_eq!(BotError::http(429, "").category(), ErrorCategory::Transient);
+        assert_eq!(BotError::http(502, "").category(), ErrorCategory::Transient);
+        assert_eq!(BotError::http(302, "").category(), ErrorCategory::Unknown);
+    }
+
+    #[test]
+    fn test_retry_after_hint() {
+        assert_eq!(
+            BotError::rate_limited(30).retry_after(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            BotError::service_unavailable_retry_after("maintenance", 120)
+                .with_context("sending message")
+                .retry_after(),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(BotError::service_unavailable("down").retry_after(), None);
+        assert_eq!(BotError::http(429, "slow down").retry_after(), None);
+        assert_eq!(BotError::timeout(100).retry_after(), None);
+    }
 }
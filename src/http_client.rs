@@ -1,16 +1,295 @@
-use crate::error::BotError;
-use log::{debug, error};
-use serde::{de::DeserializeOwned, Serialize};
+mod auth;
+mod batch;
+#[cfg(feature = "blocking-client")]
+mod blocking;
+mod cache;
+mod cookies;
+mod health;
+mod interceptor;
+mod metrics;
+mod paging;
+mod poll;
+mod progress;
+mod response;
+#[cfg(feature = "tracing")]
+mod telemetry;
+pub mod testing;
+mod transfer;
+mod transport;
+mod updates;
+
+pub use auth::{AuthScheme, OAuth2ClientCredentials, StaticToken, TokenProvider};
+pub use batch::{BatchItem, BatchOptions, FailFast};
+#[cfg(feature = "blocking-client")]
+pub use blocking::BlockingBotServerClient;
+pub use cache::{ResponseCache, DEFAULT_CACHE_MAX_BYTES, DEFAULT_CACHE_MAX_ENTRIES};
+pub use health::HealthStatus;
+pub use interceptor::{
+    CorrelationIdInterceptor, Interceptor, LoggingInterceptor, RequestParts, ResponseMeta,
+    CORRELATION_ID_HEADER,
+};
+pub use metrics::{
+    AtomicClientMetrics, ClientMetrics, EndpointMetrics, LATENCY_BUCKETS_MS, OTHER_PATH,
+};
+pub use poll::{PollBatch, PollOptions, POLL_TIMEOUT_MARGIN};
+pub use progress::{
+    Progress, ProgressTracker, TransferProgress, DEFAULT_PROGRESS_MIN_BYTES,
+    DEFAULT_PROGRESS_MIN_INTERVAL,
+};
+pub use tokio_util::sync::CancellationToken;
+pub use transport::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
+pub use updates::{
+    canonical_json, spawn_update_checker, ManifestComponent, UpdateChecker, UpdateManifest,
+    MAX_UPDATE_CHECK_BACKOFF,
+};
+
+use crate::error::{BotError, BotResult};
+use crate::limits::{
+    LimitExceeded, LimitType, SystemLimits, MAX_REQUEST_BODY_BYTES, MAX_UPLOAD_SIZE_BYTES,
+};
+use crate::models::{BotResponse, UserMessage};
+use crate::resilience::RetryConfig;
+use log::{debug, error, warn};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use reqwest::{Method, StatusCode};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Serialize;
+use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const DEFAULT_BOTSERVER_URL: &str = "https://localhost:8088";
 const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MESSAGES_ENDPOINT: &str = "/api/messages";
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    #[must_use]
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for IdempotencyKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<IdempotencyKey> for String {
+    fn from(key: IdempotencyKey) -> Self {
+        key.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Idempotent<R> {
+    pub key: String,
+    pub value: R,
+}
+
+fn idempotency_headers(key: &str) -> Result<HeaderMap, BotError> {
+    let value = HeaderValue::from_str(key)
+        .map_err(|_| BotError::validation(format!("invalid idempotency key: {key}")))?;
+    let mut headers = HeaderMap::new();
+    headers.insert(IDEMPOTENCY_KEY_HEADER, value);
+    Ok(headers)
+}
+
+fn join_url(base: &str, path: &str) -> String {
+    if path.is_empty() || path.starts_with(['?', '#']) {
+        return format!("{base}{path}");
+    }
+    format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+fn check_body_size(bytes: Vec<u8>, max_bytes: u64) -> Result<Vec<u8>, BotError> {
+    let size = bytes.len() as u64;
+    if size > max_bytes {
+        let exceeded = LimitExceeded {
+            limit_type: LimitType::RequestBody,
+            current: size,
+            maximum: max_bytes,
+            retry_after_secs: None,
+        };
+        return Err(BotError::validation(exceeded.to_string()));
+    }
+    Ok(bytes)
+}
+
+fn encode_json<T: Serialize>(body: &T, max_bytes: u64) -> Result<Vec<u8>, BotError> {
+    check_body_size(serde_json::to_vec(body)?, max_bytes)
+}
+
+fn request_headers(
+    mut headers: HeaderMap,
+    has_body: bool,
+    auth: &AuthScheme,
+) -> Result<HeaderMap, BotError> {
+    if has_body && !headers.contains_key(CONTENT_TYPE) {
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    }
+    if let Some((name, value)) = auth.header()? {
+        headers.insert(name, value);
+    }
+    Ok(headers)
+}
+
+fn error_for_status(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &[u8],
+    endpoint: &str,
+    raw_error_mapping: bool,
+) -> Result<(), BotError> {
+    if status.is_success() {
+        return Ok(());
+    }
+    let status_code = status.as_u16();
+    let error_text = String::from_utf8_lossy(body);
+    error!("HTTP {status_code} error: {error_text}");
+    Err(response::status_error(
+        status_code,
+        headers,
+        &error_text,
+        endpoint,
+        raw_error_mapping,
+    ))
+}
+
+fn parse_body<T: DeserializeOwned>(status: StatusCode, body: &[u8]) -> Result<T, BotError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return serde_json::from_value(serde_json::Value::Null).map_err(|_| {
+            BotError::internal(format!(
+                "HTTP {} response has an empty body, which cannot be read as {}",
+                status.as_u16(),
+                std::any::type_name::<T>()
+            ))
+        });
+    }
+    serde_json::from_slice(body).map_err(|e| {
+        error!("Failed to parse HTTP {} response: {e}", status.as_u16());
+        BotError::internal(format!("Failed to parse response: {e}"))
+    })
+}
+
+struct Call<'a> {
+    method: Method,
+    url: String,
+    endpoint: &'a str,
+    body: Option<&'a [u8]>,
+    auth: Option<&'a AuthScheme>,
+    headers: HeaderMap,
+    progress: TransferProgress,
+    timeout: Option<Duration>,
+}
+
+impl<'a> Call<'a> {
+    fn new(client: &BotServerClient, method: Method, endpoint: &'a str) -> Self {
+        Self {
+            method,
+            url: client.url(endpoint),
+            endpoint,
+            body: None,
+            auth: None,
+            headers: HeaderMap::new(),
+            progress: TransferProgress::default(),
+            timeout: None,
+        }
+    }
+
+    const fn body(mut self, body: Option<&'a [u8]>) -> Self {
+        self.body = body;
+        self
+    }
+
+    const fn auth(mut self, auth: Option<&'a AuthScheme>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    fn progress(mut self, progress: TransferProgress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    const fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[cfg(feature = "tracing")]
+async fn instrumented<T>(
+    method: &Method,
+    url: &str,
+    future: impl Future<Output = Result<T, BotError>>,
+) -> Result<T, BotError> {
+    let span = telemetry::request_span(method, url);
+    tracing::Instrument::instrument(
+        async {
+            let result = future.await;
+            if let Err(e) = &result {
+                telemetry::record_error(e);
+            }
+            result
+        },
+        span,
+    )
+    .await
+}
+
+#[cfg(not(feature = "tracing"))]
+async fn instrumented<T>(
+    _: &Method,
+    _: &str,
+    future: impl Future<Output = Result<T, BotError>>,
+) -> Result<T, BotError> {
+    future.await
+}
 
 #[derive(Clone)]
 pub struct BotServerClient {
-    client: Arc<reqwest::Client>,
+    transport: Arc<dyn HttpTransport>,
+    custom_transport: bool,
     base_url: String,
+    timeout: Duration,
+    max_request_body_bytes: u64,
+    max_response_bytes: u64,
+    max_upload_bytes: u64,
+    auth: AuthScheme,
+    token_provider: Option<Arc<dyn TokenProvider>>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    raw_error_mapping: bool,
+    retry: Option<RetryConfig>,
+    cache: Option<Arc<ResponseCache>>,
+    metrics: Option<Arc<dyn ClientMetrics>>,
+    path_templates: Vec<String>,
+    cookies: Option<Arc<cookies::CookieJar>>,
 }
 
 impl BotServerClient {
@@ -25,17 +304,177 @@ impl BotServerClient {
             std::env::var("BOTSERVER_URL").unwrap_or_else(|_| DEFAULT_BOTSERVER_URL.to_string())
         });
 
-        let client = reqwest::Client::builder()
-            .timeout(timeout)
-            .user_agent(format!("BotLib/{}", env!("CARGO_PKG_VERSION")))
-            .danger_accept_invalid_certs(true)
-            .build()
-            .unwrap_or_else(|_| reqwest::Client::new());
-
         Self {
-            client: Arc::new(client),
+            transport: Arc::new(ReqwestTransport::new(timeout)),
+            custom_transport: false,
             base_url: url,
+            timeout,
+            max_request_body_bytes: MAX_REQUEST_BODY_BYTES,
+            max_response_bytes: MAX_REQUEST_BODY_BYTES,
+            max_upload_bytes: MAX_UPLOAD_SIZE_BYTES,
+            auth: AuthScheme::None,
+            token_provider: None,
+            interceptors: Vec::new(),
+            raw_error_mapping: false,
+            retry: None,
+            cache: None,
+            metrics: None,
+            path_templates: Vec::new(),
+            cookies: None,
+        }
+    }
+
+    /// Send every request through `transport` instead of the default
+    /// `ReqwestTransport`. See [`testing::MockTransport`] for unit tests.
+    #[must_use]
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self.custom_transport = true;
+        self
+    }
+
+    /// Authenticate every request with `auth` unless a call overrides it.
+    #[must_use]
+    pub fn with_auth(mut self, auth: AuthScheme) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// This client with no auth scheme, token provider, cookie jar,
+    /// interceptors or response cache, for requests to hosts other than the
+    /// bot server. The transport, timeout, retry policy and size limits are
+    /// kept.
+    #[must_use]
+    pub fn without_auth(mut self) -> Self {
+        self.auth = AuthScheme::None;
+        self.token_provider = None;
+        self.cookies = None;
+        self.interceptors.clear();
+        self.cache = None;
+        self
+    }
+
+    /// Fetch the bearer token from `provider` on every request. A 401 response
+    /// invalidates the token and the request is retried once with a fresh one.
+    #[must_use]
+    pub fn with_token_provider(mut self, provider: Arc<dyn TokenProvider>) -> Self {
+        self.token_provider = Some(provider);
+        self
+    }
+
+    /// Register an interceptor. Interceptors run in registration order for
+    /// every request issued by this client.
+    #[must_use]
+    pub fn with_interceptor(mut self, interceptor: Arc<dyn Interceptor>) -> Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Error responses are mapped onto specific `BotError` variants (401 to
+    /// `Auth`, 404 to `NotFound`, ...). Enabling raw mapping restores the
+    /// previous behaviour of returning every failure as `BotError::Http` with
+    /// the untouched response body.
+    #[must_use]
+    pub const fn raw_error_mapping(mut self, enabled: bool) -> Self {
+        self.raw_error_mapping = enabled;
+        self
+    }
+
+    /// Retry transient failures (see `BotError::is_retryable`) using `config`.
+    /// Only idempotent methods and requests carrying an idempotency key are
+    /// retried, so a plain POST is never sent twice. A `Retry-After` hint
+    /// longer than the backoff delay is waited out in full.
+    #[must_use]
+    pub fn with_retry(mut self, config: RetryConfig) -> Self {
+        self.retry = Some(config);
+        self
+    }
+
+    /// Reject JSON bodies larger than `max_bytes` before any network I/O.
+    /// Defaults to `MAX_REQUEST_BODY_BYTES`.
+    #[must_use]
+    pub const fn with_max_request_body_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_request_body_bytes = max_bytes;
+        self
+    }
+
+    /// Reject files larger than `max_bytes` in `upload_file` before reading
+    /// them. Defaults to `MAX_UPLOAD_SIZE_BYTES`.
+    #[must_use]
+    pub const fn with_max_upload_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_upload_bytes = max_bytes;
+        self
+    }
+
+    /// Abort any response whose decoded body exceeds `max_bytes` with
+    /// `BotError::Validation`. Defaults to `MAX_REQUEST_BODY_BYTES`.
+    #[must_use]
+    pub const fn with_max_response_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_response_bytes = max_bytes;
+        self
+    }
+
+    /// Toggle transparent gzip and brotli response decoding on the default
+    /// `ReqwestTransport` (on by default). A transport installed with
+    /// `with_transport` is left untouched; configure compression on it
+    /// directly, e.g. with `ReqwestTransport::with_compression`.
+    #[must_use]
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        if !self.custom_transport {
+            self.transport =
+                Arc::new(ReqwestTransport::new(self.timeout).with_compression(enabled));
         }
+        self
+    }
+
+    /// Store responses fetched with `get_cached` in `cache`. Clients sharing
+    /// the same cache reuse each other's entries for identical URL and auth.
+    #[must_use]
+    pub fn with_response_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Report every completed request to `metrics`, keyed by the matching
+    /// path template (see `with_path_template`) or `OTHER_PATH`.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<dyn ClientMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Register a URL path template such as `/api/sessions/{id}` used to
+    /// group metrics. Each `{...}` segment matches any single path segment.
+    #[must_use]
+    pub fn with_path_template(mut self, template: impl Into<String>) -> Self {
+        self.path_templates.push(template.into());
+        self
+    }
+
+    /// Keep cookies set by the server and replay them on later requests
+    /// within their `Domain` and `Path`, over https only when `Secure`.
+    /// Each call starts a fresh store owned by this client and
+    /// its clones, so separately built clients never share a session.
+    #[must_use]
+    pub fn enable_cookies(mut self, enabled: bool) -> Self {
+        self.cookies = enabled.then(|| Arc::new(cookies::CookieJar::default()));
+        self
+    }
+
+    /// Re-point this client at another host, keeping auth, interceptors and
+    /// every other setting.
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// A client for the `prefix` path below this client's base URL. The
+    /// scoped client shares the transport (and its connection pool) and
+    /// inherits auth, interceptors, retry and cache settings.
+    #[must_use]
+    pub fn scoped(&self, prefix: &str) -> Self {
+        self.clone().with_base_url(join_url(&self.base_url, prefix))
     }
 
     #[must_use]
@@ -43,16 +482,16 @@ impl BotServerClient {
         &self.base_url
     }
 
+    fn url(&self, endpoint: &str) -> String {
+        join_url(&self.base_url, endpoint)
+    }
+
     /// Perform a GET request to the specified endpoint.
     ///
     /// # Errors
     /// Returns an error if the request fails or the response cannot be parsed.
     pub async fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, BotError> {
-        let url = format!("{}{endpoint}", self.base_url);
-        debug!("GET {url}");
-
-        let response = self.client.get(&url).send().await?;
-        self.handle_response(response).await
+        self.request(Method::GET, endpoint, None, None).await
     }
 
     /// Perform a POST request to the specified endpoint.
@@ -64,11 +503,8 @@ impl BotServerClient {
         endpoint: &str,
         body: &T,
     ) -> Result<R, BotError> {
-        let url = format!("{}{endpoint}", self.base_url);
-        debug!("POST {url}");
-
-        let response = self.client.post(&url).json(body).send().await?;
-        self.handle_response(response).await
+        let body = self.encode_body(body)?;
+        self.request(Method::POST, endpoint, Some(body), None).await
     }
 
     /// Perform a PUT request to the specified endpoint.
@@ -80,11 +516,8 @@ impl BotServerClient {
         endpoint: &str,
         body: &T,
     ) -> Result<R, BotError> {
-        let url = format!("{}{endpoint}", self.base_url);
-        debug!("PUT {url}");
-
-        let response = self.client.put(&url).json(body).send().await?;
-        self.handle_response(response).await
+        let body = self.encode_body(body)?;
+        self.request(Method::PUT, endpoint, Some(body), None).await
     }
 
     /// Perform a PATCH request to the specified endpoint.
@@ -96,11 +529,9 @@ impl BotServerClient {
         endpoint: &str,
         body: &T,
     ) -> Result<R, BotError> {
-        let url = format!("{}{endpoint}", self.base_url);
-        debug!("PATCH {url}");
-
-        let response = self.client.patch(&url).json(body).send().await?;
-        self.handle_response(response).await
+        let body = self.encode_body(body)?;
+        self.request(Method::PATCH, endpoint, Some(body), None)
+            .await
     }
 
     /// Perform a DELETE request to the specified endpoint.
@@ -108,11 +539,62 @@ impl BotServerClient {
     /// # Errors
     /// Returns an error if the request fails or the response cannot be parsed.
     pub async fn delete<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, BotError> {
-        let url = format!("{}{endpoint}", self.base_url);
-        debug!("DELETE {url}");
+        self.request(Method::DELETE, endpoint, None, None).await
+    }
+
+    /// Perform a GET request authenticated with `auth` instead of the client default.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn get_with_auth<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        auth: &AuthScheme,
+    ) -> Result<T, BotError> {
+        self.request(Method::GET, endpoint, None, Some(auth)).await
+    }
+
+    /// Perform a POST request authenticated with `auth` instead of the client default.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn post_with_auth<T: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &T,
+        auth: &AuthScheme,
+    ) -> Result<R, BotError> {
+        let body = self.encode_body(body)?;
+        self.request(Method::POST, endpoint, Some(body), Some(auth))
+            .await
+    }
 
-        let response = self.client.delete(&url).send().await?;
-        self.handle_response(response).await
+    /// Perform a PUT request authenticated with `auth` instead of the client default.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn put_with_auth<T: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &T,
+        auth: &AuthScheme,
+    ) -> Result<R, BotError> {
+        let body = self.encode_body(body)?;
+        self.request(Method::PUT, endpoint, Some(body), Some(auth))
+            .await
+    }
+
+    /// Perform a DELETE request authenticated with `auth` instead of the client default.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn delete_with_auth<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        auth: &AuthScheme,
+    ) -> Result<T, BotError> {
+        self.request(Method::DELETE, endpoint, None, Some(auth))
+            .await
     }
 
     /// Perform an authorized GET request with a bearer token.
@@ -124,11 +606,8 @@ impl BotServerClient {
         endpoint: &str,
         token: &str,
     ) -> Result<T, BotError> {
-        let url = format!("{}{endpoint}", self.base_url);
-        debug!("GET {url} (authorized)");
-
-        let response = self.client.get(&url).bearer_auth(token).send().await?;
-        self.handle_response(response).await
+        self.get_with_auth(endpoint, &AuthScheme::bearer(token))
+            .await
     }
 
     /// Perform an authorized POST request with a bearer token.
@@ -141,17 +620,8 @@ impl BotServerClient {
         body: &T,
         token: &str,
     ) -> Result<R, BotError> {
-        let url = format!("{}{endpoint}", self.base_url);
-        debug!("POST {url} (authorized)");
-
-        let response = self
-            .client
-            .post(&url)
-            .bearer_auth(token)
-            .json(body)
-            .send()
-            .await?;
-        self.handle_response(response).await
+        self.post_with_auth(endpoint, body, &AuthScheme::bearer(token))
+            .await
     }
 
     /// Perform an authorized DELETE request with a bearer token.
@@ -163,43 +633,346 @@ impl BotServerClient {
         endpoint: &str,
         token: &str,
     ) -> Result<T, BotError> {
-        let url = format!("{}{endpoint}", self.base_url);
-        debug!("DELETE {url} (authorized)");
+        self.delete_with_auth(endpoint, &AuthScheme::bearer(token))
+            .await
+    }
 
-        let response = self.client.delete(&url).bearer_auth(token).send().await?;
-        self.handle_response(response).await
+    /// Perform an authorized POST with `form` encoded as
+    /// `application/x-www-form-urlencoded`.
+    ///
+    /// # Errors
+    /// Returns an error if `form` cannot be encoded, the request fails or the
+    /// response cannot be parsed.
+    pub async fn post_form<T: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        form: &T,
+        token: &str,
+    ) -> Result<R, BotError> {
+        let body = serde_urlencoded::to_string(form)
+            .map_err(|e| BotError::validation(format!("invalid form body: {e}")))?;
+        let body = check_body_size(body.into_bytes(), self.max_request_body_bytes)?;
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        self.request_with_headers(
+            Method::POST,
+            endpoint,
+            Some(body),
+            Some(&AuthScheme::bearer(token)),
+            headers,
+        )
+        .await
     }
 
-    pub async fn health_check(&self) -> bool {
-        match self.get::<serde_json::Value>("/health").await {
-            Ok(_) => true,
+    /// Perform a POST whose response body, if any, is discarded.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the server responds with an
+    /// error status.
+    pub async fn post_no_response<T: Serialize + Send + Sync>(
+        &self,
+        endpoint: &str,
+        body: &T,
+    ) -> BotResult<()> {
+        let body = self.encode_body(body)?;
+        self.request::<IgnoredAny>(Method::POST, endpoint, Some(body), None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Perform an authorized POST carrying an `Idempotency-Key` header. The
+    /// server deduplicates requests sharing a key, which makes the call safe to
+    /// retry when the client is configured with `with_retry`.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn post_idempotent<T: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &T,
+        key: &str,
+        token: &str,
+    ) -> Result<R, BotError> {
+        let body = self.encode_body(body)?;
+        self.request_with_headers(
+            Method::POST,
+            endpoint,
+            Some(body),
+            Some(&AuthScheme::bearer(token)),
+            idempotency_headers(key)?,
+        )
+        .await
+    }
+
+    /// Perform a POST with a freshly generated idempotency key that is reused
+    /// across every retry attempt of this call. The key is returned alongside
+    /// the response so it can be logged.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn post_with_idempotency<T: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &T,
+    ) -> Result<Idempotent<R>, BotError> {
+        let key = String::from(IdempotencyKey::new());
+        let body = self.encode_body(body)?;
+        let result = self
+            .request_with_headers(
+                Method::POST,
+                endpoint,
+                Some(body),
+                None,
+                idempotency_headers(&key)?,
+            )
+            .await;
+
+        match result {
+            Ok(value) => Ok(Idempotent { key, value }),
             Err(e) => {
-                error!("Health check failed: {e}");
-                false
+                warn!("POST {endpoint} with idempotency key {key} failed: {e}");
+                Err(e)
             }
         }
     }
 
-    async fn handle_response<T: DeserializeOwned>(
+    /// Validate `message` against the default `SystemLimits` and POST it to
+    /// `/api/messages`, returning the bot's reply.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` without sending anything when the
+    /// message is invalid, otherwise any request or parse error.
+    pub async fn send_message(&self, message: &UserMessage) -> Result<BotResponse, BotError> {
+        message.validate(&SystemLimits::default())?;
+        self.post(MESSAGES_ENDPOINT, message).await
+    }
+
+    fn encode_body<T: Serialize>(&self, body: &T) -> Result<Vec<u8>, BotError> {
+        encode_json(body, self.max_request_body_bytes)
+    }
+
+    fn build_request(
+        parts: &RequestParts,
+        body: Option<&[u8]>,
+        auth: &AuthScheme,
+        timeout: Option<Duration>,
+    ) -> Result<HttpRequest, BotError> {
+        let headers = request_headers(parts.headers.clone(), body.is_some(), auth)?;
+        Ok(HttpRequest {
+            method: parts.method.clone(),
+            url: parts.url.clone(),
+            headers,
+            body: body.map(<[u8]>::to_vec),
+            timeout,
+            max_response_bytes: None,
+        })
+    }
+
+    async fn send_once(
         &self,
-        response: reqwest::Response,
-    ) -> Result<T, BotError> {
-        let status = response.status();
-        let status_code = status.as_u16();
+        call: &Call<'_>,
+        parts: &RequestParts,
+        auth: &AuthScheme,
+        progress: TransferProgress,
+        attempt: u32,
+    ) -> Result<HttpResponse, BotError> {
+        let mut request = Self::build_request(parts, call.body, auth, call.timeout)?;
+        request.max_response_bytes = Some(self.max_response_bytes);
+        if let Some(jar) = &self.cookies {
+            jar.attach(&mut request);
+        }
+        let started = Instant::now();
+        let response = if progress.is_empty() {
+            self.transport.execute(request).await?
+        } else {
+            self.transport
+                .execute_with_progress(request, progress)
+                .await?
+        };
 
-        if !status.is_success() {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            error!("HTTP {status_code} error: {error_text}");
-            return Err(BotError::http(status_code, error_text));
+        transport::ensure_response_size(response.body.len() as u64, Some(self.max_response_bytes))?;
+        if let Some(jar) = &self.cookies {
+            jar.store(&parts.url, &response.headers);
         }
 
-        response.json().await.map_err(|e| {
-            error!("Failed to parse response: {e}");
-            BotError::http(status_code, format!("Failed to parse response: {e}"))
-        })
+        let meta = ResponseMeta {
+            method: parts.method.clone(),
+            url: parts.url.clone(),
+            status: response.status.as_u16(),
+            elapsed: started.elapsed(),
+            request_bytes: call.body.map_or(0, |b| b.len() as u64),
+            response_bytes: Some(response.body.len() as u64),
+        };
+        debug!(
+            "{} {} -> {} (attempt {attempt})",
+            meta.method, meta.url, meta.status
+        );
+        #[cfg(feature = "tracing")]
+        telemetry::record_attempt(meta.status, meta.elapsed, attempt);
+        for interceptor in &self.interceptors {
+            interceptor.on_response(&meta).await;
+        }
+
+        Ok(response)
+    }
+
+    async fn request<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<Vec<u8>>,
+        auth: Option<&AuthScheme>,
+    ) -> Result<R, BotError> {
+        self.request_with_headers(method, endpoint, body, auth, HeaderMap::new())
+            .await
+    }
+
+    async fn request_with_headers<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<Vec<u8>>,
+        auth: Option<&AuthScheme>,
+        headers: HeaderMap,
+    ) -> Result<R, BotError> {
+        let call = Call::new(self, method, endpoint)
+            .body(body.as_deref())
+            .auth(auth)
+            .headers(headers);
+        self.perform(&call).await
+    }
+
+    async fn perform<R: DeserializeOwned>(&self, call: &Call<'_>) -> Result<R, BotError> {
+        let future = async {
+            let fetched = self.fetch(call).await?;
+            parse_body(fetched.status, &fetched.body)
+        };
+        instrumented(&call.method, &call.url, future).await
+    }
+
+    async fn fetch(&self, call: &Call<'_>) -> Result<HttpResponse, BotError> {
+        let retry = self.retry.as_ref().filter(|_| {
+            call.method.is_idempotent() || call.headers.contains_key(IDEMPOTENCY_KEY_HEADER)
+        });
+        let max_attempts = retry.map_or(1, |config| config.max_attempts);
+        let conditional = call.headers.contains_key(IF_NONE_MATCH)
+            || call.headers.contains_key(IF_MODIFIED_SINCE);
+        let started = Instant::now();
+        let mut received = None;
+        let mut attempt = 0;
+
+        loop {
+            let result = async {
+                let response = self.execute(call, attempt).await?;
+                received = Some((response.status.as_u16(), response.body.len() as u64));
+                self.check_status(response, call.endpoint, conditional)
+            }
+            .await;
+
+            match (result, retry) {
+                (Err(e), Some(config)) if e.is_retryable() && attempt + 1 < max_attempts => {
+                    attempt += 1;
+                    let backoff = config.calculate_delay(attempt);
+                    let delay = e.retry_after().map_or(backoff, |hint| backoff.max(hint));
+                    debug!(
+                        "{} {} failed ({e}), retrying in {delay:?}",
+                        call.method, call.url
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                (result, _) => {
+                    self.record_metrics(call, received, started.elapsed());
+                    return result;
+                }
+            }
+        }
+    }
+
+    fn record_metrics(&self, call: &Call<'_>, received: Option<(u16, u64)>, elapsed: Duration) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let path = reqwest::Url::parse(&call.url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| response::endpoint_path(call.endpoint).to_string());
+        let template = metrics::normalize_path(&self.path_templates, &path);
+        let (status, bytes) = received.map_or((None, 0), |(status, bytes)| (Some(status), bytes));
+        metrics.on_request_complete(&call.method, &template, status, elapsed, bytes);
+    }
+
+    async fn execute(&self, call: &Call<'_>, attempt: u32) -> Result<HttpResponse, BotError> {
+        let mut parts = RequestParts {
+            method: call.method.clone(),
+            url: call.url.clone(),
+            headers: call.headers.clone(),
+        };
+        for interceptor in &self.interceptors {
+            interceptor.on_request(&mut parts).await;
+        }
+
+        let provider = match (&self.token_provider, call.auth) {
+            (Some(provider), None) => provider,
+            (_, auth) => {
+                let scheme = auth.unwrap_or(&self.auth);
+                if scheme.is_none() {
+                    debug!("{} {}", parts.method, parts.url);
+                } else {
+                    debug!("{} {} ({} auth)", parts.method, parts.url, scheme.kind());
+                }
+                return self
+                    .send_once(call, &parts, scheme, call.progress.clone(), attempt)
+                    .await;
+            }
+        };
+
+        debug!("{} {} (token provider)", parts.method, parts.url);
+        let rejected = provider.get_token().await?;
+        let auth = AuthScheme::Bearer(rejected.clone());
+        let response = self
+            .send_once(call, &parts, &auth, call.progress.clone(), attempt)
+            .await?;
+
+        if response.status != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        debug!(
+            "{} {} returned 401, refreshing token and retrying once",
+            parts.method, parts.url
+        );
+        provider.invalidate_token(&rejected).await;
+        let token = provider.get_token().await?;
+        self.send_once(
+            call,
+            &parts,
+            &AuthScheme::Bearer(token),
+            call.progress.restarted(),
+            attempt + 1,
+        )
+        .await
+    }
+
+    fn check_status(
+        &self,
+        response: HttpResponse,
+        endpoint: &str,
+        conditional: bool,
+    ) -> Result<HttpResponse, BotError> {
+        let not_modified = conditional && response.status == StatusCode::NOT_MODIFIED;
+        if not_modified {
+            return Ok(response);
+        }
+        error_for_status(
+            response.status,
+            &response.headers,
+            &response.body,
+            endpoint,
+            self.raw_error_mapping,
+        )?;
+        Ok(response)
     }
 }
 
@@ -207,6 +980,20 @@ impl std::fmt::Debug for BotServerClient {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BotServerClient")
             .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("max_request_body_bytes", &self.max_request_body_bytes)
+            .field("max_response_bytes", &self.max_response_bytes)
+            .field("max_upload_bytes", &self.max_upload_bytes)
+            .field("custom_transport", &self.custom_transport)
+            .field("auth", &self.auth)
+            .field("token_provider", &self.token_provider.is_some())
+            .field("interceptors", &self.interceptors.len())
+            .field("raw_error_mapping", &self.raw_error_mapping)
+            .field("retry", &self.retry)
+            .field("response_cache", &self.cache.is_some())
+            .field("metrics", &self.metrics.is_some())
+            .field("path_templates", &self.path_templates)
+            .field("cookies", &self.cookies)
             .finish_non_exhaustive()
     }
 }
@@ -214,6 +1001,33 @@ impl std::fmt::Debug for BotServerClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    #[cfg(feature = "tracing")]
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use wiremock::matchers::{body_string, header, header_regex, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct RotatingToken {
+        invalidations: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl TokenProvider for RotatingToken {
+        async fn get_token(&self) -> Result<String, BotError> {
+            if self.invalidations.load(Ordering::SeqCst) == 0 {
+                Ok("stale".to_string())
+            } else {
+                Ok("fresh".to_string())
+            }
+        }
+
+        async fn invalidate(&self) {
+            self.invalidations.fetch_add(1, Ordering::SeqCst);
+        }
+    }
 
     #[test]
     fn test_client_creation() {
@@ -244,6 +1058,83 @@ mod tests {
         assert_eq!(client.base_url(), DEFAULT_BOTSERVER_URL);
     }
 
+    #[test]
+    fn test_join_url() {
+        assert_eq!(join_url("http://h", "/health"), "http://h/health");
+        assert_eq!(join_url("http://h/", "/health"), "http://h/health");
+        assert_eq!(join_url("http://h/", "health"), "http://h/health");
+        assert_eq!(join_url("http://h/api//", "//v1/"), "http://h/api/v1/");
+        assert_eq!(join_url("http://h/api", ""), "http://h/api");
+        assert_eq!(join_url("http://h/api", "?page=2"), "http://h/api?page=2");
+    }
+
+    #[test]
+    fn test_scoped_clients_share_transport() {
+        let root = BotServerClient::new(Some("http://host/".to_string()))
+            .with_auth(AuthScheme::bearer("t"))
+            .raw_error_mapping(true);
+        let admin = root.scoped("/admin/").scoped("users");
+        let other = root.clone().with_base_url("http://other:9000");
+
+        assert_eq!(admin.base_url(), "http://host/admin/users");
+        assert_eq!(other.base_url(), "http://other:9000");
+        assert!(Arc::ptr_eq(&root.transport, &admin.transport));
+        assert!(Arc::ptr_eq(&root.transport, &other.transport));
+        assert_eq!(admin.auth, root.auth);
+        assert!(admin.raw_error_mapping);
+    }
+
+    #[tokio::test]
+    async fn test_scoped_client_inherits_auth_and_interceptors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/messages/inbox"))
+            .and(header("authorization", "Bearer scoped"))
+            .and(header("x-interceptor", "scoped"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let messages = BotServerClient::new(Some(format!("{}/", server.uri())))
+            .with_auth(AuthScheme::bearer("scoped"))
+            .with_interceptor(Arc::new(Recorder {
+                name: "scoped",
+                events: Arc::new(Mutex::new(Vec::new())),
+            }))
+            .scoped("/messages/");
+        let inbox: Result<Vec<serde_json::Value>, BotError> = messages.get("/inbox").await;
+
+        assert!(inbox.is_ok_and(|items| items.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_send_message_validates_before_sending() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/messages"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(BotResponse::new("bot", "s1", "u1", "Olá!", "web")),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let invalid = UserMessage::text("bot", "u1", "", "web", "");
+        let rejected = client.send_message(&invalid).await;
+        assert_eq!(
+            rejected.err().map(|e| e.to_string()).as_deref(),
+            Some("2 validation errors: session_id, content")
+        );
+
+        let reply = client
+            .send_message(&UserMessage::text("bot", "u1", "s1", "web", "Oi"))
+            .await;
+        assert_eq!(reply.ok().map(|r| r.content).as_deref(), Some("Olá!"));
+    }
+
     #[test]
     fn test_client_debug() {
         let client = BotServerClient::new(Some("http://debug-test".to_string()));
@@ -251,4 +1142,990 @@ mod tests {
         assert!(debug_str.contains("BotServerClient"));
         assert!(debug_str.contains("http://debug-test"));
     }
+
+    #[tokio::test]
+    async fn test_token_provider_refreshes_on_401() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .and(header("authorization", "Bearer stale"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .and(header("authorization", "Bearer fresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = Arc::new(RotatingToken {
+            invalidations: AtomicUsize::new(0),
+        });
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_token_provider(Arc::clone(&provider) as Arc<dyn TokenProvider>);
+
+        let body: serde_json::Value = client.get("/api/bots").await.unwrap_or_default();
+        assert_eq!(body, json!({"ok": true}));
+        assert_eq!(provider.invalidations.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_static_token_is_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/me"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!("me")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_token_provider(Arc::new(StaticToken::new("secret")));
+        let me: Result<String, BotError> = client.get("/api/me").await;
+        assert_eq!(me.ok().as_deref(), Some("me"));
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_concurrent_requests_share_refresh() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"access_token": "abc", "expires_in": 3600}))
+                    .set_delay(Duration::from_millis(100)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/ping"))
+            .and(header("authorization", "Bearer abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!("pong")))
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let provider = OAuth2ClientCredentials::new(
+            format!("{}/oauth/token", server.uri()),
+            "client",
+            "secret",
+        );
+        let client =
+            BotServerClient::new(Some(server.uri())).with_token_provider(Arc::new(provider));
+
+        let (a, b, c, d) = tokio::join!(
+            client.get::<String>("/api/ping"),
+            client.get::<String>("/api/ping"),
+            client.get::<String>("/api/ping"),
+            client.get::<String>("/api/ping"),
+        );
+        for result in [a, b, c, d] {
+            assert_eq!(result.ok().as_deref(), Some("pong"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_401s_share_one_token_refresh() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"access_token": "revoked"})),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"access_token": "renewed"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/ping"))
+            .and(header("authorization", "Bearer revoked"))
+            .respond_with(ResponseTemplate::new(401).set_delay(Duration::from_millis(50)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/ping"))
+            .and(header("authorization", "Bearer renewed"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!("pong")))
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let provider = OAuth2ClientCredentials::new(
+            format!("{}/oauth/token", server.uri()),
+            "client",
+            "secret",
+        );
+        let client =
+            BotServerClient::new(Some(server.uri())).with_token_provider(Arc::new(provider));
+
+        let (a, b, c, d) = tokio::join!(
+            client.get::<String>("/api/ping"),
+            client.get::<String>("/api/ping"),
+            client.get::<String>("/api/ping"),
+            client.get::<String>("/api/ping"),
+        );
+        for result in [a, b, c, d] {
+            assert_eq!(result.ok().as_deref(), Some("pong"));
+        }
+    }
+
+    #[test]
+    fn test_oauth2_debug_redacts_secret() {
+        let provider = OAuth2ClientCredentials::new("http://auth/token", "client", "hunter2");
+        let debug_str = format!("{provider:?}");
+        assert!(!debug_str.contains("hunter2"));
+        assert!(debug_str.contains("client"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_scheme_bearer_default() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .and(header("authorization", "Bearer t0ken"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client =
+            BotServerClient::new(Some(server.uri())).with_auth(AuthScheme::bearer("t0ken"));
+        let bots: Result<Vec<String>, BotError> = client.get("/api/bots").await;
+        assert!(bots.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_auth_scheme_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/messages"))
+            .and(header("x-api-key", "k3y"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 1})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_auth(AuthScheme::api_key("X-Api-Key", "k3y"));
+        let sent: Result<serde_json::Value, BotError> =
+            client.post("/api/messages", &json!({"text": "hi"})).await;
+        assert!(sent.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_auth_scheme_basic() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/admin"))
+            .and(header("authorization", "Basic YWRtaW46czNjcmV0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!("ok")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_auth(AuthScheme::basic("admin", "s3cret"));
+        let result: Result<String, BotError> = client.get("/api/admin").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_auth_scheme_none_sends_no_credentials() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/public"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!("ok")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let result: Result<String, BotError> = client.get("/api/public").await;
+        assert!(result.is_ok());
+
+        let requests = server.received_requests().await.unwrap_or_default();
+        assert_eq!(requests.len(), 1);
+        assert!(!requests[0].headers.contains_key("authorization"));
+    }
+
+    #[tokio::test]
+    async fn test_authorized_overrides_default_scheme() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/sessions/1"))
+            .and(header("authorization", "Bearer per-call"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!(true)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_auth(AuthScheme::api_key("X-Api-Key", "k3y"));
+        let deleted: Result<bool, BotError> = client
+            .delete_authorized("/api/sessions/1", "per-call")
+            .await;
+        assert_eq!(deleted.ok(), Some(true));
+
+        let requests = server.received_requests().await.unwrap_or_default();
+        assert!(!requests[0].headers.contains_key("x-api-key"));
+    }
+
+    #[test]
+    fn test_auth_scheme_debug_redacts_secrets() {
+        let client = BotServerClient::new(Some("http://localhost".to_string()))
+            .with_auth(AuthScheme::basic("admin", "s3cret"));
+        let debug_str = format!("{client:?}");
+        assert!(debug_str.contains("admin"));
+        assert!(!debug_str.contains("s3cret"));
+
+        let api_key = format!("{:?}", AuthScheme::api_key("X-Api-Key", "k3y"));
+        assert!(!api_key.contains("k3y"));
+        let bearer = format!("{:?}", AuthScheme::bearer("t0ken"));
+        assert!(!bearer.contains("t0ken"));
+    }
+
+    struct Recorder {
+        name: &'static str,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Interceptor for Recorder {
+        async fn on_request(&self, request: &mut RequestParts) {
+            if let Ok(mut events) = self.events.lock() {
+                events.push(format!("{}:request:{}", self.name, request.method));
+            }
+            if let Ok(value) = reqwest::header::HeaderValue::from_str(self.name) {
+                request.headers.append("x-interceptor", value);
+            }
+        }
+
+        async fn on_response(&self, response: &ResponseMeta) {
+            if let Ok(mut events) = self.events.lock() {
+                events.push(format!("{}:response:{}", self.name, response.status));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptors_run_in_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/messages"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({"id": 7})))
+            .mount(&server)
+            .await;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_interceptor(Arc::new(Recorder {
+                name: "first",
+                events: Arc::clone(&events),
+            }))
+            .with_interceptor(Arc::new(Recorder {
+                name: "second",
+                events: Arc::clone(&events),
+            }));
+
+        let sent: Result<serde_json::Value, BotError> =
+            client.post("/api/messages", &json!({"text": "hi"})).await;
+        assert!(sent.is_ok());
+
+        let events = events.lock().map(|e| e.clone()).unwrap_or_default();
+        assert_eq!(
+            events,
+            vec![
+                "first:request:POST",
+                "second:request:POST",
+                "first:response:201",
+                "second:response:201",
+            ]
+        );
+
+        let requests = server.received_requests().await.unwrap_or_default();
+        let values: Vec<_> = requests[0]
+            .headers
+            .get_all("x-interceptor")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .collect();
+        assert_eq!(values, vec!["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id_reaches_the_wire() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_interceptor(Arc::new(CorrelationIdInterceptor::new()))
+            .with_interceptor(Arc::new(LoggingInterceptor));
+        let bots: Result<Vec<String>, BotError> = client.get("/api/bots").await;
+        assert!(bots.is_ok());
+
+        let requests = server.received_requests().await.unwrap_or_default();
+        let correlation_id = requests[0]
+            .headers
+            .get(CORRELATION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| uuid::Uuid::parse_str(v).ok());
+        assert!(correlation_id.is_some());
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing_capture {
+        use std::collections::HashMap;
+        use std::fmt::Debug;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Subscriber};
+        use tracing_subscriber::layer::{Context, Layer};
+
+        pub type Fields = HashMap<String, String>;
+
+        #[derive(Clone, Default)]
+        pub struct Capture {
+            pub span: Arc<Mutex<Fields>>,
+            pub events: Arc<Mutex<Vec<Fields>>>,
+        }
+
+        struct FieldVisitor<'a>(&'a mut Fields);
+
+        impl Visit for FieldVisitor<'_> {
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                self.0.insert(field.name().to_string(), value.to_string());
+            }
+
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.insert(field.name().to_string(), value.to_string());
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0
+                    .insert(field.name().to_string(), format!("{value:?}"));
+            }
+        }
+
+        impl<S: Subscriber> Layer<S> for Capture {
+            fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+                if attrs.metadata().name() == "http.request" {
+                    if let Ok(mut span) = self.span.lock() {
+                        attrs.record(&mut FieldVisitor(&mut span));
+                    }
+                }
+            }
+
+            fn on_record(&self, _: &Id, values: &Record<'_>, _: Context<'_, S>) {
+                if let Ok(mut span) = self.span.lock() {
+                    values.record(&mut FieldVisitor(&mut span));
+                }
+            }
+
+            fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+                let mut fields = Fields::new();
+                event.record(&mut FieldVisitor(&mut fields));
+                if let Ok(mut events) = self.events.lock() {
+                    events.push(fields);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_tracing_span_fields_on_success() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .mount(&server)
+            .await;
+
+        let capture = tracing_capture::Capture::default();
+        let guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+        let client =
+            BotServerClient::new(Some(server.uri())).with_auth(AuthScheme::bearer("top-secret"));
+        let bots: Result<Vec<String>, BotError> = client.get("/api/bots?page=2").await;
+        drop(guard);
+        assert!(bots.is_ok());
+
+        let span = capture.span.lock().map(|s| s.clone()).unwrap_or_default();
+        assert_eq!(span.get("http.method").map(String::as_str), Some("GET"));
+        assert_eq!(span.get("url.path").map(String::as_str), Some("/api/bots"));
+        assert_eq!(span.get("status_code").map(String::as_str), Some("200"));
+        assert_eq!(span.get("retry_attempt").map(String::as_str), Some("0"));
+        assert!(span.contains_key("elapsed_ms"));
+        assert!(span.values().all(|v| !v.contains("top-secret")));
+        let events = capture.events.lock().map(|e| e.clone()).unwrap_or_default();
+        assert!(!events
+            .iter()
+            .any(|e| e.get("message").map(String::as_str) == Some("HTTP request failed")));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_tracing_error_event_on_500() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/messages"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+            .mount(&server)
+            .await;
+
+        let capture = tracing_capture::Capture::default();
+        let guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_auth(AuthScheme::api_key("X-Api-Key", "top-secret"));
+        let sent: Result<serde_json::Value, BotError> =
+            client.post("/api/messages", &json!({"text": "hi"})).await;
+        drop(guard);
+        assert!(sent.is_err());
+
+        let span = capture.span.lock().map(|s| s.clone()).unwrap_or_default();
+        assert_eq!(span.get("status_code").map(String::as_str), Some("500"));
+
+        let events = capture.events.lock().map(|e| e.clone()).unwrap_or_default();
+        let error_event = events
+            .iter()
+            .find(|e| e.get("message").map(String::as_str) == Some("HTTP request failed"));
+        assert_eq!(
+            error_event
+                .and_then(|e| e.get("status_code"))
+                .map(String::as_str),
+            Some("500")
+        );
+        assert!(events
+            .iter()
+            .flat_map(HashMap::values)
+            .all(|v| !v.contains("top-secret")));
+    }
+
+    #[tokio::test]
+    async fn test_429_retry_after_seconds() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "120"))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let result: Result<serde_json::Value, BotError> = client.get("/api/bots").await;
+        assert!(matches!(
+            result,
+            Err(BotError::RateLimited {
+                retry_after_secs: Some(120),
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_503_retry_after_http_date() {
+        let server = MockServer::start().await;
+        let retry_at = chrono::Utc::now() + chrono::Duration::seconds(90);
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .respond_with(
+                ResponseTemplate::new(503)
+                    .insert_header("Retry-After", retry_at.to_rfc2822().as_str())
+                    .set_body_json(
+                        json!({"success": false, "error": "maintenance", "code": "maintenance"}),
+                    ),
+            )
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let result: Result<serde_json::Value, BotError> = client.get("/api/bots").await;
+        let (message, retry_after_secs) = match result {
+            Err(BotError::ServiceUnavailable {
+                message,
+                retry_after_secs,
+                ..
+            }) => (message, retry_after_secs),
+            _ => (String::new(), None),
+        };
+        assert_eq!(message, "maintenance (maintenance)");
+        assert!(retry_after_secs.is_some_and(|secs| (85..=90).contains(&secs)));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_retry_after_is_ignored() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/limited"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "later"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/down"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "later"))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let limited: Result<serde_json::Value, BotError> = client.get("/api/limited").await;
+        assert!(matches!(
+            limited,
+            Err(BotError::RateLimited {
+                retry_after_secs: None,
+                ..
+            })
+        ));
+
+        let down: Result<serde_json::Value, BotError> = client.get("/api/down").await;
+        assert!(matches!(
+            down,
+            Err(BotError::ServiceUnavailable {
+                retry_after_secs: None,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_status_mapping_over_the_wire() {
+        let server = MockServer::start().await;
+        for status in [400_u16, 401, 404, 409, 500] {
+            Mock::given(method("GET"))
+                .and(path(format!("/status/{status}")))
+                .respond_with(ResponseTemplate::new(status).set_body_string("nope"))
+                .mount(&server)
+                .await;
+        }
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let raw_client = client.clone().raw_error_mapping(true);
+
+        for status in [400_u16, 401, 404, 409, 500] {
+            let endpoint = format!("/status/{status}");
+            let typed: Result<serde_json::Value, BotError> = client.get(&endpoint).await;
+            let raw: Result<serde_json::Value, BotError> = raw_client.get(&endpoint).await;
+
+            let typed_ok = match (status, &typed) {
+                (400, Err(BotError::Validation(m)))
+                | (401, Err(BotError::Auth(m)))
+                | (409, Err(BotError::Conflict(m))) => m == "nope",
+                (
+                    404,
+                    Err(BotError::NotFound {
+                        entity, id: None, ..
+                    }),
+                ) => entity == &endpoint,
+                (
+                    500,
+                    Err(BotError::Http {
+                        status: 500,
+                        message,
+                    }),
+                ) => message == "nope",
+                _ => false,
+            };
+            assert!(typed_ok, "status {status}: {typed:?}");
+            assert!(
+                matches!(&raw, Err(BotError::Http { status: s, message }) if *s == status && message == "nope"),
+                "raw status {status}: {raw:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused_is_service_unavailable() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map(|addr| addr.port())
+            .unwrap_or(1);
+
+        let client = BotServerClient::new(Some(format!("http://127.0.0.1:{port}")));
+        let result: Result<serde_json::Value, BotError> = client.get("/health").await;
+        let err = result.err();
+        assert!(
+            matches!(&err, Some(BotError::ServiceUnavailable { message, .. }) if message.starts_with("connection failed")),
+            "{err:?}"
+        );
+        assert!(err.is_some_and(|e| e.is_retryable()));
+    }
+
+    #[tokio::test]
+    async fn test_slow_response_is_timeout_with_configured_duration() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/slow"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({}))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::with_timeout(Some(server.uri()), Duration::from_millis(100));
+        let result: Result<serde_json::Value, BotError> = client.get("/api/slow").await;
+        assert!(matches!(
+            result,
+            Err(BotError::Timeout { duration_ms: 100 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_json_is_internal_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/garbage"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>oops</html>"))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let result: Result<serde_json::Value, BotError> = client.get("/api/garbage").await;
+        let err = result.err();
+        assert!(matches!(err, Some(BotError::Internal(_))), "{err:?}");
+        assert_eq!(err.map(|e| e.status_code()), Some(500));
+    }
+
+    #[tokio::test]
+    async fn test_post_form_is_url_encoded() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/token"))
+            .and(header("content-type", "application/x-www-form-urlencoded"))
+            .and(header("authorization", "Bearer gw"))
+            .and(body_string("grant_type=password&username=bot+user"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let form = [("grant_type", "password"), ("username", "bot user")];
+        let result: Result<serde_json::Value, BotError> =
+            client.post_form("/oauth/token", &form, "gw").await;
+        assert_eq!(result.ok(), Some(json!({"ok": true})));
+    }
+
+    #[tokio::test]
+    async fn test_no_content_responses() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/sessions/1"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/events"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({"id": 3})))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let deleted: Result<(), BotError> = client.delete("/api/sessions/1").await;
+        let optional: Result<Option<serde_json::Value>, BotError> =
+            client.delete("/api/sessions/1").await;
+        let posted = client
+            .post_no_response("/api/events", &json!({"type": "ping"}))
+            .await;
+
+        assert!(deleted.is_ok());
+        assert!(matches!(optional, Ok(None)));
+        assert!(posted.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_empty_body_for_non_unit_type_is_descriptive() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots/1"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let result: Result<Vec<String>, BotError> = client.get("/api/bots/1").await;
+        assert!(matches!(
+            result,
+            Err(BotError::Internal(msg)) if msg.contains("HTTP 200 response has an empty body")
+                && msg.contains("Vec<alloc::string::String>")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reqwest_error_conversion() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(200)))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(1))
+            .build()
+            .unwrap_or_default();
+        let err = client
+            .get(server.uri())
+            .send()
+            .await
+            .err()
+            .map(BotError::from);
+        assert!(matches!(err, Some(BotError::Timeout { .. })), "{err:?}");
+
+        let err = reqwest::Client::new()
+            .get("http://127.0.0.1:1/")
+            .send()
+            .await
+            .err()
+            .map(BotError::from);
+        assert!(
+            matches!(err, Some(BotError::ServiceUnavailable { .. })),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_body_size_guard() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/bulk"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!(true)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri())).with_max_request_body_bytes(16);
+        let just_under = "x".repeat(14);
+        let just_over = "x".repeat(15);
+        assert_eq!(
+            serde_json::to_vec(&just_under).map(|b| b.len()).ok(),
+            Some(16)
+        );
+
+        let accepted: Result<bool, BotError> = client.post("/api/bulk", &just_under).await;
+        assert_eq!(accepted.ok(), Some(true));
+
+        let rejected: Result<bool, BotError> = client.put("/api/bulk", &just_over).await;
+        let err = rejected.err();
+        assert!(
+            matches!(&err, Some(BotError::Validation(m)) if m.contains("17 > 16")),
+            "{err:?}"
+        );
+        assert_eq!(server.received_requests().await.map(|r| r.len()), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_reused_across_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/messages"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 1})))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri())).with_retry(
+            RetryConfig::default()
+                .with_initial_delay(Duration::from_millis(1))
+                .with_jitter(0.0),
+        );
+        let sent: Result<Idempotent<serde_json::Value>, BotError> = client
+            .post_with_idempotency("/api/messages", &json!({"text": "hi"}))
+            .await;
+        let sent = sent.ok();
+        assert_eq!(
+            sent.as_ref().map(|s| s.value.clone()),
+            Some(json!({"id": 1}))
+        );
+
+        let requests = server.received_requests().await.unwrap_or_default();
+        let keys: Vec<_> = requests
+            .iter()
+            .filter_map(|r| r.headers.get(IDEMPOTENCY_KEY_HEADER))
+            .filter_map(|v| v.to_str().ok())
+            .collect();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0], keys[1]);
+        assert_eq!(sent.map(|s| s.key).as_deref(), Some(keys[0]));
+    }
+
+    #[tokio::test]
+    async fn test_retry_waits_for_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri())).with_retry(
+            RetryConfig::default()
+                .with_initial_delay(Duration::from_millis(1))
+                .with_jitter(0.0),
+        );
+        let started = Instant::now();
+        let bots: Result<Vec<serde_json::Value>, BotError> = client.get("/api/bots").await;
+        assert!(bots.is_ok_and(|bots| bots.is_empty()));
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_plain_post_is_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/messages"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_retry(RetryConfig::default().with_initial_delay(Duration::from_millis(1)));
+        let sent: Result<serde_json::Value, BotError> =
+            client.post("/api/messages", &json!({"text": "hi"})).await;
+        assert!(sent.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_post_idempotent_sends_key_and_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/messages"))
+            .and(header("idempotency-key", "order-42"))
+            .and(header("authorization", "Bearer t0ken"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!(true)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let sent: Result<bool, BotError> = client
+            .post_idempotent("/api/messages", &json!({}), "order-42", "t0ken")
+            .await;
+        assert_eq!(sent.ok(), Some(true));
+    }
+
+    #[test]
+    fn test_idempotency_keys_are_unique() {
+        let a = IdempotencyKey::new();
+        let b = IdempotencyKey::new();
+        assert_ne!(a, b);
+        assert!(uuid::Uuid::parse_str(a.as_str()).is_ok());
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let written = encoder.write_all(bytes);
+        assert!(written.is_ok());
+        encoder.finish().unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_gzip_response_is_decoded_transparently() {
+        let server = MockServer::start().await;
+        let results = json!({"results": vec!["chunk of a knowledge base article"; 2000]});
+        let compressed = gzip(&serde_json::to_vec(&results).unwrap_or_default());
+        Mock::given(method("GET"))
+            .and(path("/api/kb/search"))
+            .and(header_regex("accept-encoding", "gzip"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-encoding", "gzip")
+                    .insert_header("content-type", "application/json")
+                    .set_body_bytes(compressed.clone()),
+            )
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let decoded: Result<serde_json::Value, BotError> = client.get("/api/kb/search").await;
+        assert_eq!(decoded.ok(), Some(results));
+
+        let plain = BotServerClient::new(Some(server.uri())).with_compression(false);
+        let raw = plain.download("/api/kb/search", None).await;
+        assert!(raw.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_response_cap_counts_decompressed_bytes() {
+        let server = MockServer::start().await;
+        let compressed = gzip(&vec![b' '; 1024 * 1024]);
+        assert!(compressed.len() < 64 * 1024);
+        Mock::given(method("GET"))
+            .and(path("/api/kb/search"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-encoding", "gzip")
+                    .set_body_bytes(compressed),
+            )
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri())).with_max_response_bytes(64 * 1024);
+        let result: Result<serde_json::Value, BotError> = client.get("/api/kb/search").await;
+        let message = match result {
+            Err(BotError::Validation(message)) => message.into(),
+            other => format!("{other:?}"),
+        };
+        assert!(message.contains("response_body"), "{message}");
+        assert!(message.contains("65536 (max)"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn test_response_cap_applies_to_any_transport() {
+        let mock = Arc::new(testing::MockTransport::new());
+        mock.push_response(HttpResponse::new(StatusCode::OK).with_body(vec![b'1'; 32]));
+
+        let client = BotServerClient::new(Some("http://mock".to_string()))
+            .with_transport(mock)
+            .with_max_response_bytes(16);
+        let result: Result<u64, BotError> = client.get("/big").await;
+        assert!(matches!(result, Err(BotError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_compression_leaves_injected_transport_in_place() {
+        let mock = Arc::new(testing::MockTransport::new());
+        mock.push_response(HttpResponse::new(StatusCode::OK).with_body(b"7".to_vec()));
+
+        let client = BotServerClient::new(Some("http://mock".to_string()))
+            .with_transport(mock.clone())
+            .with_compression(false);
+        let result: Result<u64, BotError> = client.get("/count").await;
+        assert_eq!(result.ok(), Some(7));
+        assert_eq!(mock.requests().len(), 1);
+    }
 }
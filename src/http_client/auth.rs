@@ -0,0 +1,313 @@
+use crate::error::BotError;
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use log::debug;
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+const DEFAULT_REFRESH_SKEW_SECS: u64 = 60;
+const DEFAULT_TOKEN_LIFETIME_SECS: u64 = 3600;
+
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub enum AuthScheme {
+    Bearer(String),
+    ApiKey {
+        header_name: String,
+        key: String,
+    },
+    Basic {
+        user: String,
+        pass: String,
+    },
+    #[default]
+    None,
+}
+
+impl AuthScheme {
+    #[must_use]
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::Bearer(token.into())
+    }
+
+    #[must_use]
+    pub fn api_key(header_name: impl Into<String>, key: impl Into<String>) -> Self {
+        Self::ApiKey {
+            header_name: header_name.into(),
+            key: key.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn basic(user: impl Into<String>, pass: impl Into<String>) -> Self {
+        Self::Basic {
+            user: user.into(),
+            pass: pass.into(),
+        }
+    }
+
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Bearer(_) => "bearer",
+            Self::ApiKey { .. } => "api-key",
+            Self::Basic { .. } => "basic",
+            Self::None => "none",
+        }
+    }
+
+    #[must_use]
+    pub const fn is_none(&self) -> bool {
+        matches!(self, Self::None)
+    }
+
+    pub(crate) fn header(&self) -> Result<Option<(HeaderName, HeaderValue)>, BotError> {
+        let (name, value) = match self {
+            Self::Bearer(token) => (AUTHORIZATION, format!("Bearer {token}")),
+            Self::ApiKey { header_name, key } => {
+                let name = HeaderName::from_bytes(header_name.as_bytes()).map_err(|_| {
+                    BotError::validation(format!("invalid API key header name: {header_name}"))
+                })?;
+                (name, key.clone())
+            }
+            Self::Basic { user, pass } => {
+                let encoded = BASE64_STANDARD.encode(format!("{user}:{pass}"));
+                (AUTHORIZATION, format!("Basic {encoded}"))
+            }
+            Self::None => return Ok(None),
+        };
+        let mut value = HeaderValue::from_str(&value).map_err(|_| {
+            BotError::validation(format!(
+                "{} credentials are not a valid header value",
+                self.kind()
+            ))
+        })?;
+        value.set_sensitive(true);
+        Ok(Some((name, value)))
+    }
+
+    pub(crate) fn identity(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{}:{:016x}", self.kind(), hasher.finish())
+    }
+}
+
+impl std::fmt::Debug for AuthScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bearer(_) => f.debug_tuple("Bearer").field(&"[redacted]").finish(),
+            Self::ApiKey { header_name, .. } => f
+                .debug_struct("ApiKey")
+                .field("header_name", header_name)
+                .field("key", &"[redacted]")
+                .finish(),
+            Self::Basic { user, .. } => f
+                .debug_struct("Basic")
+                .field("user", user)
+                .field("pass", &"[redacted]")
+                .finish(),
+            Self::None => write!(f, "None"),
+        }
+    }
+}
+
+#[async_trait]
+pub trait TokenProvider: Send + Sync {
+    /// Return a bearer token that is currently valid, refreshing it if needed.
+    ///
+    /// # Errors
+    /// Returns an error if no token can be obtained.
+    async fn get_token(&self) -> Result<String, BotError>;
+
+    /// Discard any cached token so the next `get_token` call fetches a fresh one.
+    async fn invalidate(&self);
+
+    /// Discard the cached token only if it is still `rejected`, so every
+    /// request that failed with the same token shares a single refresh.
+    ///
+    /// The default compares against `get_token`; providers that cache behind
+    /// a lock should override it to compare and discard atomically.
+    async fn invalidate_token(&self, rejected: &str) {
+        if self
+            .get_token()
+            .await
+            .is_ok_and(|current| current == rejected)
+        {
+            self.invalidate().await;
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StaticToken {
+    token: String,
+}
+
+impl StaticToken {
+    #[must_use]
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for StaticToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StaticToken")
+            .field("token", &"[redacted]")
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TokenProvider for StaticToken {
+    async fn get_token(&self) -> Result<String, BotError> {
+        Ok(self.token.clone())
+    }
+
+    async fn invalidate(&self) {}
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+pub struct OAuth2ClientCredentials {
+    http: reqwest::Client,
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+    scope: Option<String>,
+    refresh_skew: Duration,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl OAuth2ClientCredentials {
+    #[must_use]
+    pub fn new(
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            scope: None,
+            refresh_skew: Duration::from_secs(DEFAULT_REFRESH_SKEW_SECS),
+            cached: Mutex::new(None),
+        }
+    }
+
+    #[must_use]
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Refresh the token this long before it actually expires.
+    #[must_use]
+    pub const fn with_refresh_skew(mut self, skew: Duration) -> Self {
+        self.refresh_skew = skew;
+        self
+    }
+
+    #[must_use]
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    async fn fetch_token(&self) -> Result<CachedToken, BotError> {
+        debug!(
+            "Requesting client-credentials token from {}",
+            self.token_url
+        );
+
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ];
+        if let Some(scope) = &self.scope {
+            form.push(("scope", scope.as_str()));
+        }
+
+        let response = self.http.post(&self.token_url).form(&form).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(BotError::auth(format!(
+                "token endpoint returned {}",
+                status.as_u16()
+            )));
+        }
+
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| BotError::auth(format!("invalid token response: {e}")))?;
+        let lifetime = Duration::from_secs(token.expires_in.unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS));
+
+        Ok(CachedToken {
+            access_token: token.access_token,
+            expires_at: Instant::now() + lifetime,
+        })
+    }
+}
+
+impl std::fmt::Debug for OAuth2ClientCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuth2ClientCredentials")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"[redacted]")
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl TokenProvider for OAuth2ClientCredentials {
+    async fn get_token(&self) -> Result<String, BotError> {
+        let mut cached = self.cached.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if Instant::now() + self.refresh_skew < token.expires_at {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let token = self.fetch_token().await?;
+        let access_token = token.access_token.clone();
+        *cached = Some(token);
+        Ok(access_token)
+    }
+
+    async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+
+    async fn invalidate_token(&self, rejected: &str) {
+        let mut cached = self.cached.lock().await;
+        if cached
+            .as_ref()
+            .is_some_and(|token| token.access_token == rejected)
+        {
+            *cached = None;
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use super::BotServerClient;
+use crate::error::BotError;
+use crate::limits::RateLimiter;
+use futures_util::stream::{self, StreamExt};
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_RATE_LIMIT_KEY: &str = "batch";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchItem<T> {
+    pub method: Method,
+    pub endpoint: String,
+    pub body: Option<T>,
+}
+
+impl<T> BatchItem<T> {
+    #[must_use]
+    pub fn new(method: Method, endpoint: impl Into<String>, body: Option<T>) -> Self {
+        Self {
+            method,
+            endpoint: endpoint.into(),
+            body,
+        }
+    }
+
+    #[must_use]
+    pub fn get(endpoint: impl Into<String>) -> Self {
+        Self::new(Method::GET, endpoint, None)
+    }
+
+    #[must_use]
+    pub fn post(endpoint: impl Into<String>, body: T) -> Self {
+        Self::new(Method::POST, endpoint, Some(body))
+    }
+
+    #[must_use]
+    pub fn put(endpoint: impl Into<String>, body: T) -> Self {
+        Self::new(Method::PUT, endpoint, Some(body))
+    }
+
+    #[must_use]
+    pub fn patch(endpoint: impl Into<String>, body: T) -> Self {
+        Self::new(Method::PATCH, endpoint, Some(body))
+    }
+
+    #[must_use]
+    pub fn delete(endpoint: impl Into<String>) -> Self {
+        Self::new(Method::DELETE, endpoint, None)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailFast {
+    #[default]
+    Disabled,
+    Enabled,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    concurrency: usize,
+    fail_fast: FailFast,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    rate_limit_key: String,
+}
+
+impl BatchOptions {
+    #[must_use]
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            fail_fast: FailFast::Disabled,
+            rate_limiter: None,
+            rate_limit_key: DEFAULT_RATE_LIMIT_KEY.to_string(),
+        }
+    }
+
+    #[must_use]
+    pub const fn with_fail_fast(mut self, fail_fast: FailFast) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    #[must_use]
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>, key: impl Into<String>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self.rate_limit_key = key.into();
+        self
+    }
+
+    #[must_use]
+    pub const fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    #[must_use]
+    pub const fn fail_fast(&self) -> FailFast {
+        self.fail_fast
+    }
+}
+
+impl BotServerClient {
+    /// Execute `requests` with at most `concurrency` in flight. Results are
+    /// returned in input order.
+    pub async fn batch<T, R>(
+        &self,
+        requests: Vec<BatchItem<T>>,
+        concurrency: usize,
+    ) -> Vec<Result<R, BotError>>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.batch_with_options(requests, &BatchOptions::new(concurrency))
+            .await
+    }
+
+    /// Like [`Self::batch`], with fail-fast and rate limiting controlled by
+    /// `options`. With `FailFast::Enabled` the first error stops the batch and
+    /// every item that did not complete is reported as cancelled.
+    pub async fn batch_with_options<T, R>(
+        &self,
+        requests: Vec<BatchItem<T>>,
+        options: &BatchOptions,
+    ) -> Vec<Result<R, BotError>>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut results: Vec<Option<Result<R, BotError>>> = std::iter::repeat_with(|| None)
+            .take(requests.len())
+            .collect();
+
+        let mut pending = stream::iter(requests.into_iter().enumerate())
+            .map(|(index, item)| async move { (index, self.run_batch_item(item, options).await) })
+            .buffer_unordered(options.concurrency);
+
+        while let Some((index, result)) = pending.next().await {
+            let failed = result.is_err();
+            if let Some(slot) = results.get_mut(index) {
+                *slot = Some(result);
+            }
+            if failed && options.fail_fast == FailFast::Enabled {
+                break;
+            }
+        }
+        drop(pending);
+
+        results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| {
+                result.unwrap_or_else(|| {
+                    Err(BotError::internal(format!(
+                        "batch item {index} cancelled after an earlier failure"
+                    )))
+                })
+            })
+            .collect()
+    }
+
+    async fn run_batch_item<T, R>(
+        &self,
+        item: BatchItem<T>,
+        options: &BatchOptions,
+    ) -> Result<R, BotError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        if let Some(limiter) = &options.rate_limiter {
+            while let Err(exceeded) = limiter.check_rate_limit(&options.rate_limit_key).await {
+                let wait = exceeded.retry_after_secs.unwrap_or(1);
+                tokio::time::sleep(Duration::from_secs(wait)).await;
+            }
+        }
+
+        let body = item
+            .body
+            .as_ref()
+            .map(|body| self.encode_body(body))
+            .transpose()?;
+        self.request(item.method, &item.endpoint, body, None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::{Interceptor, RequestParts, ResponseMeta};
+    use crate::limits::SystemLimits;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[derive(Default)]
+    struct InFlight {
+        current: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Interceptor for InFlight {
+        async fn on_request(&self, _: &mut RequestParts) {
+            let now = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(now, Ordering::SeqCst);
+        }
+
+        async fn on_response(&self, _: &ResponseMeta) {
+            self.current.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn delayed(value: serde_json::Value, millis: u64) -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .set_body_json(value)
+            .set_delay(Duration::from_millis(millis))
+    }
+
+    #[tokio::test]
+    async fn test_batch_preserves_input_order() {
+        let server = MockServer::start().await;
+        for (index, millis) in [(0, 150), (1, 10), (2, 80), (3, 0)] {
+            Mock::given(method("GET"))
+                .and(path(format!("/items/{index}")))
+                .respond_with(delayed(json!(index), millis))
+                .mount(&server)
+                .await;
+        }
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let requests = (0..4)
+            .map(|index| BatchItem::<()>::get(format!("/items/{index}")))
+            .collect();
+        let results: Vec<Result<u32, BotError>> = client.batch(requests, 4).await;
+
+        let values: Vec<u32> = results.into_iter().filter_map(Result::ok).collect();
+        assert_eq!(values, vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_honors_concurrency_cap() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .respond_with(delayed(json!({"ok": true}), 50))
+            .expect(10)
+            .mount(&server)
+            .await;
+
+        let counter = Arc::new(InFlight::default());
+        let client = BotServerClient::new(Some(server.uri())).with_interceptor(counter.clone());
+        let requests = (0..10)
+            .map(|index| BatchItem::post("/messages", json!({"session": index})))
+            .collect();
+        let results: Vec<Result<serde_json::Value, BotError>> = client.batch(requests, 3).await;
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(counter.max.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_fail_fast_cancels_remainder() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ok"))
+            .respond_with(delayed(json!(1), 0))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fail"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/after"))
+            .respond_with(delayed(json!(2), 0))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let requests = vec![
+            BatchItem::<()>::get("/ok"),
+            BatchItem::get("/fail"),
+            BatchItem::get("/after"),
+            BatchItem::get("/after"),
+        ];
+        let options = BatchOptions::new(1).with_fail_fast(FailFast::Enabled);
+        let results: Vec<Result<u32, BotError>> =
+            client.batch_with_options(requests, &options).await;
+
+        assert_eq!(results.len(), 4);
+        assert!(matches!(results.first(), Some(Ok(1))));
+        assert!(matches!(
+            results.get(1),
+            Some(Err(BotError::Http { status: 500, .. }))
+        ));
+        assert!(results
+            .iter()
+            .skip(2)
+            .all(|r| matches!(r, Err(BotError::Internal(msg)) if msg.contains("cancelled"))));
+    }
+
+    #[tokio::test]
+    async fn test_batch_without_fail_fast_runs_everything() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/fail"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/after"))
+            .respond_with(delayed(json!(2), 0))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let requests = vec![
+            BatchItem::<()>::get("/fail"),
+            BatchItem::get("/after"),
+            BatchItem::get("/after"),
+        ];
+        let results: Vec<Result<u32, BotError>> = client.batch(requests, 1).await;
+
+        assert!(results.first().is_some_and(Result::is_err));
+        assert!(results.iter().skip(1).all(|r| matches!(r, Ok(2))));
+    }
+
+    #[tokio::test]
+    async fn test_batch_consumes_shared_rate_limiter() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .respond_with(delayed(json!(true), 0))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let limits = SystemLimits {
+            max_api_calls_per_minute: 3,
+            ..SystemLimits::default()
+        };
+        let limiter = Arc::new(RateLimiter::new(limits));
+        let client = BotServerClient::new(Some(server.uri()));
+        let requests = (0..3).map(|_| BatchItem::<()>::get("/ping")).collect();
+        let options = BatchOptions::new(2).with_rate_limiter(limiter.clone(), "broadcast");
+        let results: Vec<Result<bool, BotError>> =
+            client.batch_with_options(requests, &options).await;
+
+        assert!(results.iter().all(Result::is_ok));
+        assert!(limiter.check_rate_limit("broadcast").await.is_err());
+    }
+
+    #[test]
+    fn test_batch_options_clamps_concurrency() {
+        let options = BatchOptions::new(0);
+        assert_eq!(options.concurrency(), 1);
+        assert_eq!(options.fail_fast(), FailFast::Disabled);
+    }
+}
@@ -0,0 +1,387 @@
+use super::transport::{append_capped, reqwest_error, user_agent};
+use super::{encode_json, error_for_status, join_url, parse_body, request_headers, AuthScheme};
+use super::{DEFAULT_BOTSERVER_URL, DEFAULT_TIMEOUT_SECS};
+use crate::error::BotError;
+use crate::limits::MAX_REQUEST_BODY_BYTES;
+use log::debug;
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{ErrorKind, Read};
+use std::time::Duration;
+
+const READ_CHUNK_BYTES: usize = 8 * 1024;
+
+/// Synchronous counterpart of `BotServerClient` for callers without an async
+/// runtime. It must not be used from inside one.
+#[derive(Clone)]
+pub struct BlockingBotServerClient {
+    client: reqwest::blocking::Client,
+    base_url: String,
+    timeout: Duration,
+    max_request_body_bytes: u64,
+    max_response_bytes: u64,
+    auth: AuthScheme,
+    raw_error_mapping: bool,
+}
+
+impl BlockingBotServerClient {
+    #[must_use]
+    pub fn new(base_url: Option<String>) -> Self {
+        Self::with_timeout(base_url, Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+    }
+
+    #[must_use]
+    pub fn with_timeout(base_url: Option<String>, timeout: Duration) -> Self {
+        let url = base_url.unwrap_or_else(|| {
+            std::env::var("BOTSERVER_URL").unwrap_or_else(|_| DEFAULT_BOTSERVER_URL.to_string())
+        });
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .user_agent(user_agent())
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap_or_else(|_| reqwest::blocking::Client::new());
+
+        Self {
+            client,
+            base_url: url,
+            timeout,
+            max_request_body_bytes: MAX_REQUEST_BODY_BYTES,
+            max_response_bytes: MAX_REQUEST_BODY_BYTES,
+            auth: AuthScheme::None,
+            raw_error_mapping: false,
+        }
+    }
+
+    /// Authenticate every request with `auth` unless a call overrides it.
+    #[must_use]
+    pub fn with_auth(mut self, auth: AuthScheme) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Return every failure as `BotError::Http` with the untouched response
+    /// body, as `BotServerClient::raw_error_mapping` does.
+    #[must_use]
+    pub const fn raw_error_mapping(mut self, enabled: bool) -> Self {
+        self.raw_error_mapping = enabled;
+        self
+    }
+
+    /// Reject JSON bodies larger than `max_bytes` before any network I/O.
+    #[must_use]
+    pub const fn with_max_request_body_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_request_body_bytes = max_bytes;
+        self
+    }
+
+    /// Abort any response whose body exceeds `max_bytes` with
+    /// `BotError::Validation`, as `BotServerClient::with_max_response_bytes`
+    /// does. Defaults to `MAX_REQUEST_BODY_BYTES`.
+    #[must_use]
+    pub const fn with_max_response_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_response_bytes = max_bytes;
+        self
+    }
+
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Perform a GET request to the specified endpoint.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, BotError> {
+        self.request(Method::GET, endpoint, None, None)
+    }
+
+    /// Perform a POST request to the specified endpoint.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub fn post<T: Serialize, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &T,
+    ) -> Result<R, BotError> {
+        let body = encode_json(body, self.max_request_body_bytes)?;
+        self.request(Method::POST, endpoint, Some(body), None)
+    }
+
+    /// Perform a PUT request to the specified endpoint.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub fn put<T: Serialize, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &T,
+    ) -> Result<R, BotError> {
+        let body = encode_json(body, self.max_request_body_bytes)?;
+        self.request(Method::PUT, endpoint, Some(body), None)
+    }
+
+    /// Perform a PATCH request to the specified endpoint.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub fn patch<T: Serialize, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &T,
+    ) -> Result<R, BotError> {
+        let body = encode_json(body, self.max_request_body_bytes)?;
+        self.request(Method::PATCH, endpoint, Some(body), None)
+    }
+
+    /// Perform a DELETE request to the specified endpoint.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub fn delete<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, BotError> {
+        self.request(Method::DELETE, endpoint, None, None)
+    }
+
+    /// Perform an authorized GET request with a bearer token.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub fn get_authorized<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        token: &str,
+    ) -> Result<T, BotError> {
+        self.request(
+            Method::GET,
+            endpoint,
+            None,
+            Some(&AuthScheme::bearer(token)),
+        )
+    }
+
+    /// Perform an authorized POST request with a bearer token.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub fn post_authorized<T: Serialize, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: &T,
+        token: &str,
+    ) -> Result<R, BotError> {
+        let body = encode_json(body, self.max_request_body_bytes)?;
+        self.request(
+            Method::POST,
+            endpoint,
+            Some(body),
+            Some(&AuthScheme::bearer(token)),
+        )
+    }
+
+    /// Perform an authorized DELETE request with a bearer token.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub fn delete_authorized<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        token: &str,
+    ) -> Result<T, BotError> {
+        self.request(
+            Method::DELETE,
+            endpoint,
+            None,
+            Some(&AuthScheme::bearer(token)),
+        )
+    }
+
+    fn request<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<Vec<u8>>,
+        auth: Option<&AuthScheme>,
+    ) -> Result<R, BotError> {
+        let url = join_url(&self.base_url, endpoint);
+        let auth = auth.unwrap_or(&self.auth);
+        let headers = request_headers(HeaderMap::new(), body.is_some(), auth)?;
+        let mut builder = self.client.request(method.clone(), &url).headers(headers);
+        if let Some(body) = body {
+            builder = builder.body(body);
+        }
+
+        let response = builder.send().map_err(|e| reqwest_error(e, self.timeout))?;
+        let status = response.status();
+        let response_headers = response.headers().clone();
+        let bytes = read_capped(response, self.max_response_bytes, self.timeout)?;
+        debug!("{method} {url} -> {}", status.as_u16());
+
+        error_for_status(
+            status,
+            &response_headers,
+            &bytes,
+            endpoint,
+            self.raw_error_mapping,
+        )?;
+        parse_body(status, &bytes)
+    }
+}
+
+fn read_capped(
+    mut reader: impl Read,
+    max_bytes: u64,
+    timeout: Duration,
+) -> Result<Vec<u8>, BotError> {
+    let mut body = Vec::new();
+    let mut buffer = [0_u8; READ_CHUNK_BYTES];
+    loop {
+        let read = reader.read(&mut buffer).map_err(|e| match e.kind() {
+            ErrorKind::TimedOut => {
+                BotError::timeout(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX))
+            }
+            _ => BotError::from(e),
+        })?;
+        if read == 0 {
+            return Ok(body);
+        }
+        append_capped(&mut body, &buffer[..read], Some(max_bytes))?;
+    }
+}
+
+impl std::fmt::Debug for BlockingBotServerClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingBotServerClient")
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("max_request_body_bytes", &self.max_request_body_bytes)
+            .field("max_response_bytes", &self.max_response_bytes)
+            .field("auth", &self.auth)
+            .field("raw_error_mapping", &self.raw_error_mapping)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn mock_server(mocks: Vec<Mock>) -> Option<(tokio::runtime::Runtime, MockServer)> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .ok()?;
+        let server = runtime.block_on(async {
+            let server = MockServer::start().await;
+            for mock in mocks {
+                mock.mount(&server).await;
+            }
+            server
+        });
+        Some((runtime, server))
+    }
+
+    #[test]
+    fn test_blocking_round_trip_without_runtime() {
+        let setup = mock_server(vec![
+            Mock::given(method("GET"))
+                .and(path("/api/version"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"v": "6.1.0"}))),
+            Mock::given(method("POST"))
+                .and(path("/api/bots"))
+                .and(header("authorization", "Bearer cli-token"))
+                .and(header("content-type", "application/json"))
+                .and(body_json(json!({"name": "installer"})))
+                .respond_with(ResponseTemplate::new(201).set_body_json(json!({"id": 7}))),
+            Mock::given(method("PATCH"))
+                .and(path("/api/bots/7"))
+                .and(body_json(json!({"name": "renamed"})))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 7}))),
+            Mock::given(method("DELETE"))
+                .and(path("/api/bots/7"))
+                .respond_with(ResponseTemplate::new(204)),
+        ]);
+        assert!(setup.is_some());
+        let Some((runtime, server)) = setup else {
+            return;
+        };
+
+        let client = BlockingBotServerClient::new(Some(format!("{}/", server.uri())));
+        let version: Result<serde_json::Value, BotError> = client.get("/api/version");
+        assert_eq!(version.ok(), Some(json!({"v": "6.1.0"})));
+
+        let created: Result<serde_json::Value, BotError> =
+            client.post_authorized("api/bots", &json!({"name": "installer"}), "cli-token");
+        assert_eq!(created.ok(), Some(json!({"id": 7})));
+
+        let patched: Result<serde_json::Value, BotError> =
+            client.patch("/api/bots/7", &json!({"name": "renamed"}));
+        assert_eq!(patched.ok(), Some(json!({"id": 7})));
+
+        let deleted: Result<(), BotError> = client.delete("/api/bots/7");
+        assert!(deleted.is_ok());
+        drop(runtime);
+    }
+
+    #[test]
+    fn test_blocking_shares_error_mapping() {
+        let setup = mock_server(vec![
+            Mock::given(method("GET"))
+                .and(path("/api/bots/missing"))
+                .respond_with(ResponseTemplate::new(404).set_body_string("no such bot")),
+            Mock::given(method("PUT"))
+                .and(path("/api/bots/1"))
+                .respond_with(ResponseTemplate::new(401)),
+        ]);
+        assert!(setup.is_some());
+        let Some((runtime, server)) = setup else {
+            return;
+        };
+
+        let client = BlockingBotServerClient::new(Some(server.uri()));
+        let missing: Result<serde_json::Value, BotError> = client.get("/api/bots/missing");
+        assert!(matches!(missing, Err(BotError::NotFound { .. })));
+        let denied: Result<serde_json::Value, BotError> = client.put("/api/bots/1", &json!({}));
+        assert!(matches!(denied, Err(BotError::Auth(_))));
+
+        let raw = client.raw_error_mapping(true);
+        let missing: Result<serde_json::Value, BotError> = raw.get("/api/bots/missing");
+        assert!(matches!(missing, Err(BotError::Http { status: 404, .. })));
+
+        let oversized: Result<serde_json::Value, BotError> = raw
+            .with_max_request_body_bytes(4)
+            .post("/api/bots", &json!({"name": "too long"}));
+        assert!(matches!(oversized, Err(BotError::Validation(_))));
+        drop(runtime);
+    }
+
+    #[test]
+    fn test_blocking_caps_response_body() {
+        let setup = mock_server(vec![Mock::given(method("GET"))
+            .and(path("/api/export"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!("x".repeat(20_000))),
+            )]);
+        assert!(setup.is_some());
+        let Some((runtime, server)) = setup else {
+            return;
+        };
+
+        let client = BlockingBotServerClient::new(Some(server.uri()));
+        let capped: Result<String, BotError> = client
+            .clone()
+            .with_max_response_bytes(16 * 1024)
+            .get("/api/export");
+        assert!(matches!(capped, Err(BotError::Validation(_))));
+
+        let full: Result<String, BotError> = client.get("/api/export");
+        assert_eq!(full.map(|body| body.len()).ok(), Some(20_000));
+        drop(runtime);
+    }
+}
@@ -0,0 +1,478 @@
+use super::{instrumented, parse_body, AuthScheme, BotServerClient, Call, HttpResponse};
+use crate::error::BotError;
+use log::debug;
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub const DEFAULT_CACHE_MAX_ENTRIES: usize = 256;
+pub const DEFAULT_CACHE_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    pub url: String,
+    pub identity: String,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    pub body: Vec<u8>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub stored_at: Instant,
+}
+
+impl CachedResponse {
+    fn from_fetched(fetched: HttpResponse) -> Self {
+        let header = |name| {
+            fetched
+                .headers
+                .get(name)
+                .and_then(|v: &HeaderValue| v.to_str().ok())
+                .map(String::from)
+        };
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            body: fetched.body,
+            stored_at: Instant::now(),
+        }
+    }
+
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        self.stored_at.elapsed() < ttl
+    }
+
+    fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = self
+            .etag
+            .as_deref()
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(IF_NONE_MATCH, value);
+        }
+        if let Some(value) = self
+            .last_modified
+            .as_deref()
+            .and_then(|v| HeaderValue::from_str(v).ok())
+        {
+            headers.insert(IF_MODIFIED_SINCE, value);
+        }
+        headers
+    }
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CachedResponse>,
+    order: VecDeque<CacheKey>,
+    total_bytes: usize,
+}
+
+impl CacheState {
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            if let Some(k) = self.order.remove(pos) {
+                self.order.push_back(k);
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.total_bytes = self.total_bytes.saturating_sub(entry.body.len());
+            self.order.retain(|k| k != key);
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some(key) = self.order.pop_front() {
+            if let Some(entry) = self.entries.remove(&key) {
+                self.total_bytes = self.total_bytes.saturating_sub(entry.body.len());
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResponseCache {
+    max_entries: usize,
+    max_bytes: usize,
+    state: Mutex<CacheState>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_MAX_ENTRIES, DEFAULT_CACHE_MAX_BYTES)
+    }
+}
+
+impl ResponseCache {
+    #[must_use]
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            max_entries,
+            max_bytes,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    pub async fn len(&self) -> usize {
+        self.state.lock().await.entries.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.state.lock().await.entries.is_empty()
+    }
+
+    pub async fn total_bytes(&self) -> usize {
+        self.state.lock().await.total_bytes
+    }
+
+    pub async fn clear(&self) {
+        *self.state.lock().await = CacheState::default();
+    }
+
+    pub(crate) async fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let mut state = self.state.lock().await;
+        let entry = state.entries.get(key).cloned()?;
+        state.touch(key);
+        Some(entry)
+    }
+
+    pub(crate) async fn insert(&self, key: CacheKey, entry: CachedResponse) {
+        let mut state = self.state.lock().await;
+        state.remove(&key);
+        if self.max_entries == 0 || entry.body.len() > self.max_bytes {
+            return;
+        }
+
+        while state.entries.len() >= self.max_entries
+            || state.total_bytes + entry.body.len() > self.max_bytes
+        {
+            state.evict_oldest();
+        }
+
+        state.total_bytes += entry.body.len();
+        state.order.push_back(key.clone());
+        state.entries.insert(key, entry);
+    }
+
+    pub(crate) async fn refresh(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let mut state = self.state.lock().await;
+        let entry = state.entries.get_mut(key)?;
+        entry.stored_at = Instant::now();
+        let entry = entry.clone();
+        state.touch(key);
+        Some(entry)
+    }
+
+    pub(crate) async fn invalidate_url(&self, url: &str) {
+        let mut state = self.state.lock().await;
+        let keys: Vec<CacheKey> = state
+            .entries
+            .keys()
+            .filter(|k| k.url == url)
+            .cloned()
+            .collect();
+        for key in &keys {
+            state.remove(key);
+        }
+    }
+}
+
+impl BotServerClient {
+    /// Perform a GET request through the response cache. A cached body younger
+    /// than `ttl` is returned without network I/O; older entries are
+    /// revalidated with `If-None-Match` / `If-Modified-Since` and reused on 304.
+    /// Without a configured cache this is a plain `get`.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn get_cached<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        ttl: Duration,
+    ) -> Result<T, BotError> {
+        let Some(cache) = &self.cache else {
+            return self.get(endpoint).await;
+        };
+
+        let call = Call::new(self, Method::GET, endpoint);
+        let key = CacheKey {
+            url: call.url.clone(),
+            identity: self.cache_identity().await?,
+        };
+        let cached = cache.get(&key).await;
+        if let Some(entry) = cached.as_ref().filter(|entry| entry.is_fresh(ttl)) {
+            debug!("GET {} served from cache", call.url);
+            return parse_body(StatusCode::OK, &entry.body);
+        }
+
+        let future = async {
+            let headers = cached
+                .as_ref()
+                .map(CachedResponse::conditional_headers)
+                .unwrap_or_default();
+            let revalidate = Call::new(self, Method::GET, endpoint).headers(headers);
+            let mut fetched = self.fetch(&revalidate).await?;
+
+            if fetched.status == StatusCode::NOT_MODIFIED {
+                if let Some(entry) = cache.refresh(&key).await {
+                    debug!("GET {} not modified, reusing cached body", call.url);
+                    return parse_body(StatusCode::OK, &entry.body);
+                }
+                fetched = self.fetch(&call).await?;
+            }
+
+            let status = fetched.status;
+            let entry = CachedResponse::from_fetched(fetched);
+            let value = parse_body(status, &entry.body)?;
+            cache.insert(key.clone(), entry).await;
+            Ok(value)
+        };
+        instrumented(&call.method, &call.url, future).await
+    }
+
+    /// Drop every cached response for `endpoint`, regardless of auth identity.
+    pub async fn invalidate(&self, endpoint: &str) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_url(&self.url(endpoint)).await;
+        }
+    }
+
+    /// A hash of the credential the request is sent with, so clients acting
+    /// for different users never share entries. With a token provider this
+    /// is the current token, so a refreshed token starts a new entry.
+    async fn cache_identity(&self) -> Result<String, BotError> {
+        match &self.token_provider {
+            Some(provider) => Ok(AuthScheme::Bearer(provider.get_token().await?).identity()),
+            None => Ok(self.auth.identity()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::StaticToken;
+    use serde_json::json;
+    use std::sync::Arc;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn key(url: &str) -> CacheKey {
+        CacheKey {
+            url: url.to_string(),
+            identity: "none".to_string(),
+        }
+    }
+
+    fn entry(size: usize) -> CachedResponse {
+        CachedResponse {
+            body: vec![b'x'; size],
+            etag: None,
+            last_modified: None,
+            stored_at: Instant::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used_entry() {
+        let cache = ResponseCache::new(2, 1024);
+        cache.insert(key("/a"), entry(1)).await;
+        cache.insert(key("/b"), entry(1)).await;
+        assert!(cache.get(&key("/a")).await.is_some());
+
+        cache.insert(key("/c"), entry(1)).await;
+        assert!(cache.get(&key("/a")).await.is_some());
+        assert!(cache.get(&key("/b")).await.is_none());
+        assert_eq!(cache.len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_by_total_bytes() {
+        let cache = ResponseCache::new(10, 100);
+        cache.insert(key("/a"), entry(60)).await;
+        cache.insert(key("/b"), entry(60)).await;
+        assert!(cache.get(&key("/a")).await.is_none());
+        assert_eq!(cache.total_bytes().await, 60);
+
+        cache.insert(key("/huge"), entry(101)).await;
+        assert!(cache.get(&key("/huge")).await.is_none());
+        assert_eq!(cache.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_url_drops_every_identity() {
+        let cache = ResponseCache::default();
+        cache.insert(key("/a"), entry(4)).await;
+        let other = CacheKey {
+            url: "/a".to_string(),
+            identity: "bearer".to_string(),
+        };
+        cache.insert(other, entry(4)).await;
+        cache.insert(key("/b"), entry(4)).await;
+
+        cache.invalidate_url("/a").await;
+        assert_eq!(cache.len().await, 1);
+        assert_eq!(cache.total_bytes().await, 4);
+    }
+
+    #[tokio::test]
+    async fn test_etag_revalidation_and_change() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/branding"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v1\"")
+                    .set_body_json(json!({"name": "first"})),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/branding"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/branding"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v2\"")
+                    .set_body_json(json!({"name": "second"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/branding"))
+            .and(header("if-none-match", "\"v2\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cache = Arc::new(ResponseCache::default());
+        let client = BotServerClient::new(Some(server.uri())).with_response_cache(cache.clone());
+        let mut names = Vec::new();
+        for _ in 0..4 {
+            let value: serde_json::Value = client
+                .get_cached("/branding", Duration::ZERO)
+                .await
+                .unwrap_or_default();
+            names.push(value["name"].as_str().unwrap_or_default().to_string());
+        }
+
+        assert_eq!(names, vec!["first", "first", "second", "second"]);
+        assert_eq!(cache.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fresh_entry_skips_network_until_invalidated() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/version"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("last-modified", "Wed, 21 Oct 2026 07:28:00 GMT")
+                    .set_body_json(json!("6.1.0")),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_response_cache(Arc::new(ResponseCache::default()));
+        let ttl = Duration::from_secs(60);
+        for _ in 0..3 {
+            let version: Result<String, BotError> = client.get_cached("/version", ttl).await;
+            assert!(matches!(version.as_deref(), Ok("6.1.0")));
+        }
+
+        client.invalidate("/version").await;
+        let version: Result<String, BotError> = client.get_cached("/version", ttl).await;
+        assert!(version.is_ok());
+
+        let requests = server.received_requests().await.unwrap_or_default();
+        assert!(requests
+            .iter()
+            .all(|r| !r.headers.contains_key("if-modified-since")));
+    }
+
+    #[tokio::test]
+    async fn test_cache_keyed_by_auth_identity() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/config"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let cache = Arc::new(ResponseCache::default());
+        let alice = BotServerClient::new(Some(server.uri()))
+            .with_response_cache(cache.clone())
+            .with_auth(AuthScheme::bearer("alice"));
+        let bob = alice.clone().with_auth(AuthScheme::bearer("bob"));
+        let ttl = Duration::from_secs(60);
+
+        for client in [&alice, &bob, &alice, &bob] {
+            let result: Result<serde_json::Value, BotError> =
+                client.get_cached("/config", ttl).await;
+            assert!(result.is_ok());
+        }
+        assert_eq!(cache.len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_keyed_by_provider_token() {
+        let server = MockServer::start().await;
+        for user in ["alice", "bob"] {
+            Mock::given(method("GET"))
+                .and(path("/me"))
+                .and(header("authorization", format!("Bearer {user}").as_str()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"user": user})))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let cache = Arc::new(ResponseCache::default());
+        let client = |token: &str| {
+            BotServerClient::new(Some(server.uri()))
+                .with_response_cache(cache.clone())
+                .with_token_provider(Arc::new(StaticToken::new(token)))
+        };
+        let (alice, bob) = (client("alice"), client("bob"));
+        let ttl = Duration::from_secs(60);
+
+        let mut users = Vec::new();
+        for client in [&alice, &bob, &alice, &bob] {
+            let value: serde_json::Value = client.get_cached("/me", ttl).await.unwrap_or_default();
+            users.push(value["user"].as_str().unwrap_or_default().to_string());
+        }
+        assert_eq!(users, ["alice", "bob", "alice", "bob"]);
+        assert_eq!(cache.len().await, 2);
+    }
+
+    #[test]
+    fn test_freshness() {
+        let cached = entry(1);
+        assert!(cached.is_fresh(Duration::from_secs(60)));
+        assert!(!cached.is_fresh(Duration::ZERO));
+    }
+}
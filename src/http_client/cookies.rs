@@ -0,0 +1,411 @@
+use super::{instrumented, parse_body, BotServerClient, Call, HttpRequest};
+use crate::error::BotError;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderValue, COOKIE, SET_COOKIE};
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Cookies keyed by domain, path and name, as RFC 6265 scopes them.
+type Cookies = BTreeMap<(String, String, String), StoredCookie>;
+
+#[derive(Default)]
+pub(crate) struct CookieJar {
+    cookies: Mutex<Cookies>,
+}
+
+struct StoredCookie {
+    value: String,
+    host_only: bool,
+    secure: bool,
+}
+
+struct Origin {
+    https: bool,
+    host: String,
+    path: String,
+}
+
+impl Origin {
+    fn parse(url: &str) -> Option<Self> {
+        let url = reqwest::Url::parse(url).ok()?;
+        Some(Self {
+            https: url.scheme() == "https",
+            host: url.host_str()?.to_ascii_lowercase(),
+            path: url.path().to_string(),
+        })
+    }
+
+    /// The path a cookie without a `Path` attribute is scoped to: the
+    /// request path up to its last `/`.
+    fn default_path(&self) -> String {
+        match self.path.rfind('/') {
+            Some(0) | None => "/".to_string(),
+            Some(end) => self.path[..end].to_string(),
+        }
+    }
+
+    fn matches_domain(&self, domain: &str) -> bool {
+        self.host == domain
+            || self
+                .host
+                .strip_suffix(domain)
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    }
+
+    fn matches_path(&self, path: &str) -> bool {
+        self.path
+            .strip_prefix(path)
+            .is_some_and(|rest| rest.is_empty() || path.ends_with('/') || rest.starts_with('/'))
+    }
+}
+
+fn attribute<'a>(attributes: &'a str, wanted: &str) -> Option<&'a str> {
+    attributes.split(';').find_map(|attribute| {
+        let (name, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        name.trim()
+            .eq_ignore_ascii_case(wanted)
+            .then_some(value.trim())
+    })
+}
+
+fn is_removal(attributes: &str, now: DateTime<Utc>) -> bool {
+    attribute(attributes, "max-age").is_some_and(|v| v.parse::<i64>().is_ok_and(|secs| secs <= 0))
+        || attribute(attributes, "expires")
+            .is_some_and(|v| DateTime::parse_from_rfc2822(v).is_ok_and(|date| date < now))
+}
+
+impl CookieJar {
+    fn lock(&self) -> MutexGuard<'_, Cookies> {
+        self.cookies.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// The cookies in scope for `url`: its host or a parent domain that set
+    /// them, a path prefix, and https only for `Secure` ones. Longer paths
+    /// come first.
+    pub fn header_for(&self, url: &str) -> Option<HeaderValue> {
+        let origin = Origin::parse(url)?;
+        let cookies = self.lock();
+        let mut matching: Vec<(&str, &str, &str)> = cookies
+            .iter()
+            .filter(|((domain, path, _), cookie)| {
+                let domain_matches = if cookie.host_only {
+                    origin.host == *domain
+                } else {
+                    origin.matches_domain(domain)
+                };
+                domain_matches && origin.matches_path(path) && (origin.https || !cookie.secure)
+            })
+            .map(|((_, path, name), cookie)| (path.as_str(), name.as_str(), cookie.value.as_str()))
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|(path, _, _)| std::cmp::Reverse(path.len()));
+        let pairs: Vec<String> = matching
+            .into_iter()
+            .map(|(_, name, value)| format!("{name}={value}"))
+            .collect();
+        let mut value = HeaderValue::from_str(&pairs.join("; ")).ok()?;
+        value.set_sensitive(true);
+        Some(value)
+    }
+
+    pub fn attach(&self, request: &mut HttpRequest) {
+        if request.headers.contains_key(COOKIE) {
+            return;
+        }
+        if let Some(value) = self.header_for(&request.url) {
+            request.headers.insert(COOKIE, value);
+        }
+    }
+
+    /// Store the `Set-Cookie` headers of a response from `url`. Cookies for
+    /// a domain `url` is not within, and `Secure` cookies sent over plain
+    /// http, are ignored.
+    pub fn store(&self, url: &str, headers: &HeaderMap) {
+        let Some(origin) = Origin::parse(url) else {
+            return;
+        };
+        let now = Utc::now();
+        let mut cookies = self.lock();
+        for header in headers.get_all(SET_COOKIE) {
+            let Ok(header) = header.to_str() else {
+                continue;
+            };
+            let (pair, attributes) = header.split_once(';').unwrap_or((header, ""));
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let secure = attribute(attributes, "secure").is_some();
+            if secure && !origin.https {
+                continue;
+            }
+            let domain = attribute(attributes, "domain")
+                .map(|d| d.trim_start_matches('.').to_ascii_lowercase())
+                .filter(|d| !d.is_empty());
+            if domain.as_ref().is_some_and(|d| !origin.matches_domain(d)) {
+                continue;
+            }
+            let path = attribute(attributes, "path")
+                .filter(|p| p.starts_with('/'))
+                .map_or_else(|| origin.default_path(), str::to_string);
+            let host_only = domain.is_none();
+            let key = (
+                domain.unwrap_or_else(|| origin.host.clone()),
+                path,
+                name.to_string(),
+            );
+            if is_removal(attributes, now) {
+                cookies.remove(&key);
+            } else {
+                let cookie = StoredCookie {
+                    value: value.trim().to_string(),
+                    host_only,
+                    secure,
+                };
+                cookies.insert(key, cookie);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for CookieJar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CookieJar")
+            .field("cookies", &self.len())
+            .finish()
+    }
+}
+
+impl BotServerClient {
+    /// POST `credentials` to a login endpoint and keep the session cookie it
+    /// sets for later requests. Requires `enable_cookies(true)`.
+    ///
+    /// # Errors
+    /// Returns `BotError::Config` when cookies are disabled, `BotError::Auth`
+    /// when the response has no `Set-Cookie` header or sets no cookie that
+    /// is in scope for `endpoint`, or any request or parse error.
+    pub async fn post_login<T: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        credentials: &T,
+    ) -> Result<R, BotError> {
+        let Some(jar) = &self.cookies else {
+            return Err(BotError::config(
+                "post_login requires a cookie store; enable it with enable_cookies(true)",
+            ));
+        };
+
+        let body = self.encode_body(credentials)?;
+        let call = Call::new(self, Method::POST, endpoint).body(Some(&body));
+        let future = async {
+            let fetched = self.fetch(&call).await?;
+            if !fetched.headers.contains_key(SET_COOKIE) || jar.header_for(&call.url).is_none() {
+                return Err(BotError::auth(format!(
+                    "login at {endpoint} did not set a session cookie"
+                )));
+            }
+            parse_body(fetched.status, &fetched.body)
+        };
+        instrumented(&call.method, &call.url, future).await
+    }
+
+    /// Forget every cookie held by this client's cookie store.
+    pub fn clear_cookies(&self) {
+        if let Some(jar) = &self.cookies {
+            jar.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn set_cookies(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.append(SET_COOKIE, value);
+            }
+        }
+        headers
+    }
+
+    #[test]
+    fn test_jar_is_scoped_by_host_and_honors_removal() {
+        let jar = CookieJar::default();
+        jar.store(
+            "http://gw.example/login",
+            &set_cookies(&["sid=abc; Path=/; HttpOnly", "theme=dark"]),
+        );
+        jar.store("http://other.example/", &set_cookies(&["sid=zzz"]));
+
+        let header = jar.header_for("http://gw.example/api/bots");
+        assert_eq!(
+            header.as_ref().and_then(|v| v.to_str().ok()),
+            Some("sid=abc; theme=dark")
+        );
+        assert!(header.is_some_and(|v| v.is_sensitive()));
+
+        jar.store(
+            "http://gw.example/logout",
+            &set_cookies(&[
+                "sid=; Max-Age=0",
+                "theme=x; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+            ]),
+        );
+        assert!(jar.header_for("http://gw.example/").is_none());
+        assert_eq!(jar.len(), 1);
+        assert!(!format!("{jar:?}").contains("zzz"));
+    }
+
+    #[test]
+    fn test_jar_honors_path_domain_and_secure() {
+        let jar = CookieJar::default();
+        jar.store(
+            "https://gw.example/app/login",
+            &set_cookies(&[
+                "sid=abc; Secure; HttpOnly",
+                "pref=1; Path=/app/settings",
+                "lang=pt; Domain=.example",
+                "evil=1; Domain=other.example",
+                "root=1; Path=/",
+            ]),
+        );
+        jar.store("http://gw.example/", &set_cookies(&["plain=1; Secure"]));
+        let header = |url: &str| {
+            jar.header_for(url)
+                .and_then(|v| v.to_str().ok().map(str::to_string))
+        };
+
+        assert_eq!(
+            header("https://gw.example/app/settings/theme").as_deref(),
+            Some("pref=1; lang=pt; sid=abc; root=1")
+        );
+        assert_eq!(
+            header("http://gw.example/app/bots").as_deref(),
+            Some("lang=pt; root=1")
+        );
+        assert_eq!(
+            header("https://gw.example/application").as_deref(),
+            Some("root=1")
+        );
+        assert_eq!(
+            header("https://api.example/app").as_deref(),
+            Some("lang=pt")
+        );
+        assert_eq!(header("https://api.example/"), None);
+        assert_eq!(header("https://other.example/"), None);
+        assert_eq!(jar.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_login_cookie_is_replayed() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/sso/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("set-cookie", "gw_session=s3cr3t; Path=/; HttpOnly")
+                    .set_body_json(json!({"user": "bot"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .and(header("cookie", "gw_session=s3cr3t"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri())).enable_cookies(true);
+        let login: Result<serde_json::Value, BotError> = client
+            .post_login("/sso/login", &json!({"user": "bot", "password": "pw"}))
+            .await;
+        assert_eq!(login.ok(), Some(json!({"user": "bot"})));
+        assert!(!format!("{client:?}").contains("s3cr3t"));
+
+        let bots: Result<Vec<serde_json::Value>, BotError> = client.get("/api/bots").await;
+        assert!(bots.is_ok());
+
+        client.clear_cookies();
+        let after_clear: Result<Vec<serde_json::Value>, BotError> = client.get("/api/bots").await;
+        assert!(matches!(after_clear, Err(BotError::Auth(_))));
+    }
+
+    #[tokio::test]
+    async fn test_login_requires_cookie_store_and_cookie() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/sso/login"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        let disabled = BotServerClient::new(Some(server.uri()));
+        let result: Result<(), BotError> = disabled.post_login("/sso/login", &json!({})).await;
+        assert!(matches!(result, Err(BotError::Config(_))));
+
+        let enabled = BotServerClient::new(Some(server.uri())).enable_cookies(true);
+        let result: Result<(), BotError> = enabled.post_login("/sso/login", &json!({})).await;
+        assert!(matches!(result, Err(BotError::Auth(_))));
+    }
+
+    #[tokio::test]
+    async fn test_relogin_with_unchanged_cookie_succeeds() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/sso/login"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("set-cookie", "gw_session=same; Path=/")
+                    .set_body_json(json!({"user": "bot"})),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri())).enable_cookies(true);
+        for _ in 0..2 {
+            let login: Result<serde_json::Value, BotError> =
+                client.post_login("/sso/login", &json!({})).await;
+            assert!(login.is_ok(), "{login:?}");
+        }
+    }
+
+    #[test]
+    fn test_cookie_stores_are_per_client() {
+        let first = BotServerClient::new(Some("http://gw".to_string())).enable_cookies(true);
+        let second = first.clone().enable_cookies(true);
+        if let (Some(a), Some(b)) = (&first.cookies, &second.cookies) {
+            a.store("http://gw/", &set_cookies(&["sid=1"]));
+            assert_eq!(b.len(), 0);
+        }
+        assert!(second.cookies.is_some());
+    }
+}
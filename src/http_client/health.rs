@@ -0,0 +1,257 @@
+use super::{instrumented, BotServerClient, Call};
+use crate::error::BotError;
+use crate::version::ComponentVersion;
+use log::error;
+use reqwest::Method;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const HEALTH_ENDPOINT: &str = "/health";
+const VERSION_ENDPOINT: &str = "/version";
+
+#[derive(Debug, Clone)]
+pub struct HealthStatus {
+    pub reachable: bool,
+    pub status_code: Option<u16>,
+    pub latency: Duration,
+    pub version: Option<String>,
+    pub components: Option<Vec<ComponentVersion>>,
+    pub error: Option<String>,
+}
+
+impl HealthStatus {
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.reachable
+            && self
+                .status_code
+                .is_some_and(|code| (200..300).contains(&code))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HealthBody {
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionBody {
+    #[serde(alias = "version")]
+    core_version: Option<String>,
+    #[serde(default)]
+    components: HashMap<String, ComponentVersion>,
+}
+
+impl BotServerClient {
+    /// Check whether `endpoint` exists with a HEAD request. A 404 yields
+    /// `Ok(false)`.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the server responds with any
+    /// other error status.
+    pub async fn exists(&self, endpoint: &str) -> Result<bool, BotError> {
+        let call = Call::new(self, Method::HEAD, endpoint);
+        let future = async {
+            match self.fetch(&call).await {
+                Ok(_) => Ok(true),
+                Err(e) if e.status_code() == 404 => Ok(false),
+                Err(e) => Err(e),
+            }
+        };
+        instrumented(&call.method, &call.url, future).await
+    }
+
+    pub async fn health_check(&self) -> bool {
+        let status = self.health_check_detailed().await;
+        if let Some(e) = &status.error {
+            error!("Health check failed: {e}");
+        }
+        status.is_healthy()
+    }
+
+    /// Probe `/health` and, when the server is healthy, `/version`. Failures
+    /// are reported in the returned status rather than as an error; a missing
+    /// or malformed `/version` leaves `components` as `None`.
+    pub async fn health_check_detailed(&self) -> HealthStatus {
+        let started = Instant::now();
+        let call = Call::new(self, Method::GET, HEALTH_ENDPOINT);
+        let response = self.execute(&call, 0).await;
+        let latency = started.elapsed();
+
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                return HealthStatus {
+                    reachable: false,
+                    status_code: None,
+                    latency,
+                    version: None,
+                    components: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+
+        let status_code = response.status.as_u16();
+        if !response.status.is_success() {
+            return HealthStatus {
+                reachable: true,
+                status_code: Some(status_code),
+                latency,
+                version: None,
+                components: None,
+                error: Some(format!("{HEALTH_ENDPOINT} returned HTTP {status_code}")),
+            };
+        }
+
+        let health: HealthBody = serde_json::from_slice(&response.body).unwrap_or_default();
+        let (version, components) = match self.get::<VersionBody>(VERSION_ENDPOINT).await {
+            Ok(body) => {
+                let mut components: Vec<ComponentVersion> = body.components.into_values().collect();
+                components.sort_by(|a, b| a.name.cmp(&b.name));
+                (body.core_version.or(health.version), Some(components))
+            }
+            Err(_) => (health.version, None),
+        };
+
+        HealthStatus {
+            reachable: true,
+            status_code: Some(status_code),
+            latency,
+            version,
+            components,
+            error: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn component(name: &str, version: &str) -> serde_json::Value {
+        json!({
+            "name": name,
+            "version": version,
+            "latest_version": null,
+            "update_available": false,
+            "status": "Running",
+            "last_checked": null,
+            "source": "Builtin",
+            "metadata": {}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_health_with_version_components() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "ok"})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "core_version": "6.1.0",
+                "components": {
+                    "botserver": component("botserver", "6.1.0"),
+                    "botlib": component("botlib", "6.1.0"),
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let status = client.health_check_detailed().await;
+
+        assert!(status.is_healthy());
+        assert_eq!(status.status_code, Some(200));
+        assert_eq!(status.version.as_deref(), Some("6.1.0"));
+        let names: Vec<String> = status
+            .components
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["botlib", "botserver"]);
+        assert!(client.health_check().await);
+    }
+
+    #[tokio::test]
+    async fn test_health_without_version_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"status": "ok", "version": "6.0.9"})),
+            )
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let status = client.health_check_detailed().await;
+
+        assert!(status.is_healthy());
+        assert_eq!(status.version.as_deref(), Some("6.0.9"));
+        assert!(status.components.is_none());
+        assert!(status.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_status_and_unreachable() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let degraded = BotServerClient::new(Some(server.uri()))
+            .health_check_detailed()
+            .await;
+        assert!(degraded.reachable);
+        assert_eq!(degraded.status_code, Some(503));
+        assert!(!degraded.is_healthy());
+
+        let down = BotServerClient::new(Some("http://127.0.0.1:1".to_string()))
+            .health_check_detailed()
+            .await;
+        assert!(!down.reachable);
+        assert!(down.status_code.is_none());
+        assert!(down.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_exists_uses_head() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/api/bots/1"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/api/bots/2"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/api/bots/3"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        assert!(matches!(client.exists("/api/bots/1").await, Ok(true)));
+        assert!(matches!(client.exists("/api/bots/2").await, Ok(false)));
+        assert!(matches!(
+            client.exists("/api/bots/3").await,
+            Err(BotError::Auth(_))
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Method;
+use std::time::Duration;
+use uuid::Uuid;
+
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+#[derive(Debug, Clone)]
+pub struct RequestParts {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseMeta {
+    pub method: Method,
+    pub url: String,
+    pub status: u16,
+    pub elapsed: Duration,
+    pub request_bytes: u64,
+    pub response_bytes: Option<u64>,
+}
+
+#[async_trait]
+pub trait Interceptor: Send + Sync {
+    async fn on_request(&self, _: &mut RequestParts) {}
+
+    async fn on_response(&self, _: &ResponseMeta) {}
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingInterceptor;
+
+#[async_trait]
+impl Interceptor for LoggingInterceptor {
+    async fn on_request(&self, request: &mut RequestParts) {
+        debug!("--> {} {}", request.method, request.url);
+    }
+
+    async fn on_response(&self, response: &ResponseMeta) {
+        debug!(
+            "<-- {} {} {} in {}ms ({} bytes sent)",
+            response.status,
+            response.method,
+            response.url,
+            response.elapsed.as_millis(),
+            response.request_bytes
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CorrelationIdInterceptor {
+    header: HeaderName,
+}
+
+impl CorrelationIdInterceptor {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            header: HeaderName::from_static(CORRELATION_ID_HEADER),
+        }
+    }
+
+    #[must_use]
+    pub fn with_header(header: HeaderName) -> Self {
+        Self { header }
+    }
+}
+
+impl Default for CorrelationIdInterceptor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Interceptor for CorrelationIdInterceptor {
+    async fn on_request(&self, request: &mut RequestParts) {
+        if request.headers.contains_key(&self.header) {
+            return;
+        }
+        if let Ok(value) = HeaderValue::from_str(&Uuid::new_v4().to_string()) {
+            request.headers.insert(self.header.clone(), value);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use reqwest::Method;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+pub const OTHER_PATH: &str = "other";
+pub const LATENCY_BUCKETS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+pub trait ClientMetrics: Send + Sync {
+    /// Called once per logical request, after retries. `status` is `None` when
+    /// no response was received and `bytes` is the response body size.
+    fn on_request_complete(
+        &self,
+        method: &Method,
+        path_template: &str,
+        status: Option<u16>,
+        elapsed: Duration,
+        bytes: u64,
+    );
+}
+
+pub(crate) fn normalize_path(templates: &[String], path: &str) -> String {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    templates
+        .iter()
+        .find(|template| {
+            let parts: Vec<&str> = template.trim_matches('/').split('/').collect();
+            parts.len() == segments.len()
+                && parts.iter().zip(&segments).all(|(part, segment)| {
+                    (part.starts_with('{') && part.ends_with('}') && !segment.is_empty())
+                        || part == segment
+                })
+        })
+        .cloned()
+        .unwrap_or_else(|| OTHER_PATH.to_string())
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    total_latency_ms: AtomicU64,
+    max_latency_ms: AtomicU64,
+    bytes: AtomicU64,
+    buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointMetrics {
+    pub method: String,
+    pub path: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub total_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub bytes: u64,
+    /// Request counts per `LATENCY_BUCKETS_MS` upper bound, followed by one
+    /// overflow bucket.
+    pub latency_buckets: Vec<u64>,
+}
+
+impl EndpointMetrics {
+    #[must_use]
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.requests as f64
+    }
+
+    /// Upper bound of the bucket holding the `quantile` (0.0..=1.0) latency,
+    /// or `max_latency_ms` when it falls in the overflow bucket.
+    #[must_use]
+    pub fn latency_quantile_ms(&self, quantile: f64) -> u64 {
+        let target = (self.requests as f64 * quantile.clamp(0.0, 1.0)).ceil() as u64;
+        let mut seen = 0;
+        for (index, count) in self.latency_buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                return LATENCY_BUCKETS_MS
+                    .get(index)
+                    .copied()
+                    .unwrap_or(self.max_latency_ms);
+            }
+        }
+        self.max_latency_ms
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AtomicClientMetrics {
+    endpoints: RwLock<HashMap<(String, String), Arc<Counters>>>,
+}
+
+impl AtomicClientMetrics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn counters(&self, method: &Method, path: &str) -> Arc<Counters> {
+        let key = (method.to_string(), path.to_string());
+        if let Some(counters) = self
+            .endpoints
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+        {
+            return Arc::clone(counters);
+        }
+        let mut endpoints = self
+            .endpoints
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(endpoints.entry(key).or_default())
+    }
+
+    /// Per-endpoint totals sorted by path then method.
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<EndpointMetrics> {
+        let endpoints = self
+            .endpoints
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut snapshot: Vec<EndpointMetrics> = endpoints
+            .iter()
+            .map(|((method, path), counters)| EndpointMetrics {
+                method: method.clone(),
+                path: path.clone(),
+                requests: counters.requests.load(Ordering::Relaxed),
+                errors: counters.errors.load(Ordering::Relaxed),
+                total_latency_ms: counters.total_latency_ms.load(Ordering::Relaxed),
+                max_latency_ms: counters.max_latency_ms.load(Ordering::Relaxed),
+                bytes: counters.bytes.load(Ordering::Relaxed),
+                latency_buckets: counters
+                    .buckets
+                    .iter()
+                    .map(|bucket| bucket.load(Ordering::Relaxed))
+                    .collect(),
+            })
+            .collect();
+        snapshot.sort_by(|a, b| (&a.path, &a.method).cmp(&(&b.path, &b.method)));
+        snapshot
+    }
+
+    pub fn reset(&self) {
+        self.endpoints
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+impl ClientMetrics for AtomicClientMetrics {
+    fn on_request_complete(
+        &self,
+        method: &Method,
+        path_template: &str,
+        status: Option<u16>,
+        elapsed: Duration,
+        bytes: u64,
+    ) {
+        let counters = self.counters(method, path_template);
+        let latency_ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        counters.requests.fetch_add(1, Ordering::Relaxed);
+        if status.is_none_or(|code| code >= 400) {
+            counters.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        counters
+            .total_latency_ms
+            .fetch_add(latency_ms, Ordering::Relaxed);
+        counters
+            .max_latency_ms
+            .fetch_max(latency_ms, Ordering::Relaxed);
+        counters.bytes.fetch_add(bytes, Ordering::Relaxed);
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        if let Some(bucket) = counters.buckets.get(bucket) {
+            bucket.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::BotError;
+    use crate::http_client::BotServerClient;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_normalize_path() {
+        let templates = vec![
+            "/api/sessions/{id}".to_string(),
+            "/api/sessions/{id}/messages".to_string(),
+            "/health".to_string(),
+        ];
+        assert_eq!(
+            normalize_path(&templates, "/api/sessions/42"),
+            "/api/sessions/{id}"
+        );
+        assert_eq!(
+            normalize_path(&templates, "/api/sessions/42/messages"),
+            "/api/sessions/{id}/messages"
+        );
+        assert_eq!(normalize_path(&templates, "/health"), "/health");
+        assert_eq!(normalize_path(&templates, "/api/sessions"), OTHER_PATH);
+        assert_eq!(normalize_path(&templates, "/api/bots/1"), OTHER_PATH);
+        assert_eq!(normalize_path(&[], "/anything"), OTHER_PATH);
+    }
+
+    #[test]
+    fn test_quantile_from_buckets() {
+        let metrics = AtomicClientMetrics::new();
+        for ms in [1, 2, 3, 4, 5, 6, 7, 8, 9, 120] {
+            metrics.on_request_complete(
+                &Method::GET,
+                "/x",
+                Some(200),
+                Duration::from_millis(ms),
+                0,
+            );
+        }
+        let snapshot = metrics.snapshot();
+        let stats = snapshot.first().cloned();
+        assert_eq!(stats.as_ref().map(|s| s.latency_quantile_ms(0.5)), Some(5));
+        assert_eq!(
+            stats.as_ref().map(|s| s.latency_quantile_ms(0.95)),
+            Some(250)
+        );
+        assert_eq!(stats.map(|s| s.max_latency_ms), Some(120));
+    }
+
+    #[tokio::test]
+    async fn test_client_records_per_template_metrics() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/api/sessions/[^/]+$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/sessions/missing/messages"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/unknown"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .mount(&server)
+            .await;
+
+        let metrics = Arc::new(AtomicClientMetrics::new());
+        let client = BotServerClient::new(Some(server.uri()))
+            .with_metrics(metrics.clone())
+            .with_path_template("/api/sessions/{id}")
+            .with_path_template("/api/sessions/{id}/messages");
+
+        for id in ["a", "b", "c?verbose=1"] {
+            let ok: Result<serde_json::Value, BotError> =
+                client.get(&format!("/api/sessions/{id}")).await;
+            assert!(ok.is_ok());
+        }
+        let missing: Result<serde_json::Value, BotError> =
+            client.get("/api/sessions/missing/messages").await;
+        assert!(missing.is_err());
+        let other: Result<serde_json::Value, BotError> = client.get("/api/unknown").await;
+        assert!(other.is_ok());
+        let refused = BotServerClient::new(Some("http://127.0.0.1:1".to_string()))
+            .with_metrics(metrics.clone())
+            .get::<serde_json::Value>("/health")
+            .await;
+        assert!(refused.is_err());
+
+        let summary: Vec<(String, u64, u64)> = metrics
+            .snapshot()
+            .into_iter()
+            .map(|m| (m.path, m.requests, m.errors))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/api/sessions/{id}".to_string(), 3, 0),
+                ("/api/sessions/{id}/messages".to_string(), 1, 1),
+                (OTHER_PATH.to_string(), 2, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use super::BotServerClient;
+use crate::error::BotError;
+use crate::models::{ApiResponse, PaginatedResponse};
+use serde::de::DeserializeOwned;
+
+fn paged_endpoint(endpoint: &str, cursor: Option<&str>, limit: u32) -> Result<String, BotError> {
+    let limit = limit.to_string();
+    let mut params = vec![("limit", limit.as_str())];
+    if let Some(cursor) = cursor {
+        params.push(("cursor", cursor));
+    }
+    let query = serde_urlencoded::to_string(params)
+        .map_err(|e| BotError::internal(format!("invalid page query: {e}")))?;
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    Ok(format!("{endpoint}{separator}{query}"))
+}
+
+impl BotServerClient {
+    /// Fetch one page of a list endpoint, passing `limit` and `cursor` as
+    /// query parameters and reading an `ApiResponse<PaginatedResponse<T>>`.
+    /// Pass the returned `next_cursor` to fetch the following page.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, the response cannot be parsed,
+    /// or the envelope reports a failure.
+    pub async fn get_paged<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> Result<PaginatedResponse<T>, BotError> {
+        let url = paged_endpoint(endpoint, cursor, limit)?;
+        let envelope: ApiResponse<PaginatedResponse<T>> = self.get(&url).await?;
+        match envelope.data {
+            Some(page) if envelope.success => Ok(page),
+            _ => Err(BotError::internal(envelope.error.unwrap_or_else(|| {
+                format!("{endpoint} returned no page of results")
+            }))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PageCursor;
+    use chrono::Utc;
+    use serde_json::json;
+    use uuid::Uuid;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_paged_endpoint_query() {
+        assert_eq!(
+            paged_endpoint("/api/bots", None, 20).ok().as_deref(),
+            Some("/api/bots?limit=20")
+        );
+        assert_eq!(
+            paged_endpoint("/api/bots?active=true", Some("ab_c-"), 5)
+                .ok()
+                .as_deref(),
+            Some("/api/bots?active=true&limit=5&cursor=ab_c-")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_paged_follows_cursor() {
+        let cursor = PageCursor::new(Utc::now(), Uuid::new_v4()).encode();
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .and(query_param("cursor", cursor.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "data": {"items": ["c"], "total": 3, "limit": 2}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .and(query_param("limit", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "data": {"items": ["a", "b"], "total": 3, "next_cursor": cursor, "limit": 2}
+            })))
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let first: Result<PaginatedResponse<String>, BotError> =
+            client.get_paged("/api/bots", None, 2).await;
+        let first = first.ok();
+        assert_eq!(first.as_ref().map(|p| p.items.len()), Some(2));
+        let next = first.and_then(|p| p.next_cursor);
+        assert_eq!(next.as_deref(), Some(cursor.as_str()));
+
+        let second: Result<PaginatedResponse<String>, BotError> =
+            client.get_paged("/api/bots", next.as_deref(), 2).await;
+        let second = second.ok();
+        assert_eq!(
+            second.as_ref().map(|p| p.items.clone()),
+            Some(vec!["c".to_string()])
+        );
+        assert_eq!(second.map(|p| p.has_more()), Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_get_paged_envelope_failure() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/bots"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"success": false, "error": "index rebuilding"})),
+            )
+            .mount(&server)
+            .await;
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let page: Result<PaginatedResponse<String>, BotError> =
+            client.get_paged("/api/bots", None, 10).await;
+        assert_eq!(
+            page.err().map(|e| e.to_string()).as_deref(),
+            Some("Internal error: index rebuilding")
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use super::{BotServerClient, Call};
+use crate::error::{BotError, BotResult};
+use crate::models::BotResponse;
+use crate::resilience::RetryConfig;
+use log::{debug, warn};
+use reqwest::Method;
+use serde::Deserialize;
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+pub const POLL_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PollBatch {
+    #[serde(default)]
+    pub items: Vec<BotResponse>,
+    #[serde(default)]
+    pub next_token: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PollOptions {
+    hold: Duration,
+    retry: RetryConfig,
+    cancel: CancellationToken,
+}
+
+impl PollOptions {
+    /// Poll a server that holds each request open for up to `hold` before
+    /// answering with an empty batch, until `cancel` fires.
+    #[must_use]
+    pub fn new(hold: Duration, cancel: CancellationToken) -> Self {
+        Self {
+            hold,
+            retry: RetryConfig::default(),
+            cancel,
+        }
+    }
+
+    /// Back off on transient errors using `retry`. `max_attempts` bounds the
+    /// number of consecutive failed polls before the loop gives up.
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    #[must_use]
+    pub const fn hold(&self) -> Duration {
+        self.hold
+    }
+
+    /// Timeout applied to each poll request: the hold time plus
+    /// `POLL_TIMEOUT_MARGIN`.
+    #[must_use]
+    pub fn request_timeout(&self) -> Duration {
+        self.hold.saturating_add(POLL_TIMEOUT_MARGIN)
+    }
+}
+
+fn poll_endpoint(endpoint: &str, token: Option<&str>) -> Result<String, BotError> {
+    let Some(token) = token else {
+        return Ok(endpoint.to_string());
+    };
+    let query = serde_urlencoded::to_string([("since", token)])
+        .map_err(|e| BotError::internal(format!("invalid poll token: {e}")))?;
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    Ok(format!("{endpoint}{separator}{query}"))
+}
+
+impl BotServerClient {
+    /// Long-poll `endpoint`, passing the resume token as `since`, and hand
+    /// every non-empty batch to `handler`. The token advances only after the
+    /// handler succeeds. Empty responses and poll timeouts simply start the
+    /// next poll; transient errors back off using the options' `RetryConfig`.
+    ///
+    /// Returns the last resume token once the cancellation token fires.
+    ///
+    /// # Errors
+    /// Returns the first non-retryable error, the last error once retries are
+    /// exhausted, or any error returned by `handler`.
+    pub async fn poll_loop<F, Fut>(
+        &self,
+        endpoint: &str,
+        initial_token: Option<String>,
+        options: &PollOptions,
+        mut handler: F,
+    ) -> Result<Option<String>, BotError>
+    where
+        F: FnMut(Vec<BotResponse>) -> Fut,
+        Fut: Future<Output = BotResult<()>>,
+    {
+        let mut token = initial_token;
+        let mut failures = 0;
+
+        loop {
+            let url = poll_endpoint(endpoint, token.as_deref())?;
+            let call = Call::new(self, Method::GET, &url).timeout(Some(options.request_timeout()));
+            let polled = options
+                .cancel
+                .run_until_cancelled(self.perform::<Option<PollBatch>>(&call))
+                .await;
+            let Some(result) = polled else {
+                return Ok(token);
+            };
+
+            match result {
+                Ok(batch) => {
+                    failures = 0;
+                    let Some(batch) = batch else {
+                        continue;
+                    };
+                    if !batch.items.is_empty() {
+                        handler(batch.items).await?;
+                    }
+                    if batch.next_token.is_some() {
+                        token = batch.next_token;
+                    }
+                }
+                Err(BotError::Timeout { .. }) => {
+                    failures = 0;
+                    debug!("Poll of {endpoint} timed out without data");
+                }
+                Err(e) if e.is_retryable() && failures + 1 < options.retry.max_attempts => {
+                    failures += 1;
+                    let delay = options.retry.calculate_delay(failures);
+                    warn!("Poll of {endpoint} failed ({e}), retrying in {delay:?}");
+                    let slept = options
+                        .cancel
+                        .run_until_cancelled(tokio::time::sleep(delay))
+                        .await;
+                    if slept.is_none() {
+                        return Ok(token);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::testing::MockTransport;
+    use crate::http_client::HttpResponse;
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn response(content: &str) -> serde_json::Value {
+        serde_json::to_value(BotResponse::new("bot", "s1", "u1", content, "web"))
+            .unwrap_or_default()
+    }
+
+    fn fast_options(cancel: &CancellationToken) -> PollOptions {
+        PollOptions::new(Duration::from_millis(200), cancel.clone())
+            .with_retry(RetryConfig::default().with_initial_delay(Duration::from_millis(1)))
+    }
+
+    #[test]
+    fn test_poll_endpoint_appends_encoded_token() {
+        assert_eq!(
+            poll_endpoint("/api/poll", None).ok(),
+            Some("/api/poll".to_string())
+        );
+        assert_eq!(
+            poll_endpoint("/api/poll", Some("a b&c")).ok(),
+            Some("/api/poll?since=a+b%26c".to_string())
+        );
+        assert_eq!(
+            poll_endpoint("/api/poll?session=1", Some("t1")).ok(),
+            Some("/api/poll?session=1&since=t1".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_poll_loop_batches_transient_error_and_cancellation() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/poll"))
+            .and(query_param("since", "t2"))
+            .respond_with(ResponseTemplate::new(204))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/poll"))
+            .and(query_param("since", "t2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"items": []}))
+                    .set_delay(Duration::from_secs(30)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/poll"))
+            .and(query_param("since", "t1"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/poll"))
+            .and(query_param("since", "t1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [response("second"), response("third")],
+                "next_token": "t2"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/poll"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [response("first")],
+                "next_token": "t1"
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+
+        let cancel = CancellationToken::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let client = BotServerClient::new(Some(server.uri()));
+        let started = Instant::now();
+        let result = client
+            .poll_loop("/api/poll", None, &fast_options(&cancel), |items| {
+                let seen = Arc::clone(&seen);
+                let cancel = cancel.clone();
+                async move {
+                    let mut seen = seen
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner);
+                    seen.extend(items.into_iter().map(|r| r.content));
+                    if seen.len() == 3 {
+                        tokio::spawn(async move {
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            cancel.cancel();
+                        });
+                    }
+                    Ok(())
+                }
+            })
+            .await;
+
+        assert_eq!(result.ok(), Some(Some("t2".to_string())));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let seen = seen.lock().map(|s| s.clone()).unwrap_or_default();
+        assert_eq!(seen, vec!["first", "second", "third"]);
+
+        let queries: Vec<Option<String>> = server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .map(|r| r.url.query().map(str::to_string))
+            .collect();
+        assert_eq!(
+            queries,
+            vec![
+                None,
+                Some("since=t1".to_string()),
+                Some("since=t1".to_string()),
+                Some("since=t2".to_string()),
+                Some("since=t2".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_poll_timeout_is_not_an_error() {
+        let mock = Arc::new(MockTransport::new());
+        mock.push_error(BotError::timeout(5200));
+        mock.push_response(HttpResponse::new(StatusCode::NO_CONTENT));
+        mock.push_response(
+            HttpResponse::json(
+                StatusCode::OK,
+                &json!({"items": [response("late")], "next_token": "t9"}),
+            )
+            .unwrap_or_else(|_| HttpResponse::new(StatusCode::OK)),
+        );
+
+        let cancel = CancellationToken::new();
+        let client =
+            BotServerClient::new(Some("http://mock".to_string())).with_transport(mock.clone());
+        let result = client
+            .poll_loop(
+                "/api/poll",
+                Some("t8".to_string()),
+                &fast_options(&cancel),
+                |_| {
+                    cancel.cancel();
+                    async { Ok(()) }
+                },
+            )
+            .await;
+
+        assert_eq!(result.ok(), Some(Some("t9".to_string())));
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|r| r.timeout == Some(Duration::from_millis(5200))));
+        assert!(requests.iter().all(|r| r.url.ends_with("since=t8")));
+    }
+
+    #[tokio::test]
+    async fn test_poll_gives_up_on_permanent_error() {
+        let mock = Arc::new(MockTransport::new());
+        mock.push_response(HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE));
+        mock.push_response(HttpResponse::new(StatusCode::FORBIDDEN));
+
+        let cancel = CancellationToken::new();
+        let client = BotServerClient::new(Some("http://mock".to_string())).with_transport(mock);
+        let result = client
+            .poll_loop("/api/poll", None, &fast_options(&cancel), |_| async {
+                Ok(())
+            })
+            .await;
+
+        assert!(matches!(result, Err(BotError::Auth(_))));
+    }
+}
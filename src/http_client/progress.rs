@@ -0,0 +1,248 @@
+use log::warn;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_PROGRESS_MIN_BYTES: u64 = 256 * 1024;
+pub const DEFAULT_PROGRESS_MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+type ProgressFn = dyn Fn(u64, Option<u64>) + Send + Sync;
+
+#[derive(Clone)]
+pub struct Progress {
+    callback: Arc<ProgressFn>,
+    min_bytes: u64,
+    min_interval: Duration,
+}
+
+impl Progress {
+    /// Report transfer progress to `callback` as `(bytes_transferred, total)`.
+    /// The callback fires at most once per `min_bytes` or `min_interval`,
+    /// whichever comes first, and always once when the transfer completes.
+    #[must_use]
+    pub fn new(callback: impl Fn(u64, Option<u64>) + Send + Sync + 'static) -> Self {
+        Self {
+            callback: Arc::new(callback),
+            min_bytes: DEFAULT_PROGRESS_MIN_BYTES,
+            min_interval: DEFAULT_PROGRESS_MIN_INTERVAL,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_min_bytes(mut self, min_bytes: u64) -> Self {
+        self.min_bytes = min_bytes;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    #[must_use]
+    pub fn tracker(&self, total: Option<u64>) -> ProgressTracker {
+        ProgressTracker {
+            progress: self.clone(),
+            total,
+            skip: 0,
+            seen: 0,
+            reported: None,
+            last_report: Instant::now(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("min_bytes", &self.min_bytes)
+            .field("min_interval", &self.min_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    progress: Progress,
+    total: Option<u64>,
+    skip: u64,
+    seen: u64,
+    reported: Option<u64>,
+    last_report: Instant,
+}
+
+impl ProgressTracker {
+    /// Ignore the first `bytes` seen, e.g. multipart framing ahead of the
+    /// file contents.
+    #[must_use]
+    pub const fn skipping(mut self, bytes: u64) -> Self {
+        self.skip = bytes;
+        self
+    }
+
+    #[must_use]
+    pub const fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    pub fn set_total_if_unknown(&mut self, total: Option<u64>) {
+        if self.total.is_none() {
+            self.total = total;
+        }
+    }
+
+    /// A copy of this tracker that starts over from zero bytes, for a
+    /// transfer that is sent again from the beginning.
+    #[must_use]
+    pub fn restarted(&self) -> Self {
+        Self {
+            seen: 0,
+            reported: None,
+            last_report: Instant::now(),
+            ..self.clone()
+        }
+    }
+
+    #[must_use]
+    pub fn transferred(&self) -> u64 {
+        let done = self.seen.saturating_sub(self.skip);
+        self.total.map_or(done, |total| done.min(total))
+    }
+
+    pub fn advance(&mut self, bytes: u64) {
+        self.seen = self.seen.saturating_add(bytes);
+        let done = self.transferred();
+        let last = self.reported.unwrap_or(0);
+        if done > last
+            && (done - last >= self.progress.min_bytes
+                || self.last_report.elapsed() >= self.progress.min_interval)
+        {
+            self.report(done);
+        }
+    }
+
+    pub fn finish(&mut self) {
+        let done = self.transferred();
+        if self.reported != Some(done) {
+            self.report(done);
+        }
+    }
+
+    fn report(&mut self, done: u64) {
+        self.reported = Some(done);
+        self.last_report = Instant::now();
+        let callback = &self.progress.callback;
+        let total = self.total;
+        if catch_unwind(AssertUnwindSafe(|| callback(done, total))).is_err() {
+            warn!("Progress callback panicked at {done} bytes; transfer continues");
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TransferProgress {
+    pub upload: Option<ProgressTracker>,
+    pub download: Option<ProgressTracker>,
+}
+
+impl TransferProgress {
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.upload.is_none() && self.download.is_none()
+    }
+
+    /// Fresh trackers for a request that is resent after a failed attempt.
+    #[must_use]
+    pub fn restarted(&self) -> Self {
+        Self {
+            upload: self.upload.as_ref().map(ProgressTracker::restarted),
+            download: self.download.as_ref().map(ProgressTracker::restarted),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    type Calls = Arc<Mutex<Vec<(u64, Option<u64>)>>>;
+
+    fn recording() -> (Progress, Calls) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&calls);
+        let progress = Progress::new(move |done, total| {
+            if let Ok(mut calls) = sink.lock() {
+                calls.push((done, total));
+            }
+        });
+        (progress, calls)
+    }
+
+    #[test]
+    fn test_reports_are_throttled_by_bytes() {
+        let (progress, calls) = recording();
+        let progress = progress
+            .with_min_bytes(100)
+            .with_min_interval(Duration::from_secs(3600));
+        let mut tracker = progress.tracker(Some(350));
+        for _ in 0..35 {
+            tracker.advance(10);
+        }
+        tracker.finish();
+
+        let calls = calls.lock().map(|c| c.clone()).unwrap_or_default();
+        assert_eq!(
+            calls,
+            vec![
+                (100, Some(350)),
+                (200, Some(350)),
+                (300, Some(350)),
+                (350, Some(350))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skipped_prefix_and_total_clamp() {
+        let (progress, calls) = recording();
+        let mut tracker = progress.with_min_bytes(1).tracker(Some(10)).skipping(5);
+        tracker.advance(4);
+        tracker.advance(8);
+        tracker.advance(6);
+        tracker.finish();
+
+        let calls = calls.lock().map(|c| c.clone()).unwrap_or_default();
+        assert_eq!(calls, vec![(7, Some(10)), (10, Some(10))]);
+    }
+
+    #[test]
+    fn test_panicking_callback_does_not_propagate() {
+        let progress = Progress::new(|done, _| {
+            assert!(done == 0, "callback failure");
+        })
+        .with_min_bytes(1);
+        let mut tracker = progress.tracker(None);
+        tracker.advance(5);
+        tracker.finish();
+        assert_eq!(tracker.transferred(), 5);
+    }
+
+    #[test]
+    fn test_restarted_tracker_reports_from_zero() {
+        let (progress, calls) = recording();
+        let mut tracker = progress.with_min_bytes(1).tracker(Some(10)).skipping(2);
+        tracker.advance(12);
+        tracker.finish();
+
+        let mut retry = tracker.restarted();
+        assert_eq!(retry.transferred(), 0);
+        retry.advance(7);
+        retry.advance(5);
+        retry.finish();
+
+        let calls = calls.lock().map(|c| c.clone()).unwrap_or_default();
+        assert_eq!(calls, vec![(10, Some(10)), (5, Some(10)), (10, Some(10))]);
+    }
+}
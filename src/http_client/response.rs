@@ -0,0 +1,226 @@
+use crate::error::{BotError, ErrorMessage};
+use crate::models::ApiResponse;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+pub(crate) fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.with_timezone(&Utc) - now;
+    Some(u64::try_from(delta.num_seconds()).unwrap_or(0))
+}
+
+fn retry_after_header(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_retry_after(v, Utc::now()))
+}
+
+pub(crate) fn error_message(body: &str) -> String {
+    match serde_json::from_str::<ApiResponse<serde_json::Value>>(body) {
+        Ok(envelope) if !envelope.success => {
+            let message = envelope
+                .error
+                .or(envelope.message)
+                .unwrap_or_else(|| body.to_string());
+            match envelope.code {
+                Some(code) => format!("{message} ({code})"),
+                None => message,
+            }
+        }
+        _ => body.to_string(),
+    }
+}
+
+/// Builds the 404 error from the server's `entity`, `id` and `tenant`
+/// fields. When the server names the entity but not the id, the last path
+/// segment of the request is taken as the id; without an entity the request
+/// path itself is reported.
+fn not_found_error(body: &str, endpoint: &str) -> BotError {
+    let value = serde_json::from_str::<serde_json::Value>(body).unwrap_or_default();
+    let field = |name: &str| {
+        value
+            .get(name)
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+    };
+    let path = endpoint_path(endpoint);
+    let (entity, id, tenant) = match field("entity") {
+        Some(entity) => {
+            let id = field("id").or_else(|| {
+                path.rsplit('/')
+                    .find(|segment| !segment.is_empty())
+                    .map(str::to_string)
+            });
+            (entity, id, field("tenant"))
+        }
+        None => (path.to_string(), None, None),
+    };
+    BotError::NotFound {
+        entity,
+        id,
+        tenant,
+        body: Some(body.to_string()),
+    }
+}
+
+pub(crate) fn endpoint_path(endpoint: &str) -> &str {
+    endpoint.split(['?', '#']).next().unwrap_or(endpoint)
+}
+
+/// Map a non-2xx response onto the closest `BotError` variant, keeping the
+/// body verbatim for `BotError::response_body`. Statuses without a closer
+/// variant, including 502 and 504, become `BotError::Http` with the body as
+/// the message. With `raw` set, every status becomes `BotError::Http`.
+pub(crate) fn status_error(
+    status: u16,
+    headers: &HeaderMap,
+    body: &str,
+    endpoint: &str,
+    raw: bool,
+) -> BotError {
+    if raw {
+        return BotError::http(status, body);
+    }
+
+    let message = || ErrorMessage::with_body(error_message(body), body);
+    match status {
+        400 | 422 => BotError::Validation(message()),
+        401 | 403 => BotError::Auth(message()),
+        404 => not_found_error(body, endpoint),
+        409 => BotError::Conflict(message()),
+        429 => BotError::RateLimited {
+            retry_after_secs: retry_after_header(headers),
+            body: Some(body.to_string()),
+        },
+        503 => BotError::ServiceUnavailable {
+            message: error_message(body),
+            retry_after_secs: retry_after_header(headers),
+            body: Some(body.to_string()),
+        },
+        _ => BotError::http(status, body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_retry_after_seconds() {
+        assert_eq!(parse_retry_after("120", Utc::now()), Some(120));
+        assert_eq!(parse_retry_after(" 5 ", Utc::now()), Some(5));
+    }
+
+    #[test]
+    fn test_parse_retry_after_http_date() {
+        let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 27, 30).single();
+        let parsed = now.and_then(|now| parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now));
+        assert_eq!(parsed, Some(30));
+    }
+
+    #[test]
+    fn test_parse_retry_after_past_date_is_zero() {
+        let now = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).single();
+        let parsed = now.and_then(|now| parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now));
+        assert_eq!(parsed, Some(0));
+    }
+
+    #[test]
+    fn test_parse_retry_after_malformed() {
+        assert_eq!(parse_retry_after("soon", Utc::now()), None);
+        assert_eq!(parse_retry_after("-3", Utc::now()), None);
+    }
+
+    #[test]
+    fn test_status_error_matrix() {
+        let headers = HeaderMap::new();
+        let cases: [(u16, &str, u16); 12] = [
+            (400, "Validation error: bad", 400),
+            (401, "Auth error: bad", 401),
+            (403, "Auth error: bad", 401),
+            (404, "/api/users/7 not found", 404),
+            (409, "Conflict: bad", 409),
+            (418, "HTTP error: 418 - bad", 418),
+            (422, "Validation error: bad", 400),
+            (429, "Rate limited", 429),
+            (500, "HTTP error: 500 - bad", 500),
+            (502, "HTTP error: 502 - bad", 502),
+            (503, "Service unavailable: bad", 503),
+            (504, "HTTP error: 504 - bad", 504),
+        ];
+
+        for (status, display, status_code) in cases {
+            let err = status_error(status, &headers, "bad", "/api/users/7?full=1", false);
+            assert_eq!(err.to_string(), display, "status {status}");
+            assert_eq!(err.status_code(), status_code, "status {status}");
+            let mapped = !matches!(err, BotError::Http { .. });
+            assert_eq!(
+                err.response_body(),
+                mapped.then_some("bad"),
+                "status {status}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_status_error_not_found_entity_from_body() {
+        let err = status_error(
+            404,
+            &HeaderMap::new(),
+            r#"{"entity":"Session"}"#,
+            "/api/sessions/1?full=1",
+            false,
+        );
+        assert_eq!(err.to_string(), "Session 1 not found");
+
+        let err = status_error(
+            404,
+            &HeaderMap::new(),
+            r#"{"code":"not_found","entity":"Bot","id":"b-9","tenant":"acme"}"#,
+            "/api/bots/lookup",
+            false,
+        );
+        assert!(matches!(
+            err,
+            BotError::NotFound { ref entity, ref id, ref tenant, .. }
+                if entity == "Bot" && id.as_deref() == Some("b-9") && tenant.as_deref() == Some("acme")
+        ));
+    }
+
+    #[test]
+    fn test_status_error_keeps_envelope_body() {
+        let body = r#"{"success":false,"error":"bad input","code":"invalid_field","details":{"field":"name"}}"#;
+        let err = status_error(422, &HeaderMap::new(), body, "/api/bots", false);
+        assert_eq!(
+            err.to_string(),
+            "Validation error: bad input (invalid_field)"
+        );
+        assert_eq!(err.response_body(), Some(body));
+
+        let err = status_error(429, &HeaderMap::new(), body, "/api/bots", false);
+        assert_eq!(err.response_body(), Some(body));
+
+        let err = status_error(500, &HeaderMap::new(), body, "/api/bots", false);
+        assert!(matches!(err, BotError::Http { status: 500, ref message } if message == body));
+    }
+
+    #[test]
+    fn test_status_error_raw_mapping() {
+        let err = status_error(401, &HeaderMap::new(), "denied", "/api/me", true);
+        assert!(matches!(err, BotError::Http { status: 401, ref message } if message == "denied"));
+    }
+
+    #[test]
+    fn test_error_message_from_envelope() {
+        let body = r#"{"success":false,"error":"bad input","code":"invalid_field"}"#;
+        assert_eq!(error_message(body), "bad input (invalid_field)");
+        assert_eq!(error_message("plain failure"), "plain failure");
+    }
+}
@@ -0,0 +1,41 @@
+use crate::error::BotError;
+use reqwest::Method;
+use std::time::Duration;
+use tracing::field::Empty;
+use tracing::Span;
+
+pub(super) fn url_path(url: &str) -> String {
+    reqwest::Url::parse(url).map_or_else(
+        |_| url.split(['?', '#']).next().unwrap_or_default().to_string(),
+        |parsed| parsed.path().to_string(),
+    )
+}
+
+pub(super) fn request_span(method: &Method, url: &str) -> Span {
+    tracing::info_span!(
+        "http.request",
+        http.method = %method,
+        url.path = %url_path(url),
+        status_code = Empty,
+        elapsed_ms = Empty,
+        retry_attempt = Empty,
+    )
+}
+
+pub(super) fn record_attempt(status: u16, elapsed: Duration, attempt: u32) {
+    let span = Span::current();
+    span.record("status_code", status);
+    span.record(
+        "elapsed_ms",
+        u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+    );
+    span.record("retry_attempt", attempt);
+}
+
+pub(super) fn record_error(error: &BotError) {
+    tracing::error!(
+        status_code = error.status_code(),
+        error = %error,
+        "HTTP request failed"
+    );
+}
@@ -0,0 +1,136 @@
+//! In-memory transport for unit testing code built on `BotServerClient`.
+//!
+//! ```
+//! use botlib::http_client::testing::MockTransport;
+//! use botlib::http_client::{BotServerClient, HttpResponse};
+//! use reqwest::StatusCode;
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), botlib::BotError> {
+//! let mock = Arc::new(MockTransport::new());
+//! mock.push_response(HttpResponse::json(StatusCode::OK, &serde_json::json!({"status": "ok"}))?);
+//!
+//! let client = BotServerClient::new(Some("http://botserver".to_string()))
+//!     .with_transport(mock.clone());
+//! let health: serde_json::Value = client.get("/health").await?;
+//!
+//! assert_eq!(health["status"], "ok");
+//! assert_eq!(mock.requests()[0].url, "http://botserver/health");
+//! # Ok(())
+//! # }
+//! ```
+
+use super::transport::{HttpRequest, HttpResponse, HttpTransport};
+use crate::error::BotError;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    requests: Mutex<Vec<HttpRequest>>,
+    responses: Mutex<VecDeque<Result<HttpResponse, BotError>>>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl MockTransport {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue `response` to be returned by the next unanswered request.
+    pub fn push_response(&self, response: HttpResponse) {
+        lock(&self.responses).push_back(Ok(response));
+    }
+
+    /// Queue a transport failure, as if the request never reached the server.
+    pub fn push_error(&self, error: BotError) {
+        lock(&self.responses).push_back(Err(error));
+    }
+
+    /// Every request received so far, in order.
+    #[must_use]
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        lock(&self.requests).clone()
+    }
+
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        lock(&self.responses).len()
+    }
+}
+
+#[async_trait]
+impl HttpTransport for MockTransport {
+    async fn execute(&self, request: HttpRequest) -> Result<HttpResponse, BotError> {
+        let description = format!("{} {}", request.method, request.url);
+        lock(&self.requests).push(request);
+        lock(&self.responses).pop_front().unwrap_or_else(|| {
+            Err(BotError::internal(format!(
+                "MockTransport has no scripted response for {description}"
+            )))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::{AuthScheme, BotServerClient};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn client(mock: &Arc<MockTransport>) -> BotServerClient {
+        BotServerClient::new(Some("http://mock".to_string())).with_transport(mock.clone())
+    }
+
+    #[tokio::test]
+    async fn test_records_requests_and_replays_responses() {
+        let mock = Arc::new(MockTransport::new());
+        let created = HttpResponse::json(StatusCode::CREATED, &json!({"id": 7}));
+        mock.push_response(created.unwrap_or_else(|_| HttpResponse::new(StatusCode::CREATED)));
+        mock.push_response(HttpResponse::new(StatusCode::NOT_FOUND));
+
+        let client = client(&mock).with_auth(AuthScheme::basic("bot", "secret"));
+        let created: Result<serde_json::Value, BotError> =
+            client.post("/sessions", &json!({"user": "u1"})).await;
+        let missing: Result<serde_json::Value, BotError> = client.get("/sessions/9").await;
+
+        assert_eq!(created.ok(), Some(json!({"id": 7})));
+        assert!(matches!(missing, Err(BotError::NotFound { .. })));
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 2);
+        let post = requests.first().cloned();
+        assert!(post
+            .as_ref()
+            .is_some_and(|r| r.url == "http://mock/sessions"));
+        assert!(post.as_ref().is_some_and(|r| {
+            r.headers.get("authorization").and_then(|v| v.to_str().ok())
+                == Some("Basic Ym90OnNlY3JldA==")
+        }));
+        assert!(post.is_some_and(|r| r.body.as_deref() == Some(br#"{"user":"u1"}"#.as_slice())));
+        assert_eq!(mock.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_errors_and_exhaustion() {
+        let mock = Arc::new(MockTransport::new());
+        mock.push_error(BotError::timeout(250));
+
+        let client = client(&mock);
+        let first: Result<serde_json::Value, BotError> = client.get("/slow").await;
+        let second: Result<serde_json::Value, BotError> = client.get("/unscripted").await;
+
+        assert!(matches!(first, Err(BotError::Timeout { duration_ms: 250 })));
+        assert!(
+            matches!(second, Err(BotError::Internal(msg)) if msg.contains("GET http://mock/unscripted"))
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use super::progress::{Progress, TransferProgress};
+use super::{BotServerClient, Call, HttpResponse};
+use crate::error::BotError;
+use crate::limits::{LimitExceeded, LimitType};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+struct Multipart {
+    boundary: String,
+    prefix: Vec<u8>,
+    suffix: Vec<u8>,
+}
+
+impl Multipart {
+    fn new(field: &str, filename: &str) -> Self {
+        let boundary = format!("botlib-{}", uuid::Uuid::new_v4().simple());
+        let field = field.replace('"', "%22");
+        let filename = filename.replace('"', "%22");
+        let prefix = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{field}\"; filename=\"{filename}\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+        );
+        let suffix = format!("\r\n--{boundary}--\r\n");
+        Self {
+            boundary,
+            prefix: prefix.into_bytes(),
+            suffix: suffix.into_bytes(),
+        }
+    }
+
+    fn content_type(&self) -> Result<HeaderValue, BotError> {
+        HeaderValue::from_str(&format!("multipart/form-data; boundary={}", self.boundary))
+            .map_err(|e| BotError::internal(format!("invalid multipart boundary: {e}")))
+    }
+
+    fn encode(&self, contents: &[u8]) -> Vec<u8> {
+        let mut body = Vec::with_capacity(self.prefix.len() + contents.len() + self.suffix.len());
+        body.extend_from_slice(&self.prefix);
+        body.extend_from_slice(contents);
+        body.extend_from_slice(&self.suffix);
+        body
+    }
+}
+
+impl BotServerClient {
+    /// Upload the file at `path` as a `multipart/form-data` POST with the
+    /// contents in form field `field`. `progress` receives the number of file
+    /// bytes sent and the file size from its metadata.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, exceeds the limit set
+    /// with `with_max_upload_bytes`, the request fails or the response cannot
+    /// be parsed.
+    pub async fn upload_file<R: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        path: impl AsRef<Path>,
+        field: &str,
+        progress: Option<Progress>,
+    ) -> Result<R, BotError> {
+        let path = path.as_ref();
+        let size = tokio::fs::metadata(path).await?.len();
+        if size > self.max_upload_bytes {
+            let exceeded = LimitExceeded {
+                limit_type: LimitType::UploadSize,
+                current: size,
+                maximum: self.max_upload_bytes,
+                retry_after_secs: None,
+            };
+            return Err(BotError::validation(exceeded.to_string()));
+        }
+
+        let contents = tokio::fs::read(path).await?;
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let multipart = Multipart::new(field, &filename);
+        let body = multipart.encode(&contents);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, multipart.content_type()?);
+        let progress = TransferProgress {
+            upload: progress.map(|p| {
+                p.tracker(Some(size))
+                    .skipping(multipart.prefix.len() as u64)
+            }),
+            download: None,
+        };
+        let call = Call::new(self, Method::POST, endpoint)
+            .body(Some(&body))
+            .headers(headers)
+            .progress(progress);
+        self.perform(&call).await
+    }
+
+    /// Download `endpoint` into memory. `progress` receives the bytes read and
+    /// the `Content-Length` when the server sends one.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the server responds with an
+    /// error status.
+    pub async fn download(
+        &self,
+        endpoint: &str,
+        progress: Option<Progress>,
+    ) -> Result<Vec<u8>, BotError> {
+        let progress = TransferProgress {
+            upload: None,
+            download: progress.map(|p| p.tracker(None)),
+        };
+        let call = Call::new(self, Method::GET, endpoint).progress(progress);
+        let future = async { self.fetch(&call).await.map(|response| response.body) };
+        super::instrumented(&call.method, &call.url, future).await
+    }
+
+    /// GET `endpoint` with `headers` and return the whole response, for
+    /// callers that need its headers. A 304 is returned rather than treated
+    /// as an error when `headers` make the request conditional.
+    pub(crate) async fn get_response(
+        &self,
+        endpoint: &str,
+        headers: HeaderMap,
+    ) -> Result<HttpResponse, BotError> {
+        let call = Call::new(self, Method::GET, endpoint).headers(headers);
+        super::instrumented(&call.method, &call.url, self.fetch(&call)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::testing::MockTransport;
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use wiremock::matchers::{header_regex, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    type Calls = Arc<Mutex<Vec<(u64, Option<u64>)>>>;
+
+    fn counting() -> (Progress, Calls) {
+        let calls: Calls = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&calls);
+        let progress = Progress::new(move |done, total| {
+            if let Ok(mut calls) = sink.lock() {
+                calls.push((done, total));
+            }
+        })
+        .with_min_bytes(16 * 1024);
+        (progress, calls)
+    }
+
+    fn snapshot(calls: &Calls) -> Vec<(u64, Option<u64>)> {
+        calls.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    fn assert_monotonic(calls: &[(u64, Option<u64>)], size: u64) {
+        assert!(calls.len() > 1, "{calls:?}");
+        assert!(calls.windows(2).all(|w| matches!(w, [a, b] if a.0 < b.0)));
+        assert!(calls.iter().all(|(_, total)| *total == Some(size)));
+        assert_eq!(calls.last().map(|(done, _)| *done), Some(size));
+    }
+
+    #[tokio::test]
+    async fn test_upload_reports_file_progress() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/kb/upload"))
+            .and(header_regex(
+                "content-type",
+                "^multipart/form-data; boundary=",
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({"stored": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let size: u64 = 300 * 1024;
+        let file = std::env::temp_dir().join(format!("botlib-upload-{}.bin", uuid::Uuid::new_v4()));
+        let written = tokio::fs::write(&file, vec![7u8; 300 * 1024]).await;
+        assert!(written.is_ok());
+
+        let (progress, calls) = counting();
+        let client = BotServerClient::new(Some(server.uri()));
+        let result: Result<serde_json::Value, BotError> = client
+            .upload_file("/api/kb/upload", &file, "document", Some(progress))
+            .await;
+        let removed = tokio::fs::remove_file(&file).await;
+
+        assert_eq!(result.ok(), Some(json!({"stored": true})));
+        assert!(removed.is_ok());
+        assert_monotonic(&snapshot(&calls), size);
+
+        let requests = server.received_requests().await.unwrap_or_default();
+        let body = requests.first().map(|r| r.body.clone()).unwrap_or_default();
+        let text = String::from_utf8_lossy(&body);
+        assert!(text.contains("name=\"document\"; filename=\"botlib-upload-"));
+        assert_eq!(body.iter().filter(|b| **b == 7).count(), 300 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_download_reports_content_length_progress() {
+        let server = MockServer::start().await;
+        let payload = vec![b'x'; 200 * 1024];
+        Mock::given(method("GET"))
+            .and(path("/api/files/report.pdf"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(payload.clone()))
+            .mount(&server)
+            .await;
+
+        let (progress, calls) = counting();
+        let client = BotServerClient::new(Some(server.uri()));
+        let body = client
+            .download("/api/files/report.pdf", Some(progress))
+            .await
+            .unwrap_or_default();
+
+        assert_eq!(body, payload);
+        assert_monotonic(&snapshot(&calls), payload.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_callback_does_not_fail_transfer() {
+        let mock = Arc::new(MockTransport::new());
+        mock.push_response(HttpResponse::new(StatusCode::OK).with_body(vec![1u8; 64]));
+
+        let client = BotServerClient::new(Some("http://mock".to_string())).with_transport(mock);
+        let progress = Progress::new(|done, _| assert!(done == 0, "broken progress UI"));
+        let body = client.download("/file", Some(progress)).await;
+
+        assert_eq!(body.map(|b| b.len()).ok(), Some(64));
+    }
+
+    #[tokio::test]
+    async fn test_upload_missing_file_is_io_error() {
+        let client = BotServerClient::new(Some("http://mock".to_string()));
+        let missing: Result<serde_json::Value, BotError> = client
+            .upload_file("/api/kb/upload", "/nonexistent/botlib.bin", "file", None)
+            .await;
+        assert!(matches!(missing, Err(BotError::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn test_upload_honors_overridden_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/kb/upload"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({"stored": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let file = std::env::temp_dir().join(format!("botlib-limit-{}.bin", uuid::Uuid::new_v4()));
+        let written = tokio::fs::write(&file, vec![7u8; 1024]).await;
+        assert!(written.is_ok());
+
+        let client = BotServerClient::new(Some(server.uri()));
+        let under: Result<serde_json::Value, BotError> = client
+            .clone()
+            .with_max_upload_bytes(1024)
+            .upload_file("/api/kb/upload", &file, "document", None)
+            .await;
+        let over: Result<serde_json::Value, BotError> = client
+            .with_max_upload_bytes(1023)
+            .upload_file("/api/kb/upload", &file, "document", None)
+            .await;
+        let removed = tokio::fs::remove_file(&file).await;
+
+        assert_eq!(under.ok(), Some(json!({"stored": true})));
+        assert!(matches!(over, Err(BotError::Validation(ref message)) if message.contains("1024")));
+        assert!(removed.is_ok());
+    }
+}
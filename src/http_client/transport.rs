@@ -0,0 +1,266 @@
+use super::progress::{ProgressTracker, TransferProgress};
+use crate::error::BotError;
+use crate::limits::{LimitExceeded, LimitType};
+use async_trait::async_trait;
+use futures_util::stream::{self, Stream};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+const UPLOAD_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<Vec<u8>>,
+    /// Overrides the transport's default timeout for this request only.
+    pub timeout: Option<Duration>,
+    /// Abort once the decoded response body grows past this many bytes.
+    pub max_response_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    #[must_use]
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Build a response whose body is `value` serialized as JSON.
+    ///
+    /// # Errors
+    /// Returns an error if `value` cannot be serialized.
+    pub fn json<T: serde::Serialize>(status: StatusCode, value: &T) -> Result<Self, BotError> {
+        let mut response = Self::new(status).with_body(serde_json::to_vec(value)?);
+        response.headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            reqwest::header::HeaderValue::from_static("application/json"),
+        );
+        Ok(response)
+    }
+
+    #[must_use]
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Add a response header, silently skipping names or values that are not
+    /// valid HTTP.
+    #[must_use]
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            self.headers.insert(name, value);
+        }
+        self
+    }
+}
+
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    async fn execute(&self, request: HttpRequest) -> Result<HttpResponse, BotError>;
+
+    /// Execute `request` while reporting body progress. Transports that cannot
+    /// stream bodies report each direction once, after the exchange completes.
+    async fn execute_with_progress(
+        &self,
+        request: HttpRequest,
+        progress: TransferProgress,
+    ) -> Result<HttpResponse, BotError> {
+        let TransferProgress { upload, download } = progress;
+        let sent = request.body.as_ref().map_or(0, |body| body.len() as u64);
+        let response = self.execute(request).await?;
+
+        if let Some(mut tracker) = upload {
+            tracker.advance(sent);
+            tracker.finish();
+        }
+        if let Some(mut tracker) = download {
+            tracker.set_total_if_unknown(Some(response.body.len() as u64));
+            tracker.advance(response.body.len() as u64);
+            tracker.finish();
+        }
+        Ok(response)
+    }
+}
+
+fn upload_stream(
+    body: Vec<u8>,
+    mut tracker: ProgressTracker,
+) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> {
+    let chunks: Vec<Vec<u8>> = body
+        .chunks(UPLOAD_CHUNK_BYTES)
+        .map(<[u8]>::to_vec)
+        .collect();
+    let count = chunks.len();
+    if count == 0 {
+        tracker.finish();
+    }
+    stream::iter(chunks.into_iter().enumerate().map(move |(index, chunk)| {
+        tracker.advance(chunk.len() as u64);
+        if index + 1 == count {
+            tracker.finish();
+        }
+        Ok(chunk)
+    }))
+}
+
+pub(crate) fn user_agent() -> String {
+    format!("BotLib/{}", env!("CARGO_PKG_VERSION"))
+}
+
+pub(crate) fn response_too_large(current: u64, maximum: u64) -> BotError {
+    let exceeded = LimitExceeded {
+        limit_type: LimitType::ResponseBody,
+        current,
+        maximum,
+        retry_after_secs: None,
+    };
+    BotError::validation(exceeded.to_string())
+}
+
+pub(crate) fn ensure_response_size(received: u64, maximum: Option<u64>) -> Result<(), BotError> {
+    match maximum.filter(|maximum| received > *maximum) {
+        Some(maximum) => Err(response_too_large(received, maximum)),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn append_capped(
+    body: &mut Vec<u8>,
+    chunk: &[u8],
+    maximum: Option<u64>,
+) -> Result<(), BotError> {
+    ensure_response_size((body.len() + chunk.len()) as u64, maximum)?;
+    body.extend_from_slice(chunk);
+    Ok(())
+}
+
+pub(crate) fn reqwest_error(err: reqwest::Error, timeout: Duration) -> BotError {
+    if err.is_timeout() {
+        return BotError::timeout(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+    }
+    err.into()
+}
+
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    timeout: Duration,
+}
+
+impl ReqwestTransport {
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        Self::build(timeout, true)
+    }
+
+    fn build(timeout: Duration, compression: bool) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent(user_agent())
+            .danger_accept_invalid_certs(true)
+            .gzip(compression)
+            .brotli(compression)
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        Self { client, timeout }
+    }
+
+    /// Advertise and transparently decode gzip and brotli responses. Enabled
+    /// by default; rebuilds the underlying client, so settings from a client
+    /// passed to `from_client` are discarded.
+    #[must_use]
+    pub fn with_compression(self, enabled: bool) -> Self {
+        Self::build(self.timeout, enabled)
+    }
+
+    /// Wrap a preconfigured client. `timeout` should match the one `client`
+    /// was built with; it is reported in `BotError::Timeout`.
+    #[must_use]
+    pub const fn from_client(client: reqwest::Client, timeout: Duration) -> Self {
+        Self { client, timeout }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn execute(&self, request: HttpRequest) -> Result<HttpResponse, BotError> {
+        self.execute_with_progress(request, TransferProgress::default())
+            .await
+    }
+
+    async fn execute_with_progress(
+        &self,
+        request: HttpRequest,
+        progress: TransferProgress,
+    ) -> Result<HttpResponse, BotError> {
+        let TransferProgress {
+            upload,
+            mut download,
+        } = progress;
+        let timeout = request.timeout.unwrap_or(self.timeout);
+        let max_response_bytes = request.max_response_bytes;
+        let mut builder = self
+            .client
+            .request(request.method, &request.url)
+            .headers(request.headers);
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(body) = request.body {
+            builder = match upload {
+                Some(tracker) => builder
+                    .header(CONTENT_LENGTH, body.len())
+                    .body(reqwest::Body::wrap_stream(upload_stream(body, tracker))),
+                None => builder.body(body),
+            };
+        }
+
+        let mut response = builder
+            .send()
+            .await
+            .map_err(|e| reqwest_error(e, timeout))?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        if let Some(tracker) = &mut download {
+            tracker.set_total_if_unknown(response.content_length());
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| reqwest_error(e, timeout))?
+        {
+            append_capped(&mut body, &chunk, max_response_bytes)?;
+            if let Some(tracker) = &mut download {
+                tracker.advance(chunk.len() as u64);
+            }
+        }
+        if let Some(tracker) = &mut download {
+            tracker.finish();
+        }
+
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
@@ -0,0 +1,841 @@
+use super::BotServerClient;
+use crate::error::BotError;
+use crate::resilience::RetryConfig;
+use crate::version::{metadata_keys, ComponentError, VersionRegistry, VersionRegistryHandle};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::Utc;
+use ed25519_dalek::{Signature, VerifyingKey};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// How far `spawn_update_checker` backs off after consecutive failures: at
+/// most this many check intervals between attempts.
+pub const MAX_UPDATE_CHECK_BACKOFF: u32 = 16;
+
+/// What the update server knows about one component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestComponent {
+    pub name: String,
+    pub latest_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_core_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// The manifest in the update server's reply to `check_for_updates`. The
+/// reply wraps it with a detached signature:
+/// `{"manifest": {...}, "signature": "<base64 ed25519>"}`, signed over
+/// `canonical_json` of the manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateManifest {
+    #[serde(default)]
+    pub components: Vec<ManifestComponent>,
+}
+
+#[derive(Debug, Serialize)]
+struct InstalledComponent {
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, Serialize)]
+struct UpdateCheck {
+    core_version: String,
+    components: Vec<InstalledComponent>,
+}
+
+impl UpdateCheck {
+    async fn send(
+        &self,
+        client: &BotServerClient,
+        url: String,
+    ) -> Result<serde_json::Value, BotError> {
+        client.clone().with_base_url(url).post("", self).await
+    }
+}
+
+/// `value` as compact JSON with object keys sorted, the form update
+/// manifests are signed in.
+#[must_use]
+pub fn canonical_json(value: &serde_json::Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::Value::from(key.as_str()).to_string());
+                out.push(':');
+                write_canonical(item, out);
+            }
+            out.push('}');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+fn parse_signing_key(key: &str) -> Result<VerifyingKey, BotError> {
+    let bytes = BASE64_STANDARD
+        .decode(key.trim())
+        .map_err(|e| BotError::config(format!("update signing key is not base64: {e}")))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| BotError::config("update signing key is not a 32-byte ed25519 public key"))?;
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|e| BotError::config(format!("invalid update signing key: {e}")))
+}
+
+impl VersionRegistry {
+    /// POSTs the installed components to `update_url` and applies the
+    /// returned `UpdateManifest`: each listed component that is registered
+    /// gets its latest version, `update_available` and `last_checked`, and
+    /// the manifest's download URL, minimum core version and notes in its
+    /// metadata. Components the manifest omits are left as they were, and
+    /// entries for components not registered here are ignored. Returns how
+    /// many components were updated.
+    ///
+    /// The manifest must be signed by one of `update_signing_keys`, unless
+    /// `allow_unsigned_updates` is set.
+    ///
+    /// `client` supplies the transport, auth and retry settings; its base
+    /// URL is not used.
+    ///
+    /// # Errors
+    /// Returns `BotError::Config` if `update_url` is not set or a signing
+    /// key is invalid, `BotError::Auth` if the manifest is unsigned or no
+    /// trusted key signed it, and the client's error if the request fails
+    /// or the reply is not a manifest. The registry is then unchanged.
+    pub async fn check_for_updates(&mut self, client: &BotServerClient) -> Result<usize, BotError> {
+        let (url, check) = self.update_check()?;
+        let reply = check.send(client, url).await?;
+        let manifest = self.verify_update_manifest(reply)?;
+        Ok(self.apply_update_manifest(manifest))
+    }
+
+    /// Takes the manifest out of a signed reply, checking its signature
+    /// against `update_signing_keys`.
+    fn verify_update_manifest(&self, reply: serde_json::Value) -> Result<UpdateManifest, BotError> {
+        let (manifest, signature) = match reply {
+            serde_json::Value::Object(mut reply) if reply.contains_key("manifest") => (
+                reply.remove("manifest").unwrap_or_default(),
+                reply.remove("signature"),
+            ),
+            bare => (bare, None),
+        };
+        let signature = signature.as_ref().and_then(serde_json::Value::as_str);
+        if let Err(e) = self.verify_manifest_signature(&manifest, signature) {
+            if !self.allow_unsigned_updates {
+                return Err(e);
+            }
+            warn!("INSECURE: accepting an unverified update manifest ({e}) because allow_unsigned_updates is set");
+        }
+        Ok(serde_json::from_value(manifest)?)
+    }
+
+    fn verify_manifest_signature(
+        &self,
+        manifest: &serde_json::Value,
+        signature: Option<&str>,
+    ) -> Result<(), BotError> {
+        let signature = signature.ok_or_else(|| BotError::auth("update manifest is not signed"))?;
+        let signature = BASE64_STANDARD
+            .decode(signature.trim())
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| BotError::auth("update manifest signature is malformed"))?;
+        if self.update_signing_keys.is_empty() {
+            return Err(BotError::auth(
+                "no update_signing_keys configured to verify the update manifest",
+            ));
+        }
+        let message = canonical_json(manifest);
+        for key in &self.update_signing_keys {
+            if parse_signing_key(key)?
+                .verify_strict(message.as_bytes(), &signature)
+                .is_ok()
+            {
+                return Ok(());
+            }
+        }
+        Err(BotError::auth(
+            "update manifest signature does not match any trusted key",
+        ))
+    }
+
+    fn update_check(&self) -> Result<(String, UpdateCheck), BotError> {
+        let url = self
+            .update_url
+            .clone()
+            .ok_or_else(|| BotError::config("no update_url configured"))?;
+        let mut components: Vec<_> = self
+            .components
+            .values()
+            .map(|c| InstalledComponent {
+                name: c.name.clone(),
+                version: c.version.clone(),
+            })
+            .collect();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        let check = UpdateCheck {
+            core_version: self.core_version.clone(),
+            components,
+        };
+        Ok((url, check))
+    }
+
+    /// Applies `manifest` as `check_for_updates` does. Returns how many
+    /// components were updated.
+    pub fn apply_update_manifest(&mut self, manifest: UpdateManifest) -> usize {
+        let mut updated = 0;
+        for entry in manifest.components {
+            let Some(component) = self.components.get_mut(&entry.name) else {
+                debug!("Ignoring update for unknown component {}", entry.name);
+                continue;
+            };
+            if entry.latest_version.trim().is_empty() {
+                debug!("Ignoring update for {} without a version", entry.name);
+                continue;
+            }
+            for (key, value) in [
+                (metadata_keys::DOWNLOAD_URL, entry.download_url),
+                (metadata_keys::MIN_CORE_VERSION, entry.min_core_version),
+                (metadata_keys::RELEASE_NOTES, entry.notes),
+            ] {
+                match value {
+                    Some(value) => component.metadata.insert(key.to_string(), value),
+                    None => component.metadata.remove(key),
+                };
+            }
+            if self
+                .set_latest_version(&entry.name, entry.latest_version)
+                .is_ok()
+            {
+                updated += 1;
+            }
+        }
+        self.last_update_check = Some(Utc::now());
+        updated
+    }
+}
+
+/// Clears `VersionRegistryHandle::checking` when a check ends, including
+/// when it is cancelled.
+struct InFlight<'a>(&'a AtomicBool);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl VersionRegistryHandle {
+    /// `VersionRegistry::check_for_updates` without holding the lock while
+    /// the request is in flight: the installed versions are read first and
+    /// the manifest is applied to the registry as it is when the reply
+    /// arrives, so components deregistered meanwhile are skipped. Success
+    /// clears `last_check_error`; failure records it and changes nothing
+    /// else. Returns `None`, without sending anything, if another check on
+    /// this registry is still running.
+    ///
+    /// # Errors
+    /// As `VersionRegistry::check_for_updates`.
+    pub async fn check_for_updates(
+        &self,
+        client: &BotServerClient,
+    ) -> Result<Option<usize>, BotError> {
+        if self.checking.swap(true, Ordering::AcqRel) {
+            debug!("Skipping update check: one is already in flight");
+            return Ok(None);
+        }
+        let _in_flight = InFlight(&self.checking);
+        let checked = async {
+            let (url, check) = self.read(VersionRegistry::update_check)?;
+            check.send(client, url).await
+        }
+        .await;
+        self.write(|registry| {
+            match checked.and_then(|reply| registry.verify_update_manifest(reply)) {
+                Ok(manifest) => {
+                    registry.last_check_error = None;
+                    Ok(Some(registry.apply_update_manifest(manifest)))
+                }
+                Err(e) => {
+                    registry.last_check_error = Some(ComponentError {
+                        message: e.to_string(),
+                        at: Utc::now(),
+                    });
+                    Err(e)
+                }
+            }
+        })
+    }
+}
+
+/// A background update checker started by `spawn_update_checker`.
+#[derive(Debug)]
+pub struct UpdateChecker {
+    cancel: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl UpdateChecker {
+    /// Stops checking. A check in flight is abandoned without touching the
+    /// registry.
+    pub fn stop(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Stops checking and waits for the task to end.
+    pub async fn shutdown(self) {
+        self.cancel.cancel();
+        if let Err(e) = self.task.await {
+            warn!("Update checker ended abnormally: {e}");
+        }
+    }
+
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+}
+
+/// Checks `handle` for updates every `interval`, on the current tokio
+/// runtime, until stopped. Each wait is drawn at random from `interval`
+/// plus or minus `jitter_fraction` of it, and the first from up to
+/// `jitter_fraction` of it, so instances started together spread their
+/// checks out. After consecutive failures the interval doubles, up to
+/// `MAX_UPDATE_CHECK_BACKOFF` times, as a `RetryConfig` starting at
+/// `interval` would, and the next success resets it. The next wait starts
+/// only when a check has finished, so checks never overlap.
+#[must_use]
+pub fn spawn_update_checker(
+    handle: VersionRegistryHandle,
+    client: BotServerClient,
+    interval: Duration,
+    jitter_fraction: f64,
+) -> UpdateChecker {
+    let jitter_fraction = jitter_fraction.clamp(0.0, 1.0);
+    let backoff = RetryConfig::default()
+        .with_initial_delay(interval)
+        .with_max_delay(interval.saturating_mul(MAX_UPDATE_CHECK_BACKOFF))
+        .with_backoff_multiplier(2.0)
+        .with_jitter(0.0);
+    let cancel = CancellationToken::new();
+    let task = tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            let mut delay = interval.mul_f64(jitter_fraction * random_unit());
+            let mut failures = 0;
+            loop {
+                if cancel
+                    .run_until_cancelled(tokio::time::sleep(delay))
+                    .await
+                    .is_none()
+                {
+                    return;
+                }
+                let Some(checked) = cancel
+                    .run_until_cancelled(handle.check_for_updates(&client))
+                    .await
+                else {
+                    return;
+                };
+                let base = match checked {
+                    Ok(_) => {
+                        failures = 0;
+                        interval
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        let base = backoff.calculate_delay(failures + 1);
+                        warn!("Update check failed ({e}); next attempt in about {base:?}");
+                        base
+                    }
+                };
+                delay = base.mul_f64(jitter_fraction.mul_add(2.0 * random_unit() - 1.0, 1.0));
+            }
+        }
+    });
+    UpdateChecker { cancel, task }
+}
+
+/// A random number in `[0, 1]`, different in every process.
+fn random_unit() -> f64 {
+    let [a, b, c, d, ..] = *uuid::Uuid::new_v4().as_bytes();
+    f64::from(u32::from_le_bytes([a, b, c, d])) / f64::from(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_client::{HttpRequest, HttpResponse, HttpTransport};
+    use crate::version::{ComponentSource, ComponentStatus, ComponentVersion};
+    use async_trait::async_trait;
+    use ed25519_dalek::{Signer, SigningKey};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use tokio::time::Instant;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(seed: u8) -> String {
+        BASE64_STANDARD.encode(signing_key(seed).verifying_key().to_bytes())
+    }
+
+    /// `manifest` wrapped as the update server sends it, signed by the key
+    /// made from `seed`.
+    fn signed(manifest: serde_json::Value, seed: u8) -> serde_json::Value {
+        let signature = signing_key(seed).sign(canonical_json(&manifest).as_bytes());
+        json!({
+            "manifest": manifest,
+            "signature": BASE64_STANDARD.encode(signature.to_bytes()),
+        })
+    }
+
+    fn installed(update_url: Option<String>) -> VersionRegistry {
+        let mut registry = VersionRegistry {
+            core_version: "6.1.0".to_string(),
+            update_url,
+            update_signing_keys: vec![public_key(1)],
+            ..VersionRegistry::default()
+        };
+        for (name, version) in [("basic", "6.1.0"), ("llm", "2.3.0")] {
+            let registered = registry.register_component(ComponentVersion {
+                name: name.to_string(),
+                version: version.to_string(),
+                latest_version: None,
+                update_available: false,
+                status: ComponentStatus::Running,
+                last_checked: None,
+                last_heartbeat: None,
+                heartbeat_timeout_secs: None,
+                status_before_stale: None,
+                depends_on: Vec::new(),
+                compat: HashMap::new(),
+                started_at: None,
+                restart_count: 0,
+                recent_restarts: Vec::new(),
+                last_error: None,
+                source: ComponentSource::Builtin,
+                metadata: HashMap::new(),
+            });
+            assert!(registered.is_ok());
+        }
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_check_for_updates_applies_manifest() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/updates"))
+            .and(body_json(json!({
+                "core_version": "6.1.0",
+                "components": [
+                    {"name": "basic", "version": "6.1.0"},
+                    {"name": "llm", "version": "2.3.0"}
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(signed(
+                json!({
+                    "components": [
+                        {
+                            "name": "llm",
+                            "latest_version": "3.0.0",
+                            "download_url": "https://dl.example/llm-3.0.0.tar.gz",
+                            "min_core_version": "6.0.0",
+                            "notes": "New providers"
+                        },
+                        {"name": "basic", "latest_version": "6.1.0"}
+                    ]
+                }),
+                1,
+            )))
+            .mount(&server)
+            .await;
+
+        let mut registry = installed(Some(format!("{}/updates", server.uri())));
+        let client = BotServerClient::new(Some("http://unused".to_string()));
+        let updated = registry.check_for_updates(&client).await.ok();
+
+        assert_eq!(updated, Some(2));
+        assert!(registry.last_update_check.is_some());
+        let llm = registry.get_component("llm");
+        assert!(llm.is_some_and(|c| c.update_available && c.is_major_update()));
+        assert!(llm.is_some_and(|c| c.last_checked.is_some()));
+        assert_eq!(
+            llm.and_then(|c| c.metadata.get("download_url"))
+                .map(String::as_str),
+            Some("https://dl.example/llm-3.0.0.tar.gz")
+        );
+        assert_eq!(
+            llm.and_then(|c| c.metadata.get("release_notes"))
+                .map(String::as_str),
+            Some("New providers")
+        );
+        assert!(registry
+            .get_component("basic")
+            .is_some_and(|c| !c.update_available && c.latest_version.as_deref() == Some("6.1.0")));
+    }
+
+    #[tokio::test]
+    async fn test_failed_check_keeps_existing_data() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+
+        let mut registry = installed(Some(server.uri()));
+        assert!(registry
+            .set_latest_version("llm", "2.4.0".to_string())
+            .is_ok());
+        let before = registry.get_component("llm").cloned();
+
+        let client = BotServerClient::new(None);
+        assert!(registry.check_for_updates(&client).await.is_err());
+        assert_eq!(
+            registry
+                .get_component("llm")
+                .map(|c| (&c.latest_version, c.update_available)),
+            before
+                .as_ref()
+                .map(|c| (&c.latest_version, c.update_available))
+        );
+        assert!(registry.last_update_check.is_none());
+
+        let mut unconfigured = installed(None);
+        assert!(matches!(
+            unconfigured.check_for_updates(&client).await,
+            Err(BotError::Config(_))
+        ));
+    }
+
+    fn llm_update(latest: &str) -> serde_json::Value {
+        json!({"components": [{"name": "llm", "latest_version": latest}]})
+    }
+
+    fn verified(registry: &VersionRegistry, reply: serde_json::Value) -> Result<usize, BotError> {
+        let manifest = registry.verify_update_manifest(reply)?;
+        Ok(manifest.components.len())
+    }
+
+    #[test]
+    fn test_manifest_signatures() {
+        let registry = installed(None);
+        assert_eq!(
+            verified(&registry, signed(llm_update("2.4.0"), 1)).ok(),
+            Some(1)
+        );
+
+        let mut tampered = signed(llm_update("2.4.0"), 1);
+        tampered["manifest"]["components"][0]["latest_version"] = json!("9.9.9");
+        assert!(matches!(
+            verified(&registry, tampered),
+            Err(BotError::Auth(_))
+        ));
+        assert!(matches!(
+            verified(&registry, signed(llm_update("2.4.0"), 2)),
+            Err(BotError::Auth(_))
+        ));
+        assert!(matches!(
+            verified(&registry, llm_update("2.4.0")),
+            Err(BotError::Auth(_))
+        ));
+        assert!(matches!(
+            verified(
+                &registry,
+                json!({"manifest": llm_update("2.4.0"), "signature": "bm9wZQ=="})
+            ),
+            Err(BotError::Auth(_))
+        ));
+
+        // Key order and whitespace do not matter, only content.
+        let reordered: serde_json::Value = serde_json::from_str(
+            r#"{"manifest": {"components": [{"latest_version": "2.4.0", "name": "llm"}]},
+                "signature": ""}"#,
+        )
+        .unwrap_or_default();
+        let mut resigned = signed(llm_update("2.4.0"), 1);
+        resigned["manifest"] = reordered["manifest"].clone();
+        assert_eq!(verified(&registry, resigned).ok(), Some(1));
+    }
+
+    #[test]
+    fn test_key_rotation_and_dev_bypass() {
+        let mut registry = installed(None);
+        registry.update_signing_keys = vec![public_key(1), public_key(2)];
+        assert_eq!(
+            verified(&registry, signed(llm_update("2.4.0"), 2)).ok(),
+            Some(1)
+        );
+        assert_eq!(
+            verified(&registry, signed(llm_update("2.4.0"), 1)).ok(),
+            Some(1)
+        );
+        assert!(matches!(
+            verified(&registry, signed(llm_update("2.4.0"), 3)),
+            Err(BotError::Auth(_))
+        ));
+
+        registry.update_signing_keys = vec!["not a key".to_string()];
+        assert!(matches!(
+            verified(&registry, signed(llm_update("2.4.0"), 1)),
+            Err(BotError::Config(_))
+        ));
+        registry.update_signing_keys.clear();
+        assert!(matches!(
+            verified(&registry, signed(llm_update("2.4.0"), 1)),
+            Err(BotError::Auth(_))
+        ));
+
+        registry.allow_unsigned_updates = true;
+        assert_eq!(verified(&registry, llm_update("2.4.0")).ok(), Some(1));
+        assert_eq!(
+            verified(&registry, signed(llm_update("2.4.0"), 3)).ok(),
+            Some(1)
+        );
+        let saved = registry.to_json().unwrap_or_default();
+        assert!(!saved.contains("allow_unsigned_updates"));
+    }
+
+    #[tokio::test]
+    async fn test_badly_signed_manifest_leaves_registry_untouched() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(signed(llm_update("3.0.0"), 2)))
+            .mount(&server)
+            .await;
+
+        let mut registry = installed(Some(server.uri()));
+        let client = BotServerClient::new(None);
+        assert!(matches!(
+            registry.check_for_updates(&client).await,
+            Err(BotError::Auth(_))
+        ));
+        assert!(registry
+            .get_component("llm")
+            .is_some_and(|c| c.latest_version.is_none() && !c.update_available));
+        assert!(registry.last_update_check.is_none());
+    }
+
+    #[test]
+    fn test_manifest_schema_and_unknown_components() {
+        let manifest: UpdateManifest = serde_json::from_value(json!({
+            "components": [
+                {"name": "vault", "latest_version": "9.9.9", "channel": "stable"},
+                {"name": "llm", "latest_version": "2.3.1"},
+                {"name": "basic", "latest_version": " "}
+            ],
+            "generated_at": "2024-05-01T00:00:00Z"
+        }))
+        .unwrap_or_default();
+        assert_eq!(
+            manifest.components.get(1),
+            Some(&ManifestComponent {
+                name: "llm".to_string(),
+                latest_version: "2.3.1".to_string(),
+                download_url: None,
+                min_core_version: None,
+                notes: None,
+            })
+        );
+        assert!(
+            serde_json::from_value::<UpdateManifest>(json!({"components": [{"name": "x"}]}))
+                .is_err()
+        );
+
+        let mut registry = installed(None);
+        assert_eq!(registry.apply_update_manifest(manifest), 1);
+        assert!(registry.get_component("vault").is_none());
+        assert!(registry
+            .get_component("llm")
+            .is_some_and(|c| c.update_available));
+        assert!(registry
+            .get_component("basic")
+            .is_some_and(|c| c.latest_version.is_none()));
+        assert_eq!(
+            serde_json::from_value::<UpdateManifest>(json!({})).ok(),
+            Some(UpdateManifest::default())
+        );
+    }
+
+    /// Answers each check after `delay` with the next scripted outcome,
+    /// `true` for an empty manifest and `false` for a 503, recording when
+    /// each request started and finished.
+    #[derive(Debug, Default)]
+    struct ScriptedServer {
+        delay: Duration,
+        outcomes: Mutex<VecDeque<bool>>,
+        calls: Mutex<Vec<(Instant, Instant)>>,
+    }
+
+    impl ScriptedServer {
+        fn new(delay: Duration, outcomes: &[bool]) -> Arc<Self> {
+            Arc::new(Self {
+                delay,
+                outcomes: Mutex::new(outcomes.iter().copied().collect()),
+                calls: Mutex::default(),
+            })
+        }
+
+        /// Start times, relative to `origin`, in whole seconds.
+        fn starts(&self, origin: Instant) -> Vec<u64> {
+            self.calls
+                .lock()
+                .map(|calls| {
+                    calls
+                        .iter()
+                        .map(|(start, _)| start.duration_since(origin).as_secs())
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+
+    #[async_trait]
+    impl HttpTransport for ScriptedServer {
+        async fn execute(&self, _request: HttpRequest) -> Result<HttpResponse, BotError> {
+            let start = Instant::now();
+            tokio::time::sleep(self.delay).await;
+            if let Ok(mut calls) = self.calls.lock() {
+                calls.push((start, Instant::now()));
+            }
+            let ok = self
+                .outcomes
+                .lock()
+                .ok()
+                .and_then(|mut outcomes| outcomes.pop_front())
+                .unwrap_or(true);
+            if ok {
+                HttpResponse::json(StatusCode::OK, &signed(json!({"components": []}), 1))
+            } else {
+                Ok(HttpResponse::new(StatusCode::SERVICE_UNAVAILABLE))
+            }
+        }
+    }
+
+    fn checked_handle() -> VersionRegistryHandle {
+        VersionRegistryHandle::new(installed(Some("http://updates/check".to_string())))
+    }
+
+    fn scripted_client(server: &Arc<ScriptedServer>) -> BotServerClient {
+        BotServerClient::new(None).with_transport(server.clone())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_update_checker_spreads_checks_within_jitter() {
+        let server = ScriptedServer::new(Duration::ZERO, &[]);
+        let origin = Instant::now();
+        let checker = spawn_update_checker(
+            checked_handle(),
+            scripted_client(&server),
+            Duration::from_secs(100),
+            0.2,
+        );
+        tokio::time::sleep(Duration::from_secs(1000)).await;
+        checker.shutdown().await;
+
+        let starts = server.starts(origin);
+        assert!(starts.len() >= 8, "{starts:?}");
+        assert!(
+            starts.first().is_some_and(|&first| first <= 20),
+            "{starts:?}"
+        );
+        for gap in starts.windows(2).map(|w| w[1] - w[0]) {
+            assert!((80..=120).contains(&gap), "{starts:?}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_update_checker_backs_off_and_records_errors() {
+        let server = ScriptedServer::new(Duration::ZERO, &[false; 6]);
+        let handle = checked_handle();
+        let origin = Instant::now();
+        let checker = spawn_update_checker(
+            handle.clone(),
+            scripted_client(&server),
+            Duration::from_secs(10),
+            0.0,
+        );
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        let failed = handle.read(|r| (r.last_check_error.clone(), r.last_update_check));
+        assert!(failed.0.is_some_and(|e| !e.message.is_empty()));
+        assert!(failed.1.is_none());
+
+        tokio::time::sleep(Duration::from_secs(800)).await;
+        checker.shutdown().await;
+        // Doubling from 20s, capped at 16 intervals, until the seventh check
+        // succeeds and the interval resets.
+        assert_eq!(
+            server.starts(origin),
+            vec![
+                0, 20, 60, 140, 300, 460, 620, 630, 640, 650, 660, 670, 680, 690, 700, 710, 720,
+                730, 740, 750, 760, 770, 780, 790, 800
+            ]
+        );
+        let recovered = handle.read(|r| (r.last_check_error.clone(), r.last_update_check));
+        assert!(recovered.0.is_none());
+        assert!(recovered.1.is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_update_checks_never_overlap() {
+        let server = ScriptedServer::new(Duration::from_secs(50), &[]);
+        let handle = checked_handle();
+        let client = scripted_client(&server);
+        let checker =
+            spawn_update_checker(handle.clone(), client.clone(), Duration::from_secs(10), 0.0);
+
+        tokio::time::sleep(Duration::from_secs(25)).await;
+        assert_eq!(handle.check_for_updates(&client).await.ok(), Some(None));
+
+        tokio::time::sleep(Duration::from_secs(300)).await;
+        checker.stop();
+        tokio::time::sleep(Duration::from_secs(100)).await;
+        assert!(!checker.is_running());
+
+        let calls = server.calls.lock().map(|c| c.clone()).unwrap_or_default();
+        assert!(calls.len() >= 5, "{calls:?}");
+        for pair in calls.windows(2) {
+            assert!(
+                pair[1].0 >= pair[0].1 + Duration::from_secs(10),
+                "{calls:?}"
+            );
+        }
+        assert_eq!(handle.check_for_updates(&client).await.ok(), Some(Some(0)));
+    }
+}
@@ -0,0 +1,565 @@
+use crate::error::{BotError, BotResult};
+use crate::models::{
+    Attachment, AttachmentType, ContactCard, Location, MessagePayload, UserMessage,
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+const WHATSAPP: &str = "whatsapp";
+const TELEGRAM: &str = "telegram";
+const TELEGRAM_UNSUPPORTED: [&str; 6] = ["sticker", "poll", "dice", "game", "story", "invoice"];
+
+static NULL: Value = Value::Null;
+
+/// A value inside a webhook body together with its dotted path, so a
+/// missing field can be reported as `entry.0.changes.0.value.messages`.
+struct Node<'a> {
+    value: &'a Value,
+    path: String,
+}
+
+impl<'a> Node<'a> {
+    const fn root(value: &'a Value) -> Self {
+        Self {
+            value,
+            path: String::new(),
+        }
+    }
+
+    fn join(&self, segment: &str) -> String {
+        if self.path.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}.{segment}", self.path)
+        }
+    }
+
+    fn field(&self, key: &str) -> Self {
+        Self {
+            value: self.value.get(key).unwrap_or(&NULL),
+            path: self.join(key),
+        }
+    }
+
+    fn index(&self, index: usize) -> Self {
+        Self {
+            value: self.value.get(index).unwrap_or(&NULL),
+            path: self.join(&index.to_string()),
+        }
+    }
+
+    fn items(&self) -> impl Iterator<Item = Node<'a>> + '_ {
+        self.value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, value)| Node {
+                value,
+                path: self.join(&index.to_string()),
+            })
+    }
+
+    fn exists(&self) -> bool {
+        !self.value.is_null()
+    }
+
+    fn invalid(&self, expected: &str) -> BotError {
+        BotError::validation(format!("{} is missing or not {expected}", self.path))
+    }
+
+    fn str(&self) -> BotResult<&'a str> {
+        self.value.as_str().ok_or_else(|| self.invalid("a string"))
+    }
+
+    fn opt_str(&self) -> Option<String> {
+        self.value.as_str().map(str::to_string)
+    }
+
+    fn f64(&self) -> BotResult<f64> {
+        self.value.as_f64().ok_or_else(|| self.invalid("a number"))
+    }
+
+    /// Ids arrive as strings on WhatsApp and as numbers on Telegram.
+    fn id(&self) -> BotResult<String> {
+        match self.value {
+            Value::String(id) if !id.is_empty() => Ok(id.clone()),
+            Value::Number(id) => Ok(id.to_string()),
+            _ => Err(self.invalid("an id")),
+        }
+    }
+
+    fn timestamp(&self) -> BotResult<DateTime<Utc>> {
+        self.id()
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .ok_or_else(|| self.invalid("a unix timestamp"))
+    }
+}
+
+impl UserMessage {
+    /// Parses the first message of a WhatsApp Cloud API webhook body. The
+    /// sender's number becomes both `user_id` and `session_id`, and the
+    /// receiving `phone_number_id` becomes `bot_id`. Media attachments carry
+    /// the WhatsApp media id as their `url`; it still has to be resolved
+    /// through the Graph API before download.
+    ///
+    /// # Errors
+    /// Returns `BotError::Validation` naming the path of the first missing or
+    /// mistyped field, and `BotError::UnsupportedInbound` for message kinds
+    /// such as stickers and reactions.
+    pub fn from_whatsapp_webhook(body: &Value) -> BotResult<Self> {
+        let value = Node::root(body)
+            .field("entry")
+            .index(0)
+            .field("changes")
+            .index(0)
+            .field("value");
+        let bot_id = value.field("metadata").field("phone_number_id").id()?;
+        let message = value.field("messages").index(0);
+        let from = message.field("from").id()?;
+        let kind = message.field("type").str()?;
+
+        let mut msg = Self::text(bot_id, from.clone(), from, WHATSAPP, "");
+        msg.timestamp = message.field("timestamp").timestamp()?;
+        msg.set_meta("channel_message_id", message.field("id").str()?);
+
+        match kind {
+            "text" => msg.content = message.field("text").field("body").str()?.to_string(),
+            "image" | "audio" | "video" | "document" => {
+                let media = message.field(kind);
+                let attachment_type = match kind {
+                    "image" => AttachmentType::Image,
+                    "audio" => AttachmentType::Audio,
+                    "video" => AttachmentType::Video,
+                    _ => AttachmentType::Document,
+                };
+                let mut attachment = Attachment::new(attachment_type, media.field("id").str()?);
+                attachment.mime_type = media.field("mime_type").opt_str();
+                attachment.filename = media.field("filename").opt_str();
+                if media.field("voice").value.as_bool().unwrap_or_default() {
+                    msg.set_meta("voice_note", true);
+                }
+                msg.content = media.field("caption").opt_str().unwrap_or_default();
+                msg.attachments.push(attachment);
+            }
+            "location" => {
+                let location = message.field("location");
+                msg.payload = Some(MessagePayload::Location(Location {
+                    name: location.field("name").opt_str(),
+                    address: location.field("address").opt_str(),
+                    ..Location::new(
+                        location.field("latitude").f64()?,
+                        location.field("longitude").f64()?,
+                    )
+                }));
+            }
+            "contacts" => {
+                let contact = message.field("contacts").index(0);
+                let mut card =
+                    ContactCard::new(contact.field("name").field("formatted_name").str()?);
+                for phone in contact.field("phones").items() {
+                    card.phones.push(phone.field("phone").str()?.to_string());
+                }
+                for email in contact.field("emails").items() {
+                    card.emails.push(email.field("email").str()?.to_string());
+                }
+                msg.payload = Some(MessagePayload::Contact(card));
+            }
+            "interactive" => {
+                let interactive = message.field("interactive");
+                let reply_kind = interactive.field("type").str()?;
+                if !matches!(reply_kind, "button_reply" | "list_reply") {
+                    return Err(BotError::unsupported_inbound(
+                        WHATSAPP,
+                        format!("interactive.{reply_kind}"),
+                    ));
+                }
+                let reply = interactive.field(reply_kind);
+                msg.content = reply.field("title").str()?.to_string();
+                msg.set_meta("postback", reply.field("id").str()?);
+            }
+            "button" => {
+                let button = message.field("button");
+                msg.content = button.field("text").str()?.to_string();
+                msg.set_meta("postback", button.field("payload").str()?);
+            }
+            other => return Err(BotError::unsupported_inbound(WHATSAPP, other)),
+        }
+        Ok(msg)
+    }
+
+    /// Parses a Telegram Bot API update: a new or edited message, or a
+    /// callback query from an inline keyboard, whose `data` becomes both the
+    /// content and the `postback` metadata. The sender becomes `user_id` and
+    /// the chat `session_id`. Updates do not name the receiving bot, so
+    /// `bot_id` is left empty for the caller to fill in. Attachments carry
+    /// the Telegram `file_id` as their `url`.
+    ///
+    /// # Errors
+    /// Returns `BotError::Validation` naming the path of the first missing or
+    /// mistyped field, and `BotError::UnsupportedInbound` for stickers, polls
+    /// and other message kinds with no `UserMessage` equivalent.
+    pub fn from_telegram_update(update: &Value) -> BotResult<Self> {
+        let root = Node::root(update);
+        let callback = root.field("callback_query");
+        if callback.exists() {
+            let message = callback.field("message");
+            let mut msg = Self::text(
+                "",
+                callback.field("from").field("id").id()?,
+                message.field("chat").field("id").id()?,
+                TELEGRAM,
+                callback.field("data").str()?,
+            );
+            msg.set_meta("postback", msg.content.clone());
+            msg.set_meta("callback_query_id", callback.field("id").id()?);
+            return Ok(msg);
+        }
+
+        let message = ["message", "edited_message"]
+            .into_iter()
+            .map(|kind| root.field(kind))
+            .find(Node::exists)
+            .ok_or_else(|| {
+                let kind = update
+                    .as_object()
+                    .and_then(|fields| fields.keys().find(|key| *key != "update_id"))
+                    .map_or("unknown", String::as_str);
+                BotError::unsupported_inbound(TELEGRAM, kind)
+            })?;
+
+        let mut msg = Self::text(
+            "",
+            message.field("from").field("id").id()?,
+            message.field("chat").field("id").id()?,
+            TELEGRAM,
+            "",
+        );
+        msg.timestamp = message.field("date").timestamp()?;
+        msg.set_meta("channel_message_id", message.field("message_id").id()?);
+
+        if let Some(kind) = TELEGRAM_UNSUPPORTED
+            .into_iter()
+            .find(|kind| message.field(kind).exists())
+        {
+            return Err(BotError::unsupported_inbound(TELEGRAM, kind));
+        }
+
+        let text = message.field("text");
+        if text.exists() {
+            msg.content = text.str()?.to_string();
+            return Ok(msg);
+        }
+        msg.content = message.field("caption").opt_str().unwrap_or_default();
+
+        let photo = message.field("photo");
+        if photo.exists() {
+            // Sizes are listed smallest first.
+            let largest = photo
+                .items()
+                .last()
+                .ok_or_else(|| photo.invalid("a non-empty array"))?;
+            msg.attachments
+                .push(telegram_file(&largest, AttachmentType::Image)?.with_mime_type("image/jpeg"));
+            return Ok(msg);
+        }
+
+        for (kind, attachment_type) in [
+            ("voice", AttachmentType::Audio),
+            ("audio", AttachmentType::Audio),
+            ("video", AttachmentType::Video),
+            ("video_note", AttachmentType::Video),
+            ("animation", AttachmentType::Video),
+            ("document", AttachmentType::Document),
+        ] {
+            let file = message.field(kind);
+            if file.exists() {
+                if kind == "voice" {
+                    msg.set_meta("voice_note", true);
+                }
+                msg.attachments.push(telegram_file(&file, attachment_type)?);
+                return Ok(msg);
+            }
+        }
+
+        let venue = message.field("venue");
+        let location = if venue.exists() {
+            venue.field("location")
+        } else {
+            message.field("location")
+        };
+        if location.exists() {
+            msg.payload = Some(MessagePayload::Location(Location {
+                name: venue.field("title").opt_str(),
+                address: venue.field("address").opt_str(),
+                ..Location::new(
+                    location.field("latitude").f64()?,
+                    location.field("longitude").f64()?,
+                )
+            }));
+            return Ok(msg);
+        }
+
+        let contact = message.field("contact");
+        if contact.exists() {
+            let first = contact.field("first_name").str()?;
+            let display_name = match contact.field("last_name").opt_str() {
+                Some(last) => format!("{first} {last}"),
+                None => first.to_string(),
+            };
+            msg.payload = Some(MessagePayload::Contact(
+                ContactCard::new(display_name).with_phone(contact.field("phone_number").str()?),
+            ));
+            return Ok(msg);
+        }
+
+        Err(BotError::unsupported_inbound(TELEGRAM, "unknown"))
+    }
+}
+
+fn telegram_file(file: &Node<'_>, attachment_type: AttachmentType) -> BotResult<Attachment> {
+    let mut attachment = Attachment::new(attachment_type, file.field("file_id").str()?);
+    attachment.mime_type = file.field("mime_type").opt_str();
+    attachment.filename = file.field("file_name").opt_str();
+    attachment.size = file.field("file_size").value.as_u64();
+    Ok(attachment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::SystemLimits;
+    use serde_json::json;
+
+    fn fixture(raw: &str) -> Value {
+        serde_json::from_str(raw).unwrap_or_default()
+    }
+
+    fn whatsapp(raw: &str) -> BotResult<UserMessage> {
+        UserMessage::from_whatsapp_webhook(&fixture(raw))
+    }
+
+    fn telegram(raw: &str) -> BotResult<UserMessage> {
+        UserMessage::from_telegram_update(&fixture(raw))
+    }
+
+    fn first_attachment(msg: Option<&UserMessage>) -> Option<&Attachment> {
+        msg.and_then(|m| m.attachments.first())
+    }
+
+    fn validation_message<T>(result: BotResult<T>) -> Option<String> {
+        match result {
+            Err(BotError::Validation(message)) => Some(message.into()),
+            _ => None,
+        }
+    }
+
+    fn unsupported_kind<T>(result: BotResult<T>) -> Option<(String, String)> {
+        match result {
+            Err(BotError::UnsupportedInbound { channel, kind }) => Some((channel, kind)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_whatsapp_text() {
+        let msg = whatsapp(include_str!("../tests/fixtures/inbound/whatsapp_text.json")).ok();
+        let msg = msg.as_ref();
+        assert_eq!(msg.map(|m| m.bot_id.as_str()), Some("106540352242922"));
+        assert_eq!(msg.map(|m| m.user_id.as_str()), Some("5511988887777"));
+        assert_eq!(msg.map(|m| m.session_id.as_str()), Some("5511988887777"));
+        assert_eq!(msg.map(|m| m.channel.as_str()), Some("whatsapp"));
+        assert_eq!(
+            msg.map(|m| m.content.as_str()),
+            Some("Qual o horário de atendimento?")
+        );
+        assert_eq!(msg.map(|m| m.timestamp.timestamp()), Some(1_700_000_000));
+        assert_eq!(
+            msg.and_then(|m| m.get_meta_str("channel_message_id")),
+            Some("wamid.HBgNNTUxMTk4ODg4Nzc3NxUCABIYFjNFQjBDMEQ5RjBBNjM3RkQ1NjgwAA==")
+        );
+        assert!(msg.is_some_and(|m| m.validate(&SystemLimits::default()).is_ok()));
+    }
+
+    #[test]
+    fn test_whatsapp_media() {
+        let image = whatsapp(include_str!(
+            "../tests/fixtures/inbound/whatsapp_image.json"
+        ))
+        .ok();
+        assert_eq!(
+            image.as_ref().map(|m| m.content.as_str()),
+            Some("Comprovante")
+        );
+        let attachment = first_attachment(image.as_ref());
+        assert_eq!(attachment.map(|a| a.url.as_str()), Some("1479539546320432"));
+        assert_eq!(
+            attachment.and_then(|a| a.mime_type.as_deref()),
+            Some("image/jpeg")
+        );
+        assert!(attachment.is_some_and(Attachment::is_image));
+        assert!(image.is_some_and(|m| m.validate(&SystemLimits::default()).is_ok()));
+
+        let voice = whatsapp(include_str!(
+            "../tests/fixtures/inbound/whatsapp_voice.json"
+        ))
+        .ok();
+        assert!(voice.as_ref().is_some_and(|m| m.content.is_empty()));
+        assert_eq!(
+            voice.as_ref().and_then(|m| m.get_meta("voice_note")),
+            Some(&json!(true))
+        );
+        let attachment = first_attachment(voice.as_ref());
+        assert_eq!(
+            attachment.map(|a| a.attachment_type),
+            Some(AttachmentType::Audio)
+        );
+        assert_eq!(
+            attachment.and_then(|a| a.mime_type.as_deref()),
+            Some("audio/ogg; codecs=opus")
+        );
+    }
+
+    #[test]
+    fn test_whatsapp_location() {
+        let msg = whatsapp(include_str!(
+            "../tests/fixtures/inbound/whatsapp_location.json"
+        ))
+        .ok();
+        assert_eq!(
+            msg.and_then(|m| m.payload),
+            Some(MessagePayload::Location(
+                Location::new(-23.5613, -46.6565)
+                    .with_name("MASP")
+                    .with_address("Av. Paulista, 1578 - Bela Vista, São Paulo")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_whatsapp_unsupported_and_malformed() {
+        assert_eq!(
+            unsupported_kind(whatsapp(include_str!(
+                "../tests/fixtures/inbound/whatsapp_sticker.json"
+            ))),
+            Some(("whatsapp".to_string(), "sticker".to_string()))
+        );
+
+        let mut body = fixture(include_str!("../tests/fixtures/inbound/whatsapp_text.json"));
+        if let Some(message) = body.pointer_mut("/entry/0/changes/0/value/messages/0") {
+            message["from"] = json!(null);
+        }
+        assert_eq!(
+            validation_message(UserMessage::from_whatsapp_webhook(&body)).as_deref(),
+            Some("entry.0.changes.0.value.messages.0.from is missing or not an id")
+        );
+        assert_eq!(
+            validation_message(UserMessage::from_whatsapp_webhook(&json!({}))).as_deref(),
+            Some("entry.0.changes.0.value.metadata.phone_number_id is missing or not an id")
+        );
+    }
+
+    #[test]
+    fn test_telegram_text_and_callback() {
+        let msg = telegram(include_str!("../tests/fixtures/inbound/telegram_text.json")).ok();
+        let msg = msg.as_ref();
+        assert_eq!(msg.map(|m| m.user_id.as_str()), Some("123456789"));
+        assert_eq!(msg.map(|m| m.session_id.as_str()), Some("123456789"));
+        assert_eq!(msg.map(|m| m.channel.as_str()), Some("telegram"));
+        assert_eq!(msg.map(|m| m.content.as_str()), Some("/start"));
+        assert_eq!(msg.map(|m| m.timestamp.timestamp()), Some(1_700_000_100));
+        assert!(msg.is_some_and(|m| m.bot_id.is_empty()));
+
+        let update = json!({
+            "update_id": 1,
+            "callback_query": {
+                "id": "4382bfdwdsb323b2d9",
+                "from": {"id": 123_456_789, "first_name": "Maria"},
+                "message": {"message_id": 7, "chat": {"id": -100_200, "type": "group"}},
+                "data": "plan:pro"
+            }
+        });
+        let msg = UserMessage::from_telegram_update(&update).ok();
+        assert_eq!(msg.as_ref().map(|m| m.session_id.as_str()), Some("-100200"));
+        assert_eq!(
+            msg.as_ref().and_then(|m| m.get_meta_str("postback")),
+            Some("plan:pro")
+        );
+    }
+
+    #[test]
+    fn test_telegram_media() {
+        let photo = telegram(include_str!(
+            "../tests/fixtures/inbound/telegram_photo.json"
+        ))
+        .ok();
+        assert_eq!(
+            photo.as_ref().map(|m| m.content.as_str()),
+            Some("Meu pedido chegou assim")
+        );
+        let attachment = first_attachment(photo.as_ref());
+        assert_eq!(
+            attachment.map(|a| a.url.as_str()),
+            Some("AgACAgEAAxkBAAIBZ2VkX3-large")
+        );
+        assert_eq!(attachment.and_then(|a| a.size), Some(102_400));
+        assert_eq!(
+            attachment.and_then(|a| a.mime_type.as_deref()),
+            Some("image/jpeg")
+        );
+
+        let voice = telegram(include_str!(
+            "../tests/fixtures/inbound/telegram_voice.json"
+        ))
+        .ok();
+        assert_eq!(
+            voice.as_ref().and_then(|m| m.get_meta("voice_note")),
+            Some(&json!(true))
+        );
+        assert_eq!(
+            first_attachment(voice.as_ref()).and_then(|a| a.mime_type.as_deref()),
+            Some("audio/ogg")
+        );
+
+        let update = json!({
+            "update_id": 2,
+            "message": {
+                "message_id": 9,
+                "date": 1_700_000_300,
+                "from": {"id": 1},
+                "chat": {"id": 1},
+                "location": {"latitude": -22.9519, "longitude": -43.2105}
+            }
+        });
+        assert_eq!(
+            UserMessage::from_telegram_update(&update)
+                .ok()
+                .and_then(|m| m.payload_kind()),
+            Some("location")
+        );
+    }
+
+    #[test]
+    fn test_telegram_unsupported_and_malformed() {
+        assert_eq!(
+            unsupported_kind(telegram(include_str!(
+                "../tests/fixtures/inbound/telegram_poll.json"
+            ))),
+            Some(("telegram".to_string(), "poll".to_string()))
+        );
+        assert_eq!(
+            unsupported_kind(UserMessage::from_telegram_update(
+                &json!({"update_id": 3, "my_chat_member": {}})
+            )),
+            Some(("telegram".to_string(), "my_chat_member".to_string()))
+        );
+
+        let update = json!({"update_id": 4, "message": {"message_id": 1, "chat": {"id": 1}}});
+        assert_eq!(
+            validation_message(UserMessage::from_telegram_update(&update)).as_deref(),
+            Some("message.from.id is missing or not an id")
+        );
+    }
+}
@@ -0,0 +1,64 @@
+/// Locales `label` methods translate to, in the order of each label table.
+/// English comes first and is the fallback.
+pub const SUPPORTED_LOCALES: [&str; 3] = ["en", "pt-BR", "es"];
+
+/// One label per entry of `SUPPORTED_LOCALES`, in the same order.
+pub(crate) type Labels = [&'static str; SUPPORTED_LOCALES.len()];
+
+#[must_use]
+pub const fn supported_locales() -> &'static [&'static str] {
+    &SUPPORTED_LOCALES
+}
+
+/// The supported locale to use for `locale`: an exact match ignoring case
+/// and `_` versus `-`, else the first with the same language, else English.
+fn locale_index(locale: &str) -> usize {
+    let wanted = locale.trim().replace('_', "-");
+    let language = wanted.split('-').next().unwrap_or_default();
+    SUPPORTED_LOCALES
+        .iter()
+        .position(|supported| supported.eq_ignore_ascii_case(&wanted))
+        .or_else(|| {
+            SUPPORTED_LOCALES.iter().position(|supported| {
+                supported
+                    .split('-')
+                    .next()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+        })
+        .unwrap_or(0)
+}
+
+/// The entry of `labels` for `locale`.
+pub(crate) fn pick(labels: &Labels, locale: &str) -> &'static str {
+    labels
+        .get(locale_index(locale))
+        .or_else(|| labels.first())
+        .copied()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_matching() {
+        let labels: Labels = ["Running", "Em execução", "En ejecución"];
+        for (locale, expected) in [
+            ("en", "Running"),
+            ("en-US", "Running"),
+            ("pt-BR", "Em execução"),
+            ("pt_br", "Em execução"),
+            ("pt", "Em execução"),
+            ("pt-PT", "Em execução"),
+            ("es", "En ejecución"),
+            ("ES-mx", "En ejecución"),
+            ("fr", "Running"),
+            ("", "Running"),
+        ] {
+            assert_eq!(pick(&labels, locale), expected, "{locale}");
+        }
+        assert_eq!(supported_locales().first(), Some(&"en"));
+    }
+}
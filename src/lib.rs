@@ -1,37 +1,115 @@
+#[cfg(feature = "axum")]
+mod axum_response;
 pub mod branding;
+pub mod builder;
+pub mod context;
+#[cfg(feature = "msgpack")]
+pub mod encoding;
 pub mod error;
 #[cfg(feature = "http-client")]
 pub mod http_client;
+mod inbound;
+pub mod labels;
 pub mod limits;
+mod macros;
+mod markup;
 pub mod message_types;
 pub mod models;
+pub mod outbound;
+pub mod problem;
+#[cfg(feature = "metrics")]
+mod prometheus;
+pub mod redact;
 pub mod resilience;
+pub mod sanitize;
+pub mod schedule;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod streaming;
 pub mod version;
+pub mod version_diff;
+pub mod version_manifest;
+pub mod version_table;
+pub mod versioned;
+pub mod wire_codes;
 
 pub use branding::{
-    branding, init_branding, is_white_label, platform_name, platform_short, BrandingConfig,
+    branding, init_branding, init_branding_with, is_white_label, platform_name, platform_short,
+    with_branding, BrandingConfig, BrandingLayers, BrandingProvenance, BrandingSource,
+};
+pub use builder::{BotResponseBuilder, ComponentVersionBuilder, UserMessageBuilder};
+pub use context::{ApproxTokenCounter, CharCounter, ContextWindow, TokenCounter};
+pub use error::{
+    parse_uuid, BotError, BotResult, BotResultExt, DatabaseError, ErrorCategory, ErrorMessage,
+    FieldError, ValidationErrors,
 };
-pub use error::{BotError, BotResult};
 pub use limits::{
     check_array_length_limit, check_file_size_limit, check_loop_limit, check_recursion_limit,
-    check_string_length_limit, format_limit_error_response, LimitExceeded, LimitType, RateLimiter,
-    SystemLimits, MAX_API_CALLS_PER_HOUR, MAX_API_CALLS_PER_MINUTE, MAX_ARRAY_LENGTH,
-    MAX_BOTS_PER_TENANT, MAX_CONCURRENT_REQUESTS_GLOBAL, MAX_CONCURRENT_REQUESTS_PER_USER,
-    MAX_DB_CONNECTIONS_PER_TENANT, MAX_DB_QUERY_RESULTS, MAX_DRIVE_STORAGE_BYTES,
-    MAX_FILE_SIZE_BYTES, MAX_KB_DOCUMENTS_PER_BOT, MAX_KB_DOCUMENT_SIZE_BYTES,
-    MAX_LLM_REQUESTS_PER_MINUTE, MAX_LLM_TOKENS_PER_REQUEST, MAX_LOOP_ITERATIONS,
-    MAX_PENDING_TASKS, MAX_RECURSION_DEPTH, MAX_REQUEST_BODY_BYTES, MAX_SCRIPT_EXECUTION_SECONDS,
-    MAX_SESSIONS_PER_USER, MAX_SESSION_IDLE_SECONDS, MAX_STRING_LENGTH, MAX_TOOLS_PER_BOT,
-    MAX_UPLOAD_SIZE_BYTES, MAX_WEBSOCKET_CONNECTIONS_GLOBAL, MAX_WEBSOCKET_CONNECTIONS_PER_USER,
+    check_string_length_limit, format_limit_error_response, LimitErrorBody, LimitExceeded,
+    LimitType, RateLimiter, SystemLimits, MAX_API_CALLS_PER_HOUR, MAX_API_CALLS_PER_MINUTE,
+    MAX_ARRAY_LENGTH, MAX_BOTS_PER_TENANT, MAX_CONCURRENT_REQUESTS_GLOBAL,
+    MAX_CONCURRENT_REQUESTS_PER_USER, MAX_DB_CONNECTIONS_PER_TENANT, MAX_DB_QUERY_RESULTS,
+    MAX_DRIVE_STORAGE_BYTES, MAX_FILE_SIZE_BYTES, MAX_KB_DOCUMENTS_PER_BOT,
+    MAX_KB_DOCUMENT_SIZE_BYTES, MAX_LLM_REQUESTS_PER_MINUTE, MAX_LLM_TOKENS_PER_REQUEST,
+    MAX_LOOP_ITERATIONS, MAX_PENDING_TASKS, MAX_RECURSION_DEPTH, MAX_REQUEST_BODY_BYTES,
+    MAX_SCRIPT_EXECUTION_SECONDS, MAX_SESSIONS_PER_USER, MAX_SESSION_IDLE_SECONDS,
+    MAX_SESSION_METADATA_ENTRIES, MAX_SESSION_METADATA_KEY_LENGTH,
+    MAX_SESSION_METADATA_VALUE_LENGTH, MAX_SESSION_TAGS, MAX_SESSION_TAG_LENGTH, MAX_STRING_LENGTH,
+    MAX_SUGGESTIONS_PER_RESPONSE, MAX_TOOLS_PER_BOT, MAX_UPLOAD_SIZE_BYTES,
+    MAX_WEBSOCKET_CONNECTIONS_GLOBAL, MAX_WEBSOCKET_CONNECTIONS_PER_USER,
     RATE_LIMIT_BURST_MULTIPLIER, RATE_LIMIT_WINDOW_SECONDS,
 };
-pub use message_types::MessageType;
-pub use models::{ApiResponse, BotResponse, Session, Suggestion, UserMessage};
+pub use message_types::{
+    KnownMessageType, MessageType, MessageTypeError, MessageTypeMap, MessageTypeSet,
+};
+pub use models::{
+    ApiResponse, BotResponse, Card, CardButton, CloseReason, ContactCard, ContentFormat,
+    Conversation, InboundFrame, Location, Mention, Message, MessageDirection, MessageEvent,
+    MessageEventType, MessagePayload, PageCursor, PaginatedResponse, Participant, ParticipantRole,
+    Reaction, Session, SessionState, StateTransition, Suggestion, SuggestionAction, UserMessage,
+    DEFAULT_MAX_CARD_BUTTONS, MAX_REACTION_EMOJI_CHARS,
+};
+pub use outbound::{ChannelSerializer, TelegramSerializer, WebhookSerializer, WhatsAppSerializer};
+pub use problem::{default_problem_base_uri, ProblemDetails, PROBLEM_JSON_CONTENT_TYPE};
+pub use redact::{redact_url_credentials, Redact, RedactedDebug, RedactionPolicy};
 pub use resilience::{ResilienceError, RetryConfig};
+pub use sanitize::{
+    is_effectively_empty, sanitize_content, truncate_chars, truncate_graphemes, ELLIPSIS,
+    MAX_WHITESPACE_RUN,
+};
+pub use schedule::ScheduledQueue;
+pub use streaming::{StreamReassembler, StreamUpdate, DEFAULT_GAP_TIMEOUT};
 pub use version::{
-    get_botserver_version, init_version_registry, register_component, version_string,
-    ComponentSource, ComponentStatus, ComponentVersion, VersionRegistry, BOTSERVER_VERSION,
+    build_info, deregister_component, disable_version_registry_autosave,
+    enable_version_registry_autosave, flush_version_registry, get_botserver_version,
+    init_version_registry, init_version_registry_from_file, parse_version, record_heartbeat,
+    register_component, registry_manager, run_stale_sweeper, sweep_stale_components,
+    version_registry_handle, version_string, BuildInfo, CompatIssue, ComponentError,
+    ComponentHealth, ComponentSource, ComponentStatus, ComponentVersion, HealthResponse,
+    OverallHealth, RegistryEvent, RegistryManager, UpdateSeverity, VersionRegistry,
+    VersionRegistryHandle, VersionReport, VersionReportOptions, BOTSERVER_VERSION,
+};
+pub use version_diff::{ComponentChange, DiffOptions, RegistryDiff};
+pub use version_manifest::{
+    ExpectedComponent, ExpectedComponents, VerificationReport, VersionTooOld, WrongSource,
 };
+pub use version_table::{TableBorder, TableColumn, TableOptions, TableSort};
+pub use versioned::{encode_versioned, Migrator, Persisted, Versioned};
+pub use wire_codes::{CodecRegistry, LegacyV5Codec, MessageTypeCodec, NativeCodec};
 
+#[cfg(feature = "axum")]
+pub use axum_response::{negotiate_error, prefers_html};
+#[cfg(feature = "msgpack")]
+pub use encoding::{BinaryEncode, ENCODING_VERSION};
+#[cfg(feature = "tracing")]
+pub use error::record_error;
+#[cfg(feature = "blocking-client")]
+pub use http_client::BlockingBotServerClient;
 #[cfg(feature = "http-client")]
-pub use http_client::BotServerClient;
+pub use http_client::{
+    AuthScheme, BatchItem, BatchOptions, BotServerClient, CancellationToken, FailFast,
+    HealthStatus, HttpTransport, Interceptor, OAuth2ClientCredentials, PollBatch, PollOptions,
+    Progress, ResponseCache, StaticToken, TokenProvider, UpdateChecker,
+};
+#[cfg(feature = "schema")]
+pub use schema::schemas;
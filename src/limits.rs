@@ -1,3 +1,5 @@
+use crate::error::FieldError;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -30,6 +32,12 @@ pub const MAX_SESSIONS_PER_USER: u32 = 10;
 pub const MAX_BOTS_PER_TENANT: u32 = 100;
 pub const MAX_TOOLS_PER_BOT: u32 = 500;
 pub const MAX_PENDING_TASKS: u32 = 1000;
+pub const MAX_SUGGESTIONS_PER_RESPONSE: usize = 20;
+pub const MAX_SESSION_METADATA_ENTRIES: usize = 50;
+pub const MAX_SESSION_METADATA_KEY_LENGTH: usize = 64;
+pub const MAX_SESSION_METADATA_VALUE_LENGTH: usize = 1024;
+pub const MAX_SESSION_TAGS: usize = 50;
+pub const MAX_SESSION_TAG_LENGTH: usize = 64;
 pub const RATE_LIMIT_WINDOW_SECONDS: u64 = 60;
 pub const RATE_LIMIT_BURST_MULTIPLIER: f64 = 1.5;
 
@@ -61,8 +69,12 @@ pub struct SystemLimits {
     pub max_bots_per_tenant: u32,
     pub max_tools_per_bot: u32,
     pub max_pending_tasks: u32,
+    pub max_suggestions_per_response: usize,
     pub rate_limit_window_seconds: u64,
     pub rate_limit_burst_multiplier: f64,
+    /// When set, `validate` rejects message types this build does not know
+    /// instead of carrying them through.
+    pub strict_message_types: bool,
 }
 
 impl Default for SystemLimits {
@@ -94,8 +106,10 @@ impl Default for SystemLimits {
             max_bots_per_tenant: MAX_BOTS_PER_TENANT,
             max_tools_per_bot: MAX_TOOLS_PER_BOT,
             max_pending_tasks: MAX_PENDING_TASKS,
+            max_suggestions_per_response: MAX_SUGGESTIONS_PER_RESPONSE,
             rate_limit_window_seconds: RATE_LIMIT_WINDOW_SECONDS,
             rate_limit_burst_multiplier: RATE_LIMIT_BURST_MULTIPLIER,
+            strict_message_types: false,
         }
     }
 }
@@ -107,6 +121,7 @@ pub enum LimitType {
     FileSize,
     UploadSize,
     RequestBody,
+    ResponseBody,
     StringLength,
     ArrayLength,
     ConcurrentRequests,
@@ -136,6 +151,7 @@ impl std::fmt::Display for LimitType {
             Self::FileSize => write!(f, "file_size"),
             Self::UploadSize => write!(f, "upload_size"),
             Self::RequestBody => write!(f, "request_body"),
+            Self::ResponseBody => write!(f, "response_body"),
             Self::StringLength => write!(f, "string_length"),
             Self::ArrayLength => write!(f, "array_length"),
             Self::ConcurrentRequests => write!(f, "concurrent_requests"),
@@ -159,6 +175,39 @@ impl std::fmt::Display for LimitType {
     }
 }
 
+impl LimitType {
+    /// HTTP status for a request rejected by this limit: 413 for payload
+    /// sizes, 429 for request rates, 403 for account quotas, 503 for server
+    /// capacity and 400/422 for invalid input or scripts.
+    #[must_use]
+    pub const fn status_code(self) -> u16 {
+        match self {
+            Self::FileSize | Self::UploadSize | Self::RequestBody | Self::KbDocumentSize => 413,
+            Self::ApiCallsMinute
+            | Self::ApiCallsHour
+            | Self::LlmRequests
+            | Self::ConcurrentRequests
+            | Self::WebsocketConnections => 429,
+            Self::KbDocuments
+            | Self::DriveStorage
+            | Self::SessionsPerUser
+            | Self::BotsPerTenant
+            | Self::ToolsPerBot
+            | Self::PendingTasks => 403,
+            Self::DbConnections => 503,
+            Self::ResponseBody => 502,
+            Self::SessionIdle => 401,
+            Self::LoopIterations | Self::RecursionDepth | Self::ScriptExecution => 422,
+            Self::StringLength | Self::ArrayLength | Self::LlmTokens | Self::DbQueryResults => 400,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_rate_limit(self) -> bool {
+        self.status_code() == 429
+    }
+}
+
 #[derive(Debug)]
 pub struct LimitExceeded {
     pub limit_type: LimitType,
@@ -179,6 +228,26 @@ impl std::fmt::Display for LimitExceeded {
 
 impl std::error::Error for LimitExceeded {}
 
+impl LimitExceeded {
+    /// The same failure as a validation error on `field`, for limits that
+    /// apply to a single request body.
+    #[must_use]
+    pub fn to_field_error(&self, field: &str) -> FieldError {
+        let (code, message) = match self.limit_type {
+            LimitType::StringLength => (
+                "too_long",
+                format!("{field} must be at most {} characters", self.maximum),
+            ),
+            LimitType::ArrayLength => (
+                "too_many",
+                format!("{field} must have at most {} entries", self.maximum),
+            ),
+            _ => ("limit_exceeded", self.to_string()),
+        };
+        FieldError::new(field, code, message).with_rejected_value(self.current)
+    }
+}
+
 #[derive(Debug)]
 struct RateLimitEntry {
     count: AtomicU64,
@@ -462,15 +531,42 @@ pub fn check_array_length_limit(length: usize, max: usize) -> Result<(), LimitEx
     Ok(())
 }
 
+/// The JSON body `format_limit_error_response` renders for a
+/// `LimitExceeded`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LimitErrorBody {
+    /// `rate_limit_exceeded` for request-rate limits, `limit_exceeded`
+    /// otherwise.
+    pub error: String,
+    pub message: String,
+    /// Which limit was hit, e.g. `upload_size`.
+    pub limit_type: String,
+    pub current: u64,
+    pub maximum: u64,
+    /// Seconds to wait before retrying, when known.
+    pub retry_after_secs: Option<u64>,
+}
+
+impl From<&LimitExceeded> for LimitErrorBody {
+    fn from(error: &LimitExceeded) -> Self {
+        let code = if error.limit_type.is_rate_limit() {
+            "rate_limit_exceeded"
+        } else {
+            "limit_exceeded"
+        };
+        Self {
+            error: code.to_string(),
+            message: error.to_string(),
+            limit_type: error.limit_type.to_string(),
+            current: error.current,
+            maximum: error.maximum,
+            retry_after_secs: error.retry_after_secs,
+        }
+    }
+}
+
 pub fn format_limit_error_response(error: &LimitExceeded) -> (u16, String) {
-    let status = 429;
-    let body = serde_json::json!({
-        "error": "rate_limit_exceeded",
-        "message": error.to_string(),
-        "limit_type": error.limit_type.to_string(),
-        "current": error.current,
-        "maximum": error.maximum,
-        "retry_after_secs": error.retry_after_secs,
-    });
-    (status, body.to_string())
+    let body = serde_json::to_string(&LimitErrorBody::from(error)).unwrap_or_default();
+    (error.limit_type.status_code(), body)
 }
@@ -0,0 +1,205 @@
+/// Builds a `BotError` from a variant name and format arguments, a struct
+/// variant literal, a bare format string (`BotError::Other`) or any value
+/// convertible into `BotError`.
+///
+/// ```
+/// use botlib::{bot_error, BotError};
+///
+/// let id = 7;
+/// assert_eq!(bot_error!(Validation, "bad id {id}").to_string(), "Validation error: bad id 7");
+/// assert_eq!(bot_error!(NotFound, "User").to_string(), "User not found");
+/// assert!(matches!(
+///     bot_error!(RateLimited { retry_after_secs: Some(30), body: None }),
+///     BotError::RateLimited { retry_after_secs: Some(30), .. }
+/// ));
+/// assert!(matches!(bot_error!(BotError::timeout(10)), BotError::Timeout { .. }));
+/// ```
+#[macro_export]
+macro_rules! bot_error {
+    (NotFound, $($arg:tt)+) => {
+        $crate::BotError::not_found(::std::format!($($arg)+))
+    };
+    (ServiceUnavailable, $($arg:tt)+) => {
+        $crate::BotError::service_unavailable(::std::format!($($arg)+))
+    };
+    ($variant:ident { $($fields:tt)* }) => {
+        $crate::BotError::$variant { $($fields)* }
+    };
+    ($variant:ident, $($arg:tt)+) => {
+        $crate::BotError::$variant(::core::convert::Into::into(::std::format!($($arg)+)))
+    };
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::BotError::Other(::std::format!($fmt $($arg)*))
+    };
+    ($err:expr) => {
+        $crate::BotError::from($err)
+    };
+}
+
+/// Returns early with the `BotError` described by `bot_error!`.
+#[macro_export]
+macro_rules! bot_bail {
+    ($($arg:tt)+) => {
+        return ::core::result::Result::Err(::core::convert::From::from($crate::bot_error!($($arg)+)))
+    };
+}
+
+/// Returns early with the `BotError` described by `bot_error!` unless
+/// `cond` holds.
+#[macro_export]
+macro_rules! bot_ensure {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::bot_bail!($($arg)+);
+        }
+    };
+}
+
+/// Unwraps an `Option`, returning early with the `BotError` described by
+/// `bot_error!` when it is `None`.
+#[macro_export]
+macro_rules! ensure_some {
+    ($opt:expr, $($arg:tt)+) => {
+        match $opt {
+            ::core::option::Option::Some(value) => value,
+            ::core::option::Option::None => $crate::bot_bail!($($arg)+),
+        }
+    };
+}
+
+/// Builds a `MessageTypeMap` from `type => value` entries. The types must
+/// be constants, and listing one twice fails to compile.
+///
+/// ```
+/// use botlib::{message_type_map, MessageType};
+///
+/// let handlers = message_type_map! {
+///     MessageType::USER => "route to bot",
+///     MessageType::TYPING => "show indicator",
+///     MessageType(1001) => "custom",
+/// };
+/// assert_eq!(handlers.get(MessageType::TYPING), Some(&"show indicator"));
+/// ```
+///
+/// ```compile_fail
+/// use botlib::{message_type_map, MessageType};
+///
+/// let handlers = message_type_map! {
+///     MessageType::USER => 1,
+///     MessageType(1) => 2,
+/// };
+/// ```
+#[macro_export]
+macro_rules! message_type_map {
+    ($($message_type:expr => $value:expr),* $(,)?) => {{
+        const _: () = ::core::assert!(
+            $crate::message_types::all_distinct(&[$($message_type.as_i32()),*]),
+            "duplicate message type in message_type_map!"
+        );
+        let mut map = $crate::message_types::MessageTypeMap::new();
+        $(map.insert($message_type, $value);)*
+        map
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{BotError, BotResult};
+
+    fn check_age(age: u32) -> BotResult<u32> {
+        bot_ensure!(age >= 18, Validation, "age {age} is below 18");
+        Ok(age)
+    }
+
+    fn find_session(id: Option<&str>) -> BotResult<String> {
+        let id = ensure_some!(id, NotFound, "Session");
+        Ok(id.to_string())
+    }
+
+    fn throttle(remaining: u32) -> BotResult<()> {
+        bot_ensure!(
+            remaining > 0,
+            RateLimited {
+                retry_after_secs: Some(30),
+                body: None,
+            }
+        );
+        Ok(())
+    }
+
+    fn fail(kind: &str) -> BotResult<()> {
+        match kind {
+            "config" => bot_bail!(Config, "missing {}", "API key"),
+            "database" => bot_bail!(Database, "pool {} exhausted", 3),
+            "unavailable" => bot_bail!(ServiceUnavailable, "maintenance"),
+            "http" => bot_bail!(Http {
+                status: 502,
+                message: "bad gateway".to_string(),
+            }),
+            "expr" => bot_bail!(BotError::timeout(250)),
+            _ => bot_bail!("unexpected {kind}"),
+        }
+    }
+
+    fn into_anyhow() -> anyhow::Result<()> {
+        bot_bail!(Conflict, "duplicate name");
+    }
+
+    #[test]
+    fn test_ensure_formats_message() {
+        assert_eq!(check_age(21).ok(), Some(21));
+        let err = check_age(12).err().map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Validation error: age 12 is below 18"));
+    }
+
+    #[test]
+    fn test_ensure_some_unwraps_or_returns_not_found() {
+        assert_eq!(find_session(Some("s1")).ok().as_deref(), Some("s1"));
+        let err = find_session(None).err();
+        assert!(matches!(err, Some(BotError::NotFound { ref entity, .. }) if entity == "Session"));
+    }
+
+    #[test]
+    fn test_structured_variants() {
+        assert!(throttle(1).is_ok());
+        assert!(matches!(
+            throttle(0),
+            Err(BotError::RateLimited {
+                retry_after_secs: Some(30),
+                ..
+            })
+        ));
+        assert!(matches!(
+            fail("http"),
+            Err(BotError::Http { status: 502, .. })
+        ));
+        assert!(matches!(
+            fail("expr"),
+            Err(BotError::Timeout { duration_ms: 250 })
+        ));
+    }
+
+    #[test]
+    fn test_bail_messages() {
+        let message = |kind| fail(kind).err().map(|e| e.to_string());
+        assert_eq!(
+            message("config").as_deref(),
+            Some("Configuration error: missing API key")
+        );
+        assert_eq!(
+            message("unavailable").as_deref(),
+            Some("Service unavailable: maintenance")
+        );
+        assert_eq!(
+            message("database").as_deref(),
+            Some("Database error: pool 3 exhausted")
+        );
+        assert_eq!(message("other").as_deref(), Some("unexpected other"));
+    }
+
+    #[test]
+    fn test_bail_converts_into_caller_error_type() {
+        let err = into_anyhow().err().map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Conflict: duplicate name"));
+    }
+}
@@ -0,0 +1,650 @@
+const MAX_NESTING: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inline {
+    Text(String),
+    Code(String),
+    Strong(Vec<Inline>),
+    Emphasis(Vec<Inline>),
+    Strike(Vec<Inline>),
+    Link { text: Vec<Inline>, url: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ListItem {
+    indent: usize,
+    marker: Option<String>,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Block {
+    Paragraph(String),
+    Heading(usize, String),
+    Quote(String),
+    List(Vec<ListItem>),
+    Code(String),
+    Rule,
+}
+
+/// Markdown with emphasis markers removed, links written as
+/// `text (url)`, and headings, lists, quotes and code blocks kept as
+/// readable text.
+pub(crate) fn markdown_to_plain_text(markdown: &str) -> String {
+    parse_blocks(markdown)
+        .iter()
+        .map(|block| match block {
+            Block::Paragraph(text) | Block::Heading(_, text) => plain_inline(text),
+            Block::Quote(text) => plain_inline(text)
+                .lines()
+                .map(|line| format!("> {line}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::List(items) => items
+                .iter()
+                .map(|item| {
+                    let marker = item.marker.as_deref().unwrap_or("-");
+                    let text = plain_inline(&item.text);
+                    format!("{}{marker} {text}", " ".repeat(item.indent))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Code(code) => code.clone(),
+            Block::Rule => "---".to_string(),
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Markdown rendered to a small HTML subset (`p`, `h1`-`h6`, `ul`, `ol`,
+/// `li`, `blockquote`, `pre`, `code`, `strong`, `em`, `del`, `a`, `br`,
+/// `hr`). All other text is escaped, and links are only emitted for
+/// http(s) and mailto URLs.
+pub(crate) fn markdown_to_html(markdown: &str) -> String {
+    parse_blocks(markdown)
+        .iter()
+        .map(|block| match block {
+            Block::Paragraph(text) => format!("<p>{}</p>", html_inline(text)),
+            Block::Heading(level, text) => format!("<h{level}>{}</h{level}>", html_inline(text)),
+            Block::Quote(text) => format!("<blockquote>{}</blockquote>", html_inline(text)),
+            Block::List(items) => {
+                let tag = if items.first().is_some_and(|item| item.marker.is_some()) {
+                    "ol"
+                } else {
+                    "ul"
+                };
+                let items: String = items
+                    .iter()
+                    .map(|item| format!("<li>{}</li>\n", html_inline(&item.text)))
+                    .collect();
+                format!("<{tag}>\n{items}</{tag}>")
+            }
+            Block::Code(code) => format!("<pre><code>{}</code></pre>", escape_html(code)),
+            Block::Rule => "<hr>".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Plain text escaped for HTML, with line breaks as `<br>`.
+pub(crate) fn plain_text_to_html(text: &str) -> String {
+    escape_html(text).replace('\n', "<br>\n")
+}
+
+/// Tags removed (block-level closing tags and `<br>` become line breaks)
+/// and the common entities decoded.
+pub(crate) fn html_to_plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(open) = rest.find('<') {
+        text.push_str(rest.get(..open).unwrap_or_default());
+        let after = rest.get(open..).unwrap_or_default();
+        let Some(close) = after.find('>') else {
+            text.push_str(after);
+            rest = "";
+            break;
+        };
+        let tag = after
+            .get(1..close)
+            .unwrap_or_default()
+            .trim()
+            .trim_end_matches('/')
+            .to_ascii_lowercase();
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        if matches!(
+            name,
+            "br" | "hr"
+                | "/p"
+                | "/li"
+                | "/div"
+                | "/blockquote"
+                | "/pre"
+                | "/h1"
+                | "/h2"
+                | "/h3"
+                | "/h4"
+                | "/h5"
+                | "/h6"
+        ) {
+            text.push('\n');
+        }
+        rest = after.get(close + 1..).unwrap_or_default();
+    }
+    text.push_str(rest);
+    decode_entities(&text).trim().to_string()
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+fn fence(line: &str) -> Option<&'static str> {
+    ["```", "~~~"]
+        .into_iter()
+        .find(|fence| line.starts_with(fence))
+}
+
+fn is_rule(line: &str) -> bool {
+    let mut marks = line.chars().filter(|c| !c.is_whitespace());
+    let Some(first) = marks.next() else {
+        return false;
+    };
+    matches!(first, '-' | '*' | '_') && marks.clone().count() >= 2 && marks.all(|c| c == first)
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = line.get(level..)?;
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn quote(line: &str) -> Option<&str> {
+    line.strip_prefix('>').map(str::trim)
+}
+
+fn list_item(line: &str) -> Option<ListItem> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    if let Some(text) = ["- ", "* ", "+ "]
+        .into_iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))
+    {
+        return Some(ListItem {
+            indent,
+            marker: None,
+            text: text.trim().to_string(),
+        });
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let rest = trimmed.get(digits..)?;
+    if !(1..=9).contains(&digits) || !(rest.starts_with(". ") || rest.starts_with(") ")) {
+        return None;
+    }
+    Some(ListItem {
+        indent,
+        marker: trimmed.get(..=digits).map(str::to_string),
+        text: rest.get(2..).unwrap_or_default().trim().to_string(),
+    })
+}
+
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty()
+        || fence(trimmed).is_some()
+        || is_rule(trimmed)
+        || heading(trimmed).is_some()
+        || quote(trimmed).is_some()
+        || list_item(line).is_some()
+}
+
+fn parse_blocks(markdown: &str) -> Vec<Block> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while let Some(line) = lines.get(i) {
+        let trimmed = line.trim();
+        i += 1;
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(fence) = fence(trimmed) {
+            let mut code = Vec::new();
+            while let Some(next) = lines.get(i) {
+                i += 1;
+                if next.trim_start().starts_with(fence) {
+                    break;
+                }
+                code.push(*next);
+            }
+            blocks.push(Block::Code(code.join("\n")));
+        } else if is_rule(trimmed) {
+            blocks.push(Block::Rule);
+        } else if let Some((level, text)) = heading(trimmed) {
+            blocks.push(Block::Heading(level, text.to_string()));
+        } else if let Some(text) = quote(trimmed) {
+            let mut quoted = vec![text];
+            while let Some(next) = lines.get(i).and_then(|next| quote(next.trim())) {
+                quoted.push(next);
+                i += 1;
+            }
+            blocks.push(Block::Quote(quoted.join("\n")));
+        } else if let Some(item) = list_item(line) {
+            let ordered = item.marker.is_some();
+            let mut items = vec![item];
+            while let Some(next) = lines
+                .get(i)
+                .and_then(|next| list_item(next))
+                .filter(|next| next.marker.is_some() == ordered)
+            {
+                items.push(next);
+                i += 1;
+            }
+            blocks.push(Block::List(items));
+        } else {
+            let mut paragraph = vec![trimmed];
+            while let Some(next) = lines.get(i).filter(|next| !starts_block(next)) {
+                paragraph.push(next.trim());
+                i += 1;
+            }
+            blocks.push(Block::Paragraph(paragraph.join("\n")));
+        }
+    }
+    blocks
+}
+
+fn plain_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    write_plain(&parse_inline(&chars, 0), &mut out);
+    out
+}
+
+fn html_inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    write_html(&parse_inline(&chars, 0), &mut out);
+    out.replace('\n', "<br>\n")
+}
+
+fn write_plain(nodes: &[Inline], out: &mut String) {
+    for node in nodes {
+        match node {
+            Inline::Text(text) | Inline::Code(text) => out.push_str(text),
+            Inline::Strong(children) | Inline::Emphasis(children) | Inline::Strike(children) => {
+                write_plain(children, out);
+            }
+            Inline::Link { text, url } => {
+                let mut label = String::new();
+                write_plain(text, &mut label);
+                if label.trim().is_empty() || label == *url {
+                    out.push_str(url);
+                } else {
+                    out.push_str(&format!("{label} ({url})"));
+                }
+            }
+        }
+    }
+}
+
+fn write_html(nodes: &[Inline], out: &mut String) {
+    for node in nodes {
+        let (tag, children) = match node {
+            Inline::Text(text) => {
+                out.push_str(&escape_html(text));
+                continue;
+            }
+            Inline::Code(code) => {
+                out.push_str(&format!("<code>{}</code>", escape_html(code)));
+                continue;
+            }
+            Inline::Link { text, url } if is_safe_url(url) => {
+                out.push_str(&format!("<a href=\"{}\">", escape_html(url)));
+                write_html(text, out);
+                out.push_str("</a>");
+                continue;
+            }
+            Inline::Link { text, .. } => {
+                write_html(text, out);
+                continue;
+            }
+            Inline::Strong(children) => ("strong", children),
+            Inline::Emphasis(children) => ("em", children),
+            Inline::Strike(children) => ("del", children),
+        };
+        out.push_str(&format!("<{tag}>"));
+        write_html(children, out);
+        out.push_str(&format!("</{tag}>"));
+    }
+}
+
+fn run_len(chars: &[char], at: usize, c: char) -> usize {
+    chars
+        .get(at..)
+        .map_or(0, |rest| rest.iter().take_while(|&&x| x == c).count())
+}
+
+fn parse_inline(chars: &[char], depth: usize) -> Vec<Inline> {
+    let mut nodes = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+    while let Some(&c) = chars.get(i) {
+        match special(chars, i, depth) {
+            Some((node, next)) => {
+                if !text.is_empty() {
+                    nodes.push(Inline::Text(std::mem::take(&mut text)));
+                }
+                nodes.push(node);
+                i = next;
+            }
+            None => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    if !text.is_empty() {
+        nodes.push(Inline::Text(text));
+    }
+    nodes
+}
+
+fn special(chars: &[char], i: usize, depth: usize) -> Option<(Inline, usize)> {
+    match chars.get(i)? {
+        '\\' => {
+            let escaped = *chars.get(i + 1)?;
+            escaped
+                .is_ascii_punctuation()
+                .then(|| (Inline::Text(escaped.to_string()), i + 2))
+        }
+        '`' => Some(code_span(chars, i)),
+        '<' => autolink(chars, i),
+        '[' if depth < MAX_NESTING => link(chars, i + 1, depth),
+        '!' if depth < MAX_NESTING && chars.get(i + 1) == Some(&'[') => link(chars, i + 2, depth),
+        '*' | '_' | '~' if depth < MAX_NESTING => emphasis(chars, i, depth),
+        _ => None,
+    }
+}
+
+fn code_span(chars: &[char], start: usize) -> (Inline, usize) {
+    let ticks = run_len(chars, start, '`');
+    let body_start = start + ticks;
+    let mut j = body_start;
+    while j < chars.len() {
+        let run = run_len(chars, j, '`');
+        if run == ticks {
+            let code = chars
+                .get(body_start..j)
+                .unwrap_or_default()
+                .iter()
+                .collect();
+            return (Inline::Code(code), j + run);
+        }
+        j += run.max(1);
+    }
+    (Inline::Text("`".repeat(ticks)), body_start)
+}
+
+fn autolink(chars: &[char], start: usize) -> Option<(Inline, usize)> {
+    let rest = chars.get(start + 1..)?;
+    let len = rest.iter().position(|&c| c == '>')?;
+    let url: String = rest.get(..len)?.iter().collect();
+    if !is_safe_url(&url) || url.contains(char::is_whitespace) {
+        return None;
+    }
+    let text = vec![Inline::Text(url.clone())];
+    Some((Inline::Link { text, url }, start + len + 2))
+}
+
+fn link(chars: &[char], text_start: usize, depth: usize) -> Option<(Inline, usize)> {
+    let mut level = 0usize;
+    let mut close = text_start;
+    loop {
+        match chars.get(close)? {
+            ']' if level == 0 => break,
+            ']' => level -= 1,
+            '[' => level += 1,
+            _ => {}
+        }
+        close += 1;
+    }
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let url_start = close + 2;
+    let url_len = chars.get(url_start..)?.iter().position(|&c| c == ')')?;
+    let url: String = chars.get(url_start..url_start + url_len)?.iter().collect();
+    let url = url.trim().to_string();
+    if url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    let text = parse_inline(chars.get(text_start..close)?, depth + 1);
+    Some((Inline::Link { text, url }, url_start + url_len + 1))
+}
+
+fn emphasis(chars: &[char], start: usize, depth: usize) -> Option<(Inline, usize)> {
+    let c = *chars.get(start)?;
+    let width = match (c, run_len(chars, start, c)) {
+        (_, 2..) => 2,
+        ('~', _) => return None,
+        _ => 1,
+    };
+    let after_word = start
+        .checked_sub(1)
+        .and_then(|prev| chars.get(prev))
+        .is_some_and(|prev| prev.is_alphanumeric());
+    if c == '_' && after_word {
+        return None;
+    }
+    let body_start = start + width;
+    if chars
+        .get(body_start)
+        .is_none_or(|next| next.is_whitespace())
+    {
+        return None;
+    }
+    let close = find_closer(chars, body_start, c, width)?;
+    let children = parse_inline(chars.get(body_start..close)?, depth + 1);
+    let node = match (c, width) {
+        ('~', _) => Inline::Strike(children),
+        (_, 2) => Inline::Strong(children),
+        _ => Inline::Emphasis(children),
+    };
+    Some((node, close + width))
+}
+
+/// Position of the delimiter closing a span opened just before `from`:
+/// a run of `c` at least `width` long (exactly one for single
+/// delimiters) that does not follow whitespace. `_` must also end a word.
+fn find_closer(chars: &[char], from: usize, c: char, width: usize) -> Option<usize> {
+    let mut j = from + 1;
+    while j < chars.len() {
+        let run = run_len(chars, j, c);
+        if run == 0 {
+            j += 1;
+            continue;
+        }
+        let fits = if width == 2 { run >= 2 } else { run == 1 };
+        let follows_text = chars.get(j - 1).is_some_and(|prev| !prev.is_whitespace());
+        let ends_word = c != '_'
+            || !chars
+                .get(j + run)
+                .is_some_and(|next| next.is_alphanumeric());
+        if fits && follows_text && ends_word {
+            return Some(j + run - width);
+        }
+        j += run;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../tests/golden/markdown_sample.md");
+
+    #[test]
+    fn test_plain_text_golden() {
+        assert_eq!(
+            markdown_to_plain_text(SAMPLE),
+            include_str!("../tests/golden/markdown_sample.txt").trim_end()
+        );
+    }
+
+    #[test]
+    fn test_html_golden() {
+        assert_eq!(
+            markdown_to_html(SAMPLE),
+            include_str!("../tests/golden/markdown_sample.html").trim_end()
+        );
+    }
+
+    #[test]
+    fn test_inline_edge_cases() {
+        let cases = [
+            ("snake_case_name stays", "snake_case_name stays"),
+            ("2 * 3 * 4", "2 * 3 * 4"),
+            ("**unclosed bold", "**unclosed bold"),
+            ("***both***", "both"),
+            ("\\*literal\\*", "*literal*"),
+            ("[docs](https://example.com)", "docs (https://example.com)"),
+            (
+                "[https://example.com](https://example.com)",
+                "https://example.com",
+            ),
+            ("<https://example.com>", "https://example.com"),
+            ("[broken](no close", "[broken](no close"),
+            ("`a*b*c`", "a*b*c"),
+            ("``unclosed", "``unclosed"),
+        ];
+        for (markdown, plain) in cases {
+            assert_eq!(markdown_to_plain_text(markdown), plain, "{markdown}");
+        }
+    }
+
+    #[test]
+    fn test_html_is_escaped_and_links_filtered() {
+        assert_eq!(
+            markdown_to_html("<script>alert('x')</script> & [x](javascript:alert(1))"),
+            "<p>&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; &amp; x)</p>"
+        );
+        assert_eq!(
+            markdown_to_html("[a\"b](https://e.com/?q=\"x\")"),
+            "<p><a href=\"https://e.com/?q=&quot;x&quot;\">a&quot;b</a></p>"
+        );
+    }
+
+    #[test]
+    fn test_html_to_plain_text() {
+        assert_eq!(
+            html_to_plain_text("<p>Fish &amp; chips</p><p>a<br/>b</p><b>bold"),
+            "Fish & chips\na\nb\nbold"
+        );
+        assert_eq!(html_to_plain_text("1 < 2"), "1 < 2");
+        assert_eq!(plain_text_to_html("a < b\nc"), "a &lt; b<br>\nc");
+    }
+
+    /// Only the tags `markdown_to_html` emits, so any other `<` in its
+    /// output would be unescaped input.
+    fn strip_known_tags(html: &str) -> String {
+        let mut rest = html.to_string();
+        for tag in [
+            "p",
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "ul",
+            "ol",
+            "li",
+            "blockquote",
+            "pre",
+            "code",
+            "strong",
+            "em",
+            "del",
+            "a",
+        ] {
+            rest = rest
+                .replace(&format!("<{tag}>"), "")
+                .replace(&format!("</{tag}>"), "");
+        }
+        let mut out = String::new();
+        for (index, part) in rest
+            .replace("<br>", "")
+            .replace("<hr>", "")
+            .split("<a href=\"")
+            .enumerate()
+        {
+            if index == 0 {
+                out.push_str(part);
+            } else {
+                out.push_str(part.split_once("\">").map_or(part, |(_, text)| text));
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_fuzz_arbitrary_strings() {
+        const ALPHABET: &[char] = &[
+            '*', '_', '~', '`', '[', ']', '(', ')', '<', '>', '#', '-', '+', '!', '\\', '.', '1',
+            ' ', ' ', '\n', '\n', '\t', 'a', 'b', 'h', ':', '/', '"', '&', 'é', '🙂', '\u{0}',
+        ];
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..3000 {
+            let len = next() % 80;
+            let input: String = (0..len)
+                .filter_map(|_| ALPHABET.get(usize::try_from(next() % 32).unwrap_or_default()))
+                .collect();
+            let html = markdown_to_html(&input);
+            assert!(
+                !strip_known_tags(&html).contains('<'),
+                "{input:?} -> {html:?}"
+            );
+            assert!(markdown_to_plain_text(&input).len() <= input.len() * 2);
+            assert!(html_to_plain_text(&html).len() <= html.len());
+        }
+        let deep = "*_".repeat(5000) + &"[".repeat(5000);
+        assert!(!markdown_to_html(&deep).is_empty());
+    }
+}
@@ -1,22 +1,263 @@
+use crate::labels::{self, Labels};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use thiserror::Error;
 
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The kind of a message, sent as a bare number. Values are allocated in
+/// ranges so independent code never picks the same one:
+///
+/// - `0..=99` (`CORE_RANGE`): the constants defined here.
+/// - `100..=999` (`ADAPTER_RANGE`): channel adapters, for channel-specific
+///   events that never leave the adapter's own pipeline.
+/// - `1000..` (`CUSTOM_RANGE`): bots and deployments.
+/// - Negative values are reserved; `UNKNOWN` is `-1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct MessageType(pub i32);
 
 impl MessageType {
-    pub const EXTERNAL: Self = Self(0);
+    pub const EXTERNAL: Self = KnownMessageType::External.message_type();
+
+    pub const USER: Self = KnownMessageType::User.message_type();
+
+    pub const BOT_RESPONSE: Self = KnownMessageType::BotResponse.message_type();
+
+    pub const CONTINUE: Self = KnownMessageType::Continue.message_type();
+
+    pub const SUGGESTION: Self = KnownMessageType::Suggestion.message_type();
+
+    pub const CONTEXT_CHANGE: Self = KnownMessageType::ContextChange.message_type();
+
+    /// A notice from the platform rather than the bot, e.g. "an agent
+    /// joined".
+    pub const SYSTEM: Self = KnownMessageType::System.message_type();
+
+    /// A failure reported to the user.
+    pub const ERROR: Self = KnownMessageType::Error.message_type();
+
+    /// The bot invoking a tool.
+    pub const TOOL_CALL: Self = KnownMessageType::ToolCall.message_type();
+
+    /// What a tool returned.
+    pub const TOOL_RESULT: Self = KnownMessageType::ToolResult.message_type();
+
+    /// A message that is only attachments.
+    pub const ATTACHMENT: Self = KnownMessageType::Attachment.message_type();
+
+    pub const TYPING: Self = KnownMessageType::Typing.message_type();
+
+    pub const CORE_RANGE: std::ops::RangeInclusive<i32> = 0..=99;
+
+    pub const ADAPTER_RANGE: std::ops::RangeInclusive<i32> = 100..=999;
+
+    pub const CUSTOM_RANGE: std::ops::RangeFrom<i32> = 1000..;
+
+    /// What `as_name_lenient` reads a name it does not recognize as. Never
+    /// a known type.
+    pub const UNKNOWN: Self = Self(-1);
+
+    /// Every known type, in wire-value order.
+    pub const ALL: &'static [Self] = &[
+        Self::EXTERNAL,
+        Self::USER,
+        Self::BOT_RESPONSE,
+        Self::CONTINUE,
+        Self::SUGGESTION,
+        Self::CONTEXT_CHANGE,
+        Self::SYSTEM,
+        Self::ERROR,
+        Self::TOOL_CALL,
+        Self::TOOL_RESULT,
+        Self::ATTACHMENT,
+        Self::TYPING,
+    ];
+
+    /// `None` for values this build does not know, which are still carried
+    /// through unchanged.
+    #[must_use]
+    pub fn known(self) -> Option<KnownMessageType> {
+        KnownMessageType::try_from(self.0).ok()
+    }
+
+    #[must_use]
+    pub const fn as_i32(self) -> i32 {
+        self.0
+    }
+
+    #[must_use]
+    pub fn is_known(self) -> bool {
+        self.known().is_some()
+    }
+
+    /// The `SCREAMING_SNAKE_CASE` name, or `"UNKNOWN"`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        self.known().map_or("UNKNOWN", KnownMessageType::name)
+    }
+
+    /// A name for people, in `locale` if it is one of
+    /// `labels::SUPPORTED_LOCALES`, else in English.
+    #[must_use]
+    pub fn label(self, locale: &str) -> &'static str {
+        const UNKNOWN: Labels = ["Unknown", "Desconhecido", "Desconocido"];
+        labels::pick(
+            self.known().map_or(&UNKNOWN, KnownMessageType::labels),
+            locale,
+        )
+    }
+
+    #[must_use]
+    pub fn is_user(self) -> bool {
+        self == Self::USER
+    }
+
+    /// Sent by the bot: a response or suggestions.
+    #[must_use]
+    pub fn is_bot(self) -> bool {
+        matches!(self, Self::BOT_RESPONSE | Self::SUGGESTION)
+    }
 
-    pub const USER: Self = Self(1);
+    /// Steers the conversation or signals activity rather than carrying
+    /// content.
+    #[must_use]
+    pub fn is_control(self) -> bool {
+        matches!(self, Self::CONTINUE | Self::CONTEXT_CHANGE | Self::TYPING)
+    }
+
+    #[must_use]
+    pub fn is_tool_related(self) -> bool {
+        matches!(self, Self::TOOL_CALL | Self::TOOL_RESULT)
+    }
+
+    /// From the platform rather than the bot: a notice or an error.
+    #[must_use]
+    pub fn is_system(self) -> bool {
+        matches!(self, Self::SYSTEM | Self::ERROR)
+    }
+}
+
+/// The message types this build knows, with their wire values. The single
+/// source for `MessageType`'s constants, names and parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum KnownMessageType {
+    External = 0,
+    User = 1,
+    BotResponse = 2,
+    Continue = 3,
+    Suggestion = 4,
+    ContextChange = 5,
+    System = 6,
+    Error = 7,
+    ToolCall = 8,
+    ToolResult = 9,
+    Attachment = 10,
+    Typing = 11,
+}
 
-    pub const BOT_RESPONSE: Self = Self(2);
+impl KnownMessageType {
+    pub const ALL: [Self; 12] = [
+        Self::External,
+        Self::User,
+        Self::BotResponse,
+        Self::Continue,
+        Self::Suggestion,
+        Self::ContextChange,
+        Self::System,
+        Self::Error,
+        Self::ToolCall,
+        Self::ToolResult,
+        Self::Attachment,
+        Self::Typing,
+    ];
 
-    pub const CONTINUE: Self = Self(3);
+    #[must_use]
+    pub const fn message_type(self) -> MessageType {
+        MessageType(self as i32)
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::External => "EXTERNAL",
+            Self::User => "USER",
+            Self::BotResponse => "BOT_RESPONSE",
+            Self::Continue => "CONTINUE",
+            Self::Suggestion => "SUGGESTION",
+            Self::ContextChange => "CONTEXT_CHANGE",
+            Self::System => "SYSTEM",
+            Self::Error => "ERROR",
+            Self::ToolCall => "TOOL_CALL",
+            Self::ToolResult => "TOOL_RESULT",
+            Self::Attachment => "ATTACHMENT",
+            Self::Typing => "TYPING",
+        }
+    }
 
-    pub const SUGGESTION: Self = Self(4);
+    const fn labels(self) -> &'static Labels {
+        match self {
+            Self::External => &["External message", "Mensagem externa", "Mensaje externo"],
+            Self::User => &["User message", "Mensagem do usuário", "Mensaje del usuario"],
+            Self::BotResponse => &["Bot response", "Resposta do bot", "Respuesta del bot"],
+            Self::Continue => &["Continue", "Continuar", "Continuar"],
+            Self::Suggestion => &["Suggestion", "Sugestão", "Sugerencia"],
+            Self::ContextChange => &[
+                "Context change",
+                "Mudança de contexto",
+                "Cambio de contexto",
+            ],
+            Self::System => &["System notice", "Aviso do sistema", "Aviso del sistema"],
+            Self::Error => &["Error", "Erro", "Error"],
+            Self::ToolCall => &[
+                "Tool call",
+                "Chamada de ferramenta",
+                "Llamada a herramienta",
+            ],
+            Self::ToolResult => &[
+                "Tool result",
+                "Resultado de ferramenta",
+                "Resultado de herramienta",
+            ],
+            Self::Attachment => &["Attachment", "Anexo", "Adjunto"],
+            Self::Typing => &["Typing", "Digitando", "Escribiendo"],
+        }
+    }
+}
 
-    pub const CONTEXT_CHANGE: Self = Self(5);
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MessageTypeError {
+    #[error("unknown message type {0}")]
+    UnknownValue(i32),
+    #[error("unknown message type name {0:?}")]
+    UnknownName(String),
+}
+
+/// Strict conversion: values this build does not know are an error.
+impl TryFrom<i32> for KnownMessageType {
+    type Error = MessageTypeError;
+
+    fn try_from(value: i32) -> Result<Self, MessageTypeError> {
+        Self::ALL
+            .into_iter()
+            .find(|known| *known as i32 == value)
+            .ok_or(MessageTypeError::UnknownValue(value))
+    }
+}
+
+impl TryFrom<MessageType> for KnownMessageType {
+    type Error = MessageTypeError;
+
+    fn try_from(value: MessageType) -> Result<Self, MessageTypeError> {
+        Self::try_from(value.0)
+    }
+}
+
+impl From<KnownMessageType> for MessageType {
+    fn from(value: KnownMessageType) -> Self {
+        value.message_type()
+    }
 }
 
 impl From<i32> for MessageType {
@@ -39,16 +280,400 @@ impl Default for MessageType {
 
 impl std::fmt::Display for MessageType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self.0 {
-            0 => "EXTERNAL",
-            1 => "USER",
-            2 => "BOT_RESPONSE",
-            3 => "CONTINUE",
-            4 => "SUGGESTION",
-            5 => "CONTEXT_CHANGE",
-            _ => "UNKNOWN",
-        };
-        write!(f, "{name}")
+        f.write_str(self.name())
+    }
+}
+
+/// Accepts the names `Display` produces, in any letter case. `"UNKNOWN"`
+/// is not a type and is rejected.
+impl FromStr for MessageType {
+    type Err = MessageTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KnownMessageType::ALL
+            .into_iter()
+            .find(|known| known.name().eq_ignore_ascii_case(s.trim()))
+            .map(KnownMessageType::message_type)
+            .ok_or_else(|| MessageTypeError::UnknownName(s.to_string()))
+    }
+}
+
+struct NameVisitor {
+    lenient: bool,
+}
+
+impl serde::de::Visitor<'_> for NameVisitor {
+    type Value = MessageType;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a message type name or number")
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<MessageType, E> {
+        i32::try_from(value)
+            .map(MessageType)
+            .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<MessageType, E> {
+        i32::try_from(value)
+            .map(MessageType)
+            .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<MessageType, E> {
+        match value.parse() {
+            Ok(message_type) => Ok(message_type),
+            Err(_) if self.lenient => Ok(value
+                .trim()
+                .parse()
+                .map_or(MessageType::UNKNOWN, MessageType)),
+            Err(e) => Err(E::custom(e)),
+        }
+    }
+}
+
+/// Serde representation that writes `MessageType` as its name, e.g.
+/// `"BOT_RESPONSE"`, for `#[serde(with = "botlib::message_types::as_name")]`.
+/// Values without a name are written as numbers. Reads names in any letter
+/// case or numbers, and rejects names it does not know.
+pub mod as_name {
+    use super::{MessageType, NameVisitor};
+    use serde::{Deserializer, Serializer};
+
+    /// # Errors
+    /// Returns the serializer's error.
+    pub fn serialize<S: Serializer>(value: &MessageType, serializer: S) -> Result<S::Ok, S::Error> {
+        match value.known() {
+            Some(known) => serializer.serialize_str(known.name()),
+            None => serializer.serialize_i32(value.0),
+        }
+    }
+
+    /// # Errors
+    /// Returns an error naming the value if it is an unknown name, a number
+    /// outside `i32`, or neither a string nor a number.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MessageType, D::Error> {
+        deserializer.deserialize_any(NameVisitor { lenient: false })
+    }
+}
+
+/// Like `as_name`, but reads numeric strings as raw values and any other
+/// unknown name as `MessageType::UNKNOWN` instead of failing.
+pub mod as_name_lenient {
+    use super::{MessageType, NameVisitor};
+    use serde::Deserializer;
+
+    pub use super::as_name::serialize;
+
+    /// # Errors
+    /// Returns an error if the value is a number outside `i32`, or neither
+    /// a string nor a number.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MessageType, D::Error> {
+        deserializer.deserialize_any(NameVisitor { lenient: true })
+    }
+}
+
+/// Serde representation that keeps the plain numeric form but fails on
+/// values this build does not know, for services that want to reject a
+/// mistyped `message_type` at the boundary rather than store it. Use with
+/// `#[serde(with = "botlib::message_types::strict")]`.
+pub mod strict {
+    use super::{KnownMessageType, MessageType};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// # Errors
+    /// Returns the serializer's error.
+    pub fn serialize<S: Serializer>(value: &MessageType, serializer: S) -> Result<S::Ok, S::Error> {
+        value.serialize(serializer)
+    }
+
+    /// # Errors
+    /// Returns an error if the value is not an `i32` or is not a known
+    /// message type.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MessageType, D::Error> {
+        let value = i32::deserialize(deserializer)?;
+        KnownMessageType::try_from(value)
+            .map(KnownMessageType::message_type)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A set of message types, e.g. the kinds a subscriber wants delivered.
+/// Values `0..64` are kept as bits, so checking a core type is one mask;
+/// anything else is kept in an ordered overflow set.
+///
+/// Serialized as an array of names, with values that have no name written
+/// as numbers: `["BOT_RESPONSE", "SUGGESTION", 1001]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageTypeSet {
+    bits: u64,
+    overflow: BTreeSet<i32>,
+}
+
+impl MessageTypeSet {
+    /// The empty set.
+    #[must_use]
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Every type in `MessageType::ALL`.
+    #[must_use]
+    pub fn all() -> Self {
+        Self::from(MessageType::ALL)
+    }
+
+    fn bit(message_type: MessageType) -> Option<u64> {
+        u32::try_from(message_type.0)
+            .ok()
+            .and_then(|shift| 1_u64.checked_shl(shift))
+    }
+
+    /// Returns whether `message_type` was newly added.
+    pub fn insert(&mut self, message_type: MessageType) -> bool {
+        match Self::bit(message_type) {
+            Some(bit) => {
+                let added = self.bits & bit == 0;
+                self.bits |= bit;
+                added
+            }
+            None => self.overflow.insert(message_type.0),
+        }
+    }
+
+    /// Returns whether `message_type` was present.
+    pub fn remove(&mut self, message_type: MessageType) -> bool {
+        match Self::bit(message_type) {
+            Some(bit) => {
+                let present = self.bits & bit != 0;
+                self.bits &= !bit;
+                present
+            }
+            None => self.overflow.remove(&message_type.0),
+        }
+    }
+
+    #[must_use]
+    pub fn contains(&self, message_type: MessageType) -> bool {
+        Self::bit(message_type).map_or_else(
+            || self.overflow.contains(&message_type.0),
+            |bit| self.bits & bit != 0,
+        )
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            bits: self.bits | other.bits,
+            overflow: &self.overflow | &other.overflow,
+        }
+    }
+
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            bits: self.bits & other.bits,
+            overflow: &self.overflow & &other.overflow,
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0 && self.overflow.is_empty()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize + self.overflow.len()
+    }
+
+    /// The members in ascending numeric order.
+    pub fn iter(&self) -> impl Iterator<Item = MessageType> + '_ {
+        let bits = self.bits;
+        let negative = self.overflow.range(..0).copied();
+        let small = (0..64).filter(move |shift| bits & (1_u64 << shift) != 0);
+        let large = self.overflow.range(64..).copied();
+        negative.chain(small).chain(large).map(MessageType)
+    }
+}
+
+impl From<&[MessageType]> for MessageTypeSet {
+    fn from(types: &[MessageType]) -> Self {
+        types.iter().copied().collect()
+    }
+}
+
+impl<const N: usize> From<[MessageType; N]> for MessageTypeSet {
+    fn from(types: [MessageType; N]) -> Self {
+        types.into_iter().collect()
+    }
+}
+
+impl FromIterator<MessageType> for MessageTypeSet {
+    fn from_iter<I: IntoIterator<Item = MessageType>>(iter: I) -> Self {
+        let mut set = Self::none();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<MessageType> for MessageTypeSet {
+    fn extend<I: IntoIterator<Item = MessageType>>(&mut self, iter: I) {
+        for message_type in iter {
+            self.insert(message_type);
+        }
+    }
+}
+
+const KNOWN_COUNT: usize = KnownMessageType::ALL.len();
+
+/// A map keyed by message type, e.g. a dispatch table of handlers. Known
+/// types are stored in a fixed array indexed by value; custom values spill
+/// over into an ordered map. Iteration is in ascending numeric order.
+///
+/// `message_type_map!` declares one from literal entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTypeMap<V> {
+    known: [Option<V>; KNOWN_COUNT],
+    custom: BTreeMap<i32, V>,
+}
+
+impl<V> Default for MessageTypeMap<V> {
+    fn default() -> Self {
+        Self {
+            known: std::array::from_fn(|_| None),
+            custom: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V> MessageTypeMap<V> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Known types are numbered `0..KNOWN_COUNT`, so the value is the slot.
+    fn slot(message_type: MessageType) -> Option<usize> {
+        usize::try_from(message_type.0)
+            .ok()
+            .filter(|&index| index < KNOWN_COUNT)
+    }
+
+    /// Returns the value previously stored for `message_type`.
+    pub fn insert(&mut self, message_type: MessageType, value: V) -> Option<V> {
+        match Self::slot(message_type).and_then(|index| self.known.get_mut(index)) {
+            Some(slot) => slot.replace(value),
+            None => self.custom.insert(message_type.0, value),
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, message_type: MessageType) -> Option<&V> {
+        match Self::slot(message_type) {
+            Some(index) => self.known.get(index).and_then(Option::as_ref),
+            None => self.custom.get(&message_type.0),
+        }
+    }
+
+    pub fn get_mut(&mut self, message_type: MessageType) -> Option<&mut V> {
+        match Self::slot(message_type) {
+            Some(index) => self.known.get_mut(index).and_then(Option::as_mut),
+            None => self.custom.get_mut(&message_type.0),
+        }
+    }
+
+    pub fn remove(&mut self, message_type: MessageType) -> Option<V> {
+        match Self::slot(message_type) {
+            Some(index) => self.known.get_mut(index).and_then(Option::take),
+            None => self.custom.remove(&message_type.0),
+        }
+    }
+
+    #[must_use]
+    pub fn contains_key(&self, message_type: MessageType) -> bool {
+        self.get(message_type).is_some()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.known.iter().flatten().count() + self.custom.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The entries in ascending numeric order of their type.
+    pub fn iter(&self) -> impl Iterator<Item = (MessageType, &V)> + '_ {
+        let negative = self.custom.range(..0);
+        let known = (0_i32..)
+            .zip(&self.known)
+            .filter_map(|(value, slot)| slot.as_ref().map(|v| (value, v)));
+        let large = self.custom.range(0..);
+        negative
+            .map(|(&value, v)| (value, v))
+            .chain(known)
+            .chain(large.map(|(&value, v)| (value, v)))
+            .map(|(value, v)| (MessageType(value), v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = MessageType> + '_ {
+        self.iter().map(|(message_type, _)| message_type)
+    }
+}
+
+impl<V> FromIterator<(MessageType, V)> for MessageTypeMap<V> {
+    fn from_iter<I: IntoIterator<Item = (MessageType, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (message_type, value) in iter {
+            map.insert(message_type, value);
+        }
+        map
+    }
+}
+
+/// Whether no value appears twice, for `message_type_map!`'s compile-time
+/// duplicate check.
+#[doc(hidden)]
+#[must_use]
+pub const fn all_distinct(values: &[i32]) -> bool {
+    const fn contains(values: &[i32], value: i32) -> bool {
+        match values {
+            [] => false,
+            [first, rest @ ..] => *first == value || contains(rest, value),
+        }
+    }
+    match values {
+        [] => true,
+        [first, rest @ ..] => !contains(rest, *first) && all_distinct(rest),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct NamedType(#[serde(with = "as_name")] MessageType);
+
+impl Serialize for MessageTypeSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(NamedType))
+    }
+}
+
+/// Reads an array of names, in any letter case, or numbers. Unknown names
+/// are rejected.
+impl<'de> Deserialize<'de> for MessageTypeSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let types = Vec::<NamedType>::deserialize(deserializer)?;
+        Ok(types.into_iter().map(|named| named.0).collect())
     }
 }
 
@@ -73,4 +698,421 @@ mod tests {
         assert_eq!(MessageType::USER, MessageType(1));
         assert_ne!(MessageType::USER, MessageType::BOT_RESPONSE);
     }
+
+    #[test]
+    fn test_known_types_stay_in_sync() {
+        let table = [
+            (0, "EXTERNAL"),
+            (1, "USER"),
+            (2, "BOT_RESPONSE"),
+            (3, "CONTINUE"),
+            (4, "SUGGESTION"),
+            (5, "CONTEXT_CHANGE"),
+            (6, "SYSTEM"),
+            (7, "ERROR"),
+            (8, "TOOL_CALL"),
+            (9, "TOOL_RESULT"),
+            (10, "ATTACHMENT"),
+            (11, "TYPING"),
+        ];
+        assert_eq!(MessageType::ALL.len(), table.len());
+        assert_eq!(KnownMessageType::ALL.len(), table.len());
+        for (index, (value, name)) in table.into_iter().enumerate() {
+            let message_type = MessageType(value);
+            let known = KnownMessageType::try_from(value).ok();
+            assert_eq!(MessageType::ALL.get(index), Some(&message_type));
+            assert_eq!(KnownMessageType::ALL.get(index).copied(), known);
+            assert_eq!(known.map(KnownMessageType::name), Some(name));
+            assert_eq!(known.map(MessageType::from), Some(message_type));
+            assert_eq!(message_type.known(), known);
+            assert_eq!(message_type.to_string(), name);
+            assert_eq!(name.parse::<MessageType>().ok(), Some(message_type));
+            assert_eq!(
+                name.to_lowercase().parse::<MessageType>().ok(),
+                Some(message_type)
+            );
+            assert_eq!(
+                serde_json::to_string(&message_type).ok(),
+                Some(value.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_values() {
+        let future = MessageType(42);
+        assert_eq!(future.name(), "UNKNOWN");
+        assert_eq!(future.to_string(), "UNKNOWN");
+        assert!(future.known().is_none());
+        assert_eq!(
+            KnownMessageType::try_from(future),
+            Err(MessageTypeError::UnknownValue(42))
+        );
+        assert_eq!(
+            KnownMessageType::try_from(-1).map_err(|e| e.to_string()),
+            Err("unknown message type -1".to_string())
+        );
+        assert_eq!(
+            "UNKNOWN".parse::<MessageType>(),
+            Err(MessageTypeError::UnknownName("UNKNOWN".to_string()))
+        );
+        assert!("".parse::<MessageType>().is_err());
+
+        let back: Option<MessageType> = serde_json::from_str("42").ok();
+        assert_eq!(back, Some(future));
+    }
+
+    #[test]
+    fn test_predicates() {
+        let classify = |t: MessageType| (t.is_user(), t.is_bot(), t.is_control());
+        assert_eq!(classify(MessageType::USER), (true, false, false));
+        assert_eq!(classify(MessageType::BOT_RESPONSE), (false, true, false));
+        assert_eq!(classify(MessageType::SUGGESTION), (false, true, false));
+        assert_eq!(classify(MessageType::CONTINUE), (false, false, true));
+        assert_eq!(classify(MessageType::CONTEXT_CHANGE), (false, false, true));
+        assert_eq!(classify(MessageType::TYPING), (false, false, true));
+        assert_eq!(classify(MessageType::EXTERNAL), (false, false, false));
+        assert_eq!(classify(MessageType(99)), (false, false, false));
+
+        let tool_or_system = |t: MessageType| (t.is_tool_related(), t.is_system());
+        assert_eq!(tool_or_system(MessageType::TOOL_CALL), (true, false));
+        assert_eq!(tool_or_system(MessageType::TOOL_RESULT), (true, false));
+        assert_eq!(tool_or_system(MessageType::SYSTEM), (false, true));
+        assert_eq!(tool_or_system(MessageType::ERROR), (false, true));
+        assert_eq!(tool_or_system(MessageType::ATTACHMENT), (false, false));
+        assert_eq!(tool_or_system(MessageType::BOT_RESPONSE), (false, false));
+    }
+
+    #[test]
+    fn test_known_types_are_in_the_core_range() {
+        assert!(MessageType::ALL
+            .iter()
+            .all(|t| MessageType::CORE_RANGE.contains(&t.0)));
+        assert!(!MessageType::ADAPTER_RANGE.contains(&MessageType::UNKNOWN.0));
+        assert!(MessageType::CUSTOM_RANGE.contains(&1000));
+        assert_eq!(
+            MessageType::CORE_RANGE.end() + 1,
+            *MessageType::ADAPTER_RANGE.start()
+        );
+        assert_eq!(
+            MessageType::ADAPTER_RANGE.end() + 1,
+            MessageType::CUSTOM_RANGE.start
+        );
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Numeric {
+        message_type: MessageType,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Named {
+        #[serde(with = "as_name")]
+        message_type: MessageType,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Lenient {
+        #[serde(with = "as_name_lenient")]
+        message_type: MessageType,
+    }
+
+    #[test]
+    fn test_both_representations_round_trip() {
+        for &message_type in MessageType::ALL.iter().chain([&MessageType(42)]) {
+            let numeric = serde_json::to_value(Numeric { message_type }).unwrap_or_default();
+            assert_eq!(
+                numeric.get("message_type"),
+                Some(&serde_json::json!(message_type.0))
+            );
+            let back: Option<Numeric> = serde_json::from_value(numeric).ok();
+            assert_eq!(back, Some(Numeric { message_type }));
+
+            let named = serde_json::to_value(Named { message_type }).unwrap_or_default();
+            let expected = message_type.known().map_or_else(
+                || serde_json::json!(message_type.0),
+                |known| serde_json::json!(known.name()),
+            );
+            assert_eq!(named.get("message_type"), Some(&expected));
+            let back: Option<Named> = serde_json::from_value(named).ok();
+            assert_eq!(back, Some(Named { message_type }));
+        }
+    }
+
+    #[test]
+    fn test_name_mode_reads_mixed_input() {
+        let input = r#"[
+            {"message_type": "USER"},
+            {"message_type": 2},
+            {"message_type": "context_change"},
+            {"message_type": 42}
+        ]"#;
+        let parsed: Vec<Named> = serde_json::from_str(input).unwrap_or_default();
+        assert_eq!(
+            parsed.iter().map(|n| n.message_type).collect::<Vec<_>>(),
+            [
+                MessageType::USER,
+                MessageType::BOT_RESPONSE,
+                MessageType::CONTEXT_CHANGE,
+                MessageType(42)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_names_strict_and_lenient() {
+        let strict = |json: &str| {
+            serde_json::from_str::<Named>(json)
+                .err()
+                .map(|e| e.to_string())
+        };
+        assert!(strict(r#"{"message_type": "REACTION"}"#)
+            .is_some_and(|e| e.starts_with(r#"unknown message type name "REACTION""#)));
+        assert!(strict(r#"{"message_type": "7"}"#).is_some());
+        assert!(strict(r#"{"message_type": 3000000000}"#).is_some());
+        assert!(strict(r#"{"message_type": true}"#).is_some());
+
+        let lenient = |json: &str| {
+            serde_json::from_str::<Lenient>(json)
+                .ok()
+                .map(|l| l.message_type)
+        };
+        assert_eq!(
+            lenient(r#"{"message_type": "REACTION"}"#),
+            Some(MessageType::UNKNOWN)
+        );
+        assert_eq!(lenient(r#"{"message_type": "7"}"#), Some(MessageType(7)));
+        assert_eq!(
+            lenient(r#"{"message_type": "suggestion"}"#),
+            Some(MessageType::SUGGESTION)
+        );
+        assert_eq!(lenient(r#"{"message_type": 3000000000}"#), None);
+        assert_eq!(MessageType::UNKNOWN.known(), None);
+        assert_eq!(
+            serde_json::to_value(Lenient {
+                message_type: MessageType::BOT_RESPONSE
+            })
+            .ok(),
+            Some(serde_json::json!({"message_type": "BOT_RESPONSE"}))
+        );
+    }
+
+    #[test]
+    fn test_type_set_algebra() {
+        let bot = MessageTypeSet::from([MessageType::BOT_RESPONSE, MessageType::SUGGESTION]);
+        let mut wanted = MessageTypeSet::from(
+            &[
+                MessageType::SUGGESTION,
+                MessageType::CONTINUE,
+                MessageType(1001),
+            ][..],
+        );
+        assert!(wanted.contains(MessageType::CONTINUE));
+        assert!(!wanted.contains(MessageType::BOT_RESPONSE));
+
+        assert_eq!(
+            bot.intersection(&wanted),
+            MessageTypeSet::from([MessageType::SUGGESTION])
+        );
+        assert_eq!(bot.union(&wanted).len(), 4);
+
+        assert!(wanted.remove(MessageType::CONTINUE));
+        assert!(!wanted.remove(MessageType::CONTINUE));
+        assert!(!wanted.insert(MessageType::SUGGESTION));
+        assert!(wanted.insert(MessageType::TYPING));
+        assert_eq!(
+            wanted.iter().collect::<Vec<_>>(),
+            [
+                MessageType::SUGGESTION,
+                MessageType::TYPING,
+                MessageType(1001)
+            ]
+        );
+
+        assert!(MessageTypeSet::none().is_empty());
+        assert_eq!(MessageTypeSet::all().len(), MessageType::ALL.len());
+        assert!(MessageType::ALL
+            .iter()
+            .all(|&t| MessageTypeSet::all().contains(t)));
+        assert!(!MessageTypeSet::all().contains(MessageType::UNKNOWN));
+    }
+
+    #[test]
+    fn test_type_set_values_outside_the_bitset() {
+        let edges = [
+            MessageType(i32::MIN),
+            MessageType::UNKNOWN,
+            MessageType(0),
+            MessageType(63),
+            MessageType(64),
+            MessageType(i32::MAX),
+        ];
+        let mut set = MessageTypeSet::from(edges);
+        assert_eq!(set.len(), edges.len());
+        assert_eq!(set.iter().collect::<Vec<_>>(), edges);
+        assert!(!set.contains(MessageType(65)));
+        assert!(!set.contains(MessageType(-2)));
+
+        for message_type in edges {
+            assert!(set.remove(message_type), "{message_type:?}");
+        }
+        assert!(set.is_empty());
+        assert_eq!(set, MessageTypeSet::none());
+    }
+
+    #[test]
+    fn test_type_set_serde() {
+        let set = MessageTypeSet::from([
+            MessageType::SUGGESTION,
+            MessageType::BOT_RESPONSE,
+            MessageType(1001),
+        ]);
+        assert_eq!(
+            serde_json::to_string(&set).ok().as_deref(),
+            Some(r#"["BOT_RESPONSE","SUGGESTION",1001]"#)
+        );
+
+        let named: Option<MessageTypeSet> =
+            serde_json::from_str(r#"["bot_response", "SUGGESTION", 1001]"#).ok();
+        let numeric: Option<MessageTypeSet> = serde_json::from_str("[2, 4, 1001, 4]").ok();
+        assert_eq!(named.as_ref(), Some(&set));
+        assert_eq!(numeric, named);
+
+        assert!(serde_json::from_str::<MessageTypeSet>(r#"["REACTION"]"#).is_err());
+        assert!(serde_json::from_str::<MessageTypeSet>(r#""USER""#).is_err());
+        assert_eq!(
+            serde_json::from_str::<MessageTypeSet>("[]").ok(),
+            Some(MessageTypeSet::none())
+        );
+    }
+
+    #[test]
+    fn test_strict_serde_rejects_unknown_values() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Strict {
+            #[serde(with = "strict")]
+            message_type: MessageType,
+        }
+
+        let strict = |value: &str| {
+            serde_json::from_str::<Strict>(&format!(r#"{{"message_type": {value}}}"#))
+                .map(|s| s.message_type)
+                .map_err(|e| e.to_string())
+        };
+        let lenient = |value: &str| {
+            serde_json::from_str::<Numeric>(&format!(r#"{{"message_type": {value}}}"#))
+                .ok()
+                .map(|n| n.message_type)
+        };
+
+        for (value, expected) in [("0", MessageType::EXTERNAL), ("11", MessageType::TYPING)] {
+            assert_eq!(strict(value).ok(), Some(expected));
+            assert_eq!(lenient(value), Some(expected));
+        }
+        for value in ["-5", "-1", "12", "9999"] {
+            assert!(strict(value).is_err_and(|e| e.starts_with("unknown message type")));
+            assert!(lenient(value).is_some_and(|t| !t.is_known()));
+        }
+        assert!(strict("3000000000").is_err());
+        assert!(strict(r#""USER""#).is_err());
+
+        assert_eq!(
+            serde_json::to_string(&Strict {
+                message_type: MessageType::SYSTEM
+            })
+            .ok()
+            .as_deref(),
+            Some(r#"{"message_type":6}"#)
+        );
+        assert!(MessageType::ALL.iter().all(|t| t.is_known()));
+        assert!(!MessageType::UNKNOWN.is_known());
+    }
+
+    #[test]
+    fn test_type_map_known_and_custom_values() {
+        let mut map = MessageTypeMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.insert(MessageType::USER, "user"), None);
+        assert_eq!(map.insert(MessageType(1001), "custom"), None);
+        assert_eq!(map.insert(MessageType::UNKNOWN, "unknown"), None);
+        assert_eq!(map.insert(MessageType(12), "first past known"), None);
+        assert_eq!(map.insert(MessageType::USER, "user v2"), Some("user"));
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get(MessageType::USER), Some(&"user v2"));
+        assert_eq!(map.get(MessageType(1001)), Some(&"custom"));
+        assert_eq!(map.get(MessageType(12)), Some(&"first past known"));
+        assert_eq!(map.get(MessageType::TYPING), None);
+        assert!(map.contains_key(MessageType::UNKNOWN));
+
+        if let Some(value) = map.get_mut(MessageType(1001)) {
+            *value = "custom v2";
+        }
+        assert_eq!(map.remove(MessageType(1001)), Some("custom v2"));
+        assert_eq!(map.remove(MessageType(1001)), None);
+        assert_eq!(map.remove(MessageType::USER), Some("user v2"));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_type_map_iterates_in_numeric_order() {
+        let entries = [
+            (MessageType(5000), 'e'),
+            (MessageType::TYPING, 'd'),
+            (MessageType(-7), 'a'),
+            (MessageType::EXTERNAL, 'b'),
+            (MessageType(99), 'f'),
+            (MessageType::SUGGESTION, 'c'),
+        ];
+        let map: MessageTypeMap<char> = entries.into_iter().collect();
+        let mut expected = entries.to_vec();
+        expected.sort();
+        assert_eq!(
+            map.iter().map(|(t, v)| (t, *v)).collect::<Vec<_>>(),
+            expected
+        );
+
+        let reversed: MessageTypeMap<char> = entries.into_iter().rev().collect();
+        assert_eq!(reversed, map);
+        assert_eq!(
+            reversed.keys().collect::<Vec<_>>(),
+            map.keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_ordering_and_hashing() {
+        let mut types = vec![MessageType(1001), MessageType::USER, MessageType::UNKNOWN];
+        types.sort();
+        assert_eq!(
+            types,
+            [MessageType::UNKNOWN, MessageType::USER, MessageType(1001)]
+        );
+        let handlers =
+            std::collections::HashMap::from([(MessageType::USER, 1), (MessageType(1001), 2)]);
+        assert_eq!(handlers.get(&MessageType(1)), Some(&1));
+        assert_eq!(MessageType::TOOL_CALL.as_i32(), 8);
+
+        assert!(all_distinct(&[]));
+        assert!(all_distinct(&[1, 2, 1001]));
+        assert!(!all_distinct(&[1, 2, 1]));
+    }
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(MessageType::BOT_RESPONSE.label("pt-BR"), "Resposta do bot");
+        assert_eq!(MessageType::BOT_RESPONSE.label("es"), "Respuesta del bot");
+        assert_eq!(MessageType::BOT_RESPONSE.label("ja"), "Bot response");
+        assert_eq!(MessageType(1001).label("pt-BR"), "Desconhecido");
+        assert_eq!(MessageType::BOT_RESPONSE.to_string(), "BOT_RESPONSE");
+
+        for message_type in MessageType::ALL {
+            for locale in labels::supported_locales() {
+                assert!(
+                    !message_type.label(locale).trim().is_empty(),
+                    "{message_type} {locale}"
+                );
+            }
+        }
+    }
 }
@@ -1,20 +1,41 @@
-
-use crate::message_types::MessageType;
+use crate::context::ContextWindow;
+use crate::error::{BotError, BotResult, FieldError, ValidationErrors};
+use crate::limits::{
+    check_array_length_limit, check_string_length_limit, LimitExceeded, SystemLimits,
+    MAX_SESSION_METADATA_ENTRIES, MAX_SESSION_METADATA_KEY_LENGTH,
+    MAX_SESSION_METADATA_VALUE_LENGTH, MAX_SESSION_TAGS, MAX_SESSION_TAG_LENGTH,
+};
+use crate::markup;
+use crate::message_types::{MessageType, MessageTypeSet};
+use crate::sanitize::sanitize_content;
+use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ApiResponse<T> {
     pub success: bool,
+    /// The payload; present on success.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
+    /// Human-readable failure description; present on error.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    /// Machine-readable error code such as `not_found`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// The error's structured fields, such as `retry_after_secs` or
+    /// `entity`, so `into_result` rebuilds the same `BotError`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Map<String, Value>>,
 }
 
 impl<T> ApiResponse<T> {
@@ -26,6 +47,8 @@ impl<T> ApiResponse<T> {
             error: None,
             message: None,
             code: None,
+            warnings: Vec::new(),
+            details: None,
         }
     }
 
@@ -37,6 +60,8 @@ impl<T> ApiResponse<T> {
             error: None,
             message: Some(message.into()),
             code: None,
+            warnings: Vec::new(),
+            details: None,
         }
     }
 
@@ -48,6 +73,8 @@ impl<T> ApiResponse<T> {
             error: Some(message.into()),
             message: None,
             code: None,
+            warnings: Vec::new(),
+            details: None,
         }
     }
 
@@ -59,9 +86,17 @@ impl<T> ApiResponse<T> {
             error: Some(message.into()),
             message: None,
             code: Some(code.into()),
+            warnings: Vec::new(),
+            details: None,
         }
     }
 
+    #[must_use]
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings.push(warning.into());
+        self
+    }
+
     #[must_use]
     pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> ApiResponse<U> {
         ApiResponse {
@@ -70,9 +105,79 @@ impl<T> ApiResponse<T> {
             error: self.error,
             message: self.message,
             code: self.code,
+            warnings: self.warnings,
+            details: self.details,
+        }
+    }
+
+    /// Like `map`, but a failing `f` turns the response into an error
+    /// response. Warnings are kept either way.
+    #[must_use]
+    pub fn try_map<U, E, F>(self, f: F) -> ApiResponse<U>
+    where
+        F: FnOnce(T) -> Result<U, E>,
+        BotError: From<E>,
+    {
+        match self.data.map(f).transpose() {
+            Ok(data) => ApiResponse {
+                success: self.success,
+                data,
+                error: self.error,
+                message: self.message,
+                code: self.code,
+                warnings: self.warnings,
+                details: self.details,
+            },
+            Err(err) => ApiResponse {
+                warnings: self.warnings,
+                ..ApiResponse::from(BotError::from(err))
+            },
+        }
+    }
+
+    /// Chains a call that returns its own envelope. `f` only runs for a
+    /// successful response with data; warnings from both are kept.
+    #[must_use]
+    pub fn and_then<U, F: FnOnce(T) -> ApiResponse<U>>(self, f: F) -> ApiResponse<U> {
+        match self.data {
+            Some(data) if self.success => {
+                let mut next = f(data);
+                let mut warnings = self.warnings;
+                warnings.append(&mut next.warnings);
+                ApiResponse { warnings, ..next }
+            }
+            _ => ApiResponse {
+                success: self.success,
+                data: None,
+                error: self.error,
+                message: self.message,
+                code: self.code,
+                warnings: self.warnings,
+                details: self.details,
+            },
         }
     }
 
+    /// # Errors
+    /// Returns the `BotError` described by `error`/`message`, `code` and
+    /// `details` for a failed response, and `BotError::Internal` for a successful response
+    /// that carries no data.
+    pub fn into_result(self) -> BotResult<T> {
+        if !self.success {
+            let message = self
+                .error
+                .or(self.message)
+                .unwrap_or_else(|| "request failed".to_string());
+            let error = match self.code.as_deref() {
+                Some(code) => BotError::from_envelope(code, message, self.details.as_ref()),
+                None => BotError::Other(message),
+            };
+            return Err(error);
+        }
+        self.data
+            .ok_or_else(|| BotError::internal("successful response carried no data"))
+    }
+
     #[must_use]
     pub const fn is_success(&self) -> bool {
         self.success
@@ -84,13 +189,135 @@ impl<T> ApiResponse<T> {
     }
 }
 
+impl<T> From<BotError> for ApiResponse<T> {
+    fn from(err: BotError) -> Self {
+        let (message, details) = err.envelope_parts();
+        Self {
+            details,
+            ..Self::error_with_code(message, err.error_code())
+        }
+    }
+}
+
+impl<T> From<BotResult<T>> for ApiResponse<T> {
+    fn from(result: BotResult<T>) -> Self {
+        result.map_or_else(Self::from, Self::success)
+    }
+}
+
 impl<T: Default> Default for ApiResponse<T> {
     fn default() -> Self {
         Self::success(T::default())
     }
 }
 
+/// Opaque keyset cursor: the `(created_at, id)` of the last row of a page,
+/// encoded as URL-safe base64 JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl PageCursor {
+    #[must_use]
+    pub const fn new(created_at: DateTime<Utc>, id: Uuid) -> Self {
+        Self { created_at, id }
+    }
+
+    #[must_use]
+    pub fn encode(&self) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// # Errors
+    /// Returns `BotError::Validation` if `cursor` was not produced by
+    /// `encode`.
+    pub fn decode(cursor: &str) -> BotResult<Self> {
+        let bytes = BASE64_URL_SAFE_NO_PAD
+            .decode(cursor.trim())
+            .map_err(|e| BotError::validation(format!("invalid cursor: {e}")))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| BotError::validation(format!("invalid cursor: {e}")))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PaginatedResponse<T> {
+    pub items: Vec<T>,
+    /// Total matching items, when the backend can count them cheaply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    /// Opaque cursor for the next page; absent on the last page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_cursor: Option<String>,
+    pub limit: u32,
+}
+
+impl<T> PaginatedResponse<T> {
+    #[must_use]
+    pub const fn from_items(items: Vec<T>, limit: u32) -> Self {
+        Self {
+            items,
+            total: None,
+            next_cursor: None,
+            prev_cursor: None,
+            limit,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    #[must_use]
+    pub fn with_next_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.next_cursor = Some(cursor.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_prev_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.prev_cursor = Some(cursor.into());
+        self
+    }
+
+    #[must_use]
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> PaginatedResponse<U> {
+        PaginatedResponse {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            next_cursor: self.next_cursor,
+            prev_cursor: self.prev_cursor,
+            limit: self.limit,
+        }
+    }
+
+    #[must_use]
+    pub const fn has_more(&self) -> bool {
+        self.next_cursor.is_some()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<T> From<PaginatedResponse<T>> for ApiResponse<PaginatedResponse<T>> {
+    fn from(page: PaginatedResponse<T>) -> Self {
+        Self::success(page)
+    }
+}
+
+/// A conversation between one user and one bot.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Session {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -100,6 +327,28 @@ pub struct Session {
     pub updated_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<DateTime<Utc>>,
+    /// Everyone in a group chat. Empty for one-to-one sessions, where
+    /// `user_id` is the only participant.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub participants: Vec<Participant>,
+    /// Sessions stored before states existed read as `Active`.
+    #[serde(default)]
+    pub state: SessionState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_changed_at: Option<DateTime<Utc>>,
+    /// Every state change, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<StateTransition>,
+    /// Small string values such as the user's locale or the campaign the
+    /// session came from. Capped by `validate`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Fields added by newer versions of the protocol, kept so they survive
+    /// a round trip through services built against this one.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 impl Session {
@@ -114,6 +363,13 @@ impl Session {
             created_at: now,
             updated_at: now,
             expires_at: None,
+            participants: Vec::new(),
+            state: SessionState::Active,
+            state_changed_at: Some(now),
+            history: Vec::new(),
+            metadata: HashMap::new(),
+            tags: Vec::new(),
+            extra: HashMap::new(),
         }
     }
 
@@ -123,14 +379,206 @@ impl Session {
         self
     }
 
+    #[must_use]
+    pub fn with_participant(mut self, participant: Participant) -> Self {
+        self.participants.push(participant);
+        self
+    }
+
+    #[must_use]
+    pub fn with_metadata<I, K, V>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.metadata
+            .extend(entries.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    #[must_use]
+    pub fn get_meta(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
+    pub fn set_meta(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.metadata.insert(key.into(), value.into());
+    }
+
+    /// Adds `tag`, trimmed, unless it is blank or already present in any
+    /// letter case. Returns whether it was added.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim();
+        if tag.is_empty() || self.has_tag(tag) {
+            return false;
+        }
+        self.tags.push(tag.to_string());
+        true
+    }
+
+    /// Case-insensitive.
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim().to_lowercase();
+        self.tags.iter().any(|t| t.to_lowercase() == tag)
+    }
+
+    /// More than one participant other than bots.
+    #[must_use]
+    pub fn is_group(&self) -> bool {
+        self.participants
+            .iter()
+            .filter(|p| p.role != ParticipantRole::Bot)
+            .count()
+            > 1
+    }
+
+    #[must_use]
+    pub fn participant(&self, user_id: &str) -> Option<&Participant> {
+        self.participants.iter().find(|p| p.user_id == user_id)
+    }
+
     #[must_use]
     pub fn is_expired(&self) -> bool {
         self.expires_at.is_some_and(|exp| Utc::now() > exp)
     }
 
+    /// In the `Active` or `Handover` state and not expired.
     #[must_use]
     pub fn is_active(&self) -> bool {
-        !self.is_expired()
+        matches!(self.state, SessionState::Active | SessionState::Handover) && !self.is_expired()
+    }
+
+    /// Ends the session from any open state.
+    ///
+    /// # Errors
+    /// Returns `BotError::Conflict` if the session is already closed or
+    /// archived.
+    pub fn close(&mut self, reason: CloseReason) -> BotResult<()> {
+        let allowed = self.state.is_open();
+        self.transition("close", SessionState::Closed { reason }, allowed)
+    }
+
+    /// Makes an idle, handed-over or closed session active again.
+    ///
+    /// # Errors
+    /// Returns `BotError::Conflict` if the session is already active or is
+    /// archived.
+    pub fn reopen(&mut self) -> BotResult<()> {
+        let allowed = matches!(
+            self.state,
+            SessionState::Idle | SessionState::Handover | SessionState::Closed { .. }
+        );
+        self.transition("reopen", SessionState::Active, allowed)
+    }
+
+    /// # Errors
+    /// Returns `BotError::Conflict` unless the session is active.
+    pub fn mark_idle(&mut self) -> BotResult<()> {
+        let allowed = self.state == SessionState::Active;
+        self.transition("mark idle", SessionState::Idle, allowed)
+    }
+
+    /// Hands the session over to a human agent.
+    ///
+    /// # Errors
+    /// Returns `BotError::Conflict` unless the session is active or idle.
+    pub fn escalate(&mut self) -> BotResult<()> {
+        let allowed = matches!(self.state, SessionState::Active | SessionState::Idle);
+        self.transition("escalate", SessionState::Handover, allowed)
+    }
+
+    /// # Errors
+    /// Returns `BotError::Conflict` unless the session is closed.
+    pub fn archive(&mut self) -> BotResult<()> {
+        let allowed = matches!(self.state, SessionState::Closed { .. });
+        self.transition("archive", SessionState::Archived, allowed)
+    }
+
+    fn transition(&mut self, action: &str, to: SessionState, allowed: bool) -> BotResult<()> {
+        if !allowed {
+            return Err(BotError::conflict(format!(
+                "cannot {action} session {}: it is {}",
+                self.id,
+                self.state.as_str()
+            )));
+        }
+        let now = Utc::now();
+        self.history.push(StateTransition {
+            from: self.state,
+            to,
+            at: now,
+        });
+        self.state = to;
+        self.state_changed_at = Some(now);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Ids are required, `expires_at` must come after `created_at`, and
+    /// metadata and tags must stay within the `MAX_SESSION_*` limits.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` listing every invalid field.
+    pub fn validate(&self) -> BotResult<()> {
+        let mut errors = ValidationErrors::new();
+        self.check_metadata_and_tags(&mut errors);
+        errors
+            .check(
+                !self.user_id.is_nil(),
+                "user_id",
+                "required",
+                "user_id is required",
+            )
+            .check(
+                !self.bot_id.is_nil(),
+                "bot_id",
+                "required",
+                "bot_id is required",
+            )
+            .check(
+                self.expires_at.is_none_or(|exp| exp > self.created_at),
+                "expires_at",
+                "invalid",
+                "expires_at must be after created_at",
+            );
+        errors.into_result()
+    }
+
+    fn check_metadata_and_tags(&self, errors: &mut ValidationErrors) {
+        let mut limit = |field: &str, result: Result<(), LimitExceeded>| {
+            if let Err(exceeded) = result {
+                errors.push(exceeded.to_field_error(field));
+            }
+        };
+        limit(
+            "metadata",
+            check_array_length_limit(self.metadata.len(), MAX_SESSION_METADATA_ENTRIES),
+        );
+        let mut entries: Vec<_> = self.metadata.iter().collect();
+        entries.sort_unstable();
+        for (key, value) in entries {
+            let field = format!("metadata.{key}");
+            limit(
+                &field,
+                check_string_length_limit(key.chars().count(), MAX_SESSION_METADATA_KEY_LENGTH),
+            );
+            limit(
+                &field,
+                check_string_length_limit(value.chars().count(), MAX_SESSION_METADATA_VALUE_LENGTH),
+            );
+        }
+        limit(
+            "tags",
+            check_array_length_limit(self.tags.len(), MAX_SESSION_TAGS),
+        );
+        for (index, tag) in self.tags.iter().enumerate() {
+            limit(
+                &format!("tags[{index}]"),
+                check_string_length_limit(tag.chars().count(), MAX_SESSION_TAG_LENGTH),
+            );
+        }
     }
 
     #[must_use]
@@ -139,375 +587,2447 @@ impl Session {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UserMessage {
-    pub bot_id: String,
+/// Why a session was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    Resolved,
+    ClosedByUser,
+    ClosedByAgent,
+    Timeout,
+}
+
+/// Where a session is in its lifecycle, serialized as
+/// `{"status": "closed", "reason": "timeout"}` and so on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SessionState {
+    #[default]
+    Active,
+    /// No recent activity; the next message reopens it.
+    Idle,
+    /// Handed over to a human agent.
+    Handover,
+    Closed {
+        reason: CloseReason,
+    },
+    Archived,
+}
+
+impl SessionState {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Idle => "idle",
+            Self::Handover => "handover",
+            Self::Closed { .. } => "closed",
+            Self::Archived => "archived",
+        }
+    }
+
+    /// Not yet closed or archived.
+    #[must_use]
+    pub const fn is_open(&self) -> bool {
+        matches!(self, Self::Active | Self::Idle | Self::Handover)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StateTransition {
+    pub from: SessionState,
+    pub to: SessionState,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ParticipantRole {
+    #[default]
+    Member,
+    Admin,
+    Bot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Participant {
     pub user_id: String,
-    pub session_id: String,
-    pub channel: String,
-    pub content: String,
-    pub message_type: MessageType,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub media_url: Option<String>,
-    pub timestamp: DateTime<Utc>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub context_name: Option<String>,
+    pub display_name: String,
+    #[serde(default)]
+    pub role: ParticipantRole,
 }
 
-impl UserMessage {
+impl Participant {
     #[must_use]
-    pub fn text(
-        bot_id: impl Into<String>,
-        user_id: impl Into<String>,
-        session_id: impl Into<String>,
-        channel: impl Into<String>,
-        content: impl Into<String>,
-    ) -> Self {
+    pub fn new(user_id: impl Into<String>, display_name: impl Into<String>) -> Self {
         Self {
-            bot_id: bot_id.into(),
             user_id: user_id.into(),
-            session_id: session_id.into(),
-            channel: channel.into(),
-            content: content.into(),
-            message_type: MessageType::USER,
-            media_url: None,
-            timestamp: Utc::now(),
-            context_name: None,
+            display_name: display_name.into(),
+            role: ParticipantRole::default(),
         }
     }
 
     #[must_use]
-    pub fn with_media(mut self, url: impl Into<String>) -> Self {
-        self.media_url = Some(url.into());
+    pub const fn with_role(mut self, role: ParticipantRole) -> Self {
+        self.role = role;
         self
     }
+}
+
+/// An @-mention of `user_id` covering `length` bytes of the content,
+/// starting `offset` bytes in. Offsets are UTF-8 byte offsets, as in
+/// `str::get`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Mention {
+    pub user_id: String,
+    pub offset: usize,
+    pub length: usize,
+}
 
+impl Mention {
     #[must_use]
-    pub fn with_context(mut self, context: impl Into<String>) -> Self {
-        self.context_name = Some(context.into());
-        self
+    pub fn new(user_id: impl Into<String>, offset: usize, length: usize) -> Self {
+        Self {
+            user_id: user_id.into(),
+            offset,
+            length,
+        }
     }
 
+    /// The mentioned text, or `None` when the span is out of range or
+    /// splits a character.
     #[must_use]
-    pub const fn has_media(&self) -> bool {
-        self.media_url.is_some()
+    pub fn span<'a>(&self, content: &'a str) -> Option<&'a str> {
+        content.get(self.offset..self.offset.checked_add(self.length)?)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Suggestion {
-    pub text: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub context: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub action: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub icon: Option<String>,
+fn check_mentions(content: &str, mentions: &[Mention], errors: &mut ValidationErrors) {
+    for (index, mention) in mentions.iter().enumerate() {
+        let field = format!("mentions[{index}]");
+        errors.require(&format!("{field}.user_id"), &mention.user_id);
+        let end = mention.offset.saturating_add(mention.length);
+        if end > content.len() {
+            errors.push(
+                FieldError::new(
+                    &field,
+                    "out_of_range",
+                    format!(
+                        "mention ends at byte {end} but content is {} bytes",
+                        content.len()
+                    ),
+                )
+                .with_rejected_value(end),
+            );
+        } else {
+            errors.check(
+                mention.length > 0 && mention.span(content).is_some(),
+                &field,
+                "invalid_boundary",
+                "mention must cover at least one whole character",
+            );
+        }
+    }
 }
 
-impl Suggestion {
+fn mentioned_users(mentions: &[Mention]) -> Vec<&str> {
+    let mut users: Vec<&str> = Vec::new();
+    for mention in mentions {
+        if !users.contains(&mention.user_id.as_str()) {
+            users.push(&mention.user_id);
+        }
+    }
+    users
+}
+
+/// Replaces each mention span with `@` and the participant's display name.
+/// Mentions of unknown users, invalid spans and spans overlapping an
+/// earlier one are left as written.
+fn render_mentions(content: &str, mentions: &[Mention], participants: &[Participant]) -> String {
+    let mut sorted: Vec<&Mention> = mentions.iter().collect();
+    sorted.sort_by_key(|m| m.offset);
+    let mut rendered = String::with_capacity(content.len());
+    let mut cursor = 0;
+    for mention in sorted {
+        let name = participants
+            .iter()
+            .find(|p| p.user_id == mention.user_id)
+            .map(|p| p.display_name.as_str());
+        let (Some(name), Some(before), Some(_)) = (
+            name,
+            content.get(cursor..mention.offset),
+            mention.span(content),
+        ) else {
+            continue;
+        };
+        rendered.push_str(before);
+        rendered.push('@');
+        rendered.push_str(name);
+        cursor = mention.offset + mention.length;
+    }
+    rendered.push_str(content.get(cursor..).unwrap_or_default());
+    rendered
+}
+
+/// Under `limits.strict_message_types`, `message_type` must be one this
+/// build knows.
+fn check_message_type(
+    message_type: MessageType,
+    limits: &SystemLimits,
+    errors: &mut ValidationErrors,
+) {
+    if limits.strict_message_types && !message_type.is_known() {
+        errors.push(
+            FieldError::new(
+                "message_type",
+                "unknown",
+                format!("unknown message type {}", message_type.0),
+            )
+            .with_rejected_value(message_type.0),
+        );
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    let Some(rest) = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    !host.is_empty() && !url.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+impl Location {
     #[must_use]
-    pub fn new(text: impl Into<String>) -> Self {
+    pub const fn new(latitude: f64, longitude: f64) -> Self {
         Self {
-            text: text.into(),
-            context: None,
-            action: None,
-            icon: None,
+            latitude,
+            longitude,
+            name: None,
+            address: None,
         }
     }
 
     #[must_use]
-    pub fn with_context(mut self, context: impl Into<String>) -> Self {
-        self.context = Some(context.into());
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
         self
     }
 
     #[must_use]
-    pub fn with_action(mut self, action: impl Into<String>) -> Self {
-        self.action = Some(action.into());
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
+
+    fn check(&self, prefix: &str, errors: &mut ValidationErrors) {
+        for (field, value, max) in [
+            ("latitude", self.latitude, 90.0),
+            ("longitude", self.longitude, 180.0),
+        ] {
+            if !(-max..=max).contains(&value) {
+                errors.push(
+                    FieldError::new(
+                        format!("{prefix}.{field}"),
+                        "out_of_range",
+                        format!("{field} must be between -{max} and {max}"),
+                    )
+                    .with_rejected_value(value),
+                );
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ContactCard {
+    pub display_name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phones: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emails: Vec<String>,
+}
+
+impl ContactCard {
+    #[must_use]
+    pub fn new(display_name: impl Into<String>) -> Self {
+        Self {
+            display_name: display_name.into(),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_phone(mut self, phone: impl Into<String>) -> Self {
+        self.phones.push(phone.into());
         self
     }
 
     #[must_use]
-    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
-        self.icon = Some(icon.into());
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.emails.push(email.into());
         self
     }
+
+    fn check(&self, prefix: &str, errors: &mut ValidationErrors) {
+        errors
+            .require(&format!("{prefix}.display_name"), &self.display_name)
+            .check(
+                !self.phones.is_empty() || !self.emails.is_empty(),
+                &format!("{prefix}.phones"),
+                "required",
+                "a contact needs at least one phone or email",
+            );
+    }
 }
 
-impl<S: Into<String>> From<S> for Suggestion {
-    fn from(text: S) -> Self {
-        Self::new(text)
+/// Structured content sent alongside (or instead of) text, serialized as
+/// `{"type": "location", "latitude": ...}` and so on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessagePayload {
+    Location(Location),
+    Contact(ContactCard),
+}
+
+impl MessagePayload {
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Location(_) => "location",
+            Self::Contact(_) => "contact",
+        }
+    }
+
+    fn check(&self, errors: &mut ValidationErrors) {
+        match self {
+            Self::Location(location) => location.check("payload", errors),
+            Self::Contact(contact) => contact.check("payload", errors),
+        }
     }
 }
 
+/// A message from a user, as received from a channel.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BotResponse {
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "schema",
+    schemars(example = "crate::schema::user_message_example")
+)]
+pub struct UserMessage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
     pub bot_id: String,
     pub user_id: String,
     pub session_id: String,
+    /// Channel the message arrived on, e.g. `whatsapp` or `web`.
     pub channel: String,
+    /// Message text; may be empty when the message carries media or a
+    /// payload.
     pub content: String,
     pub message_type: MessageType,
+    /// Legacy single media link; prefer `attachments`.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub stream_token: Option<String>,
-    pub is_complete: bool,
+    pub media_url: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub suggestions: Vec<Suggestion>,
+    pub attachments: Vec<Attachment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<MessagePayload>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mentions: Vec<Mention>,
+    pub timestamp: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_name: Option<String>,
-    #[serde(default)]
-    pub context_length: usize,
-    #[serde(default)]
-    pub context_max_length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_id: Option<Uuid>,
+    /// Set when this message replaces the content of `reply_to_id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<DateTime<Utc>>,
+    /// The content before the edit, when the channel reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_content: Option<String>,
+    /// Tombstone: the user deleted `reply_to_id`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, Value>,
+    /// Fields added by newer versions of the protocol, kept so they survive
+    /// a round trip through services built against this one.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
-impl BotResponse {
+impl UserMessage {
     #[must_use]
-    pub fn new(
+    pub fn text(
         bot_id: impl Into<String>,
-        session_id: impl Into<String>,
         user_id: impl Into<String>,
-        content: impl Into<String>,
+        session_id: impl Into<String>,
         channel: impl Into<String>,
+        content: impl Into<String>,
     ) -> Self {
         Self {
+            id: Some(Uuid::new_v4()),
             bot_id: bot_id.into(),
             user_id: user_id.into(),
             session_id: session_id.into(),
             channel: channel.into(),
             content: content.into(),
-            message_type: MessageType::BOT_RESPONSE,
-            stream_token: None,
-            is_complete: true,
-            suggestions: Vec::new(),
+            message_type: MessageType::USER,
+            media_url: None,
+            attachments: Vec::new(),
+            payload: None,
+            mentions: Vec::new(),
+            timestamp: Utc::now(),
             context_name: None,
-            context_length: 0,
-            context_max_length: 0,
+            correlation_id: None,
+            reply_to_id: None,
+            edited_at: None,
+            original_content: None,
+            deleted: false,
+            deleted_at: None,
+            metadata: HashMap::new(),
+            extra: HashMap::new(),
         }
     }
 
+    /// A shared location; `content` is left empty.
     #[must_use]
-    pub fn streaming(
+    pub fn location(
         bot_id: impl Into<String>,
-        session_id: impl Into<String>,
         user_id: impl Into<String>,
+        session_id: impl Into<String>,
         channel: impl Into<String>,
-        stream_token: impl Into<String>,
+        location: Location,
     ) -> Self {
+        Self::text(bot_id, user_id, session_id, channel, "")
+            .with_payload(MessagePayload::Location(location))
+    }
+
+    /// An edit replacing the content of message `original_id`, which
+    /// becomes both `reply_to_id` and `correlation_id`. Routing fields are
+    /// left empty for the channel adapter to fill in.
+    #[must_use]
+    pub fn edit_of(original_id: Uuid, new_content: impl Into<String>) -> Self {
         Self {
-            bot_id: bot_id.into(),
-            user_id: user_id.into(),
-            session_id: session_id.into(),
-            channel: channel.into(),
-            content: String::new(),
-            message_type: MessageType::BOT_RESPONSE,
-            stream_token: Some(stream_token.into()),
-            is_complete: false,
-            suggestions: Vec::new(),
-            context_name: None,
-            context_length: 0,
-            context_max_length: 0,
+            reply_to_id: Some(original_id),
+            correlation_id: Some(original_id),
+            edited_at: Some(Utc::now()),
+            ..Self::text("", "", "", "", new_content)
         }
     }
 
+    /// A tombstone for message `original_id`; `content` is left empty.
+    /// Routing fields are left empty for the channel adapter to fill in.
     #[must_use]
-    pub fn with_suggestions<I, S>(mut self, suggestions: I) -> Self
-    where
-        I: IntoIterator<Item = S>,
-        S: Into<Suggestion>,
-    {
-        self.suggestions = suggestions.into_iter().map(Into::into).collect();
+    pub fn deletion_of(original_id: Uuid) -> Self {
+        Self {
+            reply_to_id: Some(original_id),
+            correlation_id: Some(original_id),
+            deleted: true,
+            deleted_at: Some(Utc::now()),
+            ..Self::text("", "", "", "", "")
+        }
+    }
+
+    #[must_use]
+    pub const fn is_edit(&self) -> bool {
+        self.edited_at.is_some() && !self.deleted
+    }
+
+    #[must_use]
+    pub const fn is_deletion(&self) -> bool {
+        self.deleted
+    }
+
+    /// A shared contact card; `content` is left empty.
+    #[must_use]
+    pub fn contact(
+        bot_id: impl Into<String>,
+        user_id: impl Into<String>,
+        session_id: impl Into<String>,
+        channel: impl Into<String>,
+        contact: ContactCard,
+    ) -> Self {
+        Self::text(bot_id, user_id, session_id, channel, "")
+            .with_payload(MessagePayload::Contact(contact))
+    }
+
+    /// Ids and channel are required, content may be empty only when the
+    /// message carries media, attachments or a payload or is a deletion,
+    /// `media_url` must be an http(s) URL, a payload must be well formed,
+    /// mentions must lie on character boundaries within the content, and
+    /// edits and deletions must name their target in `reply_to_id`. Under
+    /// `strict_message_types`, `message_type` must be a known type.
+    ///
+    /// # Errors
+    /// Returns every failed rule, one `FieldError` each.
+    pub fn validate(&self, limits: &SystemLimits) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_message_type(self.message_type, limits, &mut errors);
+        errors
+            .require("bot_id", &self.bot_id)
+            .require("user_id", &self.user_id)
+            .require("session_id", &self.session_id)
+            .require("channel", &self.channel)
+            .max_length("content", &self.content, limits.max_string_length);
+        match &self.media_url {
+            Some(url) => {
+                errors.check(
+                    is_http_url(url),
+                    "media_url",
+                    "invalid_url",
+                    "media_url must be an http(s) URL",
+                );
+            }
+            None if self.payload.is_none() && self.attachments.is_empty() && !self.deleted => {
+                errors.require("content", &self.content);
+            }
+            None => {}
+        }
+        if let Some(payload) = &self.payload {
+            payload.check(&mut errors);
+        }
+        check_mentions(&self.content, &self.mentions, &mut errors);
+        errors.check(
+            !(self.is_edit() || self.deleted) || self.reply_to_id.is_some(),
+            "reply_to_id",
+            "required",
+            "edits and deletions need reply_to_id",
+        );
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// `content` cleaned by `sanitize_content` under the default limits.
+    #[must_use]
+    pub fn sanitized(self) -> Self {
+        self.sanitized_with(&SystemLimits::default())
+    }
+
+    /// `content` and `original_content` cleaned by `sanitize_content`.
+    #[must_use]
+    pub fn sanitized_with(mut self, limits: &SystemLimits) -> Self {
+        self.content = sanitize_content(&self.content, limits);
+        self.original_content = self
+            .original_content
+            .map(|original| sanitize_content(&original, limits));
         self
     }
 
+    /// `sanitized_with` followed by `validate`, for inbound content that
+    /// should be cleaned rather than rejected. Content that was only
+    /// invisible characters is then reported as missing.
+    ///
+    /// # Errors
+    /// Returns every rule the sanitized message still fails.
+    pub fn sanitize_and_validate(self, limits: &SystemLimits) -> Result<Self, ValidationErrors> {
+        let message = self.sanitized_with(limits);
+        message.validate(limits).map(|()| message)
+    }
+
     #[must_use]
-    pub fn add_suggestion(mut self, suggestion: impl Into<Suggestion>) -> Self {
-        self.suggestions.push(suggestion.into());
+    pub fn with_media(mut self, url: impl Into<String>) -> Self {
+        self.media_url = Some(url.into());
         self
     }
 
     #[must_use]
-    pub fn with_context(
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context_name = Some(context.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn has_media(&self) -> bool {
+        self.media_url.is_some()
+    }
+
+    #[must_use]
+    pub fn with_attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    #[must_use]
+    pub fn with_payload(mut self, payload: MessagePayload) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    #[must_use]
+    pub const fn has_payload(&self) -> bool {
+        self.payload.is_some()
+    }
+
+    /// `"location"`, `"contact"`, or `None` for plain text and media.
+    #[must_use]
+    pub fn payload_kind(&self) -> Option<&'static str> {
+        self.payload.as_ref().map(MessagePayload::kind)
+    }
+
+    #[must_use]
+    pub fn with_mention(
         mut self,
-        name: impl Into<String>,
+        user_id: impl Into<String>,
+        offset: usize,
         length: usize,
-        max_length: usize,
     ) -> Self {
-        self.context_name = Some(name.into());
-        self.context_length = length;
-        self.context_max_length = max_length;
+        self.mentions.push(Mention::new(user_id, offset, length));
         self
     }
 
-    pub fn append_content(&mut self, chunk: &str) {
-        self.content.push_str(chunk);
+    /// Each mentioned user once, in order of first mention.
+    #[must_use]
+    pub fn mentioned_users(&self) -> Vec<&str> {
+        mentioned_users(&self.mentions)
     }
 
+    /// `content` with each mention replaced by `@` and the participant's
+    /// display name, for channels that only take plain text.
     #[must_use]
-    pub const fn complete(mut self) -> Self {
-        self.is_complete = true;
+    pub fn render_mentions(&self, participants: &[Participant]) -> String {
+        render_mentions(&self.content, &self.mentions, participants)
+    }
+
+    #[must_use]
+    pub const fn with_correlation_id(mut self, correlation_id: Uuid) -> Self {
+        self.correlation_id = Some(correlation_id);
         self
     }
 
     #[must_use]
-    pub const fn is_streaming(&self) -> bool {
-        self.stream_token.is_some() && !self.is_complete
+    pub fn get_meta(&self, key: &str) -> Option<&Value> {
+        self.metadata.get(key)
     }
 
     #[must_use]
-    pub const fn has_suggestions(&self) -> bool {
-        !self.suggestions.is_empty()
+    pub fn get_meta_str(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).and_then(Value::as_str)
     }
-}
 
-impl Default for BotResponse {
-    fn default() -> Self {
-        Self {
-            bot_id: String::new(),
-            user_id: String::new(),
-            session_id: String::new(),
-            channel: String::new(),
-            content: String::new(),
-            message_type: MessageType::BOT_RESPONSE,
-            stream_token: None,
-            is_complete: true,
-            suggestions: Vec::new(),
-            context_name: None,
-            context_length: 0,
-            context_max_length: 0,
-        }
+    pub fn set_meta(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.metadata.insert(key.into(), value.into());
     }
 }
 
+/// What a quick reply does when tapped, serialized as
+/// `{"type": "postback", "payload": ...}` and so on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SuggestionAction {
+    Postback { payload: String },
+    OpenUrl { url: String },
+    Call { phone: String },
+    ContextSwitch { context: String },
+    Custom { kind: String, data: Value },
+}
+
+/// A quick reply offered with a response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Attachment {
-    pub attachment_type: AttachmentType,
-    pub url: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub mime_type: Option<String>,
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Suggestion {
+    /// Label shown on the quick reply.
+    pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub filename: Option<String>,
+    pub context: Option<String>,
+    /// Legacy free-form action, interpreted by each channel adapter. New code
+    /// should set `typed_action` instead.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub size: Option<u64>,
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typed_action: Option<SuggestionAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_order: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub thumbnail_url: Option<String>,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum AttachmentType {
-    Image,
-    Audio,
-    Video,
-    Document,
-    File,
+    pub icon: Option<String>,
+    /// Fields added by newer versions of the protocol, kept so they survive
+    /// a round trip through services built against this one.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
-impl Attachment {
+impl Suggestion {
     #[must_use]
-    pub fn new(attachment_type: AttachmentType, url: impl Into<String>) -> Self {
+    pub fn new(text: impl Into<String>) -> Self {
         Self {
-            attachment_type,
-            url: url.into(),
-            mime_type: None,
-            filename: None,
-            size: None,
-            thumbnail_url: None,
+            text: text.into(),
+            context: None,
+            action: None,
+            typed_action: None,
+            display_order: None,
+            icon: None,
+            extra: HashMap::new(),
         }
     }
 
     #[must_use]
-    pub fn image(url: impl Into<String>) -> Self {
-        Self::new(AttachmentType::Image, url)
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
     }
 
+    /// Set the legacy free-form action. Prefer `with_postback`, `with_url`,
+    /// `with_call` or `with_typed_action`.
     #[must_use]
-    pub fn audio(url: impl Into<String>) -> Self {
-        Self::new(AttachmentType::Audio, url)
+    pub fn with_action(mut self, action: impl Into<String>) -> Self {
+        self.action = Some(action.into());
+        self
     }
 
     #[must_use]
-    pub fn video(url: impl Into<String>) -> Self {
-        Self::new(AttachmentType::Video, url)
+    pub fn with_typed_action(mut self, action: SuggestionAction) -> Self {
+        self.typed_action = Some(action);
+        self
     }
 
     #[must_use]
-    pub fn document(url: impl Into<String>) -> Self {
-        Self::new(AttachmentType::Document, url)
+    pub fn with_postback(self, payload: impl Into<String>) -> Self {
+        self.with_typed_action(SuggestionAction::Postback {
+            payload: payload.into(),
+        })
     }
 
     #[must_use]
-    pub fn file(url: impl Into<String>) -> Self {
-        Self::new(AttachmentType::File, url)
+    pub fn with_url(self, url: impl Into<String>) -> Self {
+        self.with_typed_action(SuggestionAction::OpenUrl { url: url.into() })
     }
 
     #[must_use]
-    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
-        self.mime_type = Some(mime_type.into());
-        self
+    pub fn with_call(self, phone: impl Into<String>) -> Self {
+        self.with_typed_action(SuggestionAction::Call {
+            phone: phone.into(),
+        })
     }
 
     #[must_use]
-    pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
-        self.filename = Some(filename.into());
-        self
+    pub fn with_context_switch(self, context: impl Into<String>) -> Self {
+        self.with_typed_action(SuggestionAction::ContextSwitch {
+            context: context.into(),
+        })
     }
 
     #[must_use]
-    pub const fn with_size(mut self, size: u64) -> Self {
-        self.size = Some(size);
+    pub const fn with_display_order(mut self, order: u32) -> Self {
+        self.display_order = Some(order);
         self
     }
 
     #[must_use]
-    pub fn with_thumbnail(mut self, thumbnail_url: impl Into<String>) -> Self {
-        self.thumbnail_url = Some(thumbnail_url.into());
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
         self
     }
 
+    /// The typed action, falling back to treating a legacy `action` string
+    /// as a postback payload.
     #[must_use]
-    pub const fn is_image(&self) -> bool {
-        matches!(self.attachment_type, AttachmentType::Image)
+    pub fn resolved_action(&self) -> Option<SuggestionAction> {
+        self.typed_action.clone().or_else(|| {
+            self.action
+                .clone()
+                .map(|payload| SuggestionAction::Postback { payload })
+        })
     }
+}
 
-    #[must_use]
-    pub const fn is_media(&self) -> bool {
-        matches!(
-            self.attachment_type,
-            AttachmentType::Image | AttachmentType::Audio | AttachmentType::Video
-        )
+impl<S: Into<String>> From<S> for Suggestion {
+    fn from(text: S) -> Self {
+        Self::new(text)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Most channels render at most three buttons per card (WhatsApp's limit).
+pub const DEFAULT_MAX_CARD_BUTTONS: usize = 3;
 
-    #[test]
-    fn test_api_response_success() {
-        let response: ApiResponse<String> = ApiResponse::success("test".to_string());
-        assert!(response.is_success());
-        assert!(!response.is_error());
-        assert_eq!(response.data, Some("test".to_string()));
-        assert!(response.error.is_none());
-    }
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CardButton {
+    pub label: String,
+    pub action: SuggestionAction,
+}
 
-    #[test]
-    fn test_api_response_error() {
-        let response: ApiResponse<String> = ApiResponse::error("something went wrong");
-        assert!(!response.is_success());
-        assert!(response.is_error());
-        assert!(response.data.is_none());
-        assert_eq!(response.error, Some("something went wrong".to_string()));
+impl CardButton {
+    #[must_use]
+    pub fn new(label: impl Into<String>, action: SuggestionAction) -> Self {
+        Self {
+            label: label.into(),
+            action,
+        }
     }
+}
 
-    #[test]
-    fn test_api_response_map() {
-        let response: ApiResponse<i32> = ApiResponse::success(42);
-        let mapped = response.map(|n| n.to_string());
-        assert_eq!(mapped.data, Some("42".to_string()));
-    }
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Card {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buttons: Vec<CardButton>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer: Option<String>,
+}
+
+impl Card {
+    #[must_use]
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            subtitle: None,
+            image_url: None,
+            buttons: Vec::new(),
+            footer: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_image(mut self, image_url: impl Into<String>) -> Self {
+        self.image_url = Some(image_url.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_button(mut self, label: impl Into<String>, action: SuggestionAction) -> Self {
+        self.buttons.push(CardButton::new(label, action));
+        self
+    }
+
+    #[must_use]
+    pub fn with_footer(mut self, footer: impl Into<String>) -> Self {
+        self.footer = Some(footer.into());
+        self
+    }
+
+    fn check(&self, prefix: &str, max_buttons: usize, errors: &mut ValidationErrors) {
+        errors.require(&format!("{prefix}.title"), &self.title);
+        if self.buttons.len() > max_buttons {
+            errors.push(
+                FieldError::new(
+                    format!("{prefix}.buttons"),
+                    "too_many",
+                    format!("a card may have at most {max_buttons} buttons"),
+                )
+                .with_rejected_value(self.buttons.len()),
+            );
+        }
+    }
+
+    /// Requires a title and at most `max_buttons` buttons; see
+    /// `DEFAULT_MAX_CARD_BUTTONS`.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` listing every invalid field.
+    pub fn validate(&self, max_buttons: usize) -> BotResult<()> {
+        let mut errors = ValidationErrors::new();
+        self.check("card", max_buttons, &mut errors);
+        errors.into_result()
+    }
+
+    /// Markdown-ish text: bold title, subtitle, image link, one bullet per
+    /// button (with its URL or phone number) and an italic footer.
+    #[must_use]
+    pub fn render_plain_text(&self) -> String {
+        let mut lines = vec![format!("*{}*", self.title)];
+        lines.extend(self.subtitle.clone());
+        lines.extend(self.image_url.clone());
+        for button in &self.buttons {
+            lines.push(match &button.action {
+                SuggestionAction::OpenUrl { url } => format!("- {}: {url}", button.label),
+                SuggestionAction::Call { phone } => format!("- {}: {phone}", button.label),
+                _ => format!("- {}", button.label),
+            });
+        }
+        lines.extend(self.footer.as_ref().map(|footer| format!("_{footer}_")));
+        lines.join("\n")
+    }
+}
+
+/// How `BotResponse::content` is written. LLM output is markdown, which
+/// is the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ContentFormat {
+    PlainText,
+    #[default]
+    Markdown,
+    Html,
+}
+
+/// A reply from a bot, or one chunk of a streamed reply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "schema",
+    schemars(example = "crate::schema::bot_response_example")
+)]
+pub struct BotResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    pub bot_id: String,
+    pub user_id: String,
+    pub session_id: String,
+    pub channel: String,
+    pub content: String,
+    #[serde(default)]
+    pub content_format: ContentFormat,
+    pub message_type: MessageType,
+    /// Groups the chunks of one streamed response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_token: Option<String>,
+    /// Position of this chunk within its stream, starting at 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    /// False for every streamed chunk but the last.
+    pub is_complete: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<Suggestion>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cards: Vec<Card>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mentions: Vec<Mention>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_name: Option<String>,
+    #[serde(default)]
+    pub context_length: usize,
+    #[serde(default)]
+    pub context_max_length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_id: Option<Uuid>,
+    /// Hold the response until this time; `None` sends immediately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_at: Option<DateTime<Utc>>,
+    /// Drop the response instead of delivering it after this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, Value>,
+    /// Fields added by newer versions of the protocol, kept so they survive
+    /// a round trip through services built against this one.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl BotResponse {
+    #[must_use]
+    pub fn new(
+        bot_id: impl Into<String>,
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+        content: impl Into<String>,
+        channel: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: None,
+            bot_id: bot_id.into(),
+            user_id: user_id.into(),
+            session_id: session_id.into(),
+            channel: channel.into(),
+            content: content.into(),
+            content_format: ContentFormat::default(),
+            message_type: MessageType::BOT_RESPONSE,
+            stream_token: None,
+            sequence: None,
+            is_complete: true,
+            suggestions: Vec::new(),
+            cards: Vec::new(),
+            mentions: Vec::new(),
+            context_name: None,
+            context_length: 0,
+            context_max_length: 0,
+            correlation_id: None,
+            reply_to_id: None,
+            send_at: None,
+            expires_at: None,
+            metadata: HashMap::new(),
+            extra: HashMap::new(),
+        }
+    }
+
+    /// A notice from the platform, e.g. "an agent joined the chat", sent as
+    /// `MessageType::SYSTEM` in plain text.
+    #[must_use]
+    pub fn system_notice(
+        bot_id: impl Into<String>,
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+        content: impl Into<String>,
+        channel: impl Into<String>,
+    ) -> Self {
+        Self {
+            message_type: MessageType::SYSTEM,
+            content_format: ContentFormat::PlainText,
+            ..Self::new(bot_id, session_id, user_id, content, channel)
+        }
+    }
+
+    /// A failure shown to the user, sent as `MessageType::ERROR` in plain
+    /// text.
+    #[must_use]
+    pub fn error_message(
+        bot_id: impl Into<String>,
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+        content: impl Into<String>,
+        channel: impl Into<String>,
+    ) -> Self {
+        Self {
+            message_type: MessageType::ERROR,
+            ..Self::system_notice(bot_id, session_id, user_id, content, channel)
+        }
+    }
+
+    /// A complete response to `source`, on the same bot, session, user,
+    /// channel and context, carrying its correlation id and replying to its
+    /// id.
+    #[must_use]
+    pub fn reply_to(source: &UserMessage, content: impl Into<String>) -> Self {
+        Self {
+            context_name: source.context_name.clone(),
+            correlation_id: source.correlation_id,
+            reply_to_id: source.id,
+            ..Self::new(
+                source.bot_id.clone(),
+                source.session_id.clone(),
+                source.user_id.clone(),
+                content,
+                source.channel.clone(),
+            )
+        }
+    }
+
+    #[must_use]
+    pub fn streaming(
+        bot_id: impl Into<String>,
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+        channel: impl Into<String>,
+        stream_token: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: None,
+            bot_id: bot_id.into(),
+            user_id: user_id.into(),
+            session_id: session_id.into(),
+            channel: channel.into(),
+            content: String::new(),
+            content_format: ContentFormat::default(),
+            message_type: MessageType::BOT_RESPONSE,
+            stream_token: Some(stream_token.into()),
+            sequence: Some(0),
+            is_complete: false,
+            suggestions: Vec::new(),
+            cards: Vec::new(),
+            mentions: Vec::new(),
+            context_name: None,
+            context_length: 0,
+            context_max_length: 0,
+            correlation_id: None,
+            reply_to_id: None,
+            send_at: None,
+            expires_at: None,
+            metadata: HashMap::new(),
+            extra: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_suggestions<I, S>(mut self, suggestions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Suggestion>,
+    {
+        self.suggestions = suggestions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    #[must_use]
+    pub fn add_suggestion(mut self, suggestion: impl Into<Suggestion>) -> Self {
+        self.suggestions.push(suggestion.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_context(
+        mut self,
+        name: impl Into<String>,
+        length: usize,
+        max_length: usize,
+    ) -> Self {
+        self.context_name = Some(name.into());
+        self.context_length = length;
+        self.context_max_length = max_length;
+        self
+    }
+
+    /// `with_context` using the window's current `usage()`.
+    #[must_use]
+    pub fn with_context_window(self, name: impl Into<String>, window: &ContextWindow) -> Self {
+        let (length, max_length) = window.usage();
+        self.with_context(name, length, max_length)
+    }
+
+    /// A chunk of this stream carrying `content` and the next sequence
+    /// number, which is then advanced. See `StreamReassembler`.
+    #[must_use]
+    pub fn next_chunk(&mut self, content: impl Into<String>) -> Self {
+        let sequence = self.sequence.unwrap_or_default();
+        self.sequence = Some(sequence + 1);
+        Self {
+            content: content.into(),
+            sequence: Some(sequence),
+            is_complete: false,
+            ..self.clone()
+        }
+    }
+
+    /// Like `next_chunk`, but marks the chunk as the end of the stream.
+    #[must_use]
+    pub fn last_chunk(&mut self, content: impl Into<String>) -> Self {
+        Self {
+            is_complete: true,
+            ..self.next_chunk(content)
+        }
+    }
+
+    pub fn append_content(&mut self, chunk: &str) {
+        self.content.push_str(chunk);
+    }
+
+    #[must_use]
+    pub const fn complete(mut self) -> Self {
+        self.is_complete = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn is_streaming(&self) -> bool {
+        self.stream_token.is_some() && !self.is_complete
+    }
+
+    /// Whether a subscriber filtering on `filter` should receive this
+    /// response.
+    #[must_use]
+    pub fn matches(&self, filter: &MessageTypeSet) -> bool {
+        filter.contains(self.message_type)
+    }
+
+    #[must_use]
+    pub const fn has_suggestions(&self) -> bool {
+        !self.suggestions.is_empty()
+    }
+
+    #[must_use]
+    pub fn with_mention(
+        mut self,
+        user_id: impl Into<String>,
+        offset: usize,
+        length: usize,
+    ) -> Self {
+        self.mentions.push(Mention::new(user_id, offset, length));
+        self
+    }
+
+    /// Each mentioned user once, in order of first mention.
+    #[must_use]
+    pub fn mentioned_users(&self) -> Vec<&str> {
+        mentioned_users(&self.mentions)
+    }
+
+    /// `content` with each mention replaced by `@` and the participant's
+    /// display name, for channels that only take plain text.
+    #[must_use]
+    pub fn render_mentions(&self, participants: &[Participant]) -> String {
+        render_mentions(&self.content, &self.mentions, participants)
+    }
+
+    #[must_use]
+    pub const fn with_send_at(mut self, send_at: DateTime<Utc>) -> Self {
+        self.send_at = Some(send_at);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Whether the response may be sent at `now`: it is unscheduled or its
+    /// `send_at` has passed.
+    #[must_use]
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.send_at.is_none_or(|at| at <= now)
+    }
+
+    #[must_use]
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|exp| now > exp)
+    }
+
+    #[must_use]
+    pub fn with_card(mut self, card: Card) -> Self {
+        self.cards.push(card);
+        self
+    }
+
+    pub fn add_card(&mut self, card: Card) {
+        self.cards.push(card);
+    }
+
+    #[must_use]
+    pub fn get_meta(&self, key: &str) -> Option<&Value> {
+        self.metadata.get(key)
+    }
+
+    #[must_use]
+    pub fn get_meta_str(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).and_then(Value::as_str)
+    }
+
+    pub fn set_meta(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.metadata.insert(key.into(), value.into());
+    }
+
+    /// A complete response needs content unless it carries suggestions or
+    /// cards; suggestions are capped at `max_suggestions_per_response`,
+    /// mentions must lie on character boundaries within the content, and
+    /// `expires_at` must come after `send_at`. Under `strict_message_types`,
+    /// `message_type` must be a known type.
+    ///
+    /// # Errors
+    /// Returns every failed rule, one `FieldError` each.
+    pub fn validate(&self, limits: &SystemLimits) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_message_type(self.message_type, limits, &mut errors);
+        errors.max_length("content", &self.content, limits.max_string_length);
+        if self.is_complete && self.suggestions.is_empty() && self.cards.is_empty() {
+            errors.require("content", &self.content);
+        }
+        if self.suggestions.len() > limits.max_suggestions_per_response {
+            errors.push(
+                FieldError::new(
+                    "suggestions",
+                    "too_many",
+                    format!(
+                        "at most {} suggestions are allowed",
+                        limits.max_suggestions_per_response
+                    ),
+                )
+                .with_rejected_value(self.suggestions.len()),
+            );
+        }
+        check_mentions(&self.content, &self.mentions, &mut errors);
+        if let (Some(send_at), Some(expires_at)) = (self.send_at, self.expires_at) {
+            errors.check(
+                expires_at > send_at,
+                "expires_at",
+                "invalid",
+                "expires_at must be after send_at",
+            );
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check every card against a channel's button limit.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` naming each offending card, e.g.
+    /// `cards[1].buttons`.
+    pub fn validate_cards(&self, max_buttons: usize) -> BotResult<()> {
+        let mut errors = ValidationErrors::new();
+        for (index, card) in self.cards.iter().enumerate() {
+            card.check(&format!("cards[{index}]"), max_buttons, &mut errors);
+        }
+        errors.into_result()
+    }
+
+    #[must_use]
+    pub const fn with_content_format(mut self, format: ContentFormat) -> Self {
+        self.content_format = format;
+        self
+    }
+
+    /// `content` as plain text: markdown emphasis is stripped and links
+    /// become `text (url)`; HTML tags are removed.
+    #[must_use]
+    pub fn to_plain_text(&self) -> String {
+        match self.content_format {
+            ContentFormat::PlainText => self.content.clone(),
+            ContentFormat::Markdown => markup::markdown_to_plain_text(&self.content),
+            ContentFormat::Html => markup::html_to_plain_text(&self.content),
+        }
+    }
+
+    /// `content` as HTML restricted to basic formatting tags, with
+    /// everything else escaped. HTML content is returned unchanged.
+    #[must_use]
+    pub fn to_html(&self) -> String {
+        match self.content_format {
+            ContentFormat::PlainText => markup::plain_text_to_html(&self.content),
+            ContentFormat::Markdown => markup::markdown_to_html(&self.content),
+            ContentFormat::Html => self.content.clone(),
+        }
+    }
+
+    /// `content` followed by each card rendered with
+    /// `Card::render_plain_text`, for channels without card support.
+    #[must_use]
+    pub fn render_plain_text(&self) -> String {
+        std::iter::once(self.content.clone())
+            .filter(|content| !content.is_empty())
+            .chain(self.cards.iter().map(Card::render_plain_text))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+impl Default for BotResponse {
+    fn default() -> Self {
+        Self {
+            id: None,
+            bot_id: String::new(),
+            user_id: String::new(),
+            session_id: String::new(),
+            channel: String::new(),
+            content: String::new(),
+            content_format: ContentFormat::default(),
+            message_type: MessageType::BOT_RESPONSE,
+            stream_token: None,
+            sequence: None,
+            is_complete: true,
+            suggestions: Vec::new(),
+            cards: Vec::new(),
+            mentions: Vec::new(),
+            context_name: None,
+            context_length: 0,
+            context_max_length: 0,
+            correlation_id: None,
+            reply_to_id: None,
+            send_at: None,
+            expires_at: None,
+            metadata: HashMap::new(),
+            extra: HashMap::new(),
+        }
+    }
+}
+
+/// A file sent with a message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Attachment {
+    pub attachment_type: AttachmentType,
+    /// Download URL, or the channel's media id for inbound media that has
+    /// not been fetched yet.
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentType {
+    Image,
+    Audio,
+    Video,
+    Document,
+    File,
+}
+
+impl Attachment {
+    #[must_use]
+    pub fn new(attachment_type: AttachmentType, url: impl Into<String>) -> Self {
+        Self {
+            attachment_type,
+            url: url.into(),
+            mime_type: None,
+            filename: None,
+            size: None,
+            thumbnail_url: None,
+        }
+    }
+
+    #[must_use]
+    pub fn image(url: impl Into<String>) -> Self {
+        Self::new(AttachmentType::Image, url)
+    }
+
+    #[must_use]
+    pub fn audio(url: impl Into<String>) -> Self {
+        Self::new(AttachmentType::Audio, url)
+    }
+
+    #[must_use]
+    pub fn video(url: impl Into<String>) -> Self {
+        Self::new(AttachmentType::Video, url)
+    }
+
+    #[must_use]
+    pub fn document(url: impl Into<String>) -> Self {
+        Self::new(AttachmentType::Document, url)
+    }
+
+    #[must_use]
+    pub fn file(url: impl Into<String>) -> Self {
+        Self::new(AttachmentType::File, url)
+    }
+
+    #[must_use]
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    #[must_use]
+    pub fn with_thumbnail(mut self, thumbnail_url: impl Into<String>) -> Self {
+        self.thumbnail_url = Some(thumbnail_url.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn is_image(&self) -> bool {
+        matches!(self.attachment_type, AttachmentType::Image)
+    }
+
+    #[must_use]
+    pub const fn is_media(&self) -> bool {
+        matches!(
+            self.attachment_type,
+            AttachmentType::Image | AttachmentType::Audio | AttachmentType::Video
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageDirection {
+    User,
+    Bot,
+    System,
+}
+
+impl std::fmt::Display for MessageDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Self::User => "User",
+            Self::Bot => "Bot",
+            Self::System => "System",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Channel signals about a conversation rather than message content.
+/// Unrecognised `type`s deserialize as `Unknown`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessageEventType {
+    TypingStart,
+    TypingStop,
+    Delivered,
+    Read,
+    Failed {
+        reason: String,
+    },
+    /// `emoji` was added to, or with `removed` taken off, `message_id`.
+    Reaction {
+        emoji: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        removed: bool,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageEvent {
+    pub event_type: MessageEventType,
+    pub session_id: String,
+    pub user_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<Uuid>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl MessageEvent {
+    #[must_use]
+    pub fn new(
+        event_type: MessageEventType,
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            event_type,
+            session_id: session_id.into(),
+            user_id: user_id.into(),
+            message_id: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[must_use]
+    pub fn typing_start(session_id: impl Into<String>, user_id: impl Into<String>) -> Self {
+        Self::new(MessageEventType::TypingStart, session_id, user_id)
+    }
+
+    #[must_use]
+    pub fn typing_stop(session_id: impl Into<String>, user_id: impl Into<String>) -> Self {
+        Self::new(MessageEventType::TypingStop, session_id, user_id)
+    }
+
+    #[must_use]
+    pub fn delivered(
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+        message_id: Uuid,
+    ) -> Self {
+        Self::new(MessageEventType::Delivered, session_id, user_id).with_message_id(message_id)
+    }
+
+    #[must_use]
+    pub fn read(
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+        message_id: Uuid,
+    ) -> Self {
+        Self::new(MessageEventType::Read, session_id, user_id).with_message_id(message_id)
+    }
+
+    #[must_use]
+    pub fn failed(
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+        message_id: Uuid,
+        reason: impl Into<String>,
+    ) -> Self {
+        let event_type = MessageEventType::Failed {
+            reason: reason.into(),
+        };
+        Self::new(event_type, session_id, user_id).with_message_id(message_id)
+    }
+
+    #[must_use]
+    pub fn reaction(
+        session_id: impl Into<String>,
+        user_id: impl Into<String>,
+        message_id: Uuid,
+        emoji: impl Into<String>,
+    ) -> Self {
+        let event_type = MessageEventType::Reaction {
+            emoji: emoji.into(),
+            removed: false,
+        };
+        Self::new(event_type, session_id, user_id).with_message_id(message_id)
+    }
+
+    #[must_use]
+    pub const fn with_message_id(mut self, message_id: Uuid) -> Self {
+        self.message_id = Some(message_id);
+        self
+    }
+
+    /// The `Reaction` a reaction event carries, or `None` for other events
+    /// and reactions without a `message_id`.
+    #[must_use]
+    pub fn to_reaction(&self) -> Option<Reaction> {
+        let MessageEventType::Reaction { emoji, removed } = &self.event_type else {
+            return None;
+        };
+        Some(Reaction {
+            reacted_at: self.timestamp,
+            removed: *removed,
+            ..Reaction::new(self.message_id?, self.user_id.clone(), emoji.clone())
+        })
+    }
+}
+
+/// Longest emoji sequence accepted as a reaction, in chars. Enough for
+/// ZWJ families and subdivision flags, short enough to reject text.
+pub const MAX_REACTION_EMOJI_CHARS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reaction {
+    pub message_id: Uuid,
+    pub user_id: String,
+    pub emoji: String,
+    pub reacted_at: DateTime<Utc>,
+    /// The user took the reaction back; applying it clears theirs.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
+}
+
+impl Reaction {
+    #[must_use]
+    pub fn new(message_id: Uuid, user_id: impl Into<String>, emoji: impl Into<String>) -> Self {
+        Self {
+            message_id,
+            user_id: user_id.into(),
+            emoji: emoji.into(),
+            reacted_at: Utc::now(),
+            removed: false,
+        }
+    }
+
+    /// `user_id` is required and `emoji` must be a short emoji sequence,
+    /// unless the reaction is a removal.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` listing every invalid field.
+    pub fn validate(&self) -> BotResult<()> {
+        let mut errors = ValidationErrors::new();
+        errors.require("user_id", &self.user_id);
+        if !self.removed && !is_emoji_sequence(&self.emoji) {
+            errors.push(
+                FieldError::new(
+                    "emoji",
+                    "invalid_emoji",
+                    format!(
+                        "emoji must be an emoji sequence of at most {MAX_REACTION_EMOJI_CHARS} characters"
+                    ),
+                )
+                .with_rejected_value(self.emoji.chars().take(MAX_REACTION_EMOJI_CHARS).collect::<String>()),
+            );
+        }
+        errors.into_result()
+    }
+}
+
+/// Emoji are symbols, not letters, digits, spaces or controls. ASCII is
+/// only allowed as the base of a keycap such as `1️⃣`.
+fn is_emoji_sequence(emoji: &str) -> bool {
+    const KEYCAP: char = '\u{20E3}';
+    let count = emoji.chars().count();
+    if count == 0 || count > MAX_REACTION_EMOJI_CHARS {
+        return false;
+    }
+    let is_keycap = emoji.ends_with(KEYCAP);
+    emoji.chars().enumerate().all(|(index, c)| {
+        if c.is_ascii() {
+            is_keycap && index == 0 && matches!(c, '0'..='9' | '#' | '*')
+        } else {
+            !c.is_alphanumeric() && !c.is_whitespace() && !c.is_control()
+        }
+    })
+}
+
+/// What the WebSocket and long-poll transports receive from a channel,
+/// tagged by `kind`: `"message"` or `"event"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InboundFrame {
+    Message(Box<UserMessage>),
+    Event(MessageEvent),
+}
+
+impl From<UserMessage> for InboundFrame {
+    fn from(msg: UserMessage) -> Self {
+        Self::Message(Box::new(msg))
+    }
+}
+
+impl From<MessageEvent> for InboundFrame {
+    fn from(event: MessageEvent) -> Self {
+        Self::Event(event)
+    }
+}
+
+/// A stored conversation message, as persisted by any service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub id: Uuid,
+    pub session_id: String,
+    pub direction: MessageDirection,
+    pub content: String,
+    pub message_type: MessageType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<DateTime<Utc>>,
+    /// The content as first sent, kept once the message is edited or
+    /// deleted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_content: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// At most one per user.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<Reaction>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub metadata: Map<String, Value>,
+}
+
+impl Message {
+    #[must_use]
+    pub fn new(
+        session_id: impl Into<String>,
+        direction: MessageDirection,
+        content: impl Into<String>,
+    ) -> Self {
+        let message_type = match direction {
+            MessageDirection::User => MessageType::USER,
+            MessageDirection::Bot => MessageType::BOT_RESPONSE,
+            MessageDirection::System => MessageType::EXTERNAL,
+        };
+        Self {
+            id: Uuid::new_v4(),
+            session_id: session_id.into(),
+            direction,
+            content: content.into(),
+            message_type,
+            attachments: Vec::new(),
+            created_at: Utc::now(),
+            edited_at: None,
+            original_content: None,
+            deleted: false,
+            deleted_at: None,
+            reactions: Vec::new(),
+            metadata: Map::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    #[must_use]
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Replaces the content, keeping the content as first sent in
+    /// `original_content`.
+    ///
+    /// # Errors
+    /// Returns `BotError::Conflict` if the message was deleted.
+    pub fn apply_edit(
+        &mut self,
+        new_content: impl Into<String>,
+        edited_at: DateTime<Utc>,
+    ) -> BotResult<()> {
+        if self.deleted {
+            return Err(BotError::conflict(format!(
+                "message {} was deleted and cannot be edited",
+                self.id
+            )));
+        }
+        let previous = std::mem::replace(&mut self.content, new_content.into());
+        self.original_content.get_or_insert(previous);
+        self.edited_at = Some(edited_at);
+        Ok(())
+    }
+
+    /// Turns the message into a tombstone, moving its content to
+    /// `original_content`. Deleting twice keeps the first `deleted_at`.
+    pub fn apply_deletion(&mut self, deleted_at: DateTime<Utc>) {
+        if self.deleted {
+            return;
+        }
+        let previous = std::mem::take(&mut self.content);
+        self.original_content.get_or_insert(previous);
+        self.deleted = true;
+        self.deleted_at = Some(deleted_at);
+    }
+
+    /// Records `reaction`, replacing any earlier reaction by the same user.
+    /// A removal clears the user's reaction instead.
+    ///
+    /// # Errors
+    /// Returns `BotError::ValidationFields` if the reaction is invalid and
+    /// `BotError::Validation` if it targets another message.
+    pub fn add_reaction(&mut self, reaction: Reaction) -> BotResult<()> {
+        reaction.validate()?;
+        if reaction.message_id != self.id {
+            return Err(BotError::validation(format!(
+                "reaction targets message {}, not {}",
+                reaction.message_id, self.id
+            )));
+        }
+        if reaction.removed {
+            self.remove_reaction(&reaction.user_id);
+            return Ok(());
+        }
+        match self
+            .reactions
+            .iter_mut()
+            .find(|r| r.user_id == reaction.user_id)
+        {
+            Some(existing) => *existing = reaction,
+            None => self.reactions.push(reaction),
+        }
+        Ok(())
+    }
+
+    /// Removes and returns `user_id`'s reaction, if any.
+    pub fn remove_reaction(&mut self, user_id: &str) -> Option<Reaction> {
+        let index = self.reactions.iter().position(|r| r.user_id == user_id)?;
+        Some(self.reactions.remove(index))
+    }
+
+    /// How many users reacted with each emoji.
+    #[must_use]
+    pub fn reaction_counts(&self) -> HashMap<String, u32> {
+        let mut counts = HashMap::new();
+        for reaction in self.reactions.iter().filter(|r| !r.removed) {
+            *counts.entry(reaction.emoji.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[must_use]
+    pub const fn is_from_user(&self) -> bool {
+        matches!(self.direction, MessageDirection::User)
+    }
+
+    #[must_use]
+    pub const fn is_from_bot(&self) -> bool {
+        matches!(self.direction, MessageDirection::Bot)
+    }
+}
+
+fn origin_metadata(
+    extra: HashMap<String, Value>,
+    bot_id: String,
+    user_id: String,
+    channel: String,
+    context_name: Option<String>,
+) -> Map<String, Value> {
+    let mut metadata: Map<String, Value> = extra.into_iter().collect();
+    metadata.insert("bot_id".to_string(), Value::String(bot_id));
+    metadata.insert("user_id".to_string(), Value::String(user_id));
+    metadata.insert("channel".to_string(), Value::String(channel));
+    if let Some(context_name) = context_name {
+        metadata.insert("context_name".to_string(), Value::String(context_name));
+    }
+    metadata
+}
+
+/// Keeps the message's id and timestamp; the media URL becomes a file
+/// attachment and the routing fields join `metadata`.
+impl From<UserMessage> for Message {
+    fn from(msg: UserMessage) -> Self {
+        Self {
+            id: msg.id.unwrap_or_else(Uuid::new_v4),
+            session_id: msg.session_id,
+            direction: MessageDirection::User,
+            content: msg.content,
+            message_type: msg.message_type,
+            attachments: msg
+                .media_url
+                .map(Attachment::file)
+                .into_iter()
+                .chain(msg.attachments)
+                .collect(),
+            created_at: msg.timestamp,
+            edited_at: msg.edited_at,
+            original_content: msg.original_content,
+            deleted: msg.deleted,
+            deleted_at: msg.deleted_at,
+            reactions: Vec::new(),
+            metadata: origin_metadata(
+                msg.metadata,
+                msg.bot_id,
+                msg.user_id,
+                msg.channel,
+                msg.context_name,
+            ),
+        }
+    }
+}
+
+/// Timestamped now; the routing fields join `metadata`.
+impl From<BotResponse> for Message {
+    fn from(response: BotResponse) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            session_id: response.session_id,
+            direction: MessageDirection::Bot,
+            content: response.content,
+            message_type: response.message_type,
+            attachments: Vec::new(),
+            created_at: Utc::now(),
+            edited_at: None,
+            original_content: None,
+            deleted: false,
+            deleted_at: None,
+            reactions: Vec::new(),
+            metadata: origin_metadata(
+                response.metadata,
+                response.bot_id,
+                response.user_id,
+                response.channel,
+                response.context_name,
+            ),
+        }
+    }
+}
+
+/// A session with a page of its history. `total_count` is the number of
+/// messages stored for the session, which may exceed `messages.len()`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub session: Session,
+    pub messages: Vec<Message>,
+    pub total_count: usize,
+}
+
+impl Conversation {
+    #[must_use]
+    pub fn new(session: Session, messages: Vec<Message>) -> Self {
+        let total_count = messages.len();
+        Self {
+            session,
+            messages,
+            total_count,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_total_count(mut self, total_count: usize) -> Self {
+        self.total_count = total_count;
+        self
+    }
+
+    #[must_use]
+    pub fn last_user_message(&self) -> Option<&Message> {
+        self.messages.iter().rev().find(|m| m.is_from_user())
+    }
+
+    #[must_use]
+    pub fn last_bot_message(&self) -> Option<&Message> {
+        self.messages.iter().rev().find(|m| m.is_from_bot())
+    }
+
+    fn edit_target(&mut self, change: &UserMessage) -> BotResult<&mut Message> {
+        let Some(target) = change.reply_to_id else {
+            return Err(BotError::validation("reply_to_id is required"));
+        };
+        self.messages
+            .iter_mut()
+            .find(|m| m.id == target)
+            .ok_or_else(|| BotError::not_found_id("Message", target.to_string()))
+    }
+
+    /// Applies an edit built with `UserMessage::edit_of` to the message it
+    /// targets.
+    ///
+    /// # Errors
+    /// Returns `BotError::Validation` if `edit` is not an edit,
+    /// `BotError::NotFound` if its target is not in `messages`, and
+    /// `BotError::Conflict` if the target was deleted.
+    pub fn apply_edit(&mut self, edit: &UserMessage) -> BotResult<()> {
+        if !edit.is_edit() {
+            return Err(BotError::validation("message is not an edit"));
+        }
+        let edited_at = edit.edited_at.unwrap_or(edit.timestamp);
+        self.edit_target(edit)?
+            .apply_edit(edit.content.clone(), edited_at)
+    }
+
+    /// Applies a tombstone built with `UserMessage::deletion_of`. Deleting
+    /// an already deleted message succeeds without changing it.
+    ///
+    /// # Errors
+    /// Returns `BotError::Validation` if `deletion` is not a deletion and
+    /// `BotError::NotFound` if its target is not in `messages`.
+    pub fn apply_deletion(&mut self, deletion: &UserMessage) -> BotResult<()> {
+        if !deletion.is_deletion() {
+            return Err(BotError::validation("message is not a deletion"));
+        }
+        let deleted_at = deletion.deleted_at.unwrap_or(deletion.timestamp);
+        self.edit_target(deletion)?.apply_deletion(deleted_at);
+        Ok(())
+    }
+
+    /// `Direction: content` lines, oldest first, keeping the newest
+    /// messages that fit in `max_chars` characters. When even the newest
+    /// message is too long, only its end is kept, cut on a character
+    /// boundary.
+    #[must_use]
+    pub fn to_prompt_transcript(&self, max_chars: usize) -> String {
+        let line = |m: &Message| format!("{}: {}", m.direction, m.content);
+        // The window costs every line with its newline, but the last line
+        // is emitted without one.
+        let window = ContextWindow::chars(max_chars.saturating_add(1));
+        let kept = window.trim_to_fit(&self.messages);
+        if !kept.is_empty() {
+            return kept.iter().map(line).collect::<Vec<_>>().join("\n");
+        }
+        let newest = self.messages.last().map(line).unwrap_or_default();
+        let excess = newest.chars().count().saturating_sub(max_chars);
+        newest.chars().skip(excess).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_response_success() {
+        let response: ApiResponse<String> = ApiResponse::success("test".to_string());
+        assert!(response.is_success());
+        assert!(!response.is_error());
+        assert_eq!(response.data, Some("test".to_string()));
+        assert!(response.error.is_none());
+    }
+
+    #[test]
+    fn test_api_response_error() {
+        let response: ApiResponse<String> = ApiResponse::error("something went wrong");
+        assert!(!response.is_success());
+        assert!(response.is_error());
+        assert!(response.data.is_none());
+        assert_eq!(response.error, Some("something went wrong".to_string()));
+    }
+
+    #[test]
+    fn test_api_response_from_bot_error() {
+        let response: ApiResponse<()> = ApiResponse::from(BotError::not_found("User"));
+        assert!(response.is_error());
+        assert_eq!(response.code.as_deref(), Some("not_found"));
+        assert_eq!(response.error.as_deref(), Some("User not found"));
+    }
+
+    #[test]
+    fn test_user_message_validate_collects_fields() {
+        let limits = SystemLimits::default();
+        assert!(UserMessage::text("bot", "user", "s1", "web", "hi")
+            .validate(&limits)
+            .is_ok());
+        assert!(UserMessage::text("bot", "user", "s1", "web", "")
+            .with_media("https://cdn/img.png")
+            .validate(&limits)
+            .is_ok());
+
+        let result = UserMessage::text("bot", "", "s1", " ", "").validate(&limits);
+        assert_eq!(
+            result.err().map(|e| e.to_string()).as_deref(),
+            Some("3 validation errors: user_id, channel, content")
+        );
+    }
+
+    #[test]
+    fn test_user_message_validate_rules() {
+        let limits = SystemLimits {
+            max_string_length: 5,
+            ..SystemLimits::default()
+        };
+        let codes = |msg: UserMessage| -> Vec<(String, String)> {
+            msg.validate(&limits)
+                .err()
+                .map(|errors| {
+                    errors
+                        .errors()
+                        .iter()
+                        .map(|e| (e.field.clone(), e.code.clone()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let pair = |field: &str, code: &str| (field.to_string(), code.to_string());
+
+        assert_eq!(
+            codes(UserMessage::text("", "u", "", "web", "hi")),
+            [pair("bot_id", "required"), pair("session_id", "required")]
+        );
+        assert_eq!(
+            codes(UserMessage::text("b", "u", "s", "web", "too long")),
+            [pair("content", "too_long")]
+        );
+        assert_eq!(
+            codes(UserMessage::text("b", "u", "s", "web", "").with_media("ftp://cdn/a.png")),
+            [pair("media_url", "invalid_url")]
+        );
+        assert_eq!(
+            codes(UserMessage::text("b", "u", "s", "web", "").with_media("https:// cdn/a.png")),
+            [pair("media_url", "invalid_url")]
+        );
+        assert!(
+            codes(UserMessage::text("b", "u", "s", "web", "").with_media("HTTPS://cdn")).is_empty()
+        );
+
+        let err: BotError = UserMessage::text("b", "u", "s", "", "hi")
+            .validate(&limits)
+            .err()
+            .map(BotError::from)
+            .unwrap_or_else(|| BotError::internal("expected an error"));
+        assert_eq!(err.status_code(), 400);
+        assert_eq!(err.error_code(), "validation_error");
+    }
+
+    #[test]
+    fn test_location_and_contact_round_trip() {
+        let location = Location::new(-23.5505, -46.6333)
+            .with_name("Paulista")
+            .with_address("Av. Paulista, 1000");
+        let msg = UserMessage::location("bot", "u1", "s1", "whatsapp", location.clone());
+        assert!(msg.has_payload());
+        assert_eq!(msg.payload_kind(), Some("location"));
+        assert_eq!(msg.message_type, MessageType::USER);
+
+        let json = serde_json::to_value(&msg).unwrap_or_default();
+        assert_eq!(json["payload"]["type"], "location");
+        assert_eq!(json["payload"]["name"], "Paulista");
+        let back: Option<UserMessage> = serde_json::from_value(json).ok();
+        assert_eq!(
+            back.and_then(|m| m.payload),
+            Some(MessagePayload::Location(location))
+        );
+
+        let contact = ContactCard::new("Ana").with_phone("+5511999999999");
+        let json = serde_json::to_string(&UserMessage::contact(
+            "bot",
+            "u1",
+            "s1",
+            "whatsapp",
+            contact.clone(),
+        ))
+        .unwrap_or_default();
+        let back: Option<UserMessage> = serde_json::from_str(&json).ok();
+        assert_eq!(
+            back.as_ref().and_then(UserMessage::payload_kind),
+            Some("contact")
+        );
+        assert_eq!(
+            back.and_then(|m| m.payload),
+            Some(MessagePayload::Contact(contact))
+        );
+
+        let plain = serde_json::to_value(UserMessage::text("bot", "u1", "s1", "web", "hi"))
+            .unwrap_or_default();
+        assert!(plain.get("payload").is_none());
+    }
+
+    #[test]
+    fn test_payload_validation() {
+        let limits = SystemLimits::default();
+        let fields = |msg: UserMessage| -> Vec<String> {
+            msg.validate(&limits)
+                .err()
+                .map(|errors| errors.errors().iter().map(|e| e.field.clone()).collect())
+                .unwrap_or_default()
+        };
+
+        assert!(fields(UserMessage::location(
+            "b",
+            "u",
+            "s",
+            "wa",
+            Location::new(90.0, -180.0)
+        ))
+        .is_empty());
+        assert_eq!(
+            fields(UserMessage::location(
+                "b",
+                "u",
+                "s",
+                "wa",
+                Location::new(91.0, 180.5)
+            )),
+            ["payload.latitude", "payload.longitude"]
+        );
+        assert!(fields(UserMessage::contact(
+            "b",
+            "u",
+            "s",
+            "wa",
+            ContactCard::new("Ana").with_email("ana@example.com")
+        ))
+        .is_empty());
+        assert_eq!(
+            fields(UserMessage::contact(
+                "b",
+                "u",
+                "s",
+                "wa",
+                ContactCard::new(" ")
+            )),
+            ["payload.display_name", "payload.phones"]
+        );
+    }
+
+    #[test]
+    fn test_bot_response_validate_rules() {
+        let limits = SystemLimits {
+            max_suggestions_per_response: 2,
+            ..SystemLimits::default()
+        };
+        assert!(BotResponse::new("b", "s", "u", "hi", "web")
+            .validate(&limits)
+            .is_ok());
+        assert!(BotResponse::default()
+            .with_suggestions(["yes"])
+            .validate(&limits)
+            .is_ok());
+        assert!(BotResponse::default()
+            .with_card(Card::new("Pro"))
+            .validate(&limits)
+            .is_ok());
+        assert!(BotResponse::streaming("b", "s", "u", "web", "t1")
+            .validate(&limits)
+            .is_ok());
+
+        assert_eq!(
+            BotResponse::default()
+                .validate(&limits)
+                .err()
+                .map(|e| e.to_string())
+                .as_deref(),
+            Some("1 validation error: content")
+        );
+        let crowded = BotResponse::default().with_suggestions(["a", "b", "c"]);
+        let errors = crowded.validate(&limits).err().unwrap_or_default();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors.errors().first().map(|e| e.code.as_str()),
+            Some("too_many")
+        );
+    }
+
+    #[test]
+    fn test_sanitized_strips_control_characters() {
+        let msg = UserMessage::text(
+            "b",
+            "u",
+            "s",
+            "web",
+            "  \u{0}Olá\u{7},\r\n\tmundo\u{1b}[31m \n",
+        )
+        .sanitized();
+        assert_eq!(msg.content, "Olá,\n\tmundo[31m");
+    }
+
+    #[test]
+    fn test_sanitize_and_validate() {
+        let limits = SystemLimits {
+            max_string_length: 8,
+            ..SystemLimits::default()
+        };
+        let long = UserMessage::text("b", "u", "s", "web", "\u{202E}uma mensagem longa");
+        assert!(long.validate(&limits).is_err());
+        let clean = long.sanitize_and_validate(&limits).ok();
+        assert_eq!(clean.map(|m| m.content), Some("uma men…".to_string()));
+
+        let invisible = UserMessage::text("b", "u", "s", "web", "\u{200B}\u{2066} ");
+        assert!(invisible.validate(&limits).is_ok());
+        let fields: Vec<String> = invisible
+            .sanitize_and_validate(&limits)
+            .err()
+            .map(|e| e.errors().iter().map(|f| f.field.clone()).collect())
+            .unwrap_or_default();
+        assert_eq!(fields, ["content"]);
+    }
+
+    #[test]
+    fn test_session_validate() {
+        let session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "Support");
+        assert!(session.validate().is_ok());
+
+        let expired_before_start = Session::new(Uuid::nil(), Uuid::new_v4(), "Support")
+            .with_expiry(Utc::now() - chrono::Duration::hours(1));
+        let err = expired_before_start.validate().err();
+        assert_eq!(
+            err.map(|e| e.to_string()).as_deref(),
+            Some("2 validation errors: user_id, expires_at")
+        );
+    }
+
+    #[test]
+    fn test_api_response_map() {
+        let response: ApiResponse<i32> = ApiResponse::success(42);
+        let mapped = response.map(|n| n.to_string());
+        assert_eq!(mapped.data, Some("42".to_string()));
+    }
+
+    #[test]
+    fn test_api_response_into_result() {
+        assert_eq!(ApiResponse::success(42).into_result().ok(), Some(42));
+
+        let failed: ApiResponse<i32> = ApiResponse::error_with_code("name taken", "conflict");
+        assert!(matches!(
+            failed.into_result(),
+            Err(BotError::Conflict(ref msg)) if msg == "name taken"
+        ));
+
+        let unknown: ApiResponse<i32> = ApiResponse::error_with_code("quota hit", "quota");
+        assert_eq!(
+            unknown
+                .into_result()
+                .err()
+                .map(|e| e.to_string())
+                .as_deref(),
+            Some("quota hit")
+        );
+    }
+
+    #[test]
+    fn test_api_response_round_trips_every_error_variant() {
+        let mut fields = ValidationErrors::new();
+        fields.require("content", "");
+        let errors = [
+            BotError::config("missing key"),
+            BotError::database("connection reset"),
+            BotError::http(502, "bad gateway"),
+            BotError::auth("token expired"),
+            BotError::validation("x"),
+            BotError::ValidationFields(fields),
+            BotError::not_found_in_tenant("Bot", "42", "acme"),
+            BotError::conflict("taken"),
+            BotError::unsupported_inbound("whatsapp", "sticker"),
+            BotError::rate_limited(30),
+            BotError::service_unavailable_retry_after("draining", 5),
+            BotError::timeout(1500),
+            BotError::internal("oops"),
+            BotError::Io(std::io::Error::other("disk full")),
+            BotError::Json(<serde_json::Error as serde::de::Error>::custom("bad json")),
+            BotError::Other("something".to_string()),
+        ];
+        for error in errors {
+            let expected = (
+                error.error_code(),
+                error.status_code(),
+                error.to_string(),
+                error.retry_after(),
+            );
+            let response: ApiResponse<()> = ApiResponse::from(error);
+            let json = serde_json::to_string(&response).unwrap_or_default();
+            let back = serde_json::from_str::<ApiResponse<()>>(&json)
+                .ok()
+                .and_then(|r| r.into_result().err())
+                .map(|e| {
+                    (
+                        e.error_code(),
+                        e.status_code(),
+                        e.to_string(),
+                        e.retry_after(),
+                    )
+                });
+            assert_eq!(back, Some(expected), "{json}");
+        }
+    }
+
+    #[test]
+    fn test_api_response_success_without_data_is_internal_error() {
+        let response: Option<ApiResponse<i32>> = serde_json::from_str(r#"{"success": true}"#).ok();
+        assert!(matches!(
+            response.map(ApiResponse::into_result),
+            Some(Err(BotError::Internal(_)))
+        ));
+    }
+
+    #[test]
+    fn test_api_response_try_map() {
+        let parsed = ApiResponse::success("42".to_string())
+            .with_warning("deprecated endpoint")
+            .try_map(|s| s.parse::<i32>());
+        assert_eq!(parsed.data, Some(42));
+        assert_eq!(parsed.warnings, ["deprecated endpoint"]);
+
+        let failed = ApiResponse::success("x".to_string())
+            .with_warning("deprecated endpoint")
+            .try_map(|s| s.parse::<i32>());
+        assert!(failed.is_error());
+        assert!(failed.data.is_none());
+        assert_eq!(failed.code.as_deref(), Some("validation_error"));
+        assert_eq!(failed.warnings, ["deprecated endpoint"]);
+    }
+
+    #[test]
+    fn test_api_response_and_then() {
+        let chained = ApiResponse::success(2)
+            .with_warning("first")
+            .and_then(|n| ApiResponse::success(n * 10).with_warning("second"));
+        assert_eq!(chained.data, Some(20));
+        assert_eq!(chained.warnings, ["first", "second"]);
+
+        let mut called = false;
+        let failed: ApiResponse<i32> = ApiResponse::error("down");
+        let chained = failed.and_then(|n| {
+            called = true;
+            ApiResponse::success(n)
+        });
+        assert!(!called);
+        assert_eq!(chained.error.as_deref(), Some("down"));
+    }
+
+    #[test]
+    fn test_api_response_from_bot_result() {
+        let ok: ApiResponse<i32> = Ok(7).into();
+        assert_eq!(ok.data, Some(7));
+        let err: ApiResponse<i32> = BotResult::Err(BotError::conflict("taken")).into();
+        assert_eq!(err.code.as_deref(), Some("conflict"));
+        assert_eq!(err.error.as_deref(), Some("taken"));
+    }
 
     #[test]
     fn test_session_creation() {
@@ -522,6 +3042,254 @@ mod tests {
         assert!(!session.is_expired());
     }
 
+    fn conflict<T>(result: BotResult<T>) -> Option<String> {
+        match result {
+            Err(BotError::Conflict(message)) => Some(message.into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_session_legal_transitions() {
+        let mut session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "Suporte");
+        let created = session.updated_at;
+        let closed = SessionState::Closed {
+            reason: CloseReason::ClosedByAgent,
+        };
+        type Step = fn(&mut Session) -> BotResult<()>;
+        let steps: [(Step, SessionState); 9] = [
+            (Session::mark_idle, SessionState::Idle),
+            (Session::reopen, SessionState::Active),
+            (Session::escalate, SessionState::Handover),
+            (Session::reopen, SessionState::Active),
+            (Session::mark_idle, SessionState::Idle),
+            (Session::escalate, SessionState::Handover),
+            (|s| s.close(CloseReason::ClosedByAgent), closed),
+            (Session::reopen, SessionState::Active),
+            (|s| s.close(CloseReason::ClosedByAgent), closed),
+        ];
+        for (step, expected) in steps {
+            let from = session.state;
+            assert!(step(&mut session).is_ok(), "{from:?} -> {expected:?}");
+            assert_eq!(session.state, expected);
+            assert_eq!(
+                session.history.last().map(|t| (t.from, t.to)),
+                Some((from, expected))
+            );
+        }
+        assert!(session.archive().is_ok());
+        assert_eq!(session.state, SessionState::Archived);
+        assert_eq!(session.history.len(), 10);
+        assert!(session.updated_at >= created);
+        assert_eq!(session.state_changed_at, Some(session.updated_at));
+
+        let mut idle = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x");
+        assert!(idle.mark_idle().is_ok());
+        assert!(idle.close(CloseReason::Timeout).is_ok());
+        assert_eq!(
+            idle.state,
+            SessionState::Closed {
+                reason: CloseReason::Timeout
+            }
+        );
+    }
+
+    #[test]
+    fn test_session_illegal_transitions() {
+        let mut session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x");
+        assert_eq!(
+            conflict(session.reopen()),
+            Some(format!(
+                "cannot reopen session {}: it is active",
+                session.id
+            ))
+        );
+        assert!(conflict(session.archive()).is_some());
+        assert!(session.escalate().is_ok());
+        assert!(conflict(session.escalate()).is_some());
+        assert!(conflict(session.mark_idle()).is_some());
+        assert!(session.close(CloseReason::Resolved).is_ok());
+        assert!(conflict(session.close(CloseReason::Resolved)).is_some());
+        assert!(conflict(session.mark_idle()).is_some());
+        assert!(conflict(session.escalate()).is_some());
+        assert!(session.archive().is_ok());
+        let history = session.history.len();
+        for result in [
+            session.reopen(),
+            session.close(CloseReason::Resolved),
+            session.archive(),
+        ] {
+            assert!(conflict(result).is_some_and(|m| m.ends_with("it is archived")));
+        }
+        assert_eq!(session.history.len(), history);
+        assert_eq!(session.state, SessionState::Archived);
+    }
+
+    #[test]
+    fn test_session_is_active_reflects_state() {
+        let mut session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x");
+        assert!(session.mark_idle().is_ok());
+        assert!(!session.is_active());
+        assert!(session.escalate().is_ok());
+        assert!(session.is_active());
+        assert!(session.close(CloseReason::ClosedByUser).is_ok());
+        assert!(!session.is_active());
+
+        let expired = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x")
+            .with_expiry(Utc::now() - chrono::Duration::minutes(1));
+        assert_eq!(expired.state, SessionState::Active);
+        assert!(!expired.is_active());
+    }
+
+    #[test]
+    fn test_session_tags_are_case_insensitive_and_deduplicated() {
+        let mut session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x");
+        assert!(session.add_tag("VIP"));
+        assert!(!session.add_tag("vip"));
+        assert!(!session.add_tag("  Vip "));
+        assert!(!session.add_tag("   "));
+        assert!(session.add_tag("Ação"));
+        assert!(!session.add_tag("AÇÃO"));
+        assert_eq!(session.tags, ["VIP", "Ação"]);
+        assert!(session.has_tag("vip") && session.has_tag("ação"));
+        assert!(!session.has_tag("churn"));
+
+        let session = session.with_metadata([("locale", "pt-BR"), ("campaign", "bf-2024")]);
+        assert_eq!(session.get_meta("locale"), Some("pt-BR"));
+        assert_eq!(session.get_meta("missing"), None);
+        assert!(session.validate().is_ok());
+    }
+
+    #[test]
+    fn test_session_metadata_and_tag_caps() {
+        let fields = |session: &Session| match session.validate() {
+            Err(BotError::ValidationFields(errors)) => errors
+                .errors()
+                .iter()
+                .map(|e| (e.field.clone(), e.code.clone(), e.rejected_value.clone()))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mut session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x");
+        session.set_meta("note", "x".repeat(MAX_SESSION_METADATA_VALUE_LENGTH + 1));
+        session.set_meta("k".repeat(MAX_SESSION_METADATA_KEY_LENGTH + 1), "v");
+        session.add_tag(&"t".repeat(MAX_SESSION_TAG_LENGTH + 1));
+        let long_key = format!(
+            "metadata.{}",
+            "k".repeat(MAX_SESSION_METADATA_KEY_LENGTH + 1)
+        );
+        assert_eq!(
+            fields(&session),
+            [
+                (
+                    long_key,
+                    "too_long".to_string(),
+                    Some(Value::from(MAX_SESSION_METADATA_KEY_LENGTH + 1))
+                ),
+                (
+                    "metadata.note".to_string(),
+                    "too_long".to_string(),
+                    Some(Value::from(MAX_SESSION_METADATA_VALUE_LENGTH + 1))
+                ),
+                (
+                    "tags[0]".to_string(),
+                    "too_long".to_string(),
+                    Some(Value::from(MAX_SESSION_TAG_LENGTH + 1))
+                ),
+            ]
+        );
+
+        let mut session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x")
+            .with_metadata((0..=MAX_SESSION_METADATA_ENTRIES).map(|i| (format!("k{i}"), "v")));
+        for i in 0..=MAX_SESSION_TAGS {
+            session.add_tag(&format!("tag-{i}"));
+        }
+        let too_many = |field: &str, count: usize| {
+            (
+                field.to_string(),
+                "too_many".to_string(),
+                Some(Value::from(count)),
+            )
+        };
+        assert_eq!(
+            fields(&session),
+            [
+                too_many("metadata", MAX_SESSION_METADATA_ENTRIES + 1),
+                too_many("tags", MAX_SESSION_TAGS + 1),
+            ]
+        );
+        let message = LimitExceeded {
+            limit_type: crate::limits::LimitType::ArrayLength,
+            current: 51,
+            maximum: 50,
+            retry_after_secs: None,
+        }
+        .to_field_error("tags")
+        .message;
+        assert_eq!(message, "tags must have at most 50 entries");
+    }
+
+    #[test]
+    fn test_session_metadata_serde() {
+        let plain = serde_json::to_value(Session::new(Uuid::new_v4(), Uuid::new_v4(), "x"))
+            .unwrap_or_default();
+        assert!(plain.get("metadata").is_none() && plain.get("tags").is_none());
+        let back: Option<Session> = serde_json::from_value(plain).ok();
+        assert_eq!(
+            back.map(|s| (s.metadata.is_empty(), s.tags.is_empty(), s.extra.is_empty())),
+            Some((true, true, true))
+        );
+
+        let mut session =
+            Session::new(Uuid::new_v4(), Uuid::new_v4(), "x").with_metadata([("locale", "pt-BR")]);
+        session.add_tag("vip");
+        let value = serde_json::to_value(&session).unwrap_or_default();
+        assert_eq!(
+            value.get("metadata"),
+            Some(&serde_json::json!({"locale": "pt-BR"}))
+        );
+        assert_eq!(value.get("tags"), Some(&serde_json::json!(["vip"])));
+        let back: Option<Session> = serde_json::from_value(value).ok();
+        assert_eq!(
+            back.map(|s| (s.metadata, s.tags, s.extra.len())),
+            Some((session.metadata, session.tags, 0))
+        );
+    }
+
+    #[test]
+    fn test_session_state_serde() {
+        let old = serde_json::json!({
+            "id": Uuid::new_v4(),
+            "user_id": Uuid::new_v4(),
+            "bot_id": Uuid::new_v4(),
+            "title": "legacy",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z"
+        });
+        let session: Option<Session> = serde_json::from_value(old).ok();
+        assert_eq!(
+            session
+                .as_ref()
+                .map(|s| (s.state, s.state_changed_at, s.history.len(), s.extra.len())),
+            Some((SessionState::Active, None, 0, 0))
+        );
+
+        let mut session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "x");
+        assert!(session.close(CloseReason::Timeout).is_ok());
+        let value = serde_json::to_value(&session).unwrap_or_default();
+        assert_eq!(
+            value.get("state"),
+            Some(&serde_json::json!({"status": "closed", "reason": "timeout"}))
+        );
+        assert_eq!(
+            value.pointer("/history/0/from"),
+            Some(&serde_json::json!({"status": "active"}))
+        );
+        let back: Option<Session> = serde_json::from_value(value).ok();
+        assert_eq!(back.as_ref().map(|s| s.state), Some(session.state));
+        assert_eq!(back.map(|s| s.history), Some(session.history));
+    }
+
     #[test]
     fn test_user_message_creation() {
         let msg =
@@ -544,6 +3312,89 @@ mod tests {
         assert_eq!(response.suggestions.len(), 2);
     }
 
+    #[test]
+    fn test_strict_message_types() {
+        let strict = SystemLimits {
+            strict_message_types: true,
+            ..SystemLimits::default()
+        };
+        let codes = |result: Result<(), ValidationErrors>| {
+            result
+                .err()
+                .map(|e| {
+                    e.errors()
+                        .iter()
+                        .map(|e| (e.field.clone(), e.code.clone(), e.rejected_value.clone()))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        for value in [-5, -1, 12, 9999, i32::MAX] {
+            let message = UserMessage {
+                message_type: MessageType(value),
+                ..UserMessage::text("bot", "u1", "s1", "web", "oi")
+            };
+            assert!(message.validate(&SystemLimits::default()).is_ok());
+            assert_eq!(
+                codes(message.validate(&strict)),
+                [(
+                    "message_type".to_string(),
+                    "unknown".to_string(),
+                    Some(serde_json::json!(value))
+                )]
+            );
+
+            let response = BotResponse {
+                message_type: MessageType(value),
+                ..BotResponse::new("bot", "s1", "u1", "Olá", "web")
+            };
+            assert!(response.validate(&SystemLimits::default()).is_ok());
+            assert_eq!(codes(response.validate(&strict)).len(), 1);
+        }
+
+        for value in [0, 11] {
+            let response = BotResponse {
+                message_type: MessageType(value),
+                ..BotResponse::new("bot", "s1", "u1", "Olá", "web")
+            };
+            assert!(response.validate(&strict).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_bot_response_matches_filter() {
+        let filter = MessageTypeSet::from([MessageType::BOT_RESPONSE, MessageType::SUGGESTION]);
+        let response = BotResponse::new("bot", "s1", "u1", "Olá", "web");
+        assert!(response.matches(&filter));
+        assert!(!BotResponse::error_message("bot", "s1", "u1", "falhou", "web").matches(&filter));
+        assert!(!response.matches(&MessageTypeSet::none()));
+    }
+
+    #[test]
+    fn test_system_notice_and_error_message() {
+        let notice = BotResponse::system_notice("bot", "s1", "u1", "Ana joined the chat", "web");
+        assert_eq!(
+            (
+                notice.message_type,
+                notice.content_format,
+                notice.is_complete
+            ),
+            (MessageType::SYSTEM, ContentFormat::PlainText, true)
+        );
+        assert_eq!(
+            (notice.session_id.as_str(), notice.user_id.as_str()),
+            ("s1", "u1")
+        );
+
+        let error = BotResponse::error_message("bot", "s1", "u1", "Payment failed", "web");
+        assert_eq!(error.message_type, MessageType::ERROR);
+        assert_eq!(error.message_type.to_string(), "ERROR");
+        assert!(error.message_type.is_system() && !error.message_type.is_bot());
+        assert_eq!(error.content, "Payment failed");
+        assert!(error.validate(&SystemLimits::default()).is_ok());
+    }
+
     #[test]
     fn test_bot_response_streaming() {
         let mut response = BotResponse::streaming("bot1", "sess1", "user1", "web", "token123");
@@ -578,4 +3429,958 @@ mod tests {
         assert_eq!(suggestion.text, "Click here");
         assert!(suggestion.context.is_none());
     }
+
+    fn conversation() -> Conversation {
+        let session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "Support");
+        let messages = vec![
+            Message::new("s1", MessageDirection::System, "Be brief"),
+            Message::new("s1", MessageDirection::User, "Olá, café?"),
+            Message::new("s1", MessageDirection::Bot, "Sim ☕"),
+            Message::new("s1", MessageDirection::User, "Obrigado"),
+        ];
+        Conversation::new(session, messages).with_total_count(40)
+    }
+
+    #[test]
+    fn test_message_from_user_message_and_bot_response() {
+        let user = UserMessage::text("bot1", "user1", "sess1", "whatsapp", "Hi")
+            .with_media("https://cdn/a.pdf")
+            .with_context("billing");
+        let timestamp = user.timestamp;
+        let message = Message::from(user);
+        assert_eq!(message.direction, MessageDirection::User);
+        assert_eq!(message.session_id, "sess1");
+        assert_eq!(message.created_at, timestamp);
+        assert!(!message.id.is_nil());
+        assert_eq!(message.attachments.len(), 1);
+        assert_eq!(message.metadata["channel"], "whatsapp");
+        assert_eq!(message.metadata["context_name"], "billing");
+
+        let reply = Message::from(BotResponse::new("bot1", "sess1", "user1", "Hello", "web"));
+        assert!(reply.is_from_bot());
+        assert_eq!(reply.message_type, MessageType::BOT_RESPONSE);
+        assert_ne!(reply.id, message.id);
+        assert!(reply.metadata.get("context_name").is_none());
+    }
+
+    #[test]
+    fn test_message_serde() {
+        let message = Message::new("s1", MessageDirection::Bot, "Hi").with_metadata("lang", "en");
+        let value = serde_json::to_value(&message).unwrap_or_default();
+        assert_eq!(value["direction"], "bot");
+        assert_eq!(value["message_type"], 2);
+        assert_eq!(value["metadata"]["lang"], "en");
+        assert!(value.get("attachments").is_none());
+
+        let back: Option<Message> = serde_json::from_value(value).ok();
+        assert_eq!(back.as_ref().map(|m| m.id), Some(message.id));
+        assert_eq!(back.map(|m| m.content), Some("Hi".to_string()));
+    }
+
+    #[test]
+    fn test_conversation_last_messages() {
+        let conversation = conversation();
+        assert_eq!(
+            conversation.last_user_message().map(|m| m.content.as_str()),
+            Some("Obrigado")
+        );
+        assert_eq!(
+            conversation.last_bot_message().map(|m| m.content.as_str()),
+            Some("Sim ☕")
+        );
+        assert_eq!(conversation.total_count, 40);
+    }
+
+    #[test]
+    fn test_prompt_transcript_evicts_oldest_messages() {
+        let conversation = conversation();
+        let full = conversation.to_prompt_transcript(usize::MAX);
+        assert_eq!(
+            full,
+            "System: Be brief\nUser: Olá, café?\nBot: Sim ☕\nUser: Obrigado"
+        );
+
+        for max_chars in 0..=full.chars().count() {
+            let transcript = conversation.to_prompt_transcript(max_chars);
+            assert!(transcript.chars().count() <= max_chars);
+            assert!(full.ends_with(&transcript));
+        }
+        assert_eq!(conversation.to_prompt_transcript(58).chars().count(), 42);
+        assert_eq!(
+            conversation.to_prompt_transcript(42),
+            "User: Olá, café?\nBot: Sim ☕\nUser: Obrigado"
+        );
+        assert_eq!(
+            conversation.to_prompt_transcript(41),
+            "Bot: Sim ☕\nUser: Obrigado"
+        );
+        assert_eq!(conversation.to_prompt_transcript(24), "User: Obrigado");
+        assert_eq!(conversation.to_prompt_transcript(10), ": Obrigado");
+    }
+
+    #[test]
+    fn test_page_cursor_round_trip() {
+        let cursor = PageCursor::new(Utc::now(), Uuid::new_v4());
+        let encoded = cursor.encode();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(PageCursor::decode(&encoded).ok(), Some(cursor));
+
+        assert!(matches!(
+            PageCursor::decode("not a cursor"),
+            Err(BotError::Validation(_))
+        ));
+        assert!(matches!(
+            PageCursor::decode(&BASE64_URL_SAFE_NO_PAD.encode("{}")),
+            Err(BotError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_page() {
+        let page: PaginatedResponse<String> = PaginatedResponse::from_items(Vec::new(), 20);
+        assert!(page.is_empty());
+        assert!(!page.has_more());
+        let value = serde_json::to_value(ApiResponse::from(page)).unwrap_or_default();
+        assert_eq!(
+            value,
+            serde_json::json!({"success": true, "data": {"items": [], "limit": 20}})
+        );
+    }
+
+    #[test]
+    fn test_page_map_preserves_cursors() {
+        let next = PageCursor::new(Utc::now(), Uuid::new_v4()).encode();
+        let page = PaginatedResponse::from_items(vec![1, 2, 3], 3)
+            .with_total(10)
+            .with_next_cursor(next.clone())
+            .with_prev_cursor("prev");
+        let mapped = page.map(|n| n.to_string());
+        assert_eq!(mapped.items, ["1", "2", "3"]);
+        assert_eq!(mapped.total, Some(10));
+        assert_eq!(mapped.next_cursor.as_deref(), Some(next.as_str()));
+        assert_eq!(mapped.prev_cursor.as_deref(), Some("prev"));
+        assert_eq!(mapped.limit, 3);
+        assert!(mapped.has_more());
+    }
+
+    #[test]
+    fn test_suggestion_legacy_action_still_deserializes() {
+        let legacy: Option<Suggestion> =
+            serde_json::from_str(r#"{"text": "Yes", "action": "foo"}"#).ok();
+        assert_eq!(
+            legacy.as_ref().and_then(|s| s.action.as_deref()),
+            Some("foo")
+        );
+        assert!(legacy.as_ref().is_some_and(|s| s.typed_action.is_none()));
+        assert_eq!(
+            legacy.and_then(|s| s.resolved_action()),
+            Some(SuggestionAction::Postback {
+                payload: "foo".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_suggestion_typed_actions_serde() {
+        let suggestion = Suggestion::new("Call us")
+            .with_call("+5511999990000")
+            .with_display_order(2);
+        let value = serde_json::to_value(&suggestion).unwrap_or_default();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "text": "Call us",
+                "typed_action": {"type": "call", "phone": "+5511999990000"},
+                "display_order": 2
+            })
+        );
+
+        let custom = SuggestionAction::Custom {
+            kind: "carousel".to_string(),
+            data: serde_json::json!({"page": 2}),
+        };
+        let round_trip: Option<Suggestion> = serde_json::to_value(
+            Suggestion::new("More")
+                .with_typed_action(custom.clone())
+                .with_action("more"),
+        )
+        .ok()
+        .and_then(|v| serde_json::from_value(v).ok());
+        assert_eq!(
+            round_trip.as_ref().and_then(|s| s.typed_action.clone()),
+            Some(custom.clone())
+        );
+        assert_eq!(round_trip.and_then(|s| s.resolved_action()), Some(custom));
+    }
+
+    #[test]
+    fn test_suggestion_builders() {
+        let response = BotResponse::default().with_suggestions([
+            Suggestion::new("Docs").with_url("https://docs.example.com"),
+            Suggestion::new("Sales").with_context_switch("sales"),
+            Suggestion::new("Yes").with_postback("confirm"),
+        ]);
+        let actions: Vec<Option<SuggestionAction>> = response
+            .suggestions
+            .iter()
+            .map(|s| s.typed_action.clone())
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                Some(SuggestionAction::OpenUrl {
+                    url: "https://docs.example.com".to_string()
+                }),
+                Some(SuggestionAction::ContextSwitch {
+                    context: "sales".to_string()
+                }),
+                Some(SuggestionAction::Postback {
+                    payload: "confirm".to_string()
+                }),
+            ]
+        );
+        let plain = BotResponse::default().with_suggestions(["a", "b"]);
+        assert!(plain
+            .suggestions
+            .iter()
+            .all(|s| s.resolved_action().is_none()));
+    }
+
+    fn product_card() -> Card {
+        Card::new("Plano Pro")
+            .with_subtitle("R$ 49/mês")
+            .with_image("https://cdn.example.com/pro.png")
+            .with_button(
+                "Assinar",
+                SuggestionAction::OpenUrl {
+                    url: "https://example.com/pro".to_string(),
+                },
+            )
+            .with_button(
+                "Ligar",
+                SuggestionAction::Call {
+                    phone: "+5511999990000".to_string(),
+                },
+            )
+            .with_button(
+                "Mais",
+                SuggestionAction::Postback {
+                    payload: "more".to_string(),
+                },
+            )
+            .with_footer("Cancele quando quiser")
+    }
+
+    #[test]
+    fn test_cards_serde_compat() {
+        let old: Option<BotResponse> = serde_json::from_value(serde_json::json!({
+            "bot_id": "b", "user_id": "u", "session_id": "s", "channel": "web",
+            "content": "hi", "message_type": 2, "is_complete": true
+        }))
+        .ok();
+        assert!(old.as_ref().is_some_and(|r| r.cards.is_empty()));
+
+        let plain = serde_json::to_value(BotResponse::default()).unwrap_or_default();
+        assert!(plain.get("cards").is_none());
+
+        let response = BotResponse::default().with_card(product_card());
+        let value = serde_json::to_value(&response).unwrap_or_default();
+        assert_eq!(value["cards"][0]["buttons"][1]["action"]["type"], "call");
+        let back: Option<BotResponse> = serde_json::from_value(value).ok();
+        assert_eq!(back.map(|r| r.cards), Some(vec![product_card()]));
+    }
+
+    #[test]
+    fn test_card_button_cap() {
+        assert!(product_card().validate(DEFAULT_MAX_CARD_BUTTONS).is_ok());
+        assert!(matches!(
+            product_card().validate(2),
+            Err(BotError::ValidationFields(_))
+        ));
+
+        let mut response = BotResponse::default().with_card(Card::new("ok"));
+        response.add_card(product_card());
+        response.add_card(Card::new(" "));
+        assert!(response.validate_cards(3).is_err());
+        assert_eq!(
+            response
+                .validate_cards(2)
+                .err()
+                .map(|e| e.to_string())
+                .as_deref(),
+            Some("2 validation errors: cards[1].buttons, cards[2].title")
+        );
+    }
+
+    #[test]
+    fn test_render_plain_text_fallback() {
+        let response = BotResponse::new("b", "s", "u", "Escolha um plano:", "sms")
+            .with_card(product_card())
+            .with_card(Card::new("Grátis"));
+        assert_eq!(
+            response.render_plain_text(),
+            "Escolha um plano:\n\n\
+             *Plano Pro*\nR$ 49/mês\nhttps://cdn.example.com/pro.png\n\
+             - Assinar: https://example.com/pro\n- Ligar: +5511999990000\n- Mais\n\
+             _Cancele quando quiser_\n\n\
+             *Grátis*"
+        );
+        assert_eq!(
+            BotResponse::default()
+                .with_card(Card::new("Only"))
+                .render_plain_text(),
+            "*Only*"
+        );
+    }
+
+    #[test]
+    fn test_content_format_conversions() {
+        let markdown = BotResponse::new("b", "s", "u", "**Hi** [docs](https://e.com)", "web");
+        assert_eq!(markdown.content_format, ContentFormat::Markdown);
+        assert_eq!(markdown.to_plain_text(), "Hi docs (https://e.com)");
+        assert_eq!(
+            markdown.to_html(),
+            "<p><strong>Hi</strong> <a href=\"https://e.com\">docs</a></p>"
+        );
+
+        let plain = BotResponse::new("b", "s", "u", "**a** < b", "sms")
+            .with_content_format(ContentFormat::PlainText);
+        assert_eq!(plain.to_plain_text(), "**a** < b");
+        assert_eq!(plain.to_html(), "**a** &lt; b");
+
+        let html = BotResponse::new("b", "s", "u", "<p>a &amp; b</p>", "web")
+            .with_content_format(ContentFormat::Html);
+        assert_eq!(html.to_plain_text(), "a & b");
+        assert_eq!(html.to_html(), "<p>a &amp; b</p>");
+
+        let value = serde_json::to_value(&html).unwrap_or_default();
+        assert_eq!(value["content_format"], "html");
+        let mut old = value;
+        if let Some(object) = old.as_object_mut() {
+            object.remove("content_format");
+        }
+        let back: Option<BotResponse> = serde_json::from_value(old).ok();
+        assert_eq!(
+            back.map(|r| r.content_format),
+            Some(ContentFormat::Markdown)
+        );
+    }
+
+    #[test]
+    fn test_metadata_and_correlation_backward_compat() {
+        let old: Option<UserMessage> = serde_json::from_value(serde_json::json!({
+            "bot_id": "b", "user_id": "u", "session_id": "s", "channel": "web",
+            "content": "hi", "message_type": 1, "timestamp": "2024-01-01T00:00:00Z"
+        }))
+        .ok();
+        assert!(old.as_ref().is_some_and(|m| m.id.is_none()
+            && m.correlation_id.is_none()
+            && m.metadata.is_empty()));
+
+        let value = serde_json::to_value(BotResponse::default()).unwrap_or_default();
+        assert!(value.get("metadata").is_none());
+        assert!(value.get("correlation_id").is_none());
+
+        let mut msg = UserMessage::text("b", "u", "s", "web", "hi");
+        msg.set_meta("campaign", "black-friday");
+        msg.set_meta("ab_bucket", 2);
+        assert_eq!(msg.get_meta_str("campaign"), Some("black-friday"));
+        assert_eq!(msg.get_meta_str("ab_bucket"), None);
+        assert_eq!(msg.get_meta("ab_bucket"), Some(&Value::from(2)));
+        let back: Option<UserMessage> = serde_json::to_value(&msg)
+            .ok()
+            .and_then(|v| serde_json::from_value(v).ok());
+        assert_eq!(
+            back.as_ref().and_then(|m| m.get_meta_str("campaign")),
+            Some("black-friday")
+        );
+        assert_eq!(back.and_then(|m| m.id), msg.id);
+    }
+
+    #[test]
+    fn test_reply_to_copies_routing_and_correlation() {
+        let correlation_id = Uuid::new_v4();
+        let source = UserMessage::text("bot1", "user1", "sess1", "whatsapp", "Oi")
+            .with_context("vendas")
+            .with_correlation_id(correlation_id);
+        let mut reply = BotResponse::reply_to(&source, "Olá!");
+        reply.set_meta("trace_id", "t-1");
+
+        assert_eq!(reply.bot_id, "bot1");
+        assert_eq!(reply.user_id, "user1");
+        assert_eq!(reply.session_id, "sess1");
+        assert_eq!(reply.channel, "whatsapp");
+        assert_eq!(reply.content, "Olá!");
+        assert_eq!(reply.context_name.as_deref(), Some("vendas"));
+        assert_eq!(reply.correlation_id, Some(correlation_id));
+        assert_eq!(reply.reply_to_id, source.id);
+        assert!(reply.is_complete);
+
+        let stored = Message::from(reply);
+        assert_eq!(stored.metadata["trace_id"], "t-1");
+        assert_eq!(
+            Message::from(source.clone()).id,
+            source.id.unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn test_inbound_frame_tagged_round_trip() {
+        let message_id = Uuid::new_v4();
+        let frames = [
+            InboundFrame::from(UserMessage::text("b", "u", "s", "web", "hi")),
+            InboundFrame::Event(MessageEvent::typing_start("s", "u")),
+            InboundFrame::Event(MessageEvent::read("s", "u", message_id)),
+            InboundFrame::Event(MessageEvent::failed("s", "u", message_id, "blocked")),
+        ];
+        for frame in frames {
+            let value = serde_json::to_value(&frame).unwrap_or_default();
+            let back: Option<InboundFrame> = serde_json::from_value(value.clone()).ok();
+            match (&frame, back) {
+                (InboundFrame::Message(sent), Some(InboundFrame::Message(received))) => {
+                    assert_eq!(value["kind"], "message");
+                    assert_eq!(received.id, sent.id);
+                    assert_eq!(received.content, sent.content);
+                }
+                (InboundFrame::Event(sent), Some(InboundFrame::Event(received))) => {
+                    assert_eq!(value["kind"], "event");
+                    assert_eq!(&received, sent);
+                }
+                (_, back) => assert!(back.is_some(), "{value}"),
+            }
+        }
+
+        let value = serde_json::to_value(InboundFrame::Event(MessageEvent::failed(
+            "s", "u", message_id, "blocked",
+        )))
+        .unwrap_or_default();
+        assert_eq!(
+            value["event_type"],
+            serde_json::json!({"type": "failed", "reason": "blocked"})
+        );
+    }
+
+    #[test]
+    fn test_unknown_event_type() {
+        let frame: Option<InboundFrame> = serde_json::from_value(serde_json::json!({
+            "kind": "event",
+            "event_type": {"type": "reaction_added", "emoji": "👍"},
+            "session_id": "s",
+            "user_id": "u",
+            "timestamp": "2024-01-01T00:00:00Z"
+        }))
+        .ok();
+        assert!(matches!(
+            frame,
+            Some(InboundFrame::Event(MessageEvent {
+                event_type: MessageEventType::Unknown,
+                message_id: None,
+                ..
+            }))
+        ));
+    }
+
+    fn mention_errors(mentions: &[Mention], content: &str) -> Vec<(String, String)> {
+        let msg = UserMessage {
+            mentions: mentions.to_vec(),
+            ..UserMessage::text("bot", "u1", "s1", "teams", content)
+        };
+        msg.validate(&SystemLimits::default())
+            .err()
+            .map(|errors| {
+                errors
+                    .errors()
+                    .iter()
+                    .map(|e| (e.field.clone(), e.code.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_mention_offset_validation() {
+        // "Olá " is 5 bytes, "@Zé" 4.
+        let content = "Olá @Zé";
+        assert_eq!(content.len(), 9);
+        assert!(mention_errors(&[Mention::new("u2", 5, 4)], content).is_empty());
+        assert!(mention_errors(&[Mention::new("u2", 9, 0)], content)
+            .iter()
+            .any(|(_, code)| code == "invalid_boundary"));
+        assert_eq!(
+            mention_errors(&[Mention::new("u2", 5, 5)], content),
+            [("mentions[0]".to_string(), "out_of_range".to_string())]
+        );
+        assert_eq!(
+            mention_errors(&[Mention::new("u2", 3, 2)], content),
+            [("mentions[0]".to_string(), "invalid_boundary".to_string())]
+        );
+        assert_eq!(
+            mention_errors(&[Mention::new("", usize::MAX, 2)], content),
+            [
+                ("mentions[0].user_id".to_string(), "required".to_string()),
+                ("mentions[0]".to_string(), "out_of_range".to_string())
+            ]
+        );
+
+        let response =
+            BotResponse::new("bot", "s1", "u1", "Obrigado @Ana", "teams").with_mention("u3", 9, 4);
+        assert!(response.validate(&SystemLimits::default()).is_ok());
+        let response = response.with_mention("u3", 9, 5);
+        assert!(response.validate(&SystemLimits::default()).is_err());
+    }
+
+    #[test]
+    fn test_render_mentions() {
+        let participants = [
+            Participant::new("5511988887777", "Maria Silva"),
+            Participant::new("5511977776666", "João"),
+        ];
+        let msg = UserMessage::text(
+            "bot",
+            "u1",
+            "s1",
+            "whatsapp",
+            "@5511977776666 e @5511988887777",
+        )
+        .with_mention("5511988887777", 17, 14)
+        .with_mention("5511977776666", 0, 14)
+        .with_mention("5511988887777", 17, 14);
+        assert_eq!(msg.render_mentions(&participants), "@João e @Maria Silva");
+        assert_eq!(msg.mentioned_users(), ["5511988887777", "5511977776666"]);
+
+        let unknown = UserMessage::text("bot", "u1", "s1", "whatsapp", "oi @123")
+            .with_mention("123", 3, 4)
+            .with_mention("5511977776666", 40, 3);
+        assert_eq!(unknown.render_mentions(&participants), "oi @123");
+
+        let response = BotResponse::new("bot", "s1", "u1", "Pronto, @joao", "whatsapp")
+            .with_mention("5511977776666", 8, 5);
+        assert_eq!(response.render_mentions(&participants), "Pronto, @João");
+    }
+
+    #[test]
+    fn test_group_session_and_mentions_serde() {
+        let session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "Equipe")
+            .with_participant(Participant::new("u1", "Maria"))
+            .with_participant(
+                Participant::new("bot", "Assistente").with_role(ParticipantRole::Bot),
+            );
+        assert!(!session.is_group());
+        let session = session
+            .with_participant(Participant::new("u2", "João").with_role(ParticipantRole::Admin));
+        assert!(session.is_group());
+        assert_eq!(
+            session.participant("u2").map(|p| p.role),
+            Some(ParticipantRole::Admin)
+        );
+
+        let value = serde_json::to_value(&session).unwrap_or_default();
+        assert_eq!(
+            value.pointer("/participants/1/role"),
+            Some(&serde_json::json!("bot"))
+        );
+        let back: Option<Session> = serde_json::from_value(value).ok();
+        assert_eq!(back.map(|s| s.participants), Some(session.participants));
+
+        let legacy = serde_json::json!({
+            "id": Uuid::new_v4(),
+            "user_id": Uuid::new_v4(),
+            "bot_id": Uuid::new_v4(),
+            "title": "Support",
+            "created_at": Utc::now(),
+            "updated_at": Utc::now()
+        });
+        let legacy: Option<Session> = serde_json::from_value(legacy).ok();
+        assert!(legacy.is_some_and(|s| s.participants.is_empty() && !s.is_group()));
+
+        let participant: Option<Participant> =
+            serde_json::from_str(r#"{"user_id": "u1", "display_name": "Maria"}"#).ok();
+        assert_eq!(participant.map(|p| p.role), Some(ParticipantRole::Member));
+
+        let msg = UserMessage::text("bot", "u1", "s1", "teams", "hi @Ana").with_mention("u2", 3, 4);
+        let value = serde_json::to_value(&msg).unwrap_or_default();
+        assert_eq!(
+            value.get("mentions"),
+            Some(&serde_json::json!([{"user_id": "u2", "offset": 3, "length": 4}]))
+        );
+        let back: Option<UserMessage> = serde_json::from_value(value).ok();
+        assert_eq!(back.map(|m| m.mentions), Some(msg.mentions));
+
+        let plain = serde_json::to_value(BotResponse::new("bot", "s1", "u1", "hi", "web"))
+            .unwrap_or_default();
+        assert!(plain.get("mentions").is_none());
+    }
+
+    #[test]
+    fn test_schedule_fields_serde_defaults() {
+        let old = serde_json::json!({
+            "bot_id": "bot",
+            "user_id": "u1",
+            "session_id": "s1",
+            "channel": "web",
+            "content": "hi",
+            "message_type": 2,
+            "is_complete": true
+        });
+        let back: Option<BotResponse> = serde_json::from_value(old).ok();
+        assert!(back.is_some_and(|r| r.id.is_none()
+            && r.send_at.is_none()
+            && r.expires_at.is_none()
+            && r.is_due(Utc::now())
+            && !r.is_expired(Utc::now())));
+
+        let plain = serde_json::to_value(BotResponse::new("bot", "s1", "u1", "hi", "web"))
+            .unwrap_or_default();
+        for field in ["id", "send_at", "expires_at"] {
+            assert!(plain.get(field).is_none(), "{field} should be omitted");
+        }
+
+        let now = Utc::now();
+        let scheduled = BotResponse::new("bot", "s1", "u1", "hi", "web")
+            .with_send_at(now)
+            .with_expiry(now - chrono::Duration::minutes(1));
+        let value = serde_json::to_value(&scheduled).unwrap_or_default();
+        let back: Option<BotResponse> = serde_json::from_value(value).ok();
+        assert_eq!(back.as_ref().and_then(|r| r.send_at), Some(now));
+        let fields: Vec<String> = scheduled
+            .validate(&SystemLimits::default())
+            .err()
+            .map(|errors| errors.errors().iter().map(|e| e.field.clone()).collect())
+            .unwrap_or_default();
+        assert_eq!(fields, ["expires_at"]);
+    }
+
+    fn history() -> (Conversation, Uuid) {
+        let original = Message::new("s1", MessageDirection::User, "Chego às 10h");
+        let id = original.id;
+        let session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "Support");
+        (Conversation::new(session, vec![original]), id)
+    }
+
+    #[test]
+    fn test_apply_edit_preserves_original_content() {
+        let (mut conversation, id) = history();
+        let edit = UserMessage::edit_of(id, "Chego às 11h");
+        assert!(edit.is_edit() && !edit.is_deletion());
+        assert_eq!(edit.reply_to_id, Some(id));
+        assert_eq!(edit.correlation_id, Some(id));
+        assert!(conversation.apply_edit(&edit).is_ok());
+        assert!(conversation
+            .apply_edit(&UserMessage::edit_of(id, "Chego às 12h"))
+            .is_ok());
+
+        let message = conversation.messages.first();
+        assert_eq!(message.map(|m| m.content.as_str()), Some("Chego às 12h"));
+        assert_eq!(
+            message.and_then(|m| m.original_content.as_deref()),
+            Some("Chego às 10h")
+        );
+        assert!(message.is_some_and(|m| m.edited_at.is_some() && !m.deleted));
+        assert_eq!(conversation.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_apply_on_missing_target() {
+        let (mut conversation, _) = history();
+        let missing = Uuid::new_v4();
+        let err = conversation
+            .apply_edit(&UserMessage::edit_of(missing, "x"))
+            .err();
+        assert_eq!(
+            err.map(|e| e.to_string()),
+            Some(format!("Message {missing} not found"))
+        );
+        assert!(matches!(
+            conversation.apply_deletion(&UserMessage::deletion_of(missing)),
+            Err(BotError::NotFound { .. })
+        ));
+        assert!(matches!(
+            conversation.apply_edit(&UserMessage::text("bot", "u1", "s1", "web", "hi")),
+            Err(BotError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_double_delete_is_idempotent() {
+        let (mut conversation, id) = history();
+        let first = UserMessage::deletion_of(id);
+        assert!(first.is_deletion());
+        assert!(conversation.apply_deletion(&first).is_ok());
+        let deleted_at = conversation.messages.first().and_then(|m| m.deleted_at);
+        assert_eq!(deleted_at, first.deleted_at);
+
+        let second = UserMessage {
+            deleted_at: Some(Utc::now() + chrono::Duration::minutes(1)),
+            ..UserMessage::deletion_of(id)
+        };
+        assert!(conversation.apply_deletion(&second).is_ok());
+        let message = conversation.messages.first();
+        assert_eq!(message.and_then(|m| m.deleted_at), deleted_at);
+        assert!(message.is_some_and(|m| m.deleted && m.content.is_empty()));
+        assert_eq!(
+            message.and_then(|m| m.original_content.as_deref()),
+            Some("Chego às 10h")
+        );
+
+        assert!(matches!(
+            conversation.apply_edit(&UserMessage::edit_of(id, "again")),
+            Err(BotError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn test_edit_and_deletion_serde_and_validation() {
+        let old = serde_json::json!({
+            "bot_id": "bot", "user_id": "u1", "session_id": "s1", "channel": "web",
+            "content": "hi", "message_type": 1, "timestamp": "2024-01-01T00:00:00Z"
+        });
+        let back: Option<UserMessage> = serde_json::from_value(old).ok();
+        assert!(back.is_some_and(|m| !m.is_edit()
+            && !m.is_deletion()
+            && m.edited_at.is_none()
+            && m.original_content.is_none()
+            && m.deleted_at.is_none()));
+
+        let plain = serde_json::to_value(UserMessage::text("bot", "u1", "s1", "web", "hi"))
+            .unwrap_or_default();
+        for field in ["edited_at", "original_content", "deleted", "deleted_at"] {
+            assert!(plain.get(field).is_none(), "{field} should be omitted");
+        }
+        let message = serde_json::to_value(Message::new("s1", MessageDirection::User, "hi"))
+            .unwrap_or_default();
+        assert!(message.get("deleted").is_none());
+
+        let id = Uuid::new_v4();
+        let deletion = UserMessage {
+            bot_id: "bot".to_string(),
+            user_id: "u1".to_string(),
+            session_id: "s1".to_string(),
+            channel: "whatsapp".to_string(),
+            ..UserMessage::deletion_of(id)
+        };
+        assert!(deletion.validate(&SystemLimits::default()).is_ok());
+        let value = serde_json::to_value(&deletion).unwrap_or_default();
+        assert_eq!(value.get("deleted"), Some(&serde_json::json!(true)));
+        let back: Option<UserMessage> = serde_json::from_value(value).ok();
+        assert!(back.is_some_and(|m| m.is_deletion() && m.reply_to_id == Some(id)));
+
+        let orphan = UserMessage {
+            reply_to_id: None,
+            ..deletion
+        };
+        let fields: Vec<String> = orphan
+            .validate(&SystemLimits::default())
+            .err()
+            .map(|errors| errors.errors().iter().map(|e| e.field.clone()).collect())
+            .unwrap_or_default();
+        assert_eq!(fields, ["reply_to_id"]);
+    }
+
+    #[test]
+    fn test_reaction_replaces_per_user_and_counts() {
+        let mut message = Message::new("s1", MessageDirection::Bot, "Pedido enviado");
+        let id = message.id;
+        assert!(message.add_reaction(Reaction::new(id, "u1", "👍")).is_ok());
+        assert!(message.add_reaction(Reaction::new(id, "u2", "👍")).is_ok());
+        assert!(message.add_reaction(Reaction::new(id, "u3", "❤️")).is_ok());
+        assert!(message.add_reaction(Reaction::new(id, "u1", "🎉")).is_ok());
+        assert_eq!(message.reactions.len(), 3);
+        assert_eq!(
+            message.reaction_counts(),
+            HashMap::from([
+                ("👍".to_string(), 1),
+                ("❤️".to_string(), 1),
+                ("🎉".to_string(), 1)
+            ])
+        );
+
+        let removal = MessageEvent {
+            event_type: MessageEventType::Reaction {
+                emoji: String::new(),
+                removed: true,
+            },
+            ..MessageEvent::reaction("s1", "u2", id, "")
+        };
+        assert!(removal
+            .to_reaction()
+            .is_some_and(|reaction| message.add_reaction(reaction).is_ok()));
+        assert!(!message.reaction_counts().contains_key("👍"));
+        assert_eq!(
+            message.remove_reaction("u3").map(|r| r.emoji),
+            Some("❤️".to_string())
+        );
+        assert!(message.remove_reaction("u3").is_none());
+        assert_eq!(message.reactions.len(), 1);
+
+        assert!(matches!(
+            message.add_reaction(Reaction::new(Uuid::new_v4(), "u1", "👍")),
+            Err(BotError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_reaction_emoji_validation() {
+        let id = Uuid::new_v4();
+        for emoji in ["👍", "👍🏽", "❤️", "1️⃣", "🇧🇷", "👨‍👩‍👧‍👦", "🏴󠁧󠁢󠁳󠁣󠁴󠁿"]
+        {
+            assert!(
+                Reaction::new(id, "u1", emoji).validate().is_ok(),
+                "{emoji} should be accepted"
+            );
+        }
+        for emoji in [
+            "",
+            "ok",
+            "1",
+            "👍 👍",
+            "é",
+            "日本",
+            "👍\n",
+            &"👍".repeat(17),
+        ] {
+            let err = Reaction::new(id, "u1", emoji).validate().err();
+            assert!(
+                matches!(err, Some(BotError::ValidationFields(ref e)) if e.errors().first().is_some_and(|f| f.code == "invalid_emoji")),
+                "{emoji:?} should be rejected"
+            );
+        }
+        let removal = Reaction {
+            removed: true,
+            ..Reaction::new(id, "u1", "")
+        };
+        assert!(removal.validate().is_ok());
+        assert!(Reaction::new(id, " ", "👍").validate().is_err());
+    }
+
+    #[test]
+    fn test_reaction_serde() {
+        let id = Uuid::new_v4();
+        let event = MessageEvent::reaction("s1", "u1", id, "🙏");
+        let value = serde_json::to_value(&event).unwrap_or_default();
+        assert_eq!(
+            value.get("event_type"),
+            Some(&serde_json::json!({"type": "reaction", "emoji": "🙏"}))
+        );
+        let back: Option<MessageEvent> = serde_json::from_value(value).ok();
+        assert_eq!(back.as_ref(), Some(&event));
+        assert_eq!(
+            back.and_then(|e| e.to_reaction())
+                .map(|r| (r.message_id, r.emoji)),
+            Some((id, "🙏".to_string()))
+        );
+        assert!(MessageEvent::typing_start("s1", "u1")
+            .to_reaction()
+            .is_none());
+
+        let mut message = Message::new("s1", MessageDirection::User, "oi");
+        let plain = serde_json::to_value(&message).unwrap_or_default();
+        assert!(plain.get("reactions").is_none());
+        assert!(message
+            .add_reaction(Reaction::new(message.id, "u1", "👍"))
+            .is_ok());
+        let value = serde_json::to_value(&message).unwrap_or_default();
+        assert!(value.pointer("/reactions/0/removed").is_none());
+        let back: Option<Message> = serde_json::from_value(value).ok();
+        assert_eq!(back.map(|m| m.reactions), Some(message.reactions));
+    }
+
+    fn round_trip<T: Serialize + serde::de::DeserializeOwned>(
+        original: &Value,
+    ) -> (Option<T>, Option<Value>) {
+        let parsed: Option<T> = serde_json::from_value(original.clone()).ok();
+        let back = parsed
+            .as_ref()
+            .and_then(|value| serde_json::to_value(value).ok());
+        (parsed, back)
+    }
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let future = serde_json::json!({"priority": "high", "thread": {"id": 7, "depth": 2}});
+        let with_future = |known: Value| {
+            let mut value = known;
+            if let (Some(object), Some(extra)) = (value.as_object_mut(), future.as_object()) {
+                object.extend(extra.clone());
+            }
+            value
+        };
+        let session = Session::new(Uuid::new_v4(), Uuid::new_v4(), "Suporte");
+        let originals = [
+            with_future(
+                serde_json::to_value(UserMessage::text("bot", "u1", "s1", "web", "oi"))
+                    .unwrap_or_default(),
+            ),
+            with_future(
+                serde_json::to_value(BotResponse::new("bot", "s1", "u1", "olá", "web"))
+                    .unwrap_or_default(),
+            ),
+            with_future(serde_json::to_value(&session).unwrap_or_default()),
+            with_future(serde_json::to_value(Suggestion::new("Sim")).unwrap_or_default()),
+        ];
+        let [user_message, response, session, suggestion] = &originals;
+
+        let (parsed, back) = round_trip::<UserMessage>(user_message);
+        assert_eq!(parsed.map(|m| m.extra.len()), Some(2));
+        assert_eq!(back.as_ref(), Some(user_message));
+
+        let (parsed, back) = round_trip::<BotResponse>(response);
+        assert_eq!(
+            parsed.and_then(|r| r.extra.get("priority").cloned()),
+            Some(Value::from("high"))
+        );
+        assert_eq!(back.as_ref(), Some(response));
+
+        let (parsed, back) = round_trip::<Session>(session);
+        assert_eq!(
+            parsed.and_then(|s| s.extra.get("thread").cloned()),
+            future.get("thread").cloned()
+        );
+        assert_eq!(back.as_ref(), Some(session));
+
+        let (parsed, back) = round_trip::<Suggestion>(suggestion);
+        assert_eq!(parsed.map(|s| s.extra.len()), Some(2));
+        assert_eq!(back.as_ref(), Some(suggestion));
+    }
+
+    #[test]
+    fn test_extra_holds_only_unknown_fields() {
+        let mut message = UserMessage::text("bot", "u1", "s1", "web", "oi")
+            .with_media("https://x/a.jpg")
+            .with_context("vendas");
+        message.set_meta("k", 1);
+        let value = serde_json::to_value(&message).unwrap_or_default();
+        let back: Option<UserMessage> = serde_json::from_value(value).ok();
+        assert_eq!(back.as_ref().map(|m| m.extra.is_empty()), Some(true));
+        assert_eq!(
+            back.and_then(|m| m.media_url),
+            Some("https://x/a.jpg".to_string())
+        );
+
+        let response = BotResponse::default()
+            .with_suggestions(["Sim"])
+            .with_send_at(Utc::now());
+        let value = serde_json::to_value(&response).unwrap_or_default();
+        let back: Option<BotResponse> = serde_json::from_value(value).ok();
+        assert_eq!(back.map(|r| r.extra.is_empty()), Some(true));
+
+        assert!(BotResponse::default().extra.is_empty());
+        assert!(BotResponse::builder()
+            .bot_id("bot")
+            .session_id("s1")
+            .user_id("u1")
+            .channel("web")
+            .content("olá")
+            .build()
+            .is_ok_and(|r| r.extra.is_empty()));
+        assert!(UserMessage::builder()
+            .bot_id("bot")
+            .user_id("u1")
+            .session_id("s1")
+            .channel("web")
+            .content("oi")
+            .build()
+            .is_ok_and(|m| m.extra.is_empty()));
+    }
 }
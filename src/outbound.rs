@@ -0,0 +1,452 @@
+use crate::error::{BotError, BotResult};
+use crate::models::{BotResponse, Card, Suggestion, SuggestionAction};
+use serde_json::{json, Value};
+
+pub const WHATSAPP_MAX_TEXT_CHARS: usize = 4096;
+pub const WHATSAPP_MAX_INTERACTIVE_BODY_CHARS: usize = 1024;
+pub const WHATSAPP_MAX_BUTTONS: usize = 3;
+pub const WHATSAPP_MAX_BUTTON_TITLE_CHARS: usize = 20;
+pub const WHATSAPP_MAX_LIST_ROWS: usize = 10;
+pub const WHATSAPP_MAX_ROW_TITLE_CHARS: usize = 24;
+pub const WHATSAPP_MAX_ROW_DESCRIPTION_CHARS: usize = 72;
+pub const TELEGRAM_MAX_TEXT_CHARS: usize = 4096;
+pub const TELEGRAM_MAX_CALLBACK_DATA_BYTES: usize = 64;
+
+/// Turns a `BotResponse` into the request bodies a channel's API expects.
+pub trait ChannelSerializer {
+    /// Every payload to send, in order. Content longer than the channel
+    /// allows is split across several payloads; suggestions ride on the
+    /// last one.
+    ///
+    /// # Errors
+    /// Returns `BotError::Validation` if the response has no recipient or
+    /// nothing the channel can send.
+    fn serialize_all(&self, response: &BotResponse) -> BotResult<Vec<Value>>;
+
+    /// `serialize_all` as a JSON array.
+    ///
+    /// # Errors
+    /// See `serialize_all`.
+    fn serialize(&self, response: &BotResponse) -> BotResult<Value> {
+        self.serialize_all(response).map(Value::Array)
+    }
+}
+
+/// Splits `text` into parts of at most `max_chars` characters, breaking at
+/// whitespace. A single word longer than `max_chars` is cut mid-word.
+#[must_use]
+pub fn split_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+    while max_chars > 0 && rest.chars().count() > max_chars {
+        let limit = rest
+            .char_indices()
+            .nth(max_chars)
+            .map_or(rest.len(), |(index, _)| index);
+        let head = rest.get(..limit).unwrap_or(rest);
+        let breaks_at_limit = rest
+            .get(limit..)
+            .is_some_and(|tail| tail.starts_with(char::is_whitespace));
+        let cut = if breaks_at_limit {
+            limit
+        } else {
+            head.rfind(char::is_whitespace)
+                .filter(|&index| index > 0)
+                .unwrap_or(limit)
+        };
+        parts.push(rest.get(..cut).unwrap_or(rest).trim_end().to_string());
+        rest = rest.get(cut..).unwrap_or_default().trim_start();
+    }
+    if !rest.is_empty() {
+        parts.push(rest.to_string());
+    }
+    parts
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+fn recipient(response: &BotResponse) -> BotResult<&str> {
+    let user_id = response.user_id.trim();
+    if user_id.is_empty() {
+        return Err(BotError::validation("response has no user_id to send to"));
+    }
+    Ok(user_id)
+}
+
+/// Content as plain text followed by any cards, for channels that render
+/// neither markdown nor cards.
+fn plain_body(response: &BotResponse) -> String {
+    std::iter::once(response.to_plain_text())
+        .chain(response.cards.iter().map(Card::render_plain_text))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Suggestions by `display_order`, unordered ones last in their original
+/// order.
+fn ordered_suggestions(response: &BotResponse) -> Vec<&Suggestion> {
+    let mut suggestions: Vec<&Suggestion> = response.suggestions.iter().collect();
+    suggestions.sort_by_key(|s| s.display_order.unwrap_or(u32::MAX));
+    suggestions
+}
+
+fn reply_id(suggestion: &Suggestion) -> String {
+    match suggestion.resolved_action() {
+        Some(SuggestionAction::Postback { payload }) => payload,
+        _ => suggestion.text.clone(),
+    }
+}
+
+/// Splits `body` so the last part fits `last_max` and every other part
+/// fits `max`.
+fn split_with_tail(body: &str, max: usize, last_max: usize) -> Vec<String> {
+    let mut parts = split_text(body, max);
+    if let Some(last) = parts.pop() {
+        parts.extend(split_text(&last, last_max));
+    }
+    parts
+}
+
+/// WhatsApp Cloud API `/messages` bodies. Up to three short suggestions
+/// become reply buttons; more, or longer, ones fall back to a list message.
+#[derive(Debug, Clone)]
+pub struct WhatsAppSerializer {
+    pub list_button_label: String,
+}
+
+impl Default for WhatsAppSerializer {
+    fn default() -> Self {
+        Self {
+            list_button_label: "Options".to_string(),
+        }
+    }
+}
+
+impl WhatsAppSerializer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_list_button_label(mut self, label: impl Into<String>) -> Self {
+        self.list_button_label = label.into();
+        self
+    }
+
+    fn text(to: &str, body: &str) -> Value {
+        json!({
+            "messaging_product": "whatsapp",
+            "recipient_type": "individual",
+            "to": to,
+            "type": "text",
+            "text": {"preview_url": false, "body": body}
+        })
+    }
+
+    fn interactive(&self, to: &str, body: &str, suggestions: &[&Suggestion]) -> Value {
+        let fits_buttons = suggestions.len() <= WHATSAPP_MAX_BUTTONS
+            && suggestions
+                .iter()
+                .all(|s| s.text.chars().count() <= WHATSAPP_MAX_BUTTON_TITLE_CHARS);
+        let interactive = if fits_buttons {
+            let buttons: Vec<Value> = suggestions
+                .iter()
+                .map(|s| json!({"type": "reply", "reply": {"id": reply_id(s), "title": s.text}}))
+                .collect();
+            json!({
+                "type": "button",
+                "body": {"text": body},
+                "action": {"buttons": buttons}
+            })
+        } else {
+            let rows: Vec<Value> = suggestions
+                .iter()
+                .take(WHATSAPP_MAX_LIST_ROWS)
+                .map(|s| {
+                    let mut row = json!({
+                        "id": reply_id(s),
+                        "title": truncate_chars(&s.text, WHATSAPP_MAX_ROW_TITLE_CHARS).trim_end(),
+                    });
+                    if s.text.chars().count() > WHATSAPP_MAX_ROW_TITLE_CHARS {
+                        row["description"] = Value::from(truncate_chars(
+                            &s.text,
+                            WHATSAPP_MAX_ROW_DESCRIPTION_CHARS,
+                        ));
+                    }
+                    row
+                })
+                .collect();
+            json!({
+                "type": "list",
+                "body": {"text": body},
+                "action": {
+                    "button": truncate_chars(&self.list_button_label, WHATSAPP_MAX_BUTTON_TITLE_CHARS),
+                    "sections": [{"rows": rows}]
+                }
+            })
+        };
+        json!({
+            "messaging_product": "whatsapp",
+            "recipient_type": "individual",
+            "to": to,
+            "type": "interactive",
+            "interactive": interactive
+        })
+    }
+}
+
+impl ChannelSerializer for WhatsAppSerializer {
+    fn serialize_all(&self, response: &BotResponse) -> BotResult<Vec<Value>> {
+        let to = recipient(response)?;
+        let body = plain_body(response);
+        let suggestions = ordered_suggestions(response);
+        if suggestions.is_empty() {
+            let parts = split_text(&body, WHATSAPP_MAX_TEXT_CHARS);
+            if parts.is_empty() {
+                return Err(BotError::validation("response has nothing to send"));
+            }
+            return Ok(parts.iter().map(|part| Self::text(to, part)).collect());
+        }
+
+        let mut parts = split_with_tail(
+            &body,
+            WHATSAPP_MAX_TEXT_CHARS,
+            WHATSAPP_MAX_INTERACTIVE_BODY_CHARS,
+        );
+        let Some(last) = parts.pop() else {
+            return Err(BotError::validation(
+                "WhatsApp interactive messages need body text",
+            ));
+        };
+        let mut payloads: Vec<Value> = parts.iter().map(|part| Self::text(to, part)).collect();
+        payloads.push(self.interactive(to, &last, &suggestions));
+        Ok(payloads)
+    }
+}
+
+/// Telegram Bot API `sendMessage` bodies using `MarkdownV2`, with
+/// suggestions as an inline keyboard of one button per row.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TelegramSerializer;
+
+impl TelegramSerializer {
+    /// Backslash-escapes every character `MarkdownV2` treats as markup.
+    #[must_use]
+    pub fn escape_markdown(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if "_*[]()~`>#+-=|{}.!\\".contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    fn button(suggestion: &Suggestion) -> Value {
+        if let Some(SuggestionAction::OpenUrl { url }) = suggestion.resolved_action() {
+            return json!({"text": suggestion.text, "url": url});
+        }
+        let mut data = reply_id(suggestion);
+        while data.len() > TELEGRAM_MAX_CALLBACK_DATA_BYTES {
+            data.pop();
+        }
+        json!({"text": suggestion.text, "callback_data": data})
+    }
+}
+
+impl ChannelSerializer for TelegramSerializer {
+    fn serialize_all(&self, response: &BotResponse) -> BotResult<Vec<Value>> {
+        let chat_id = recipient(response)?;
+        let parts = split_text(&plain_body(response), TELEGRAM_MAX_TEXT_CHARS);
+        if parts.is_empty() {
+            return Err(BotError::validation("response has nothing to send"));
+        }
+        let keyboard: Vec<Value> = ordered_suggestions(response)
+            .into_iter()
+            .map(|s| json!([Self::button(s)]))
+            .collect();
+        let last = parts.len() - 1;
+        Ok(parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| {
+                let mut payload = json!({
+                    "chat_id": chat_id,
+                    "text": Self::escape_markdown(part),
+                    "parse_mode": "MarkdownV2"
+                });
+                if index == last && !keyboard.is_empty() {
+                    payload["reply_markup"] = json!({"inline_keyboard": keyboard});
+                }
+                payload
+            })
+            .collect())
+    }
+}
+
+/// The `BotResponse` itself, unsplit, for generic HTTP webhooks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WebhookSerializer;
+
+impl ChannelSerializer for WebhookSerializer {
+    fn serialize_all(&self, response: &BotResponse) -> BotResult<Vec<Value>> {
+        Ok(vec![serde_json::to_value(response)?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentFormat;
+
+    fn response(content: &str) -> BotResponse {
+        BotResponse::new("bot", "s1", "5511999990000", content, "whatsapp")
+    }
+
+    fn golden(value: &Value, expected: &str) {
+        let actual = serde_json::to_string_pretty(value).unwrap_or_default();
+        assert_eq!(actual, expected.trim_end());
+    }
+
+    #[test]
+    fn test_split_text_keeps_words_whole() {
+        assert_eq!(
+            split_text("one two three four", 9),
+            ["one two", "three", "four"]
+        );
+        assert_eq!(split_text("abc def", 3), ["abc", "def"]);
+        assert_eq!(split_text("abcdefgh ij", 3), ["abc", "def", "gh", "ij"]);
+        assert_eq!(split_text("café ☕ olá", 6), ["café ☕", "olá"]);
+        assert!(split_text("   ", 10).is_empty());
+        assert_eq!(split_text("short", 0), ["short"]);
+        for part in split_text(&"palavra ".repeat(1000), 100) {
+            assert!(part.chars().count() <= 100);
+            assert!(part.starts_with("palavra") && part.ends_with("palavra"));
+        }
+    }
+
+    #[test]
+    fn test_whatsapp_buttons_golden() {
+        let response = response("Posso ajudar com **mais alguma coisa**?").with_suggestions([
+            Suggestion::new("Sim").with_postback("yes"),
+            Suggestion::new("Não").with_postback("no"),
+        ]);
+        let payload = WhatsAppSerializer::new().serialize(&response).ok();
+        golden(
+            &payload.unwrap_or_default(),
+            include_str!("../tests/golden/whatsapp_buttons.json"),
+        );
+    }
+
+    #[test]
+    fn test_whatsapp_button_overflow_falls_back_to_list_golden() {
+        let response = response("Escolha um horário").with_suggestions([
+            Suggestion::new("Amanhã às 9h"),
+            Suggestion::new("Amanhã às 14h"),
+            Suggestion::new("Sexta-feira pela manhã, antes das 10h"),
+            Suggestion::new("Primeiro").with_display_order(0),
+        ]);
+        let payload = WhatsAppSerializer::new()
+            .with_list_button_label("Horários")
+            .serialize(&response)
+            .ok();
+        golden(
+            &payload.unwrap_or_default(),
+            include_str!("../tests/golden/whatsapp_list.json"),
+        );
+    }
+
+    #[test]
+    fn test_whatsapp_splits_long_content() {
+        let long = "palavra ".repeat(1100);
+        let payloads = WhatsAppSerializer::new()
+            .serialize_all(&response(&long).with_suggestions(["Ok"]))
+            .unwrap_or_default();
+        let bodies: Vec<usize> = payloads
+            .iter()
+            .map(|p| {
+                p.pointer("/text/body")
+                    .or_else(|| p.pointer("/interactive/body/text"))
+                    .and_then(Value::as_str)
+                    .map_or(0, |body| body.chars().count())
+            })
+            .collect();
+        assert_eq!(bodies, [4095, 4095, 607]);
+        assert_eq!(
+            payloads.last().and_then(|p| p.pointer("/interactive/type")),
+            Some(&Value::from("button"))
+        );
+        assert!(payloads
+            .first()
+            .and_then(|p| p.pointer("/text/body"))
+            .and_then(Value::as_str)
+            .is_some_and(|body| body.ends_with("palavra")));
+    }
+
+    #[test]
+    fn test_whatsapp_requires_recipient_and_body() {
+        let mut no_user = response("hi");
+        no_user.user_id.clear();
+        assert!(matches!(
+            WhatsAppSerializer::new().serialize(&no_user),
+            Err(BotError::Validation(_))
+        ));
+        assert!(WhatsAppSerializer::new()
+            .serialize(&response("").with_suggestions(["Ok"]))
+            .is_err());
+        assert!(WhatsAppSerializer::new().serialize(&response("")).is_err());
+    }
+
+    #[test]
+    fn test_telegram_golden() {
+        let response = response("Total: R$ 10.50 (com _desconto_)!")
+            .with_content_format(ContentFormat::PlainText)
+            .with_suggestions([
+                Suggestion::new("Pagar").with_postback("pay:order-42"),
+                Suggestion::new("Ver pedido").with_url("https://shop.example.com/o/42"),
+                Suggestion::new("Cancelar").with_postback("x".repeat(80)),
+            ]);
+        let payload = TelegramSerializer.serialize(&response).ok();
+        golden(
+            &payload.unwrap_or_default(),
+            include_str!("../tests/golden/telegram_keyboard.json"),
+        );
+    }
+
+    #[test]
+    fn test_telegram_splits_long_content() {
+        let long = "a.b ".repeat(2000);
+        let payloads = TelegramSerializer
+            .serialize_all(&response(&long).with_suggestions(["Ok"]))
+            .unwrap_or_default();
+        assert_eq!(payloads.len(), 2);
+        assert!(payloads
+            .first()
+            .is_some_and(|p| p.get("reply_markup").is_none()));
+        assert!(payloads
+            .last()
+            .is_some_and(|p| p.get("reply_markup").is_some()));
+        let unescaped: Vec<usize> = payloads
+            .iter()
+            .filter_map(|p| p.get("text").and_then(Value::as_str))
+            .map(|text| text.replace('\\', "").chars().count())
+            .collect();
+        assert_eq!(unescaped, [4095, 3903]);
+    }
+
+    #[test]
+    fn test_webhook_golden() {
+        let mut response = response("Olá!").with_suggestions(["Menu"]);
+        response.channel = "webhook".to_string();
+        let payload = WebhookSerializer.serialize(&response).ok();
+        golden(
+            &payload.unwrap_or_default(),
+            include_str!("../tests/golden/webhook_response.json"),
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::branding::branding;
+use crate::error::BotError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// RFC 7807 problem details. Extension members are flattened into the
+/// top-level object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+/// `https://<branding domain>/problems/`, used by `BotError::to_problem`.
+#[must_use]
+pub fn default_problem_base_uri() -> String {
+    branding().domain.as_deref().map_or_else(
+        || "about:blank#".to_string(),
+        |domain| format!("https://{domain}/problems/"),
+    )
+}
+
+fn title(code: &str) -> &'static str {
+    match code {
+        "config_error" => "Configuration Error",
+        "database_error" => "Database Error",
+        "http_error" => "Upstream HTTP Error",
+        "auth_error" => "Authentication Failed",
+        "validation_error" => "Validation Failed",
+        "not_found" => "Not Found",
+        "conflict" => "Conflict",
+        "unsupported_inbound" => "Unsupported Message",
+        "rate_limited" => "Too Many Requests",
+        "service_unavailable" => "Service Unavailable",
+        "timeout" => "Timeout",
+        "internal_error" => "Internal Error",
+        "io_error" => "I/O Error",
+        "json_error" => "Malformed JSON",
+        _ => "Error",
+    }
+}
+
+impl BotError {
+    /// Problem details whose type URI lives under
+    /// `default_problem_base_uri()`.
+    #[must_use]
+    pub fn to_problem(&self, instance: Option<&str>) -> ProblemDetails {
+        self.to_problem_with_base(&default_problem_base_uri(), instance)
+    }
+
+    /// Problem details whose type URI is `base_uri` followed by the
+    /// hyphenated `error_code()`, e.g. `.../problems/not-found`.
+    #[must_use]
+    pub fn to_problem_with_base(&self, base_uri: &str, instance: Option<&str>) -> ProblemDetails {
+        let code = self.error_code();
+        let mut extensions = Map::new();
+        if let Some(retry_after) = self.retry_after() {
+            extensions.insert(
+                "retry_after_secs".to_string(),
+                Value::from(retry_after.as_secs()),
+            );
+        }
+        if let Self::ValidationFields(errors) = self {
+            extensions.insert(
+                "errors".to_string(),
+                serde_json::to_value(errors.errors()).unwrap_or_default(),
+            );
+        }
+
+        ProblemDetails {
+            type_uri: format!("{base_uri}{}", code.replace('_', "-")),
+            title: title(code).to_string(),
+            status: self.status_code(),
+            detail: self.to_string(),
+            instance: instance.map(str::to_string),
+            extensions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ValidationErrors;
+
+    const BASE: &str = "https://bots.example.com/problems/";
+
+    fn golden(problem: &ProblemDetails, expected: &str) {
+        let actual = serde_json::to_string_pretty(problem).unwrap_or_default();
+        assert_eq!(actual, expected.trim_end());
+        let back: Option<ProblemDetails> = serde_json::from_str(expected).ok();
+        assert_eq!(back.as_ref(), Some(problem));
+    }
+
+    #[test]
+    fn test_not_found_golden() {
+        let problem = BotError::not_found("User").to_problem_with_base(BASE, Some("/api/users/42"));
+        golden(
+            &problem,
+            include_str!("../tests/golden/problem_not_found.json"),
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_golden() {
+        let problem = BotError::rate_limited(30).to_problem_with_base(BASE, None);
+        golden(
+            &problem,
+            include_str!("../tests/golden/problem_rate_limited.json"),
+        );
+    }
+
+    #[test]
+    fn test_validation_fields_golden() {
+        let mut errors = ValidationErrors::new();
+        errors
+            .require("content", "")
+            .max_length("session_id", "abcdef", 4);
+        let problem =
+            BotError::ValidationFields(errors).to_problem_with_base(BASE, Some("/api/messages"));
+        golden(
+            &problem,
+            include_str!("../tests/golden/problem_validation_fields.json"),
+        );
+    }
+
+    #[test]
+    fn test_default_base_uses_branding_domain() {
+        let problem = BotError::timeout(100).to_problem(None);
+        assert!(problem.type_uri.starts_with(&default_problem_base_uri()));
+        assert!(problem.type_uri.ends_with("/timeout") || problem.type_uri.ends_with("#timeout"));
+        assert_eq!(problem.status, 504);
+    }
+}
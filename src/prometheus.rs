@@ -0,0 +1,245 @@
+use crate::version::{ComponentStatus, ComponentVersion, VersionRegistry};
+
+/// Escapes a label value as the text exposition format requires.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: u64) {
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+        .collect();
+    out.push_str(&format!("{name}{{{}}} {value}\n", labels.join(",")));
+}
+
+impl VersionRegistry {
+    /// The registry as Prometheus text exposition: `gb_core_info`, and per
+    /// component `gb_component_up` (1 while `Running`),
+    /// `gb_component_update_available` and
+    /// `gb_component_status_transitions_total`. Components are listed by
+    /// name so the output only changes when the registry does.
+    #[must_use]
+    pub fn render_prometheus(&self) -> String {
+        let mut components: Vec<&ComponentVersion> = self.components.values().collect();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut out = String::new();
+
+        family(&mut out, "gb_core_info", "gauge", "Core version, always 1.");
+        sample(
+            &mut out,
+            "gb_core_info",
+            &[("version", &self.core_version)],
+            1,
+        );
+
+        family(
+            &mut out,
+            "gb_component_up",
+            "gauge",
+            "Whether the component is running.",
+        );
+        for c in &components {
+            let source = format!("{:?}", c.source).to_ascii_lowercase();
+            sample(
+                &mut out,
+                "gb_component_up",
+                &[
+                    ("name", &c.name),
+                    ("version", &c.version),
+                    ("source", &source),
+                ],
+                u64::from(c.status == ComponentStatus::Running),
+            );
+        }
+
+        family(
+            &mut out,
+            "gb_component_update_available",
+            "gauge",
+            "Whether a newer version of the component is published.",
+        );
+        for c in &components {
+            sample(
+                &mut out,
+                "gb_component_update_available",
+                &[("name", &c.name)],
+                u64::from(c.update_available),
+            );
+        }
+
+        family(
+            &mut out,
+            "gb_component_status_transitions_total",
+            "counter",
+            "Status changes since the process started.",
+        );
+        for c in &components {
+            sample(
+                &mut out,
+                "gb_component_status_transitions_total",
+                &[("name", &c.name)],
+                self.status_transitions.get(&c.name).copied().unwrap_or(0),
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::ComponentSource;
+    use prometheus_parse::{Scrape, Value};
+    use std::collections::HashMap;
+
+    fn registry() -> VersionRegistry {
+        let mut registry = VersionRegistry {
+            core_version: "6.1.0".to_string(),
+            ..VersionRegistry::default()
+        };
+        for (name, version, source) in [
+            ("llm", "6.0.1", ComponentSource::Builtin),
+            ("vault", "1.15.0", ComponentSource::Docker),
+        ] {
+            let registered = registry.register_component(ComponentVersion {
+                name: name.to_string(),
+                version: version.to_string(),
+                latest_version: None,
+                update_available: false,
+                status: ComponentStatus::Running,
+                last_checked: None,
+                last_heartbeat: None,
+                heartbeat_timeout_secs: None,
+                status_before_stale: None,
+                depends_on: Vec::new(),
+                compat: HashMap::new(),
+                started_at: None,
+                restart_count: 0,
+                recent_restarts: Vec::new(),
+                last_error: None,
+                source,
+                metadata: HashMap::new(),
+            });
+            assert!(registered.is_ok());
+        }
+        assert!(registry
+            .set_latest_version("vault", "1.16.0".to_string())
+            .is_ok());
+        assert!(registry
+            .update_status("llm", ComponentStatus::Error)
+            .is_ok());
+        registry
+    }
+
+    const GOLDEN: &str = r#"# HELP gb_core_info Core version, always 1.
+# TYPE gb_core_info gauge
+gb_core_info{version="6.1.0"} 1
+# HELP gb_component_up Whether the component is running.
+# TYPE gb_component_up gauge
+gb_component_up{name="llm",version="6.0.1",source="builtin"} 0
+gb_component_up{name="vault",version="1.15.0",source="docker"} 1
+# HELP gb_component_update_available Whether a newer version of the component is published.
+# TYPE gb_component_update_available gauge
+gb_component_update_available{name="llm"} 0
+gb_component_update_available{name="vault"} 1
+# HELP gb_component_status_transitions_total Status changes since the process started.
+# TYPE gb_component_status_transitions_total counter
+gb_component_status_transitions_total{name="llm"} 1
+gb_component_status_transitions_total{name="vault"} 0
+"#;
+
+    #[test]
+    fn test_render_matches_golden_text() {
+        let registry = registry();
+        assert_eq!(registry.render_prometheus(), GOLDEN);
+        assert_eq!(
+            registry.render_prometheus(),
+            registry.clone().render_prometheus()
+        );
+    }
+
+    #[test]
+    fn test_render_parses_as_exposition() {
+        let text = registry().render_prometheus();
+        let scrape = Scrape::parse(text.lines().map(|line| Ok(line.to_string()))).ok();
+        let samples = scrape
+            .as_ref()
+            .map(|s| s.samples.as_slice())
+            .unwrap_or_default();
+        assert_eq!(samples.len(), 7);
+
+        let value = |metric: &str, name: &str| {
+            samples
+                .iter()
+                .find(|s| s.metric == metric && s.labels.get("name") == Some(name))
+                .map(|s| s.value.clone())
+        };
+        assert_eq!(value("gb_component_up", "llm"), Some(Value::Gauge(0.0)));
+        assert_eq!(
+            value("gb_component_update_available", "vault"),
+            Some(Value::Gauge(1.0))
+        );
+        assert_eq!(
+            value("gb_component_status_transitions_total", "llm"),
+            Some(Value::Counter(1.0))
+        );
+        assert!(scrape.is_some_and(|s| s.docs.len() == 4));
+    }
+
+    /// The label values of a sample line, unescaped, checking the escaping
+    /// rules the parser crate does not.
+    fn label_values(line: &str) -> Option<Vec<String>> {
+        let body = line.split_once('{')?.1.rsplit_once('}')?.0;
+        let mut values = Vec::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            if c != '"' {
+                continue;
+            }
+            let mut value = String::new();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        'n' => value.push('\n'),
+                        escaped @ ('\\' | '"') => value.push(escaped),
+                        _ => return None,
+                    },
+                    '\n' => return None,
+                    other => value.push(other),
+                }
+            }
+            values.push(value);
+        }
+        Some(values)
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        let registry = VersionRegistry {
+            core_version: "6.1.0\n\"dev\" C:\\build".to_string(),
+            ..VersionRegistry::default()
+        };
+        let text = registry.render_prometheus();
+        let line = text.lines().find(|l| l.starts_with("gb_core_info{"));
+        assert_eq!(
+            line,
+            Some(r#"gb_core_info{version="6.1.0\n\"dev\" C:\\build"} 1"#)
+        );
+        assert_eq!(
+            line.and_then(label_values),
+            Some(vec![registry.core_version.clone()])
+        );
+        assert!(text
+            .lines()
+            .all(|l| l.starts_with('#') || label_values(l).is_some()));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::ErrorCategory;
 use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
@@ -51,6 +52,36 @@ impl std::fmt::Display for ResilienceError {
 
 impl std::error::Error for ResilienceError {}
 
+impl ResilienceError {
+    /// How long to wait before retrying, when the error carries a hint.
+    #[must_use]
+    pub const fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::CircuitOpen { until } => *until,
+            Self::Timeout { .. }
+            | Self::RetriesExhausted { .. }
+            | Self::BulkheadFull { .. }
+            | Self::Operation(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn category(&self) -> ErrorCategory {
+        match self {
+            Self::Timeout { .. }
+            | Self::CircuitOpen { .. }
+            | Self::RetriesExhausted { .. }
+            | Self::BulkheadFull { .. } => ErrorCategory::Transient,
+            Self::Operation(_) => ErrorCategory::Permanent,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        matches!(self.category(), ErrorCategory::Transient)
+    }
+}
+
 #[derive(Clone)]
 pub struct RetryConfig {
     pub max_attempts: u32,
@@ -151,7 +182,7 @@ impl RetryConfig {
         }
     }
 
-    fn calculate_delay(&self, attempt: u32) -> Duration {
+    pub(crate) fn calculate_delay(&self, attempt: u32) -> Duration {
         let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(0);
         let base_delay = self.backoff_multiplier.powi(exponent) * self.initial_delay.as_secs_f64();
 
@@ -222,3 +253,44 @@ where
         .await
         .map_err(|_| ResilienceError::Timeout { duration })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resilience_error_categories() {
+        let errors = [
+            ResilienceError::Timeout {
+                duration: Duration::from_secs(1),
+            },
+            ResilienceError::CircuitOpen {
+                until: Some(Duration::from_secs(5)),
+            },
+            ResilienceError::RetriesExhausted {
+                attempts: 3,
+                last_error: "503".to_string(),
+            },
+            ResilienceError::BulkheadFull { max_concurrent: 4 },
+            ResilienceError::Operation("invalid input".to_string()),
+        ];
+        let categories: Vec<ErrorCategory> = errors.iter().map(ResilienceError::category).collect();
+        assert_eq!(
+            categories,
+            vec![
+                ErrorCategory::Transient,
+                ErrorCategory::Transient,
+                ErrorCategory::Transient,
+                ErrorCategory::Transient,
+                ErrorCategory::Permanent,
+            ]
+        );
+        let hints: Vec<Option<Duration>> =
+            errors.iter().map(ResilienceError::retry_after).collect();
+        assert_eq!(
+            hints,
+            vec![None, Some(Duration::from_secs(5)), None, None, None]
+        );
+        assert!(!errors.iter().all(ResilienceError::is_retryable));
+    }
+}
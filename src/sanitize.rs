@@ -0,0 +1,243 @@
+use crate::limits::SystemLimits;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Longest run of whitespace `sanitize_content` keeps; the rest of a longer
+/// run is dropped.
+pub const MAX_WHITESPACE_RUN: usize = 32;
+
+/// Appended by the truncation functions when they shorten text.
+pub const ELLIPSIS: char = '…';
+
+/// Embedding, override and isolate controls, which can make text display
+/// in a different order than it is stored.
+const fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+fn is_invisible(c: char) -> bool {
+    c.is_whitespace()
+        || c.is_control()
+        || is_bidi_control(c)
+        || matches!(
+            c,
+            '\u{00AD}'
+                | '\u{180E}'
+                | '\u{200B}'..='\u{200F}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{FE00}'..='\u{FE0F}'
+                | '\u{FEFF}'
+        )
+}
+
+/// Removes control characters other than newlines and tabs and bidi
+/// controls, cuts whitespace runs to `MAX_WHITESPACE_RUN`, trims, and
+/// truncates to `limits.max_string_length` characters. Text with nothing
+/// visible left becomes empty.
+#[must_use]
+pub fn sanitize_content(text: &str, limits: &SystemLimits) -> String {
+    let mut run = 0;
+    let cleaned: String = text
+        .chars()
+        .filter(|&c| (!c.is_control() || matches!(c, '\n' | '\t')) && !is_bidi_control(c))
+        .filter(|c| {
+            run = if c.is_whitespace() { run + 1 } else { 0 };
+            run <= MAX_WHITESPACE_RUN
+        })
+        .collect();
+    if is_effectively_empty(&cleaned) {
+        return String::new();
+    }
+    truncate_chars(cleaned.trim(), limits.max_string_length)
+}
+
+/// `text` cut to at most `max` characters, the last of which is `ELLIPSIS`
+/// when anything was removed.
+#[must_use]
+pub fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        None => text.to_string(),
+        Some(_) => with_ellipsis(text.char_indices().nth(max.saturating_sub(1)), text, max),
+    }
+}
+
+/// `text` cut to at most `max` grapheme clusters, so accented letters and
+/// emoji sequences stay whole, the last of which is `ELLIPSIS` when
+/// anything was removed.
+#[must_use]
+pub fn truncate_graphemes(text: &str, max: usize) -> String {
+    match text.grapheme_indices(true).nth(max) {
+        None => text.to_string(),
+        Some(_) => with_ellipsis(
+            text.grapheme_indices(true).nth(max.saturating_sub(1)),
+            text,
+            max,
+        ),
+    }
+}
+
+fn with_ellipsis<T>(cut: Option<(usize, T)>, text: &str, max: usize) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    let end = cut.map_or(text.len(), |(index, _)| index);
+    let mut truncated = text.get(..end).unwrap_or_default().to_string();
+    truncated.push(ELLIPSIS);
+    truncated
+}
+
+/// Nothing but whitespace, control, bidi and zero-width characters.
+#[must_use]
+pub fn is_effectively_empty(text: &str) -> bool {
+    text.chars().all(is_invisible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_content() {
+        let limits = SystemLimits::default();
+        assert_eq!(
+            sanitize_content("  \u{0}Olá\u{7},\r\n\tmundo\u{1b}[31m \n", &limits),
+            "Olá,\n\tmundo[31m"
+        );
+        assert_eq!(
+            sanitize_content("pay \u{202E}gnp.exe\u{202C} \u{2067}x\u{2069}", &limits),
+            "pay gnp.exe x"
+        );
+        let padded = format!("a{}b", " ".repeat(2 * 1024 * 1024));
+        assert_eq!(
+            sanitize_content(&padded, &limits),
+            format!("a{}b", " ".repeat(MAX_WHITESPACE_RUN))
+        );
+        assert_eq!(sanitize_content("\u{200B}\u{FEFF} \u{202A}\n", &limits), "");
+        assert_eq!(sanitize_content("👩‍💻", &limits), "👩‍💻");
+
+        let short = SystemLimits {
+            max_string_length: 5,
+            ..SystemLimits::default()
+        };
+        assert_eq!(sanitize_content("  olá mundo", &short), "olá …");
+    }
+
+    #[test]
+    fn test_truncate_never_splits_characters() {
+        assert_eq!(truncate_chars("olá", 3), "olá");
+        assert_eq!(truncate_chars("olá mundo", 4), "olá…");
+        assert_eq!(truncate_chars("ãããã", 1), "…");
+        assert_eq!(truncate_chars("abc", 0), "");
+        assert_eq!(truncate_chars("", 0), "");
+
+        let family = "👨‍👩‍👧";
+        assert_eq!(
+            truncate_graphemes(&format!("{family}{family}x"), 3),
+            format!("{family}{family}x")
+        );
+        assert_eq!(
+            truncate_graphemes(&format!("{family}{family}x"), 2),
+            format!("{family}…")
+        );
+        assert_eq!(
+            truncate_graphemes("e\u{301}e\u{301}e\u{301}", 2),
+            "e\u{301}…"
+        );
+        assert_eq!(truncate_chars("e\u{301}e\u{301}", 2), "e…");
+    }
+
+    #[test]
+    fn test_is_effectively_empty() {
+        assert!(is_effectively_empty(""));
+        assert!(is_effectively_empty(
+            " \t\n\u{200B}\u{200D}\u{FE0F}\u{2066}\u{0}"
+        ));
+        assert!(!is_effectively_empty("\u{200B}a"));
+        assert!(!is_effectively_empty("👍"));
+    }
+
+    /// Deterministic xorshift, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> usize {
+            usize::try_from(self.next() % n).unwrap_or_default()
+        }
+
+        fn text(&mut self) -> String {
+            const TRICKY: [char; 16] = [
+                '\0',
+                '\n',
+                '\t',
+                '\r',
+                ' ',
+                '\u{3000}',
+                '\u{202E}',
+                '\u{2066}',
+                '\u{200D}',
+                '\u{301}',
+                '\u{FE0F}',
+                '\u{1F468}',
+                '\u{1F1E7}',
+                'ã',
+                'a',
+                '\u{7F}',
+            ];
+            let len = self.below(40);
+            (0..len)
+                .map(|_| match self.below(3) {
+                    0 => TRICKY.get(self.below(16)).copied().unwrap_or('a'),
+                    1 => ' ',
+                    _ => u32::try_from(self.below(0x11_0000))
+                        .ok()
+                        .and_then(char::from_u32)
+                        .unwrap_or('\u{FFFD}'),
+                })
+                .collect()
+        }
+    }
+
+    fn strip_ellipsis(text: &str) -> &str {
+        text.strip_suffix(ELLIPSIS).unwrap_or(text)
+    }
+
+    #[test]
+    fn test_properties_on_arbitrary_unicode() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        let limits = SystemLimits {
+            max_string_length: 12,
+            ..SystemLimits::default()
+        };
+        for _ in 0..5_000 {
+            let text = rng.text();
+            let max = rng.below(10);
+
+            let chars = truncate_chars(&text, max);
+            assert!(chars.chars().count() <= max, "{text:?} {max}");
+            assert!(text.starts_with(strip_ellipsis(&chars)), "{text:?} {max}");
+            assert_eq!(chars == text, text.chars().count() <= max);
+
+            let graphemes = truncate_graphemes(&text, max);
+            assert!(graphemes.graphemes(true).count() <= max, "{text:?} {max}");
+            assert!(
+                text.starts_with(strip_ellipsis(&graphemes)),
+                "{text:?} {max}"
+            );
+
+            let clean = sanitize_content(&text, &limits);
+            assert!(clean.chars().count() <= limits.max_string_length);
+            assert!(!clean
+                .chars()
+                .any(|c| is_bidi_control(c) || (c.is_control() && !matches!(c, '\n' | '\t'))));
+            assert!(clean.is_empty() || !is_effectively_empty(&clean));
+            assert_eq!(clean.trim(), clean);
+            assert!(std::str::from_utf8(clean.as_bytes()).is_ok());
+        }
+    }
+}
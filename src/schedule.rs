@@ -0,0 +1,234 @@
+use crate::limits::{LimitExceeded, LimitType, MAX_PENDING_TASKS};
+use crate::models::BotResponse;
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use uuid::Uuid;
+
+struct Pending {
+    send_at: DateTime<Utc>,
+    seq: u64,
+    response: BotResponse,
+}
+
+impl Pending {
+    fn id(&self) -> Option<Uuid> {
+        self.response.id
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    // Reversed so the max-heap yields the earliest `send_at` first, and the
+    // earliest pushed among equal times.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .send_at
+            .cmp(&self.send_at)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// Responses waiting for their `send_at`, released in time order by
+/// `pop_due`. Unscheduled responses are due immediately.
+pub struct ScheduledQueue {
+    heap: BinaryHeap<Pending>,
+    max_pending: usize,
+    next_seq: u64,
+}
+
+impl std::fmt::Debug for ScheduledQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScheduledQueue")
+            .field("pending", &self.heap.len())
+            .field("max_pending", &self.max_pending)
+            .finish()
+    }
+}
+
+impl Default for ScheduledQueue {
+    fn default() -> Self {
+        Self::new(MAX_PENDING_TASKS as usize)
+    }
+}
+
+impl ScheduledQueue {
+    #[must_use]
+    pub fn new(max_pending: usize) -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            max_pending,
+            next_seq: 0,
+        }
+    }
+
+    /// Queues `response`, giving it an id if it has none, and returns that
+    /// id for `cancel`.
+    ///
+    /// # Errors
+    /// Returns `LimitExceeded` with `LimitType::PendingTasks` when the queue
+    /// already holds `max_pending` responses.
+    pub fn push(&mut self, mut response: BotResponse) -> Result<Uuid, LimitExceeded> {
+        if self.heap.len() >= self.max_pending {
+            return Err(LimitExceeded {
+                limit_type: LimitType::PendingTasks,
+                current: self.heap.len() as u64 + 1,
+                maximum: self.max_pending as u64,
+                retry_after_secs: None,
+            });
+        }
+        let id = *response.id.get_or_insert_with(Uuid::new_v4);
+        self.heap.push(Pending {
+            send_at: response.send_at.unwrap_or(DateTime::<Utc>::MIN_UTC),
+            seq: self.next_seq,
+            response,
+        });
+        self.next_seq += 1;
+        Ok(id)
+    }
+
+    /// Removes every response due at `now`, earliest first. Responses that
+    /// expired while waiting are dropped rather than returned.
+    pub fn pop_due(&mut self, now: DateTime<Utc>) -> Vec<BotResponse> {
+        let mut due = Vec::new();
+        while self.heap.peek().is_some_and(|next| next.send_at <= now) {
+            let Some(next) = self.heap.pop() else {
+                break;
+            };
+            if !next.response.is_expired(now) {
+                due.push(next.response);
+            }
+        }
+        due
+    }
+
+    /// Removes the response with `id`, returning it if it was still queued.
+    pub fn cancel(&mut self, id: Uuid) -> Option<BotResponse> {
+        let mut pending = std::mem::take(&mut self.heap).into_vec();
+        let cancelled = pending
+            .iter()
+            .position(|entry| entry.id() == Some(id))
+            .map(|index| pending.swap_remove(index));
+        self.heap = BinaryHeap::from(pending);
+        cancelled.map(|entry| entry.response)
+    }
+
+    /// When the earliest queued response becomes due.
+    #[must_use]
+    pub fn next_send_at(&self) -> Option<DateTime<Utc>> {
+        self.heap.peek().map(|next| next.send_at)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn response(content: &str) -> BotResponse {
+        BotResponse::new("bot", "s1", "u1", content, "whatsapp")
+    }
+
+    fn contents(responses: &[BotResponse]) -> Vec<&str> {
+        responses.iter().map(|r| r.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_pop_due_orders_by_send_at_then_insertion() {
+        let now = Utc::now();
+        let mut queue = ScheduledQueue::default();
+        for (content, offset) in [("c", 10), ("a", 5), ("b1", 7), ("b2", 7), ("later", 60)] {
+            assert!(queue
+                .push(response(content).with_send_at(now + Duration::minutes(offset)))
+                .is_ok());
+        }
+        assert!(queue.push(response("now")).is_ok());
+        assert_eq!(contents(&queue.pop_due(now)), ["now"]);
+        assert_eq!(queue.next_send_at(), Some(now + Duration::minutes(5)));
+
+        let due = queue.pop_due(now + Duration::minutes(10));
+        assert_eq!(contents(&due), ["a", "b1", "b2", "c"]);
+        assert_eq!(queue.len(), 1);
+        assert!(queue.pop_due(now + Duration::minutes(59)).is_empty());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let now = Utc::now();
+        let mut queue = ScheduledQueue::default();
+        let keep = queue.push(response("keep").with_send_at(now)).ok();
+        let drop = queue
+            .push(response("drop").with_send_at(now - Duration::minutes(1)))
+            .ok();
+        assert!(keep.is_some() && keep != drop);
+
+        let cancelled = drop.and_then(|id| queue.cancel(id));
+        assert_eq!(cancelled.map(|r| r.content), Some("drop".to_string()));
+        assert!(drop.and_then(|id| queue.cancel(id)).is_none());
+        assert!(queue.cancel(Uuid::new_v4()).is_none());
+
+        let due = queue.pop_due(now);
+        assert_eq!(contents(&due), ["keep"]);
+        assert_eq!(due.first().and_then(|r| r.id), keep);
+    }
+
+    #[test]
+    fn test_pop_due_drops_expired() {
+        let now = Utc::now();
+        let mut queue = ScheduledQueue::default();
+        let otp = response("Your code is 123456")
+            .with_send_at(now)
+            .with_expiry(now + Duration::minutes(5));
+        assert!(otp.is_due(now) && !otp.is_expired(now));
+        assert!(!otp.is_due(now - Duration::seconds(1)));
+        assert!(otp.is_expired(now + Duration::minutes(6)));
+        assert!(queue.push(otp.clone()).is_ok());
+        assert!(queue
+            .push(otp.with_send_at(now + Duration::minutes(1)))
+            .is_ok());
+        assert!(queue.push(response("promo").with_send_at(now)).is_ok());
+
+        assert_eq!(
+            contents(&queue.pop_due(now + Duration::minutes(6))),
+            ["promo"]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_max_pending() {
+        let mut queue = ScheduledQueue::new(2);
+        assert!(queue.push(response("1")).is_ok());
+        assert!(queue.push(response("2")).is_ok());
+        let err = queue.push(response("3")).err();
+        assert_eq!(
+            err.as_ref().map(|e| (e.limit_type, e.current, e.maximum)),
+            Some((LimitType::PendingTasks, 3, 2))
+        );
+        assert_eq!(queue.pop_due(Utc::now()).len(), 2);
+        assert!(queue.push(response("3")).is_ok());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::limits::LimitErrorBody;
+use crate::models::{
+    ApiResponse, Attachment, BotResponse, PaginatedResponse, Session, Suggestion, UserMessage,
+};
+use chrono::{DateTime, Utc};
+use schemars::gen::SchemaSettings;
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn root_schema<T: JsonSchema>() -> RootSchema {
+    SchemaSettings::openapi3()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// OpenAPI 3 schemas for the public API models, keyed by name, for the
+/// server to mount under `/openapi`. `LimitExceeded` describes the body of
+/// `format_limit_error_response`. Nested types are listed in each
+/// schema's `definitions` and referenced as `#/components/schemas/...`.
+/// The generic envelopes are generated with arbitrary JSON as their `data`
+/// and `items`.
+#[must_use]
+pub fn schemas() -> BTreeMap<&'static str, RootSchema> {
+    BTreeMap::from([
+        ("ApiResponse", root_schema::<ApiResponse<Value>>()),
+        ("Attachment", root_schema::<Attachment>()),
+        ("BotResponse", root_schema::<BotResponse>()),
+        ("LimitExceeded", root_schema::<LimitErrorBody>()),
+        (
+            "PaginatedResponse",
+            root_schema::<PaginatedResponse<Value>>(),
+        ),
+        ("Session", root_schema::<Session>()),
+        ("Suggestion", root_schema::<Suggestion>()),
+        ("UserMessage", root_schema::<UserMessage>()),
+    ])
+}
+
+fn example_time() -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000, 0).unwrap_or_default()
+}
+
+pub(crate) fn user_message_example() -> UserMessage {
+    UserMessage {
+        id: Some(Uuid::from_u128(0x6f1c_2b8e_0d4a_4c3e_9a51_7e2d_4b8f_1a03)),
+        timestamp: example_time(),
+        ..UserMessage::text(
+            "support-bot",
+            "5511988887777",
+            "5511988887777",
+            "whatsapp",
+            "Qual o status do meu pedido?",
+        )
+    }
+}
+
+pub(crate) fn bot_response_example() -> BotResponse {
+    BotResponse::reply_to(&user_message_example(), "Seu pedido saiu para entrega.")
+        .with_suggestions(["Rastrear", "Falar com atendente"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn required(schema: &RootSchema) -> BTreeSet<String> {
+        schema
+            .schema
+            .object
+            .as_ref()
+            .map(|object| object.required.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn properties(schema: &RootSchema) -> BTreeSet<String> {
+        schema
+            .schema
+            .object
+            .as_ref()
+            .map(|object| object.properties.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn keys(value: &impl serde::Serialize) -> BTreeSet<String> {
+        serde_json::to_value(value)
+            .ok()
+            .and_then(|value| value.as_object().map(|o| o.keys().cloned().collect()))
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_attachment_snapshot() {
+        let schema = serde_json::to_value(root_schema::<Attachment>()).unwrap_or_default();
+        let golden: Value =
+            serde_json::from_str(include_str!("../tests/golden/schema_attachment.json"))
+                .unwrap_or_default();
+        assert_eq!(schema, golden);
+    }
+
+    #[test]
+    fn test_required_fields_follow_serde_skip_rules() {
+        let all = schemas();
+        let get = |name: &str| all.get(name).cloned().unwrap_or_default();
+
+        let user_message = get("UserMessage");
+        assert_eq!(
+            required(&user_message),
+            BTreeSet::from(
+                [
+                    "bot_id",
+                    "channel",
+                    "content",
+                    "message_type",
+                    "session_id",
+                    "timestamp",
+                    "user_id"
+                ]
+                .map(String::from)
+            )
+        );
+        assert!(properties(&user_message).contains("media_url"));
+
+        // Anything a minimal value leaves out must be optional, and every
+        // field serde may skip is a documented property.
+        let minimal = UserMessage::text("bot", "u1", "s1", "web", "hi");
+        let cases = [
+            (get("UserMessage"), keys(&minimal)),
+            (
+                get("BotResponse"),
+                keys(&BotResponse::new("bot", "s1", "u1", "hi", "web")),
+            ),
+            (
+                get("Session"),
+                keys(&Session::new(Uuid::new_v4(), Uuid::new_v4(), "x")),
+            ),
+            (get("Suggestion"), keys(&Suggestion::new("Yes"))),
+            (get("Attachment"), keys(&Attachment::file("https://x/y"))),
+            (
+                get("ApiResponse"),
+                keys(&ApiResponse::<Value>::error("boom")),
+            ),
+        ];
+        for (schema, present) in cases {
+            assert!(
+                required(&schema).is_subset(&present),
+                "{:?} requires fields a minimal value omits: {:?}",
+                schema
+                    .schema
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.title.clone()),
+                required(&schema).difference(&present).collect::<Vec<_>>()
+            );
+            assert!(present.is_subset(&properties(&schema)));
+        }
+        assert!(!required(&get("BotResponse")).contains("content_format"));
+        assert!(required(&get("LimitExceeded")).contains("maximum"));
+        assert!(!required(&get("LimitExceeded")).contains("retry_after_secs"));
+    }
+
+    #[test]
+    fn test_registry_descriptions_and_examples() {
+        let all = schemas();
+        assert_eq!(
+            all.keys().copied().collect::<Vec<_>>(),
+            [
+                "ApiResponse",
+                "Attachment",
+                "BotResponse",
+                "LimitExceeded",
+                "PaginatedResponse",
+                "Session",
+                "Suggestion",
+                "UserMessage"
+            ]
+        );
+        let user_message = all
+            .get("UserMessage")
+            .and_then(|s| serde_json::to_value(s).ok())
+            .unwrap_or_default();
+        assert_eq!(
+            user_message.get("description"),
+            Some(&Value::from(
+                "A message from a user, as received from a channel."
+            ))
+        );
+        assert_eq!(
+            user_message.pointer("/example/channel"),
+            Some(&Value::from("whatsapp"))
+        );
+        assert_eq!(
+            user_message.pointer("/properties/attachments/items/$ref"),
+            Some(&Value::from("#/components/schemas/Attachment"))
+        );
+        let response = all
+            .get("BotResponse")
+            .and_then(|s| serde_json::to_value(s).ok())
+            .unwrap_or_default();
+        assert_eq!(
+            response.pointer("/example/suggestions/1/text"),
+            Some(&Value::from("Falar com atendente"))
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::error::{BotError, BotResult};
+use crate::models::BotResponse;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_GAP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Text a chunk made available. `completed` holds the whole stream once its
+/// final chunk and every chunk before it have arrived.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamUpdate {
+    pub emitted: String,
+    pub completed: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct StreamState {
+    next: u64,
+    pending: BTreeMap<u64, String>,
+    last: Option<u64>,
+    text: String,
+    waiting_since: Option<Instant>,
+}
+
+impl StreamState {
+    fn missing(&self) -> Vec<u64> {
+        let end = self
+            .last
+            .map(|last| last + 1)
+            .or_else(|| self.pending.keys().next_back().copied())
+            .unwrap_or(self.next);
+        (self.next..end)
+            .filter(|seq| !self.pending.contains_key(seq))
+            .collect()
+    }
+
+    fn is_done(&self) -> bool {
+        self.last.is_some_and(|last| self.next > last)
+    }
+}
+
+/// Reorders streamed `BotResponse` chunks by `sequence`, per
+/// `stream_token`. Out-of-order chunks are buffered, duplicates ignored, and
+/// contiguous text is emitted as soon as it is available. Chunks without a
+/// sequence are applied in arrival order.
+#[derive(Debug)]
+pub struct StreamReassembler {
+    gap_timeout: Duration,
+    streams: HashMap<String, StreamState>,
+}
+
+impl Default for StreamReassembler {
+    fn default() -> Self {
+        Self::new(DEFAULT_GAP_TIMEOUT)
+    }
+}
+
+impl StreamReassembler {
+    /// Report a stream as broken once a gap has stayed unfilled for
+    /// `gap_timeout`; see `expire_gaps`.
+    #[must_use]
+    pub fn new(gap_timeout: Duration) -> Self {
+        Self {
+            gap_timeout,
+            streams: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn active_streams(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// # Errors
+    /// Returns `BotError::Validation` if `chunk` has no `stream_token`.
+    pub fn push(&mut self, chunk: &BotResponse) -> BotResult<StreamUpdate> {
+        self.push_at(chunk, Instant::now())
+    }
+
+    /// `push` with an explicit arrival time.
+    ///
+    /// # Errors
+    /// Returns `BotError::Validation` if `chunk` has no `stream_token`.
+    pub fn push_at(&mut self, chunk: &BotResponse, now: Instant) -> BotResult<StreamUpdate> {
+        let Some(token) = chunk.stream_token.as_deref() else {
+            return Err(BotError::validation("stream chunk has no stream_token"));
+        };
+        let state = self.streams.entry(token.to_string()).or_default();
+        let sequence = chunk.sequence.unwrap_or(state.next);
+        if chunk.is_complete {
+            state.last = Some(sequence);
+        }
+        if sequence >= state.next {
+            state
+                .pending
+                .entry(sequence)
+                .or_insert_with(|| chunk.content.clone());
+        }
+
+        let mut emitted = String::new();
+        while let Some(content) = state.pending.remove(&state.next) {
+            emitted.push_str(&content);
+            state.next += 1;
+        }
+        state.text.push_str(&emitted);
+        if state.pending.is_empty() {
+            state.waiting_since = None;
+        } else if state.waiting_since.is_none() || !emitted.is_empty() {
+            state.waiting_since = Some(now);
+        }
+
+        let completed = if state.is_done() {
+            self.streams.remove(token).map(|state| state.text)
+        } else {
+            None
+        };
+        Ok(StreamUpdate { emitted, completed })
+    }
+
+    /// Drop every stream whose gap has been open for longer than the gap
+    /// timeout, returning one error per dropped stream.
+    pub fn expire_gaps(&mut self, now: Instant) -> Vec<BotError> {
+        let expired: Vec<String> = self
+            .streams
+            .iter()
+            .filter(|(_, state)| {
+                state
+                    .waiting_since
+                    .is_some_and(|since| now.saturating_duration_since(since) >= self.gap_timeout)
+            })
+            .map(|(token, _)| token.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|token| {
+                let state = self.streams.remove(&token)?;
+                Some(missing_chunks(&token, &state.missing()))
+            })
+            .collect()
+    }
+
+    /// Force a stream to end, returning its text.
+    ///
+    /// # Errors
+    /// Returns `BotError::Internal` naming the missing sequence numbers if
+    /// the final chunk or any chunk before it never arrived, and
+    /// `BotError::NotFound` for an unknown token.
+    pub fn finish(&mut self, stream_token: &str) -> BotResult<String> {
+        let Some(state) = self.streams.remove(stream_token) else {
+            return Err(BotError::not_found_id("Stream", stream_token));
+        };
+        if state.last.is_none() {
+            return Err(BotError::internal(format!(
+                "stream {stream_token} has not received its final chunk"
+            )));
+        }
+        let missing = state.missing();
+        if !missing.is_empty() {
+            return Err(missing_chunks(stream_token, &missing));
+        }
+        Ok(state.text)
+    }
+}
+
+fn missing_chunks(stream_token: &str, missing: &[u64]) -> BotError {
+    BotError::internal(format!(
+        "stream {stream_token} is missing chunks {missing:?}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(parts: &[&str]) -> Vec<BotResponse> {
+        let mut stream = BotResponse::streaming("bot", "s1", "u1", "web", "tok");
+        let mut chunks: Vec<BotResponse> =
+            parts.iter().map(|part| stream.next_chunk(*part)).collect();
+        if let Some(last) = chunks.last_mut() {
+            last.is_complete = true;
+        }
+        chunks
+    }
+
+    fn emitted(reassembler: &mut StreamReassembler, chunk: &BotResponse) -> String {
+        reassembler
+            .push(chunk)
+            .map(|update| update.emitted)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_next_chunk_increments_sequence() {
+        let mut stream = BotResponse::streaming("bot", "s1", "u1", "web", "tok");
+        let first = stream.next_chunk("a");
+        let second = stream.next_chunk("b");
+        let last = stream.last_chunk("c");
+        assert_eq!(
+            [first.sequence, second.sequence, last.sequence],
+            [Some(0), Some(1), Some(2)]
+        );
+        assert!(!second.is_complete && last.is_complete);
+        assert_eq!(last.stream_token.as_deref(), Some("tok"));
+        assert_eq!(stream.sequence, Some(3));
+    }
+
+    #[test]
+    fn test_in_order_stream() {
+        let mut reassembler = StreamReassembler::default();
+        let parts = chunks(&["Olá", ", ", "mundo"]);
+        assert_eq!(emitted(&mut reassembler, &parts[0]), "Olá");
+        assert_eq!(emitted(&mut reassembler, &parts[1]), ", ");
+        let update = reassembler.push(&parts[2]).ok();
+        assert_eq!(
+            update,
+            Some(StreamUpdate {
+                emitted: "mundo".to_string(),
+                completed: Some("Olá, mundo".to_string()),
+            })
+        );
+        assert_eq!(reassembler.active_streams(), 0);
+    }
+
+    #[test]
+    fn test_out_of_order_and_duplicate_chunks() {
+        let mut reassembler = StreamReassembler::default();
+        let parts = chunks(&["a", "b", "c", "d"]);
+        assert_eq!(emitted(&mut reassembler, &parts[2]), "");
+        assert_eq!(emitted(&mut reassembler, &parts[0]), "a");
+        assert_eq!(emitted(&mut reassembler, &parts[0]), "");
+        assert_eq!(emitted(&mut reassembler, &parts[3]), "");
+        assert_eq!(emitted(&mut reassembler, &parts[2]), "");
+
+        let update = reassembler.push(&parts[1]).ok();
+        assert_eq!(update.as_ref().map(|u| u.emitted.as_str()), Some("bcd"));
+        assert_eq!(update.and_then(|u| u.completed).as_deref(), Some("abcd"));
+    }
+
+    #[test]
+    fn test_missing_chunk_times_out() {
+        let mut reassembler = StreamReassembler::new(Duration::from_secs(2));
+        let parts = chunks(&["a", "b", "c"]);
+        let start = Instant::now();
+        assert!(reassembler.push_at(&parts[0], start).is_ok());
+        assert!(reassembler.push_at(&parts[2], start).is_ok());
+
+        assert!(reassembler
+            .expire_gaps(start + Duration::from_secs(1))
+            .is_empty());
+        let errors = reassembler.expire_gaps(start + Duration::from_secs(2));
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["Internal error: stream tok is missing chunks [1]"]
+        );
+        assert_eq!(reassembler.active_streams(), 0);
+    }
+
+    #[test]
+    fn test_finish_reports_missing_sequences() {
+        let mut reassembler = StreamReassembler::default();
+        let parts = chunks(&["a", "b", "c", "d"]);
+        for chunk in [&parts[0], &parts[3]] {
+            assert!(reassembler.push(chunk).is_ok());
+        }
+        assert_eq!(
+            reassembler.finish("tok").err().map(|e| e.to_string()),
+            Some("Internal error: stream tok is missing chunks [1, 2]".to_string())
+        );
+        assert!(matches!(
+            reassembler.finish("tok"),
+            Err(BotError::NotFound { .. })
+        ));
+        assert!(matches!(
+            reassembler.push(&BotResponse::default()),
+            Err(BotError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,422 @@
+use crate::version::{ComponentSource, ComponentStatus, ComponentVersion, VersionRegistry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// What `VersionRegistry::diff_with` counts as a change. Version, status
+/// and source always count; metadata only for the keys listed here, so
+/// values that churn on every check, such as build timestamps, are not
+/// reported. Timestamps and counters outside metadata never count.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffOptions {
+    #[serde(default)]
+    pub metadata_keys: BTreeSet<String>,
+}
+
+impl DiffOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_metadata_key(mut self, key: impl Into<String>) -> Self {
+        self.metadata_keys.insert(key.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change<T> {
+    pub from: T,
+    pub to: T,
+}
+
+/// A metadata key that was added, removed or given a new value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataChange {
+    pub key: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// How a component registered on both sides changed. Fields that did not
+/// change are `None` or empty.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentChange {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<Change<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Change<ComponentStatus>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Change<ComponentSource>>,
+    /// Sorted by key.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metadata: Vec<MetadataChange>,
+}
+
+impl ComponentChange {
+    fn between(before: &ComponentVersion, after: &ComponentVersion, options: &DiffOptions) -> Self {
+        let change = |from: &str, to: &str| {
+            (from != to).then(|| Change {
+                from: from.to_string(),
+                to: to.to_string(),
+            })
+        };
+        let metadata = options
+            .metadata_keys
+            .iter()
+            .filter_map(|key| {
+                let from = before.metadata.get(key);
+                let to = after.metadata.get(key);
+                (from != to).then(|| MetadataChange {
+                    key: key.clone(),
+                    from: from.cloned(),
+                    to: to.cloned(),
+                })
+            })
+            .collect();
+        Self {
+            name: after.name.clone(),
+            version: change(&before.version, &after.version),
+            status: (before.status != after.status).then_some(Change {
+                from: before.status,
+                to: after.status,
+            }),
+            source: (before.source != after.source).then(|| Change {
+                from: before.source.clone(),
+                to: after.source.clone(),
+            }),
+            metadata,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.version.is_none()
+            && self.status.is_none()
+            && self.source.is_none()
+            && self.metadata.is_empty()
+    }
+}
+
+impl fmt::Display for ComponentChange {
+    /// `name: version a -> b, status ...`, listing only what changed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(c) = &self.version {
+            parts.push(format!("version {} -> {}", c.from, c.to));
+        }
+        if let Some(c) = &self.status {
+            parts.push(format!("status {:?} -> {:?}", c.from, c.to));
+        }
+        if let Some(c) = &self.source {
+            parts.push(format!("source {:?} -> {:?}", c.from, c.to));
+        }
+        for c in &self.metadata {
+            parts.push(format!(
+                "{} {} -> {}",
+                c.key,
+                c.from.as_deref().unwrap_or("(none)"),
+                c.to.as_deref().unwrap_or("(none)")
+            ));
+        }
+        write!(f, "{}: {}", self.name, parts.join(", "))
+    }
+}
+
+/// A component present on only one side of a diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffEntry {
+    pub name: String,
+    pub version: String,
+}
+
+/// The result of `VersionRegistry::diff`. Each list is sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryDiff {
+    pub added: Vec<DiffEntry>,
+    pub removed: Vec<DiffEntry>,
+    pub changed: Vec<ComponentChange>,
+}
+
+impl RegistryDiff {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for RegistryDiff {
+    /// A header line, then one line per component: `+` added, `-` removed,
+    /// `~` changed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "registry diff: no changes");
+        }
+        write!(
+            f,
+            "registry diff: {} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )?;
+        for entry in &self.added {
+            write!(f, "\n  + {} {}", entry.name, entry.version)?;
+        }
+        for entry in &self.removed {
+            write!(f, "\n  - {} {}", entry.name, entry.version)?;
+        }
+        for change in &self.changed {
+            write!(f, "\n  ~ {change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl VersionRegistry {
+    /// A copy to compare against later with `diff_since`.
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// What changed between `snapshot` and now.
+    #[must_use]
+    pub fn diff_since(&self, snapshot: &Self) -> RegistryDiff {
+        snapshot.diff(self)
+    }
+
+    /// What changed going from this registry to `other`, ignoring metadata.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> RegistryDiff {
+        self.diff_with(other, &DiffOptions::default())
+    }
+
+    /// What changed going from this registry to `other`, counting metadata
+    /// changes for `options.metadata_keys`.
+    #[must_use]
+    pub fn diff_with(&self, other: &Self, options: &DiffOptions) -> RegistryDiff {
+        let entry = |c: &ComponentVersion| DiffEntry {
+            name: c.name.clone(),
+            version: c.version.clone(),
+        };
+        let mut diff = RegistryDiff {
+            added: other
+                .components
+                .values()
+                .filter(|c| !self.components.contains_key(&c.name))
+                .map(entry)
+                .collect(),
+            removed: self
+                .components
+                .values()
+                .filter(|c| !other.components.contains_key(&c.name))
+                .map(entry)
+                .collect(),
+            changed: self
+                .components
+                .values()
+                .filter_map(|before| {
+                    let after = other.components.get(&before.name)?;
+                    let change = ComponentChange::between(before, after, options);
+                    (!change.is_empty()).then_some(change)
+                })
+                .collect(),
+        };
+        diff.added.sort_by(|a, b| a.name.cmp(&b.name));
+        diff.removed.sort_by(|a, b| a.name.cmp(&b.name));
+        diff.changed.sort_by(|a, b| a.name.cmp(&b.name));
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn registry() -> VersionRegistry {
+        let mut registry = VersionRegistry::default();
+        for (name, version, source) in [
+            ("llm", "2.3.0", ComponentSource::Builtin),
+            ("vault", "1.15.0", ComponentSource::Docker),
+            ("legacy", "0.9.0", ComponentSource::System),
+        ] {
+            let registered = registry.register_component(ComponentVersion {
+                name: name.to_string(),
+                version: version.to_string(),
+                latest_version: None,
+                update_available: false,
+                status: ComponentStatus::Running,
+                last_checked: None,
+                last_heartbeat: None,
+                heartbeat_timeout_secs: None,
+                status_before_stale: None,
+                depends_on: Vec::new(),
+                compat: HashMap::new(),
+                started_at: None,
+                restart_count: 0,
+                recent_restarts: Vec::new(),
+                last_error: None,
+                source,
+                metadata: HashMap::from([
+                    ("repo".to_string(), format!("https://git.example/{name}")),
+                    ("build_timestamp".to_string(), "2024-05-01".to_string()),
+                ]),
+            });
+            assert!(registered.is_ok());
+        }
+        registry
+    }
+
+    #[test]
+    fn test_unchanged_registry_has_empty_diff() {
+        let mut registry = registry();
+        let before = registry.snapshot();
+        registry.heartbeat("llm");
+        assert!(registry
+            .update_version("vault", "1.15.0".to_string())
+            .is_ok());
+        let diff = registry.diff_since(&before);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "registry diff: no changes");
+    }
+
+    #[test]
+    fn test_added_removed_and_changed() {
+        let mut registry = registry();
+        let before = registry.snapshot();
+        registry.deregister_component("legacy");
+        let mut search = before.get_component("vault").cloned();
+        if let Some(search) = search.as_mut() {
+            search.name = "search".to_string();
+            search.version = "8.11.0".to_string();
+        }
+        assert!(search.is_some_and(|c| registry.register_component(c).is_ok()));
+        assert!(registry.update_version("llm", "3.0.0".to_string()).is_ok());
+        assert!(registry
+            .update_status("vault", ComponentStatus::Error)
+            .is_ok());
+        if let Some(vault) = registry.components.get_mut("vault") {
+            vault.source = ComponentSource::Lxc;
+        }
+
+        let diff = registry.diff_since(&before);
+        assert_eq!(
+            diff.added,
+            [DiffEntry {
+                name: "search".to_string(),
+                version: "8.11.0".to_string(),
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            [DiffEntry {
+                name: "legacy".to_string(),
+                version: "0.9.0".to_string(),
+            }]
+        );
+        assert_eq!(
+            diff.changed,
+            [
+                ComponentChange {
+                    name: "llm".to_string(),
+                    version: Some(Change {
+                        from: "2.3.0".to_string(),
+                        to: "3.0.0".to_string(),
+                    }),
+                    status: None,
+                    source: None,
+                    metadata: Vec::new(),
+                },
+                ComponentChange {
+                    name: "vault".to_string(),
+                    version: None,
+                    status: Some(Change {
+                        from: ComponentStatus::Running,
+                        to: ComponentStatus::Error,
+                    }),
+                    source: Some(Change {
+                        from: ComponentSource::Docker,
+                        to: ComponentSource::Lxc,
+                    }),
+                    metadata: Vec::new(),
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "registry diff: 1 added, 1 removed, 2 changed\n  \
+             + search 8.11.0\n  \
+             - legacy 0.9.0\n  \
+             ~ llm: version 2.3.0 -> 3.0.0\n  \
+             ~ vault: status Running -> Error, source Docker -> Lxc"
+        );
+        assert_eq!(before.diff(&registry), diff);
+        // Swapping the sides swaps added and removed.
+        assert_eq!(registry.diff(&before).added, diff.removed);
+    }
+
+    #[test]
+    fn test_metadata_counts_only_for_allowed_keys() {
+        let mut registry = registry();
+        let before = registry.snapshot();
+        if let Some(llm) = registry.components.get_mut("llm") {
+            llm.metadata
+                .insert("build_timestamp".to_string(), "2024-06-01".to_string());
+            llm.metadata.remove("repo");
+            llm.metadata
+                .insert("channel".to_string(), "beta".to_string());
+        }
+        assert!(registry.diff_since(&before).is_empty());
+
+        let options = DiffOptions::new()
+            .with_metadata_key("repo")
+            .with_metadata_key("channel");
+        let diff = before.diff_with(&registry, &options);
+        assert_eq!(
+            diff.changed.first().map(|c| c.metadata.clone()),
+            Some(vec![
+                MetadataChange {
+                    key: "channel".to_string(),
+                    from: None,
+                    to: Some("beta".to_string()),
+                },
+                MetadataChange {
+                    key: "repo".to_string(),
+                    from: Some("https://git.example/llm".to_string()),
+                    to: None,
+                },
+            ])
+        );
+        assert_eq!(
+            diff.to_string(),
+            "registry diff: 0 added, 0 removed, 1 changed\n  \
+             ~ llm: channel (none) -> beta, repo https://git.example/llm -> (none)"
+        );
+    }
+
+    #[test]
+    fn test_diff_serde_round_trip() {
+        let mut registry = registry();
+        let before = registry.snapshot();
+        assert!(registry.update_version("llm", "3.0.0".to_string()).is_ok());
+        let diff = registry.diff_since(&before);
+        let json = serde_json::to_value(&diff).unwrap_or_default();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "added": [],
+                "removed": [],
+                "changed": [
+                    {"name": "llm", "version": {"from": "2.3.0", "to": "3.0.0"}}
+                ]
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<RegistryDiff>(json).ok(),
+            Some(diff)
+        );
+    }
+}
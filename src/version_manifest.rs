@@ -0,0 +1,453 @@
+use crate::error::{BotError, BotResult};
+use crate::version::{
+    cmp_precedence, parse_version, ComponentSource, ComponentStatus, VersionRegistry,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+
+const fn default_required() -> bool {
+    true
+}
+
+/// One entry of an `ExpectedComponents` manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedComponent {
+    pub name: String,
+    /// Lowest acceptable version, compared by semver precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<String>,
+    /// Optional components may be absent, but are checked when present.
+    #[serde(default = "default_required")]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ComponentSource>,
+}
+
+impl ExpectedComponent {
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            min_version: None,
+            required: true,
+            source: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_min_version(mut self, version: impl Into<String>) -> Self {
+        self.min_version = Some(version.into());
+        self
+    }
+
+    #[must_use]
+    pub const fn with_source(mut self, source: ComponentSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    #[must_use]
+    pub const fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+}
+
+/// The components a deployment ships with, checked by
+/// `VersionRegistry::verify`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedComponents {
+    #[serde(default)]
+    pub components: Vec<ExpectedComponent>,
+    /// Whether registered components missing from the manifest fail
+    /// verification rather than only being reported.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl ExpectedComponents {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_component(mut self, component: ExpectedComponent) -> Self {
+        self.components.push(component);
+        self
+    }
+
+    #[must_use]
+    pub const fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Parses a manifest with a `[[components]]` table per entry.
+    ///
+    /// # Errors
+    /// Returns `BotError::Config` if `text` is not a valid manifest.
+    pub fn from_toml(text: &str) -> BotResult<Self> {
+        toml::from_str(text).map_err(|e| BotError::config(format!("invalid manifest: {e}")))
+    }
+
+    /// # Errors
+    /// Returns `BotError::Json` if `text` is not a valid manifest.
+    pub fn from_json(text: &str) -> BotResult<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionTooOld {
+    pub name: String,
+    pub required: String,
+    pub found: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrongSource {
+    pub name: String,
+    pub expected: ComponentSource,
+    pub found: ComponentSource,
+}
+
+/// The result of `VersionRegistry::verify`. Each list is sorted by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// Required components that are not registered.
+    pub missing: Vec<String>,
+    /// Required components that are registered but not `Running`.
+    pub not_running: Vec<(String, ComponentStatus)>,
+    pub too_old: Vec<VersionTooOld>,
+    pub wrong_source: Vec<WrongSource>,
+    /// Registered components the manifest does not list.
+    pub unexpected: Vec<String>,
+    /// Whether `unexpected` entries fail verification.
+    pub strict: bool,
+    /// How many manifest entries were checked.
+    pub checked: usize,
+}
+
+impl VerificationReport {
+    /// Whether the registry satisfies the manifest, for gating startup.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.missing.is_empty()
+            && self.not_running.is_empty()
+            && self.too_old.is_empty()
+            && self.wrong_source.is_empty()
+            && (!self.strict || self.unexpected.is_empty())
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    /// A header line, then one line per kind of violation found.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.passed() { "passed" } else { "FAILED" };
+        write!(
+            f,
+            "manifest verification {verdict}: {} expected components checked",
+            self.checked
+        )?;
+        if !self.missing.is_empty() {
+            write!(f, "\n  missing: {}", self.missing.join(", "))?;
+        }
+        if !self.not_running.is_empty() {
+            let entries: Vec<String> = self
+                .not_running
+                .iter()
+                .map(|(name, status)| format!("{name} ({status:?})"))
+                .collect();
+            write!(f, "\n  not running: {}", entries.join(", "))?;
+        }
+        if !self.too_old.is_empty() {
+            let entries: Vec<String> = self
+                .too_old
+                .iter()
+                .map(|e| format!("{} {} < {}", e.name, e.found, e.required))
+                .collect();
+            write!(f, "\n  too old: {}", entries.join(", "))?;
+        }
+        if !self.wrong_source.is_empty() {
+            let entries: Vec<String> = self
+                .wrong_source
+                .iter()
+                .map(|e| format!("{} from {:?}, expected {:?}", e.name, e.found, e.expected))
+                .collect();
+            write!(f, "\n  wrong source: {}", entries.join(", "))?;
+        }
+        if !self.unexpected.is_empty() {
+            write!(f, "\n  unexpected: {}", self.unexpected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `found` is at least `required`. A version that does not parse
+/// never is.
+fn at_least(found: &str, required: &str) -> bool {
+    match (parse_version(found), parse_version(required)) {
+        (Some(found), Some(required)) => cmp_precedence(&found, &required) != Ordering::Less,
+        _ => false,
+    }
+}
+
+impl VersionRegistry {
+    /// Checks the registry against `expected`: every required component
+    /// must be registered and `Running`, and every listed component that
+    /// is registered must meet its minimum version and source.
+    #[must_use]
+    pub fn verify(&self, expected: &ExpectedComponents) -> VerificationReport {
+        let mut report = VerificationReport {
+            strict: expected.strict,
+            checked: expected.components.len(),
+            ..VerificationReport::default()
+        };
+        for entry in &expected.components {
+            let Some(component) = self.components.get(&entry.name) else {
+                if entry.required {
+                    report.missing.push(entry.name.clone());
+                }
+                continue;
+            };
+            if entry.required && component.status != ComponentStatus::Running {
+                report
+                    .not_running
+                    .push((entry.name.clone(), component.status));
+            }
+            if let Some(required) = &entry.min_version {
+                if !at_least(&component.version, required) {
+                    report.too_old.push(VersionTooOld {
+                        name: entry.name.clone(),
+                        required: required.clone(),
+                        found: component.version.clone(),
+                    });
+                }
+            }
+            if let Some(source) = &entry.source {
+                if *source != component.source {
+                    report.wrong_source.push(WrongSource {
+                        name: entry.name.clone(),
+                        expected: source.clone(),
+                        found: component.source.clone(),
+                    });
+                }
+            }
+        }
+        report.unexpected = self
+            .components
+            .keys()
+            .filter(|name| !expected.components.iter().any(|e| &e.name == *name))
+            .cloned()
+            .collect();
+
+        report.missing.sort();
+        report.not_running.sort_by(|a, b| a.0.cmp(&b.0));
+        report.too_old.sort_by(|a, b| a.name.cmp(&b.name));
+        report.wrong_source.sort_by(|a, b| a.name.cmp(&b.name));
+        report.unexpected.sort();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::ComponentVersion;
+    use std::collections::HashMap;
+
+    fn registry() -> VersionRegistry {
+        let mut registry = VersionRegistry::default();
+        for (name, version, status, source) in [
+            (
+                "llm",
+                "6.0.1",
+                ComponentStatus::Running,
+                ComponentSource::Builtin,
+            ),
+            (
+                "vault",
+                "1.15.0",
+                ComponentStatus::Running,
+                ComponentSource::Docker,
+            ),
+            (
+                "cache",
+                "7.2.4",
+                ComponentStatus::Stopped,
+                ComponentSource::System,
+            ),
+        ] {
+            let registered = registry.register_component(ComponentVersion {
+                name: name.to_string(),
+                version: version.to_string(),
+                latest_version: None,
+                update_available: false,
+                status,
+                last_checked: None,
+                last_heartbeat: None,
+                heartbeat_timeout_secs: None,
+                status_before_stale: None,
+                depends_on: Vec::new(),
+                compat: HashMap::new(),
+                started_at: None,
+                restart_count: 0,
+                recent_restarts: Vec::new(),
+                last_error: None,
+                source,
+                metadata: HashMap::new(),
+            });
+            assert!(registered.is_ok());
+        }
+        registry
+    }
+
+    const MANIFEST: &str = r#"
+strict = true
+
+[[components]]
+name = "llm"
+min_version = "6.0.0"
+source = "Builtin"
+
+[[components]]
+name = "vault"
+min_version = "1.15.0-rc.1"
+
+[[components]]
+name = "cache"
+required = false
+"#;
+
+    #[test]
+    fn test_passing_manifest_from_toml() {
+        let manifest = ExpectedComponents::from_toml(MANIFEST).unwrap_or_default();
+        assert_eq!(manifest.components.len(), 3);
+        assert!(manifest
+            .components
+            .iter()
+            .find(|c| c.name == "cache")
+            .is_some_and(|c| !c.required));
+
+        let report = registry().verify(&manifest);
+        assert!(report.passed(), "{report}");
+        assert_eq!(
+            report.to_string(),
+            "manifest verification passed: 3 expected components checked"
+        );
+    }
+
+    #[test]
+    fn test_missing_and_not_running() {
+        let manifest = ExpectedComponents::new()
+            .with_component(ExpectedComponent::new("llm"))
+            .with_component(ExpectedComponent::new("vault"))
+            .with_component(ExpectedComponent::new("cache"))
+            .with_component(ExpectedComponent::new("directory"))
+            .with_component(ExpectedComponent::new("search").optional());
+        let report = registry().verify(&manifest);
+        assert!(!report.passed());
+        assert_eq!(report.missing, ["directory"]);
+        assert_eq!(
+            report.not_running,
+            [("cache".to_string(), ComponentStatus::Stopped)]
+        );
+    }
+
+    #[test]
+    fn test_version_too_old() {
+        let manifest = ExpectedComponents::new()
+            .with_component(ExpectedComponent::new("llm").with_min_version("6.1.0"))
+            .with_component(ExpectedComponent::new("vault").with_min_version("v1.9.0"));
+        let mut registry = registry();
+        let report = registry.verify(&manifest);
+        assert_eq!(
+            report.too_old,
+            [VersionTooOld {
+                name: "llm".to_string(),
+                required: "6.1.0".to_string(),
+                found: "6.0.1".to_string(),
+            }]
+        );
+
+        assert!(registry
+            .update_version("vault", "nightly".to_string())
+            .is_ok());
+        let report = registry.verify(&manifest);
+        assert_eq!(report.too_old.len(), 2);
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn test_wrong_source() {
+        let manifest = ExpectedComponents::new()
+            .with_component(ExpectedComponent::new("vault").with_source(ComponentSource::Lxc))
+            .with_component(
+                ExpectedComponent::new("cache")
+                    .optional()
+                    .with_source(ComponentSource::Docker),
+            );
+        let report = registry().verify(&manifest);
+        assert_eq!(
+            report.wrong_source,
+            [
+                WrongSource {
+                    name: "cache".to_string(),
+                    expected: ComponentSource::Docker,
+                    found: ComponentSource::System,
+                },
+                WrongSource {
+                    name: "vault".to_string(),
+                    expected: ComponentSource::Lxc,
+                    found: ComponentSource::Docker,
+                },
+            ]
+        );
+        assert!(!report.passed());
+    }
+
+    #[test]
+    fn test_unexpected_extras_fail_only_when_strict() {
+        let json = r#"{"components": [{"name": "llm"}, {"name": "vault"}]}"#;
+        let manifest = ExpectedComponents::from_json(json).unwrap_or_default();
+        let report = registry().verify(&manifest);
+        assert_eq!(report.unexpected, ["cache"]);
+        assert!(report.passed());
+
+        let report = registry().verify(&manifest.strict());
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "manifest verification FAILED: 2 expected components checked\n  unexpected: cache"
+        );
+    }
+
+    #[test]
+    fn test_report_render_and_parse_errors() {
+        let manifest = ExpectedComponents::new()
+            .with_component(ExpectedComponent::new("directory"))
+            .with_component(ExpectedComponent::new("cache").with_min_version("8.0"))
+            .with_component(ExpectedComponent::new("llm").with_source(ComponentSource::Docker));
+        let report = registry().verify(&manifest);
+        assert_eq!(
+            report.to_string(),
+            "manifest verification FAILED: 3 expected components checked\n  \
+             missing: directory\n  \
+             not running: cache (Stopped)\n  \
+             too old: cache 7.2.4 < 8.0\n  \
+             wrong source: llm from Builtin, expected Docker\n  \
+             unexpected: vault"
+        );
+
+        assert!(matches!(
+            ExpectedComponents::from_toml("[[components]]\nrequired = true"),
+            Err(BotError::Config(_))
+        ));
+        assert!(matches!(
+            ExpectedComponents::from_json("{\"components\": 1}"),
+            Err(BotError::Json(_))
+        ));
+    }
+}
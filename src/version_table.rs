@@ -0,0 +1,392 @@
+use crate::sanitize::truncate_graphemes;
+use crate::version::{ComponentStatus, ComponentVersion, VersionRegistry};
+use chrono::{DateTime, Utc};
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableColumn {
+    Name,
+    Version,
+    Latest,
+    Status,
+    Source,
+    LastCheck,
+}
+
+impl TableColumn {
+    pub const ALL: [Self; 6] = [
+        Self::Name,
+        Self::Version,
+        Self::Latest,
+        Self::Status,
+        Self::Source,
+        Self::LastCheck,
+    ];
+
+    const fn header(self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::Version => "Version",
+            Self::Latest => "Latest",
+            Self::Status => "Status",
+            Self::Source => "Source",
+            Self::LastCheck => "Last check",
+        }
+    }
+
+    fn cell(self, component: &ComponentVersion, now: DateTime<Utc>) -> String {
+        match self {
+            Self::Name => component.name.clone(),
+            Self::Version => component.version.clone(),
+            Self::Latest => component
+                .latest_version
+                .clone()
+                .unwrap_or_else(|| "-".to_string()),
+            Self::Status => component.status.to_string(),
+            Self::Source => component.source.to_string(),
+            Self::LastCheck => component
+                .last_checked
+                .map_or_else(|| "never".to_string(), |at| age(now, at)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableSort {
+    #[default]
+    Name,
+    /// Worst status first, then by name.
+    Severity,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableBorder {
+    #[default]
+    Unicode,
+    /// `+`, `-` and `|` only, for terminals without box drawing.
+    Ascii,
+}
+
+/// Characters for the top, header separator and bottom rules as (left,
+/// join, right, line), then the cell separator.
+struct Glyphs {
+    top: [char; 4],
+    middle: [char; 4],
+    bottom: [char; 4],
+    bar: char,
+}
+
+impl TableBorder {
+    const fn glyphs(self) -> Glyphs {
+        match self {
+            Self::Unicode => Glyphs {
+                top: ['┌', '┬', '┐', '─'],
+                middle: ['├', '┼', '┤', '─'],
+                bottom: ['└', '┴', '┘', '─'],
+                bar: '│',
+            },
+            Self::Ascii => Glyphs {
+                top: ['+', '+', '+', '-'],
+                middle: ['+', '+', '+', '-'],
+                bottom: ['+', '+', '+', '-'],
+                bar: '|',
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableOptions {
+    pub columns: Vec<TableColumn>,
+    pub sort: TableSort,
+    /// Only failing or degraded components.
+    pub problems_only: bool,
+    pub border: TableBorder,
+    /// Longer cells are cut to this many characters, ending in `…`.
+    pub max_cell_width: usize,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            columns: TableColumn::ALL.to_vec(),
+            sort: TableSort::default(),
+            problems_only: false,
+            border: TableBorder::default(),
+            max_cell_width: 24,
+        }
+    }
+}
+
+impl TableOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_columns(mut self, columns: impl IntoIterator<Item = TableColumn>) -> Self {
+        self.columns = columns.into_iter().collect();
+        self
+    }
+
+    #[must_use]
+    pub const fn with_sort(mut self, sort: TableSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    #[must_use]
+    pub const fn problems_only(mut self) -> Self {
+        self.problems_only = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn ascii(mut self) -> Self {
+        self.border = TableBorder::Ascii;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_cell_width(mut self, width: usize) -> Self {
+        self.max_cell_width = width;
+        self
+    }
+}
+
+/// Lower is worse.
+const fn severity(status: ComponentStatus) -> u8 {
+    match status {
+        ComponentStatus::Error => 0,
+        ComponentStatus::Stale => 1,
+        ComponentStatus::Unknown => 2,
+        ComponentStatus::Updating => 3,
+        ComponentStatus::Stopped => 4,
+        ComponentStatus::NotInstalled => 5,
+        ComponentStatus::Running => 6,
+    }
+}
+
+fn age(now: DateTime<Utc>, at: DateTime<Utc>) -> String {
+    let secs = now.signed_duration_since(at).num_seconds().max(0);
+    match secs {
+        0..=59 => format!("{secs}s ago"),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+fn width(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+fn rule(out: &mut String, [left, join, right, line]: [char; 4], widths: &[usize]) {
+    out.push(left);
+    let segments: Vec<String> = widths
+        .iter()
+        .map(|w| line.to_string().repeat(w + 2))
+        .collect();
+    out.push_str(&segments.join(&join.to_string()));
+    out.push(right);
+    out.push('\n');
+}
+
+fn row(out: &mut String, bar: char, cells: &[String], widths: &[usize]) {
+    for (cell, w) in cells.iter().zip(widths) {
+        out.push(bar);
+        out.push(' ');
+        out.push_str(cell);
+        out.push_str(&" ".repeat(w - width(cell) + 1));
+    }
+    out.push(bar);
+    out.push('\n');
+}
+
+impl VersionRegistry {
+    /// The components as an aligned plain-text table, for terminals.
+    #[must_use]
+    pub fn to_table(&self, options: &TableOptions) -> String {
+        self.to_table_at(options, Utc::now())
+    }
+
+    /// `to_table` with last check ages measured from `now`.
+    #[must_use]
+    pub fn to_table_at(&self, options: &TableOptions, now: DateTime<Utc>) -> String {
+        let mut components: Vec<&ComponentVersion> = self
+            .components
+            .values()
+            .filter(|c| !options.problems_only || c.is_failing() || c.is_degraded())
+            .collect();
+        match options.sort {
+            TableSort::Name => components.sort_by(|a, b| a.name.cmp(&b.name)),
+            TableSort::Severity => components
+                .sort_by(|a, b| (severity(a.status), &a.name).cmp(&(severity(b.status), &b.name))),
+        }
+
+        let header: Vec<String> = options
+            .columns
+            .iter()
+            .map(|c| c.header().to_string())
+            .collect();
+        let rows: Vec<Vec<String>> = components
+            .iter()
+            .map(|component| {
+                options
+                    .columns
+                    .iter()
+                    .map(|c| truncate_graphemes(&c.cell(component, now), options.max_cell_width))
+                    .collect()
+            })
+            .collect();
+        let widths: Vec<usize> = (0..header.len())
+            .map(|i| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .filter_map(|cells| cells.get(i))
+                    .map(|cell| width(cell))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let glyphs = options.border.glyphs();
+        let mut out = String::new();
+        rule(&mut out, glyphs.top, &widths);
+        row(&mut out, glyphs.bar, &header, &widths);
+        rule(&mut out, glyphs.middle, &widths);
+        for cells in &rows {
+            row(&mut out, glyphs.bar, cells, &widths);
+        }
+        rule(&mut out, glyphs.bottom, &widths);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::ComponentSource;
+    use std::collections::HashMap;
+
+    fn registry(now: DateTime<Utc>) -> VersionRegistry {
+        let mut registry = VersionRegistry::default();
+        for (name, version, status, source, checked) in [
+            (
+                "llm",
+                "6.0.1",
+                ComponentStatus::Running,
+                ComponentSource::Builtin,
+                Some(90),
+            ),
+            (
+                "vault",
+                "1.15.0",
+                ComponentStatus::Error,
+                ComponentSource::Docker,
+                Some(7200),
+            ),
+            (
+                "speech-recognition-whisper-large",
+                "2.0.0",
+                ComponentStatus::Stale,
+                ComponentSource::Lxc,
+                None,
+            ),
+            (
+                "cache",
+                "7.2.4",
+                ComponentStatus::Stopped,
+                ComponentSource::System,
+                Some(5),
+            ),
+        ] {
+            let registered = registry.register_component(ComponentVersion {
+                name: name.to_string(),
+                version: version.to_string(),
+                latest_version: None,
+                update_available: false,
+                status,
+                last_checked: checked.map(|secs| now - chrono::Duration::seconds(secs)),
+                last_heartbeat: None,
+                heartbeat_timeout_secs: None,
+                status_before_stale: None,
+                depends_on: Vec::new(),
+                compat: HashMap::new(),
+                started_at: None,
+                restart_count: 0,
+                recent_restarts: Vec::new(),
+                last_error: None,
+                source,
+                metadata: HashMap::new(),
+            });
+            assert!(registered.is_ok());
+        }
+        if let Some(llm) = registry.components.get_mut("llm") {
+            llm.latest_version = Some("6.1.0".to_string());
+        }
+        registry
+    }
+
+    #[test]
+    fn test_unicode_table() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap_or_default();
+        let table = registry(now).to_table_at(&TableOptions::new(), now);
+        assert_eq!(
+            table,
+            "\
+┌──────────────────────────┬─────────┬────────┬────────────────┬──────────┬────────────┐
+│ Name                     │ Version │ Latest │ Status         │ Source   │ Last check │
+├──────────────────────────┼─────────┼────────┼────────────────┼──────────┼────────────┤
+│ cache                    │ 7.2.4   │ -      │ [STOP] Stopped │ System   │ 5s ago     │
+│ llm                      │ 6.0.1   │ 6.1.0  │ [OK] Running   │ Built-in │ 1m ago     │
+│ speech-recognition-whis… │ 2.0.0   │ -      │ [STALE] Stale  │ LXC      │ never      │
+│ vault                    │ 1.15.0  │ -      │ [ERR] Error    │ Docker   │ 2h ago     │
+└──────────────────────────┴─────────┴────────┴────────────────┴──────────┴────────────┘
+"
+        );
+    }
+
+    #[test]
+    fn test_ascii_problems_by_severity() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap_or_default();
+        let options = TableOptions::new()
+            .ascii()
+            .problems_only()
+            .with_sort(TableSort::Severity)
+            .with_columns([TableColumn::Name, TableColumn::Status])
+            .with_max_cell_width(13);
+        assert_eq!(
+            registry(now).to_table_at(&options, now),
+            "\
++---------------+---------------+
+| Name          | Status        |
++---------------+---------------+
+| vault         | [ERR] Error   |
+| speech-recog… | [STALE] Stale |
++---------------+---------------+
+"
+        );
+    }
+
+    #[test]
+    fn test_severity_sort_and_empty_table() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap_or_default();
+        let options = TableOptions::new()
+            .ascii()
+            .with_sort(TableSort::Severity)
+            .with_columns([TableColumn::Name]);
+        let table = registry(now).to_table_at(&options, now);
+        let names: Vec<&str> = table
+            .lines()
+            .filter(|l| l.starts_with('|'))
+            .skip(1)
+            .map(|l| l.trim_matches(|c| c == '|' || c == ' '))
+            .collect();
+        assert_eq!(names, ["vault", "speech-recognition-whis…", "cache", "llm"]);
+
+        let empty = VersionRegistry::default().to_table_at(&options.problems_only(), now);
+        assert_eq!(empty, "+------+\n| Name |\n+------+\n+------+\n");
+    }
+}
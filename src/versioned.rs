@@ -0,0 +1,286 @@
+use crate::error::{BotError, BotResult};
+use crate::models::{BotResponse, Session, UserMessage};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A model persisted as JSON, tagged with the schema version it is written
+/// at.
+pub trait Persisted: Serialize + DeserializeOwned {
+    /// Names the type in `Migrator` registrations and error messages.
+    const TYPE_NAME: &'static str;
+    /// Bump when a change to the type cannot read rows written before it,
+    /// and register a migration from the previous version.
+    const SCHEMA_VERSION: u32;
+}
+
+impl Persisted for UserMessage {
+    const TYPE_NAME: &'static str = "UserMessage";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl Persisted for BotResponse {
+    const TYPE_NAME: &'static str = "BotResponse";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl Persisted for Session {
+    const TYPE_NAME: &'static str = "Session";
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+/// The stored form of a persisted model:
+/// `{"schema_version": 1, "payload": {...}}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub schema_version: u32,
+    pub payload: T,
+}
+
+impl<T: Persisted> Versioned<T> {
+    /// Wraps `payload` at its type's current schema version.
+    #[must_use]
+    pub const fn new(payload: T) -> Self {
+        Self {
+            schema_version: T::SCHEMA_VERSION,
+            payload,
+        }
+    }
+}
+
+/// `value` wrapped in a `Versioned` envelope at its current schema version,
+/// as JSON.
+///
+/// # Errors
+/// Returns `BotError::Internal` if the value cannot be serialized.
+pub fn encode_versioned<T: Persisted>(value: &T) -> BotResult<Vec<u8>> {
+    let envelope = Versioned {
+        schema_version: T::SCHEMA_VERSION,
+        payload: value,
+    };
+    serde_json::to_vec(&envelope)
+        .map_err(|e| BotError::internal(format!("{} encoding failed: {e}", T::TYPE_NAME)))
+}
+
+type Migration = Box<dyn Fn(&mut Value) + Send + Sync>;
+
+/// Fix-ups that bring old payloads up to the current schema, one per type
+/// and version step, applied to the raw JSON before it is deserialized.
+#[derive(Default)]
+pub struct Migrator {
+    migrations: HashMap<(&'static str, u32), Migration>,
+}
+
+impl std::fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Migrator")
+            .field("migrations", &self.migrations.len())
+            .finish()
+    }
+}
+
+impl Migrator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the fix-up that turns a `T` payload at `from_version` into
+    /// one at `from_version + 1`, replacing any earlier registration.
+    pub fn register<T: Persisted>(
+        &mut self,
+        from_version: u32,
+        migration: impl Fn(&mut Value) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.migrations
+            .insert((T::TYPE_NAME, from_version), Box::new(migration));
+        self
+    }
+
+    /// Runs every migration from `from_version` up to `T::SCHEMA_VERSION`,
+    /// in order.
+    ///
+    /// # Errors
+    /// Returns `BotError::Validation` if `from_version` is 0 or newer than
+    /// this build, and `BotError::Internal` if a step has no migration.
+    pub fn migrate<T: Persisted>(&self, from_version: u32, mut payload: Value) -> BotResult<Value> {
+        if from_version == 0 || from_version > T::SCHEMA_VERSION {
+            return Err(BotError::validation(format!(
+                "{} payload has schema version {from_version}, but this build reads versions 1 to {}",
+                T::TYPE_NAME,
+                T::SCHEMA_VERSION
+            )));
+        }
+        for version in from_version..T::SCHEMA_VERSION {
+            let migration = self
+                .migrations
+                .get(&(T::TYPE_NAME, version))
+                .ok_or_else(|| {
+                    BotError::internal(format!(
+                        "no migration registered for {} from schema version {version}",
+                        T::TYPE_NAME
+                    ))
+                })?;
+            migration(&mut payload);
+        }
+        Ok(payload)
+    }
+
+    /// Reads a `Versioned` envelope written by `encode_versioned` at any
+    /// supported version, migrating its payload to the current schema.
+    ///
+    /// # Errors
+    /// Returns `BotError::Validation` if `bytes` is not an envelope or the
+    /// migrated payload is not a valid `T`, and the errors of `migrate`.
+    pub fn decode_versioned<T: Persisted>(&self, bytes: &[u8]) -> BotResult<T> {
+        let envelope: Versioned<Value> = serde_json::from_slice(bytes)
+            .map_err(|e| BotError::validation(format!("invalid versioned payload: {e}")))?;
+        let payload = self.migrate::<T>(envelope.schema_version, envelope.payload)?;
+        serde_json::from_value(payload)
+            .map_err(|e| BotError::validation(format!("invalid {} payload: {e}", T::TYPE_NAME)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AttachmentType;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    /// A model at version 3: v2 renamed `text` to `content`, and v3 moved
+    /// `media_url` into `attachments`.
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Note {
+        content: String,
+        #[serde(default)]
+        attachments: Vec<crate::models::Attachment>,
+    }
+
+    impl Persisted for Note {
+        const TYPE_NAME: &'static str = "Note";
+        const SCHEMA_VERSION: u32 = 3;
+    }
+
+    fn rename_text(payload: &mut Value) {
+        if let Some(object) = payload.as_object_mut() {
+            if let Some(text) = object.remove("text") {
+                object.insert("content".to_string(), text);
+            }
+        }
+    }
+
+    fn media_url_to_attachments(payload: &mut Value) {
+        if let Some(object) = payload.as_object_mut() {
+            if let Some(url) = object.remove("media_url").filter(|url| !url.is_null()) {
+                object.insert(
+                    "attachments".to_string(),
+                    json!([{"attachment_type": "file", "url": url}]),
+                );
+            }
+        }
+    }
+
+    fn message<T>(result: BotResult<T>) -> Option<String> {
+        match result {
+            Err(BotError::Validation(message)) => Some(message.into()),
+            Err(BotError::Internal(message)) => Some(message),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_at_current_version() {
+        let response = BotResponse::new("bot", "s1", "u1", "Olá", "web");
+        let bytes = encode_versioned(&response).unwrap_or_default();
+        let envelope: Option<Value> = serde_json::from_slice(&bytes).ok();
+        assert_eq!(
+            envelope.as_ref().and_then(|e| e.get("schema_version")),
+            Some(&json!(1))
+        );
+        assert_eq!(
+            envelope
+                .as_ref()
+                .and_then(|e| e.pointer("/payload/content")),
+            Some(&json!("Olá"))
+        );
+
+        let back = Migrator::new().decode_versioned::<BotResponse>(&bytes).ok();
+        assert_eq!(back.map(|r| r.content), Some(response.content));
+        assert_eq!(
+            Versioned::new(Session::new(Uuid::nil(), Uuid::nil(), "x")).schema_version,
+            Session::SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn test_v1_payload_migrates_through_two_steps() {
+        let mut migrator = Migrator::new();
+        migrator
+            .register::<Note>(2, media_url_to_attachments)
+            .register::<Note>(1, rename_text);
+        let v1 = json!({
+            "schema_version": 1,
+            "payload": {"text": "recibo", "media_url": "https://x/r.pdf"}
+        });
+        let note = migrator
+            .decode_versioned::<Note>(v1.to_string().as_bytes())
+            .ok();
+        assert_eq!(note.as_ref().map(|n| n.content.as_str()), Some("recibo"));
+        assert_eq!(
+            note.as_ref()
+                .and_then(|n| n.attachments.first())
+                .map(|a| (a.attachment_type, a.url.as_str())),
+            Some((AttachmentType::File, "https://x/r.pdf"))
+        );
+
+        let v2 = json!({"schema_version": 2, "payload": {"content": "oi", "media_url": null}});
+        let note = migrator
+            .decode_versioned::<Note>(v2.to_string().as_bytes())
+            .ok();
+        assert_eq!(
+            note.map(|n| (n.content, n.attachments.len())),
+            Some(("oi".to_string(), 0))
+        );
+    }
+
+    #[test]
+    fn test_missing_migration() {
+        let mut migrator = Migrator::new();
+        migrator.register::<Note>(2, media_url_to_attachments);
+        let v1 = json!({"schema_version": 1, "payload": {"text": "recibo"}});
+        let result = migrator.decode_versioned::<Note>(v1.to_string().as_bytes());
+        assert!(matches!(result, Err(BotError::Internal(_))));
+        assert_eq!(
+            message(result),
+            Some("no migration registered for Note from schema version 1".to_string())
+        );
+
+        // Registrations are per type.
+        migrator.register::<BotResponse>(1, rename_text);
+        assert!(migrator.migrate::<Note>(1, json!({})).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_envelopes() {
+        let migrator = Migrator::new();
+        let decode =
+            |value: Value| message(migrator.decode_versioned::<Note>(value.to_string().as_bytes()));
+        assert_eq!(
+            decode(json!({"schema_version": 4, "payload": {}})),
+            Some(
+                "Note payload has schema version 4, but this build reads versions 1 to 3"
+                    .to_string()
+            )
+        );
+        assert!(decode(json!({"schema_version": 0, "payload": {}}))
+            .is_some_and(|m| m.contains("schema version 0")));
+        assert!(decode(json!({"content": "no envelope"}))
+            .is_some_and(|m| m.starts_with("invalid versioned payload")));
+        assert!(
+            decode(json!({"schema_version": 3, "payload": {"text": "old"}}))
+                .is_some_and(|m| m.starts_with("invalid Note payload"))
+        );
+    }
+}
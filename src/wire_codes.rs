@@ -0,0 +1,210 @@
+use crate::message_types::{MessageType, MessageTypeError};
+use std::collections::HashMap;
+
+/// Translates between `MessageType` and the integer codes a channel or
+/// protocol puts on the wire.
+pub trait MessageTypeCodec: Send + Sync {
+    /// The wire code for `message_type`.
+    fn encode(&self, message_type: MessageType) -> i32;
+
+    /// `None` for codes this codec has no mapping for.
+    fn decode(&self, code: i32) -> Option<MessageType>;
+}
+
+/// Our own format: the wire code is the `MessageType` value, including
+/// custom values.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NativeCodec;
+
+impl MessageTypeCodec for NativeCodec {
+    fn encode(&self, message_type: MessageType) -> i32 {
+        message_type.as_i32()
+    }
+
+    fn decode(&self, code: i32) -> Option<MessageType> {
+        Some(MessageType(code))
+    }
+}
+
+/// Codes of the legacy v5 protocol, which only has the original six types.
+pub const LEGACY_V5_CODES: [(MessageType, i32); 6] = [
+    (MessageType::EXTERNAL, 900),
+    (MessageType::USER, 100),
+    (MessageType::BOT_RESPONSE, 200),
+    (MessageType::SUGGESTION, 201),
+    (MessageType::CONTINUE, 300),
+    (MessageType::CONTEXT_CHANGE, 301),
+];
+
+/// The legacy v5 protocol, per `LEGACY_V5_CODES`. Types v5 has no code for
+/// are sent as their own value.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LegacyV5Codec;
+
+impl MessageTypeCodec for LegacyV5Codec {
+    fn encode(&self, message_type: MessageType) -> i32 {
+        LEGACY_V5_CODES
+            .iter()
+            .find(|(t, _)| *t == message_type)
+            .map_or(message_type.as_i32(), |&(_, code)| code)
+    }
+
+    fn decode(&self, code: i32) -> Option<MessageType> {
+        LEGACY_V5_CODES
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map(|&(message_type, _)| message_type)
+    }
+}
+
+/// The codec each channel uses, by channel name as it appears in a
+/// message's `channel` field. Channels without a registration use
+/// `NativeCodec`.
+///
+/// An inbound code the channel's codec cannot map becomes
+/// `MessageType::EXTERNAL`, or another default set with
+/// `with_unknown_default`; a `strict` registry rejects it instead.
+pub struct CodecRegistry {
+    codecs: HashMap<String, Box<dyn MessageTypeCodec>>,
+    unknown_default: Option<MessageType>,
+}
+
+impl Default for CodecRegistry {
+    fn default() -> Self {
+        Self {
+            codecs: HashMap::new(),
+            unknown_default: Some(MessageType::EXTERNAL),
+        }
+    }
+}
+
+impl std::fmt::Debug for CodecRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut channels: Vec<_> = self.codecs.keys().collect();
+        channels.sort();
+        f.debug_struct("CodecRegistry")
+            .field("channels", &channels)
+            .field("unknown_default", &self.unknown_default)
+            .finish()
+    }
+}
+
+impl CodecRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// What unmappable inbound codes become.
+    #[must_use]
+    pub const fn with_unknown_default(mut self, message_type: MessageType) -> Self {
+        self.unknown_default = Some(message_type);
+        self
+    }
+
+    /// Rejects unmappable inbound codes instead of defaulting them.
+    #[must_use]
+    pub const fn strict(mut self) -> Self {
+        self.unknown_default = None;
+        self
+    }
+
+    /// Uses `codec` for `channel`, replacing any earlier registration.
+    /// Channel names are matched case-insensitively.
+    pub fn register(&mut self, channel: &str, codec: impl MessageTypeCodec + 'static) -> &mut Self {
+        self.codecs
+            .insert(channel.trim().to_ascii_lowercase(), Box::new(codec));
+        self
+    }
+
+    #[must_use]
+    pub fn codec(&self, channel: &str) -> &dyn MessageTypeCodec {
+        self.codecs
+            .get(&channel.trim().to_ascii_lowercase())
+            .map_or(&NativeCodec, |codec| codec.as_ref())
+    }
+
+    #[must_use]
+    pub fn encode(&self, channel: &str, message_type: MessageType) -> i32 {
+        self.codec(channel).encode(message_type)
+    }
+
+    /// # Errors
+    /// Returns `MessageTypeError::UnknownValue` if the registry is strict
+    /// and `channel`'s codec has no mapping for `code`.
+    pub fn decode(&self, channel: &str, code: i32) -> Result<MessageType, MessageTypeError> {
+        self.codec(channel)
+            .decode(code)
+            .or(self.unknown_default)
+            .ok_or(MessageTypeError::UnknownValue(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_round_trip() {
+        let codec = NativeCodec;
+        for message_type in MessageType::ALL
+            .iter()
+            .copied()
+            .chain([MessageType(1001), MessageType::UNKNOWN])
+        {
+            assert_eq!(codec.encode(message_type), message_type.as_i32());
+            assert_eq!(codec.decode(codec.encode(message_type)), Some(message_type));
+        }
+    }
+
+    #[test]
+    fn test_legacy_v5_round_trip() {
+        let codec = LegacyV5Codec;
+        for (message_type, code) in LEGACY_V5_CODES {
+            assert_eq!(codec.encode(message_type), code);
+            assert_eq!(codec.decode(code), Some(message_type));
+        }
+        assert_eq!(codec.encode(MessageType::TYPING), 11);
+        assert_eq!(codec.decode(1), None);
+        assert_eq!(codec.decode(11), None);
+    }
+
+    #[test]
+    fn test_registration_overrides_one_channel() {
+        let mut registry = CodecRegistry::new();
+        registry.register("Legacy-Web", LegacyV5Codec);
+
+        assert_eq!(registry.encode("legacy-web", MessageType::USER), 100);
+        assert_eq!(
+            registry.decode(" LEGACY-WEB", 200),
+            Ok(MessageType::BOT_RESPONSE)
+        );
+        assert_eq!(registry.encode("whatsapp", MessageType::USER), 1);
+        assert_eq!(registry.decode("whatsapp", 200), Ok(MessageType(200)));
+
+        registry.register("legacy-web", NativeCodec);
+        assert_eq!(registry.encode("legacy-web", MessageType::USER), 1);
+    }
+
+    #[test]
+    fn test_unknown_inbound_codes() {
+        let mut lenient = CodecRegistry::new();
+        lenient.register("legacy", LegacyV5Codec);
+        assert_eq!(lenient.decode("legacy", 7), Ok(MessageType::EXTERNAL));
+
+        let mut custom = CodecRegistry::new().with_unknown_default(MessageType::UNKNOWN);
+        custom.register("legacy", LegacyV5Codec);
+        assert_eq!(custom.decode("legacy", 7), Ok(MessageType::UNKNOWN));
+
+        let mut strict = CodecRegistry::new().strict();
+        strict.register("legacy", LegacyV5Codec);
+        assert_eq!(
+            strict.decode("legacy", 7),
+            Err(MessageTypeError::UnknownValue(7))
+        );
+        assert_eq!(
+            strict.decode("legacy", 301),
+            Ok(MessageType::CONTEXT_CHANGE)
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Sets the process-wide branding, so it runs in its own test binary.
+
+use botlib::branding::{
+    branding, copyright_text, init_branding_with, platform_name, with_branding, BrandingConfig,
+};
+
+fn acme() -> BrandingConfig {
+    BrandingConfig {
+        name: "Acme Bots".to_string(),
+        short_name: "AB".to_string(),
+        company: Some("Acme Inc".to_string()),
+        copyright: None,
+        is_white_label: true,
+        ..BrandingConfig::default()
+    }
+}
+
+#[test]
+fn test_explicit_configs_in_sequence() {
+    init_branding_with(acme());
+    assert_eq!(platform_name(), "Acme Bots");
+    assert!(copyright_text().ends_with(" Acme Inc"));
+    let held = branding();
+
+    init_branding_with(BrandingConfig::default());
+    assert_eq!(platform_name(), "General Bots");
+    assert_eq!(held.name, "Acme Bots");
+
+    let scoped = with_branding(acme(), platform_name);
+    assert_eq!(scoped, "Acme Bots");
+    assert_eq!(platform_name(), "General Bots");
+}